@@ -0,0 +1,448 @@
+//! Procedural macros of `awesome`, use them through the re-exports of the main crate.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::Parse;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, FnArg, GenericParam, ImplItem, ItemFn, ItemImpl, LitStr, ReturnType, Token, Type};
+
+/// Adds the function to the overload set `name`, which is declared with `awesome::overload_set!`.
+///
+/// expands to the function and an impl of `name::Overload<Args>` for `name::Marker` that calls it,
+/// `Args` is the parameter type for one parameter, a tuple otherwise.
+#[proc_macro_attribute]
+pub fn overload(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut name: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported overload property, expected `name`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let func = parse_macro_input!(item as ItemFn);
+
+    let Some(name) = name else {
+        return syn::Error::new(proc_macro2::Span::call_site(), "missing `name`, e.g. #[overload(name = \"foo\")]")
+            .into_compile_error()
+            .into();
+    };
+    let set = match syn::parse_str::<syn::Ident>(&name.value()) {
+        Ok(ident) => syn::Ident::new(&ident.to_string(), name.span()),
+        Err(_) => return syn::Error::new(name.span(), "`name` must be an identifier").into_compile_error().into(),
+    };
+
+    let mut types = Vec::new();
+    for input in &func.sig.inputs {
+        match input {
+            FnArg::Typed(pat) => types.push(&*pat.ty),
+            FnArg::Receiver(receiver) => {
+                return syn::Error::new_spanned(receiver, "methods cannot be overloaded, only free functions")
+                    .into_compile_error()
+                    .into()
+            }
+        }
+    }
+    let args: Vec<_> = (0..types.len()).map(|i| format_ident!("arg{}", i)).collect();
+
+    let (arg_ty, arg_pat) = match types.as_slice() {
+        [ty] => (quote!(#ty), quote!(#(#args)*)),
+        _ => (quote!((#(#types),*)), quote!((#(#args),*))),
+    };
+    let output = match &func.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let ident = &func.sig.ident;
+    let (impl_generics, _, where_clause) = func.sig.generics.split_for_impl();
+
+    quote! {
+        #func
+
+        impl #impl_generics #set::Overload<#arg_ty> for #set::Marker #where_clause {
+            type Output = #output;
+
+            fn call(#arg_pat: #arg_ty) -> Self::Output {
+                #ident(#(#args),*)
+            }
+        }
+    }
+    .into()
+}
+
+/// The derive version of `r#impl!` in `awesome::impl_macro`, implements `Foo<T>` (adds to the only field) for every type of `#[foo(types(..))]`.
+///
+/// the struct must have exactly one field, and at most one type parameter, which is replaced by each type.
+#[proc_macro_derive(FooAll, attributes(foo))]
+pub fn derive_foo_all(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    foo_all(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn foo_all(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut types: Option<Punctuated<Type, Token![,]>> = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("foo")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("types") {
+                if types.is_some() {
+                    return Err(meta.error("duplicate `types`"));
+                }
+                let content;
+                syn::parenthesized!(content in meta.input);
+                types = Some(content.parse_terminated(Type::parse, Token![,])?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported foo property, expected `types(..)`"))
+            }
+        })?;
+    }
+    let Some(types) = types else {
+        return Err(syn::Error::new_spanned(&input.ident, "missing `#[foo(types(..))]`, e.g. #[foo(types(u8, u16))]"));
+    };
+    if types.is_empty() {
+        return Err(syn::Error::new_spanned(&input.ident, "`types(..)` is empty, list at least one type"));
+    }
+
+    let field = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if fields.named.len() == 1 => {
+                let ident = fields.named[0].ident.as_ref().unwrap();
+                quote!(#ident)
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote!(0),
+            fields => return Err(syn::Error::new_spanned(fields, "FooAll needs a struct with exactly one field")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "FooAll can only be derived for structs")),
+    };
+
+    let mut params = input.generics.params.iter();
+    let generic = match (params.next(), params.next()) {
+        (None, _) => false,
+        (Some(GenericParam::Type(_)), None) => true,
+        _ => return Err(syn::Error::new_spanned(&input.generics, "FooAll supports no generics, or a single type parameter")),
+    };
+
+    let name = &input.ident;
+    let impls = types.iter().map(|ty| {
+        let self_ty = if generic { quote!(#name<#ty>) } else { quote!(#name) };
+
+        quote! {
+            impl ::awesome::impl_macro::Foo<#ty> for #self_ty {
+                type Output = #ty;
+
+                fn foo(&self, x: #ty) -> Self::Output {
+                    self.#field + x
+                }
+            }
+        }
+    });
+
+    Ok(quote!(#(#impls)*))
+}
+
+/// Generates a lens constructor for every named field, `Name::field_lens()` returns an `awesome::optics::Lens<Name, FieldType>`.
+///
+/// the constructors have the visibility of their fields. `view` clones the field, so the lens of a field exists only if its type is `Clone`,
+/// and the struct must be `'static`, like `Lens` itself.
+#[proc_macro_derive(Lenses)]
+pub fn derive_lenses(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    lenses(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn lenses(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => return Err(syn::Error::new_spanned(&input.ident, "Lenses needs a struct with named fields, it has no fields")),
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "Lenses needs a struct with named fields, a tuple struct has no field names",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Lenses can only be derived for structs, use a prism for an enum",
+            ))
+        }
+    };
+    if fields.is_empty() {
+        return Err(syn::Error::new_spanned(&input.ident, "Lenses needs at least one field"));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let constructors = fields.iter().map(|field| {
+        let (vis, ident, ty) = (&field.vis, field.ident.as_ref().unwrap(), &field.ty);
+        let constructor = format_ident!("{}_lens", ident.to_string().trim_start_matches("r#"));
+        let doc = format!("The lens on `{}`.", ident);
+
+        quote! {
+            #[doc = #doc]
+            #vis fn #constructor() -> ::awesome::optics::Lens<Self, #ty>
+            where
+                Self: 'static,
+                #ty: ::std::clone::Clone + 'static,
+            {
+                ::awesome::optics::Lens::new(
+                    |s: &Self| ::std::clone::Clone::clone(&s.#ident),
+                    |mut s: Self, value| {
+                        s.#ident = value;
+                        s
+                    },
+                )
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#constructors)*
+        }
+    })
+}
+
+/// Put on an `impl` block with a `combine(self, rhs, op: Op)` method, implements `Add`, `Sub`, `Mul`, `Div` and their `*Assign` variants with it.
+///
+/// `self` can be taken by value or by reference, `rhs` as `Self` or `&Self`.
+/// with `self` by value, the `*Assign` impls clone `self`, so the type must be `Clone`.
+#[proc_macro_attribute]
+pub fn impl_ops(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemImpl);
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return syn::Error::new_spanned(attr, "impl_ops takes no arguments").into_compile_error().into();
+    }
+
+    impl_ops_for(item).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn impl_ops_for(item: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(path, "impl_ops must be put on an inherent impl block"));
+    }
+    let combine = item
+        .items
+        .iter()
+        .find_map(|item| match item {
+            ImplItem::Fn(f) if f.sig.ident == "combine" => Some(f),
+            _ => None,
+        })
+        .ok_or_else(|| syn::Error::new_spanned(&item.self_ty, "expected a `fn combine(self, rhs: Self, op: Op) -> Self` in this impl block"))?;
+
+    let inputs: Vec<_> = combine.sig.inputs.iter().collect();
+    let (by_ref, rhs_ref) = match inputs.as_slice() {
+        [FnArg::Receiver(receiver), FnArg::Typed(rhs), FnArg::Typed(_)] if receiver.mutability.is_none() => {
+            (receiver.reference.is_some(), matches!(&*rhs.ty, Type::Reference(_)))
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &combine.sig,
+                "`combine` must take `self` or `&self`, the right-hand side and an `Op`",
+            ))
+        }
+    };
+
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let rhs = if rhs_ref { quote!(&rhs) } else { quote!(rhs) };
+    let (lhs, lhs_assign) = if by_ref {
+        (quote!(&self), quote!(self))
+    } else {
+        (quote!(self), quote!(::std::clone::Clone::clone(self)))
+    };
+
+    let ops = [
+        ("Add", "add", "AddAssign", "add_assign"),
+        ("Sub", "sub", "SubAssign", "sub_assign"),
+        ("Mul", "mul", "MulAssign", "mul_assign"),
+        ("Div", "div", "DivAssign", "div_assign"),
+    ];
+    let impls = ops.iter().map(|(tr, method, assign_tr, assign_method)| {
+        let (tr, method) = (format_ident!("{}", tr), format_ident!("{}", method));
+        let (assign_tr, assign_method) = (format_ident!("{}", assign_tr), format_ident!("{}", assign_method));
+
+        quote! {
+            impl #impl_generics ::std::ops::#tr for #self_ty #where_clause {
+                type Output = Self;
+
+                fn #method(self, rhs: Self) -> Self::Output {
+                    Self::combine(#lhs, #rhs, ::awesome::impl_macro::Op::#tr)
+                }
+            }
+
+            impl #impl_generics ::std::ops::#assign_tr for #self_ty #where_clause {
+                fn #assign_method(&mut self, rhs: Self) {
+                    *self = Self::combine(#lhs_assign, #rhs, ::awesome::impl_macro::Op::#tr);
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        #(#impls)*
+    })
+}
+
+/// `Name { From --event--> To, ... }`, with optional attributes and visibility before `Name`.
+struct StateMachineInput {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    name: syn::Ident,
+    transitions: Punctuated<Transition, Token![,]>,
+}
+
+struct Transition {
+    from: syn::Ident,
+    event: syn::Ident,
+    to: syn::Ident,
+}
+
+impl Parse for StateMachineInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        Ok(StateMachineInput {
+            attrs: input.call(syn::Attribute::parse_outer)?,
+            vis: input.parse()?,
+            name: input.parse()?,
+            transitions: {
+                syn::braced!(content in input);
+                content.parse_terminated(Transition::parse, Token![,])?
+            },
+        })
+    }
+}
+
+impl Parse for Transition {
+    // `-->` is lexed as `-` then `->`.
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let from = input.parse()?;
+        input.parse::<Token![-]>()?;
+        input.parse::<Token![-]>()?;
+        let event = input.parse()?;
+        input.parse::<Token![-]>()?;
+        input.parse::<Token![->]>()?;
+        let to = input.parse()?;
+        Ok(Transition { from, event, to })
+    }
+}
+
+/// `open_door` -> `OpenDoor`, the events are written like methods but become enum variants.
+fn upper_camel_case(ident: &syn::Ident) -> syn::Ident {
+    let name: String = ident
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .concat();
+    syn::Ident::new(&name, ident.span())
+}
+
+/// Generates a finite state machine from its transitions, see `awesome::fsm`.
+///
+/// `Door { Closed --open--> Open }` generates the enums `DoorState` and `DoorEvent` (the events in `UpperCamelCase`)
+/// and the unit struct `Door`, which implements `awesome::fsm::StateMachine`. the first state is the default one.
+///
+/// it is a procedural macro because the states have to be deduplicated, which `macro_rules!` can't do.
+#[proc_macro]
+pub fn state_machine(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as StateMachineInput);
+
+    state_machine_for(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn state_machine_for(input: StateMachineInput) -> syn::Result<proc_macro2::TokenStream> {
+    let StateMachineInput { attrs, vis, name, transitions } = input;
+    if transitions.is_empty() {
+        return Err(syn::Error::new_spanned(&name, "a state machine needs at least one transition"));
+    }
+
+    // in the order of their first appearance, so the generated enums follow the source.
+    fn push_unique(list: &mut Vec<syn::Ident>, ident: syn::Ident) {
+        if !list.contains(&ident) {
+            list.push(ident);
+        }
+    }
+    let (mut states, mut events) = (Vec::new(), Vec::new());
+    let mut seen: Vec<(&syn::Ident, &syn::Ident)> = Vec::new();
+    for transition in &transitions {
+        if seen.contains(&(&transition.from, &transition.event)) {
+            return Err(syn::Error::new_spanned(
+                &transition.event,
+                format!("the transition from `{}` on `{}` is defined twice", transition.from, transition.event),
+            ));
+        }
+        seen.push((&transition.from, &transition.event));
+        push_unique(&mut states, transition.from.clone());
+        push_unique(&mut states, transition.to.clone());
+        push_unique(&mut events, upper_camel_case(&transition.event));
+    }
+
+    let state = format_ident!("{}State", name);
+    let event = format_ident!("{}Event", name);
+    let (initial, rest) = (&states[0], &states[1..]);
+    let arms = transitions.iter().map(|Transition { from, event: on, to }| {
+        let on = upper_camel_case(on);
+        quote!((#state::#from, #event::#on) => ::std::result::Result::Ok(#state::#to),)
+    });
+    let table = transitions.iter().map(|Transition { from, event: on, to }| {
+        let on = upper_camel_case(on);
+        quote!((#state::#from, #event::#on, #state::#to),)
+    });
+    let state_doc = format!("The states of [`{name}`].");
+    let event_doc = format!("The events of [`{name}`].");
+
+    Ok(quote! {
+        #[doc = #state_doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        #vis enum #state {
+            #[default]
+            #initial,
+            #(#rest,)*
+        }
+
+        #[doc = #event_doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #vis enum #event {
+            #(#events,)*
+        }
+
+        #(#attrs)*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        #vis struct #name;
+
+        impl #name {
+            /// The state after `event` in `state`.
+            #vis fn transition(state: #state, event: #event) -> ::std::result::Result<#state, ::awesome::fsm::InvalidTransition<#state, #event>> {
+                #[allow(unreachable_patterns)]
+                match (state, event) {
+                    #(#arms)*
+                    (state, event) => ::std::result::Result::Err(::awesome::fsm::InvalidTransition { state, event }),
+                }
+            }
+        }
+
+        impl ::awesome::fsm::StateMachine for #name {
+            type State = #state;
+            type Event = #event;
+
+            const TRANSITIONS: &'static [(#state, #event, #state)] = &[#(#table)*];
+
+            fn transition(state: #state, event: #event) -> ::std::result::Result<#state, ::awesome::fsm::InvalidTransition<#state, #event>> {
+                #name::transition(state, event)
+            }
+        }
+    })
+}
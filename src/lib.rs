@@ -1,3 +1,6 @@
+// the derive macros generate `::awesome::...` paths, this makes them work inside this crate too.
+extern crate self as awesome;
+
 /// # Function Overloading
 ///
 /// `Function overloading` is means defining multiple functions by assigning a function with the same name to different types of `parameters` or `return type`.
@@ -9,6 +12,11 @@ pub mod overloading {
     /// generic `T` is the type of the function argument.
     ///
     /// a function takes only one argument. if it takes multiple arguments, use `tuple` etc.
+    #[diagnostic::on_unimplemented(
+        message = "no overload of `foo` accepts `{T}`",
+        label = "`Overloading` does not implement `Foo<{T}>`",
+        note = "the overloads are the `impl Foo<_> for Overloading` items, multiple arguments are passed as a tuple"
+    )]
     pub trait Foo<T> {
         /// The return type of the function.
         ///
@@ -23,6 +31,10 @@ pub mod overloading {
         /// example:
         ///
         /// ```rust
+        /// # use awesome::overloading::Foo;
+        /// # struct Bar;
+        /// # impl Foo<usize> for Bar {
+        /// # type Output = Result<usize, String>;
         /// fn ctor(arg: usize) -> Self::Output {
         ///     if arg < 10 {
         ///        Ok(arg)
@@ -30,16 +42,48 @@ pub mod overloading {
         ///        Err("too large".to_string())
         ///     }
         /// }
+        /// # }
         /// ```
         fn ctor(arg: T) -> Self::Output;
     }
 
-    /// ctor(usize) -> usize
-    impl Foo<usize> for Overloading {
-        type Output = usize;
+    mod sealed {
+        pub trait Sealed {}
+    }
 
-        fn ctor(arg: usize) -> Self::Output {
-            arg * 10
+    /// The numeric types, it is sealed, so no other crate can implement it.
+    ///
+    /// because it's sealed (and local), the compiler knows `String` is not a `Number`,
+    /// so the blanket `impl<T: Number> Foo<T>` can coexist with the concrete `Foo<String>`.
+    #[diagnostic::on_unimplemented(
+        message = "no overload of `foo` accepts `{Self}`",
+        label = "`{Self}` is not a `Number`, and there is no other `Foo<{Self}>` impl",
+        note = "the overloads are the `impl Foo<_> for Overloading` items, multiple arguments are passed as a tuple"
+    )]
+    pub trait Number: sealed::Sealed + Copy + std::ops::Mul<Output = Self> {
+        const TEN: Self;
+    }
+
+    macro_rules! number {
+        ($($t:ty)*) => {
+            $(
+                impl sealed::Sealed for $t {}
+
+                impl Number for $t {
+                    const TEN: Self = 10 as $t;
+                }
+            )*
+        };
+    }
+
+    number! { usize u8 u16 u32 u64 u128 isize i8 i16 i32 i64 i128 f32 f64 }
+
+    /// ctor(T) -> T, for every `Number`, instead of separate impls per type.
+    impl<T: Number> Foo<T> for Overloading {
+        type Output = T;
+
+        fn ctor(arg: T) -> Self::Output {
+            arg * T::TEN
         }
     }
 
@@ -52,6 +96,240 @@ pub mod overloading {
         }
     }
 
+    // Overloads can take borrows, so callers do not have to clone to call them.
+    // the lifetime is a part of the argument type `T`, so the helper `foo` does not need a lifetime parameter.
+    // a blanket `impl<T: AsRef<str>> Foo<T>` would conflict with the other impls (coherence), see `foo_as_ref` instead.
+
+    /// ctor(&usize) -> usize
+    impl Foo<&usize> for Overloading {
+        type Output = usize;
+
+        fn ctor(arg: &usize) -> Self::Output {
+            arg * 10
+        }
+    }
+
+    /// ctor(&str) -> String
+    impl Foo<&str> for Overloading {
+        type Output = String;
+
+        fn ctor(arg: &str) -> Self::Output {
+            format!("{arg}!")
+        }
+    }
+
+    /// ctor(&String) -> String, same as `&str`.
+    impl Foo<&String> for Overloading {
+        type Output = String;
+
+        fn ctor(arg: &String) -> Self::Output {
+            <Overloading as Foo<&str>>::ctor(arg)
+        }
+    }
+
+    /// ctor(&'a mut String) -> &'a mut String, appends in place and returns the same borrow.
+    impl<'a> Foo<&'a mut String> for Overloading {
+        type Output = &'a mut String;
+
+        fn ctor(arg: &'a mut String) -> Self::Output {
+            arg.push('!');
+            arg
+        }
+    }
+
+    /// ctor(&[T]) -> Vec<String>, for slices of anything that can be borrowed as `str` (`&[String]`, `&[&str]`, ...).
+    ///
+    /// pass a slice explicitly, like `&v[..]` or `v.as_slice()`.
+    /// the bound is `AsRef<str>` rather than `Overloading: Foo<&T>`, a recursive bound makes type inference overflow for calls like `foo(10)`.
+    impl<T: AsRef<str>> Foo<&[T]> for Overloading {
+        type Output = Vec<String>;
+
+        fn ctor(arg: &[T]) -> Self::Output {
+            arg.iter().map(foo_as_ref).collect()
+        }
+    }
+
+    /// Generates `Foo<(A, B, ...)>` for the given tuple and every shorter tuple (down to arity 2).
+    ///
+    /// each element is passed to its own overload, for example `ctor((usize, String)) -> (usize, String)`.
+    macro_rules! overload_tuple {
+        ($t:ident $v:ident) => {};
+        ($t0:ident $v0:ident, $($t:ident $v:ident),+) => {
+            impl<$t0, $($t),+> Foo<($t0, $($t),+)> for Overloading
+            where
+                Overloading: Foo<$t0> $(+ Foo<$t>)+,
+            {
+                type Output = (<Overloading as Foo<$t0>>::Output, $(<Overloading as Foo<$t>>::Output),+);
+
+                fn ctor(($v0, $($v),+): ($t0, $($t),+)) -> Self::Output {
+                    (<Overloading as Foo<$t0>>::ctor($v0), $(<Overloading as Foo<$t>>::ctor($v)),+)
+                }
+            }
+
+            overload_tuple!($($t $v),+);
+        };
+    }
+
+    // ctor((A, B)), ctor((A, B, C)), ... ctor((A, B, C, D, E, F, G, H))
+    overload_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+    /// Named and optional arguments, emulated with a builder, every field has a default and a setter of the same name.
+    ///
+    /// ```rust
+    /// # use awesome::overloading::{foo, FooArgs};
+    /// assert_eq!(foo(FooArgs::default().width(3)), "3x1");
+    /// assert_eq!(foo(FooArgs::default().height(2).label("box")), "box: 1x2");
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FooArgs {
+        pub width: usize,
+        pub height: usize,
+        pub label: Option<String>,
+    }
+
+    impl Default for FooArgs {
+        fn default() -> Self {
+            FooArgs {
+                width: 1,
+                height: 1,
+                label: None,
+            }
+        }
+    }
+
+    impl FooArgs {
+        pub fn width(mut self, width: usize) -> Self {
+            self.width = width;
+            self
+        }
+
+        pub fn height(mut self, height: usize) -> Self {
+            self.height = height;
+            self
+        }
+
+        pub fn label(mut self, label: impl Into<String>) -> Self {
+            self.label = Some(label.into());
+            self
+        }
+    }
+
+    /// ctor(FooArgs) -> String, the keyword-argument overload.
+    impl Foo<FooArgs> for Overloading {
+        type Output = String;
+
+        fn ctor(arg: FooArgs) -> Self::Output {
+            match arg.label {
+                Some(label) => format!("{label}: {}x{}", arg.width, arg.height),
+                None => format!("{}x{}", arg.width, arg.height),
+            }
+        }
+    }
+
+    /// Builds `FooArgs` from `name = value` pairs, the omitted ones keep their defaults.
+    ///
+    /// ```rust
+    /// # use awesome::{args, overloading::foo};
+    /// assert_eq!(foo(args!(height = 4, width = 2)), "2x4");
+    /// assert_eq!(foo(args!()), "1x1");
+    /// ```
+    #[macro_export]
+    macro_rules! args {
+        ($($name:ident = $value:expr),* $(,)?) => {
+            $crate::overloading::FooArgs::default()$(.$name($value))*
+        };
+    }
+
+    /// Defines an overload set in one block, it expands to a trait, a marker struct, trait impls and the helper function.
+    ///
+    /// every function must have the same name, the helper has the visibility of the first one.
+    /// identifiers cannot be concatenated in `macro_rules`, so the trait and the marker are placed in a module with the same name as the function
+    /// (modules and functions live in different namespaces). a parameter is a single token, like `r` or `(w, h)`.
+    ///
+    /// ```rust
+    /// # use awesome::overload;
+    /// overload! {
+    ///     fn area(r: f64) -> f64 {
+    ///         std::f64::consts::PI * r * r
+    ///     }
+    ///     fn area((w, h): (f64, f64)) -> f64 {
+    ///         w * h
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(area((2.0, 3.0)), 6.0);
+    /// assert!(area(1.0) > 3.14);
+    /// ```
+    #[macro_export]
+    macro_rules! overload {
+        ($vis:vis fn $name:ident $($rest:tt)*) => {
+            $crate::overload!(@impl $vis $name; $vis fn $name $($rest)*);
+        };
+        (@impl $vis:vis $name:ident; $($v:vis fn $n:ident ($arg:tt : $t:ty) -> $r:ty $body:block)+) => {
+            $crate::overload_set!($vis $name);
+
+            $(
+                impl $n::Overload<$t> for $n::Marker {
+                    type Output = $r;
+
+                    fn call($arg: $t) -> Self::Output $body
+                }
+            )+
+        };
+    }
+
+    /// Declares an empty overload set, the trait and the marker in a module `$name`, and the helper function `$name`.
+    ///
+    /// `overload!` uses it, and the overloads of `#[overload(name = "...")]` are wired into it,
+    /// attribute macros are expanded one by one, so they cannot declare the shared parts themselves.
+    #[macro_export]
+    macro_rules! overload_set {
+        ($vis:vis $name:ident) => {
+            $vis mod $name {
+                #[diagnostic::on_unimplemented(message = "no overload of this set accepts `{T}`", label = "there is no `impl Overload<{T}> for Marker` in the set")]
+                pub trait Overload<T> {
+                    type Output;
+
+                    fn call(arg: T) -> Self::Output;
+                }
+
+                pub struct Marker;
+            }
+
+            $vis fn $name<T>(arg: T) -> <$name::Marker as $name::Overload<T>>::Output
+            where
+                $name::Marker: $name::Overload<T>,
+            {
+                <$name::Marker as $name::Overload<T>>::call(arg)
+            }
+        };
+    }
+
+    /// Adds a free function to an overload set declared with `overload_set!`, the function itself is kept as is.
+    ///
+    /// a function with one parameter is called with the argument, more parameters are passed as a tuple.
+    ///
+    /// ```rust
+    /// # use awesome::overload_set;
+    /// use awesome::overloading::overload;
+    ///
+    /// overload_set!(area);
+    ///
+    /// #[overload(name = "area")]
+    /// fn circle(r: f64) -> f64 {
+    ///     std::f64::consts::PI * r * r
+    /// }
+    ///
+    /// #[overload(name = "area")]
+    /// fn rectangle(w: f64, h: f64) -> f64 {
+    ///     w * h
+    /// }
+    ///
+    /// assert_eq!(area((2.0, 3.0)), 6.0);
+    /// assert_eq!(area(1.0), circle(1.0));
+    /// ```
+    pub use awesome_macros::overload;
+
     /// Overloading functions can be conveniently used with helper functions, etc.
     pub fn foo<T>(arg: T) -> <Overloading as Foo<T>>::Output
     where
@@ -59,132 +337,15351 @@ pub mod overloading {
     {
         <Overloading as Foo<T>>::ctor(arg)
     }
-}
 
-/// # Monad
-///
-/// Monad is a typical functional programming languages. See [here](https://en.wikipedia.org/wiki/Monad_(functional_programming)) for more details.
-pub mod monad {
-    /// A simple monad implementation.
+    /// Constructor overloading, generic `T` is the argument of `new`.
+    #[diagnostic::on_unimplemented(message = "no overload of `{Self}::new` accepts `{T}`", label = "`{Self}` does not implement `New<{T}>`")]
+    pub trait New<T> {
+        fn new(arg: T) -> Self;
+    }
+
+    /// A point that can be constructed from nothing, one coordinate or both of them.
     ///
-    /// It takes types `T` and `E`, which can be implemented in `Option<T>`, `Result<T, E>`, etc.
-    pub trait Monad {
-        type T;
-        type U;
+    /// ```rust
+    /// # use awesome::overloading::MyPoint;
+    /// assert_eq!(MyPoint::new(3), MyPoint { x: 3, y: 3 });
+    /// assert_eq!(MyPoint::new((3, 4)), MyPoint { x: 3, y: 4 });
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MyPoint {
+        pub x: i32,
+        pub y: i32,
+    }
 
-        /// for example:
-        ///
-        /// ```rust
-        /// assert_eq!(Some(2).bind(|x| Some(x + 1)), Some(3));
-        /// ```
-        fn bind<F>(self, f: F) -> Self::U
+    impl MyPoint {
+        /// The inherent `new` is found before the trait one, so `MyPoint::new(..)` works without importing `New`.
+        pub fn new<T>(arg: T) -> Self
         where
-            F: FnOnce(Self::T) -> Self::U;
+            Self: New<T>,
+        {
+            <Self as New<T>>::new(arg)
+        }
     }
 
-    /// Monad implementation for `Option<T>`.
-    impl<T> Monad for Option<T> {
-        type T = T;
-        type U = Option<T>;
+    /// The origin.
+    impl New<()> for MyPoint {
+        fn new(_: ()) -> Self {
+            MyPoint { x: 0, y: 0 }
+        }
+    }
 
-        fn bind<F>(self, f: F) -> Self::U
-        where
-            F: FnOnce(Self::T) -> Self::U,
-        {
-            match self {
-                Some(x) => f(x),
-                None => None,
-            }
+    /// A point on the diagonal.
+    impl New<i32> for MyPoint {
+        fn new(v: i32) -> Self {
+            MyPoint { x: v, y: v }
         }
     }
-}
 
-/// # Implementing a trait for a type with macro
-///
-/// Implement a repeated `impl` using a `macro_rules`.
-pub mod impl_macro {
-    /// `Foo` trait, generic `T`
-    pub trait Foo<T> {
-        /// The return type of the function.
+    impl New<(i32, i32)> for MyPoint {
+        fn new((x, y): (i32, i32)) -> Self {
+            MyPoint { x, y }
+        }
+    }
+
+    impl New<[i32; 2]> for MyPoint {
+        fn new([x, y]: [i32; 2]) -> Self {
+            MyPoint { x, y }
+        }
+    }
+
+    /// Return type overloading, like `str::parse` or `Into`, the caller chooses the overload by the type it expects.
+    ///
+    /// generic `T` is the type of the argument, `Self` is the return type.
+    #[diagnostic::on_unimplemented(
+        message = "no overload of `from_foo` returns `{Self}`",
+        label = "`{Self}` does not implement `FromFoo<{T}>`",
+        note = "the result type chooses the overload, annotate it with one of the `FromFoo` implementors"
+    )]
+    pub trait FromFoo<T>: Sized {
+        fn from_foo(arg: T) -> Self;
+    }
+
+    impl FromFoo<&str> for String {
+        fn from_foo(arg: &str) -> Self {
+            arg.to_string()
+        }
+    }
+
+    impl FromFoo<&str> for Vec<char> {
+        fn from_foo(arg: &str) -> Self {
+            arg.chars().collect()
+        }
+    }
+
+    /// The fallible overload, `None` if `arg` cannot be parsed.
+    impl<T: std::str::FromStr> FromFoo<&str> for Option<T> {
+        fn from_foo(arg: &str) -> Self {
+            arg.trim().parse().ok()
+        }
+    }
+
+    /// The fallible overload with the reason, like `str::parse`.
+    impl<T: std::str::FromStr> FromFoo<&str> for Result<T, T::Err> {
+        fn from_foo(arg: &str) -> Self {
+            arg.trim().parse()
+        }
+    }
+
+    /// The helper of `FromFoo`, the return type can be inferred or given with a turbofish.
+    ///
+    /// ```rust
+    /// # use awesome::overloading::from_foo;
+    /// let x: Option<usize> = from_foo("42");
+    /// let y: Result<f64, _> = from_foo("42");
+    ///
+    /// assert_eq!(x, Some(42));
+    /// assert_eq!(y, Ok(42.0));
+    /// assert_eq!(from_foo::<Option<u8>>("256"), None);
+    /// ```
+    pub fn from_foo<R>(arg: &str) -> R
+    where
+        R: for<'a> FromFoo<&'a str>,
+    {
+        R::from_foo(arg)
+    }
+
+    /// A wrapper used by `foo_or_default!` to choose between an overload and the fallback at compile time.
+    pub struct Overload<T>(pub T);
+
+    /// The specific overload, implemented for `Overload<T>` (method resolution tries it first, by value).
+    pub trait ViaFoo {
         type Output;
-        /// `foo` function, `x` is a parameter of type `T`. it returns `Output`.
-        fn foo(&self, x: T) -> Self::Output;
+
+        fn call(self) -> Self::Output;
     }
 
-    pub struct MyStruct<T>(pub T);
+    impl<T> ViaFoo for Overload<T>
+    where
+        Overloading: Foo<T>,
+    {
+        type Output = <Overloading as Foo<T>>::Output;
 
-    /// Keywords cannot be used as identifiers, but can be decalred using `r#` prefix.
-    macro_rules! r#impl {
-        ($($t:ty /* `ty` is `type` */)*) => {
-            $(
-                impl Foo<$t> for MyStruct<$t> {
-                    type Output = $t;
+        fn call(self) -> Self::Output {
+            <Overloading as Foo<T>>::ctor(self.0)
+        }
+    }
 
-                    fn foo(&self, x: $t) -> Self::Output {
-                        self.0 + x
-                    }
-                }
-            )*
-        };
+    /// The catch-all overload, implemented for `&Overload<T>` (method resolution tries it after auto-ref), it uses `Display`.
+    pub trait ViaDisplay {
+        fn call(self) -> String;
     }
 
-    // `{ }` can omit the semicolon.
-    //
-    // https://play.rust-lang.org/?gist=dee6113358f0390a3a353b3dd95e411f
-    r#impl! { usize u8 u16 u32 u64 u128 isize i8 i16 i32 i64 i128 }
+    impl<T: std::fmt::Display> ViaDisplay for &Overload<T> {
+        fn call(self) -> String {
+            format!("<{}>", self.0)
+        }
+    }
 
-    pub fn x() -> impl Foo<usize, Output = usize> {
-        MyStruct(42)
+    /// Calls the overload of `foo` if there is one for the argument type, otherwise the `Display`-based fallback,
+    /// without specialization (autoref-based specialization).
+    ///
+    /// method resolution looks for `call` on `Overload<T>` first, and only on `&Overload<T>` if there's no match.
+    /// it only works with a concrete type at the call site, so it's a macro. in a generic function, it is chosen by the bounds of `T`, not the actual type.
+    ///
+    /// ```rust
+    /// # use awesome::foo_or_default;
+    /// assert_eq!(foo_or_default!(10usize), 100);
+    /// assert_eq!(foo_or_default!('c'), "<c>");
+    /// ```
+    #[macro_export]
+    macro_rules! foo_or_default {
+        ($e:expr) => {{
+            #[allow(unused_imports)]
+            use $crate::overloading::{ViaDisplay as _, ViaFoo as _};
+
+            $crate::overloading::Overload($e).call()
+        }};
     }
-}
 
-#[macro_export]
-macro_rules! linq {
-    (from $r:ident in $d:expr; select $s:expr;) => {
-        $d.map(|$r| $s)
-    };
-    (from $r:ident in $d:expr; $(where $w:expr;)* select $s:expr;) => {
-        $d.filter(|&$r| (true $(&$w)*)).map(|$r| $s)
-    };
+    /// Accepts anything that can be borrowed as `str` (`&String`, `&str`, `&Box<str>`, `&Cow<str>`, ...) and calls the `&str` overload.
+    pub fn foo_as_ref<S>(arg: &S) -> <Overloading as Foo<&str>>::Output
+    where
+        S: AsRef<str> + ?Sized,
+    {
+        <Overloading as Foo<&str>>::ctor(arg.as_ref())
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// # Monoid
+///
+/// A semigroup is a type with an associative binary operation, a monoid is a semigroup with an identity element.
+/// See [here](https://en.wikipedia.org/wiki/Monoid) for more details.
+pub mod monoid {
+    use crate::nonempty::NonEmpty;
+    use std::ops::{Add, Mul};
 
-    #[test]
-    fn overloading_test() {
-        assert_eq!(overloading::foo(10), 100);
-        assert_eq!(overloading::foo("hello".to_string()), "hello!");
+    /// A simple semigroup implementation.
+    ///
+    /// `combine` must be associative: `a.combine(b).combine(c) == a.combine(b.combine(c))`.
+    pub trait Semigroup {
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::monoid::Semigroup;
+        /// assert_eq!("foo".to_string().combine("bar".to_string()), "foobar");
+        /// assert_eq!(vec![1, 2].combine(vec![3]), vec![1, 2, 3]);
+        /// ```
+        fn combine(self, other: Self) -> Self;
     }
 
-    #[test]
-    fn monad_test() {
-        use crate::monad::*;
+    /// A simple monoid implementation.
+    ///
+    /// `mempty` must be the identity of `combine`: `T::mempty().combine(a) == a` and `a.combine(T::mempty()) == a`.
+    pub trait Monoid: Semigroup {
+        /// The identity element.
+        fn mempty() -> Self;
+    }
 
-        assert_eq!(Some(10).bind(|x| Some(x * 10)), Some(100));
-        assert_eq!(None::<usize>.bind(|x| Some(x * 10)), None);
+    /// `String` concatenation.
+    impl Semigroup for String {
+        fn combine(self, other: Self) -> Self {
+            self + &other
+        }
+    }
 
-        let (mul_5, div_10) = (|x: usize| Some(x * 5), |x: usize| Some(x / 10));
+    /// The identity is the empty string.
+    impl Monoid for String {
+        fn mempty() -> Self {
+            String::new()
+        }
+    }
 
-        assert_eq!(Some(10).bind(mul_5).bind(div_10), Some(5));
+    /// `Vec<T>` concatenation.
+    impl<T> Semigroup for Vec<T> {
+        fn combine(mut self, other: Self) -> Self {
+            self.extend(other);
+            self
+        }
     }
 
-    #[test]
-    fn impl_macro_test() {
-        use crate::impl_macro::*;
+    /// The identity is the empty vector.
+    impl<T> Monoid for Vec<T> {
+        fn mempty() -> Self {
+            Vec::new()
+        }
+    }
 
-        assert_eq!(impl_macro::x().foo(8), 50);
+    /// Combines the inner values if both are `Some`, otherwise keeps the one that is, so `None` is the identity.
+    ///
+    /// it turns any semigroup into a monoid, e.g. `Option<Max<T>>` is the maximum with `None` for no values.
+    impl<T: Semigroup> Semigroup for Option<T> {
+        fn combine(self, other: Self) -> Self {
+            match (self, other) {
+                (Some(a), Some(b)) => Some(a.combine(b)),
+                (a, None) => a,
+                (None, b) => b,
+            }
+        }
     }
 
-    #[test]
-    fn linq_test() {
-        let result = linq!(
-            from i in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10].iter();
-            where i % 2 == 0;
-            select i + 10;
-        );
+    impl<T: Semigroup> Monoid for Option<T> {
+        fn mempty() -> Self {
+            None
+        }
+    }
 
-        assert_eq!(result.collect::<Vec<i32>>(), vec![12, 14, 16, 18, 20]);
+    /// The monoid of `+`, the identity is `0`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct Sum<T>(pub T);
+
+    /// The monoid of `*`, the identity is `1`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct Product<T>(pub T);
+
+    /// The semigroup of the minimum, the first one of equal values is kept. the numbers are monoids, with their maximum as the identity.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct Min<T>(pub T);
+
+    /// The semigroup of the maximum, the first one of equal values is kept. the numbers are monoids, with their minimum as the identity.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct Max<T>(pub T);
+
+    impl<T: Add<Output = T>> Semigroup for Sum<T> {
+        fn combine(self, other: Self) -> Self {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    // the empty sum and product of `std::iter` are `0` and `1`.
+    impl<T: Add<Output = T> + std::iter::Sum> Monoid for Sum<T> {
+        fn mempty() -> Self {
+            Sum(std::iter::empty().sum())
+        }
+    }
+
+    impl<T: Mul<Output = T>> Semigroup for Product<T> {
+        fn combine(self, other: Self) -> Self {
+            Product(self.0 * other.0)
+        }
+    }
+
+    impl<T: Mul<Output = T> + std::iter::Product> Monoid for Product<T> {
+        fn mempty() -> Self {
+            Product(std::iter::empty().product())
+        }
+    }
+
+    impl<T: PartialOrd> Semigroup for Min<T> {
+        fn combine(self, other: Self) -> Self {
+            if other < self {
+                other
+            } else {
+                self
+            }
+        }
+    }
+
+    impl<T: PartialOrd> Semigroup for Max<T> {
+        fn combine(self, other: Self) -> Self {
+            if other > self {
+                other
+            } else {
+                self
+            }
+        }
+    }
+
+    macro_rules! bounded {
+        ($($t:ty: $min:expr, $max:expr;)+) => {
+            $(
+                impl Monoid for Min<$t> {
+                    fn mempty() -> Self {
+                        Min($max)
+                    }
+                }
+
+                impl Monoid for Max<$t> {
+                    fn mempty() -> Self {
+                        Max($min)
+                    }
+                }
+            )+
+        };
+    }
+
+    bounded! {
+        i8: i8::MIN, i8::MAX; i16: i16::MIN, i16::MAX; i32: i32::MIN, i32::MAX; i64: i64::MIN, i64::MAX; i128: i128::MIN, i128::MAX; isize: isize::MIN, isize::MAX;
+        u8: u8::MIN, u8::MAX; u16: u16::MIN, u16::MAX; u32: u32::MIN, u32::MAX; u64: u64::MIN, u64::MAX; u128: u128::MIN, u128::MAX; usize: usize::MIN, usize::MAX;
+        f32: f32::NEG_INFINITY, f32::INFINITY; f64: f64::NEG_INFINITY, f64::INFINITY;
+    }
+
+    /// Combines all the values in order, `mempty` if there are none, Haskell's `mconcat`.
+    ///
+    /// ```rust
+    /// # use awesome::monoid::{mconcat, Max, Product, Sum};
+    /// assert_eq!(mconcat([1, 2, 3, 4].map(Sum)), Sum(10));
+    /// assert_eq!(mconcat([1, 2, 3, 4].map(Product)), Product(24));
+    /// assert_eq!(mconcat([3, 9, 4].map(|n| Some(Max(n)))), Some(Max(9)));
+    /// assert_eq!(mconcat(Vec::<Sum<i32>>::new()), Sum(0));
+    /// ```
+    pub fn mconcat<M: Monoid>(values: impl IntoIterator<Item = M>) -> M {
+        values.into_iter().fold(M::mempty(), M::combine)
+    }
+
+    /// Combines all the values in order, there is at least one so a semigroup is enough, Haskell's `sconcat`.
+    pub fn sconcat<S: Semigroup>(values: NonEmpty<S>) -> S {
+        values.fold1(S::combine)
+    }
+
+    /// Generic checks for the semigroup and monoid laws, like `monad::laws`.
+    pub mod laws {
+        use super::{Monoid, Semigroup};
+
+        /// `a.combine(b).combine(c) == a.combine(b.combine(c))`
+        pub fn check_associativity<S>(values: impl IntoIterator<Item = (S, S, S)>) -> bool
+        where
+            S: Semigroup + Clone + PartialEq,
+        {
+            values
+                .into_iter()
+                .all(|(a, b, c)| a.clone().combine(b.clone()).combine(c.clone()) == a.combine(b.combine(c)))
+        }
+
+        /// `M::mempty().combine(a) == a` and `a.combine(M::mempty()) == a`
+        pub fn check_identity<M>(values: impl IntoIterator<Item = M>) -> bool
+        where
+            M: Monoid + Clone + PartialEq,
+        {
+            values.into_iter().all(|a| M::mempty().combine(a.clone()) == a && a.clone().combine(M::mempty()) == a)
+        }
+    }
+}
+
+/// # Functor
+///
+/// A functor is a type that can be mapped over, like `Option::map` or `Iterator::map`. See [here](https://en.wikipedia.org/wiki/Functor_(functional_programming)) for more details.
+pub mod functor {
+    /// A functor implementation using generic associated types.
+    ///
+    /// `fmap` must satisfy the functor laws:
+    ///
+    /// - identity: `x.fmap(|a| a) == x`
+    /// - composition: `x.fmap(|a| g(f(a))) == x.fmap(f).fmap(g)`
+    pub trait Functor {
+        /// The inner type of the functor.
+        type T;
+        /// The same functor, wrapping `U` instead of `T`.
+        type Wrapped<U>;
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::functor::Functor;
+        /// assert_eq!(Some(2).fmap(|x| x * 2), Some(4));
+        /// assert_eq!(vec![1, 2, 3].fmap(|x| x.to_string()), vec!["1", "2", "3"]);
+        /// ```
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U;
+    }
+
+    /// Functor implementation for `Option<T>`.
+    impl<T> Functor for Option<T> {
+        type T = T;
+        type Wrapped<U> = Option<U>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.map(f)
+        }
+    }
+
+    /// Functor implementation for `Result<T, E>`, only the `Ok` value is mapped.
+    impl<T, E> Functor for Result<T, E> {
+        type T = T;
+        type Wrapped<U> = Result<U, E>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.map(f)
+        }
+    }
+
+    /// Functor implementation for `Vec<T>`, `f` is applied to every element.
+    impl<T> Functor for Vec<T> {
+        type T = T;
+        type Wrapped<U> = Vec<U>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.into_iter().map(f).collect()
+        }
+    }
+
+    /// Functor implementation for `Box<T>`.
+    impl<T> Functor for Box<T> {
+        type T = T;
+        type Wrapped<U> = Box<U>;
+
+        fn fmap<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            Box::new(f(*self))
+        }
+    }
+}
+
+/// # Applicative
+///
+/// An applicative functor sits between `Functor` and `Monad`, it can combine independent effects. See [here](https://en.wikipedia.org/wiki/Applicative_functor) for more details.
+pub mod applicative {
+    use crate::functor::Functor;
+
+    /// A boxed curried function, used by `lift2` and `lift3` to partially apply a function inside a functor.
+    pub type Curried<'a, A, B> = Box<dyn Fn(A) -> B + 'a>;
+
+    /// A simple applicative implementation.
+    ///
+    /// `ap` takes the wrapped function as an argument, so `x.ap(f)` is Haskell's `f <*> x`.
+    pub trait Applicative: Functor {
+        /// Wraps a value into the applicative, Haskell's `pure`.
+        ///
+        /// ```rust
+        /// # use awesome::applicative::Applicative;
+        /// assert_eq!(Option::pure(1), Some(1));
+        /// ```
+        fn pure(x: Self::T) -> Self;
+
+        /// Applies a wrapped function to the wrapped value.
+        ///
+        /// the functions are `Fn`, so an applicative can call them through a reference, for example from the shared nodes of a `List`.
+        ///
+        /// ```rust
+        /// # use awesome::applicative::Applicative;
+        /// assert_eq!(Some(2).ap(Some(|x| x + 1)), Some(3));
+        /// ```
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U;
+    }
+
+    /// Applicative implementation for `Option<T>`.
+    impl<T> Applicative for Option<T> {
+        fn pure(x: Self::T) -> Self {
+            Some(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            match (f, self) {
+                (Some(f), Some(x)) => Some(f(x)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Applicative implementation for `Result<T, E>`.
+    ///
+    /// if both are `Err`, the error of the function comes first.
+    impl<T, E> Applicative for Result<T, E> {
+        fn pure(x: Self::T) -> Self {
+            Ok(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            match (f, self) {
+                (Ok(f), Ok(x)) => Ok(f(x)),
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            }
+        }
+    }
+
+    /// Applicative implementation for `Vec<T>`, every function is applied to every element (cartesian product).
+    ///
+    /// ```rust
+    /// # use awesome::applicative::Applicative;
+    /// assert_eq!(vec![1, 2].ap(vec![|x| x + 10, |x| x * 10]), vec![11, 12, 10, 20]);
+    /// ```
+    impl<T: Clone> Applicative for Vec<T> {
+        fn pure(x: Self::T) -> Self {
+            vec![x]
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            f.into_iter().flat_map(|f| self.iter().cloned().map(f).collect::<Vec<_>>()).collect()
+        }
+    }
+
+    /// A monoid on applicative functors, Haskell's `Alternative` (and `MonadPlus`).
+    ///
+    /// `mzero` is a failed computation, `mplus` combines two computations (for example, choose the first success).
+    pub trait Alternative: Applicative {
+        /// The identity of `mplus`, for example `None` or `vec![]`.
+        fn mzero() -> Self;
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::applicative::Alternative;
+        /// assert_eq!(None.mplus(Some(2)), Some(2));
+        /// assert_eq!(vec![1].mplus(vec![2, 3]), vec![1, 2, 3]);
+        /// ```
+        fn mplus(self, other: Self) -> Self;
+    }
+
+    /// The first `Some` wins.
+    impl<T> Alternative for Option<T> {
+        fn mzero() -> Self {
+            None
+        }
+
+        fn mplus(self, other: Self) -> Self {
+            self.or(other)
+        }
+    }
+
+    /// Both results are kept (concatenation).
+    impl<T: Clone> Alternative for Vec<T> {
+        fn mzero() -> Self {
+            Vec::new()
+        }
+
+        fn mplus(mut self, other: Self) -> Self {
+            self.extend(other);
+            self
+        }
+    }
+
+    /// Returns `pure(())` if `cond` is true, otherwise `mzero()`.
+    ///
+    /// inside `do_monad!`, use the `guard cond;` statement instead, the type is inferred there.
+    ///
+    /// ```rust
+    /// # use awesome::applicative::guard;
+    /// assert_eq!(guard::<Option<()>>(true), Some(()));
+    /// assert_eq!(guard::<Vec<()>>(false), vec![]);
+    /// ```
+    pub fn guard<M>(cond: bool) -> M
+    where
+        M: Alternative<T = ()>,
+    {
+        if cond {
+            M::pure(())
+        } else {
+            M::mzero()
+        }
+    }
+
+    /// Combines two independent effects with a binary function.
+    ///
+    /// ```rust
+    /// # use awesome::applicative::lift2;
+    /// assert_eq!(lift2(Some(1), Some(2), |a, b| a + b), Some(3));
+    /// assert_eq!(lift2(vec![1, 2], vec![10, 20], |a, b| a + b), vec![11, 21, 12, 22]);
+    /// ```
+    pub fn lift2<'a, A, B, C, F>(a: A, b: B, f: F) -> B::Wrapped<C>
+    where
+        A: Functor<Wrapped<Curried<'a, B::T, C>> = B::Wrapped<Curried<'a, B::T, C>>>,
+        A::T: Clone + 'a,
+        B: Applicative,
+        F: Fn(A::T, B::T) -> C + Clone + 'a,
+    {
+        b.ap(a.fmap(|x| {
+            let f = f.clone();
+            Box::new(move |y| f(x.clone(), y)) as Curried<'a, B::T, C>
+        }))
+    }
+
+    /// Combines three independent effects with a ternary function.
+    ///
+    /// ```rust
+    /// # use awesome::applicative::lift3;
+    /// assert_eq!(lift3(Some(1), Some(2), Some(3), |a, b, c| a + b + c), Some(6));
+    /// ```
+    pub fn lift3<'a, A, B, C, D, F>(a: A, b: B, c: C, f: F) -> C::Wrapped<D>
+    where
+        A: Functor<Wrapped<Curried<'a, B::T, Curried<'a, C::T, D>>> = B::Wrapped<Curried<'a, B::T, Curried<'a, C::T, D>>>>,
+        A::T: Clone + 'a,
+        B: Applicative<Wrapped<Curried<'a, C::T, D>> = C::Wrapped<Curried<'a, C::T, D>>>,
+        B::T: Clone + 'a,
+        C: Applicative,
+        F: Fn(A::T, B::T, C::T) -> D + Clone + 'a,
+    {
+        c.ap(lift2(a, b, move |x: A::T, y: B::T| {
+            let f = f.clone();
+            Box::new(move |z| f(x.clone(), y.clone(), z)) as Curried<'a, C::T, D>
+        }))
+    }
+}
+
+/// # Monad
+///
+/// Monad is a typical functional programming languages. See [here](https://en.wikipedia.org/wiki/Monad_(functional_programming)) for more details.
+pub mod monad {
+    use crate::{applicative::Applicative, functor::Functor, monoid::Monoid};
+    #[cfg(feature = "async")]
+    use futures::future::{self, FutureExt};
+    use std::rc::Rc;
+    #[cfg(feature = "async")]
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// A simple monad implementation.
+    ///
+    /// It takes types `T` and `E`, which can be implemented in `Option<T>`, `Result<T, E>`, etc.
+    ///
+    /// `Monad` builds on [`Applicative`] (and `Functor`), the inner type `T` and the generic associated type `Wrapped<U>` come from `Functor`.
+    /// `Wrapped<U>` lets `bind` change the inner type, for example `Option<T>` becomes `Option<U>`.
+    ///
+    /// the bound function is `FnMut` because some monads call it more than once, for example `Vec<T>` calls it for every element.
+    ///
+    /// `State`, `Reader`, `Cont` and `BoxFuture` store the bound function, which needs bounds this trait doesn't have,
+    /// so they are not instances: they have their own inherent `pure` and `bind` with the same shape.
+    pub trait Monad: Applicative {
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::monad::Monad;
+        /// assert_eq!(Some(2).bind(|x| Some(x + 1)), Some(3));
+        /// assert_eq!(Some(10).bind(|x| Some(x.to_string())), Some("10".to_string()));
+        /// ```
+        fn bind<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>;
+
+        /// Sequences two computations, discarding the value of the first one, Haskell's `>>`.
+        ///
+        /// `next` is cloned because `bind` may call the bound function more than once.
+        ///
+        /// ```rust
+        /// # use awesome::monad::Monad;
+        /// assert_eq!(Some(1).then(Some("next")), Some("next"));
+        /// assert_eq!(None::<i32>.then(Some("next")), None);
+        /// ```
+        fn then<U>(self, next: Self::Wrapped<U>) -> Self::Wrapped<U>
+        where
+            Self: Sized,
+            Self::Wrapped<U>: Clone,
+        {
+            self.bind(|_| next.clone())
+        }
+    }
+
+    /// Flattens a nested monad, `join(m) == m.bind(|x| x)`.
+    ///
+    /// ```rust
+    /// # use awesome::monad::join;
+    /// assert_eq!(join(Some(Some(3))), Some(3));
+    /// assert_eq!(join(Some(None::<i32>)), None);
+    /// ```
+    pub fn join<M, N>(m: M) -> N
+    where
+        M: Monad<T = N, Wrapped<N::T> = N>,
+        N: Functor,
+    {
+        m.bind::<N::T, _>(|x| x)
+    }
+
+    /// Kleisli composition, composes `A -> M<B>` with `B -> M<C>` into `A -> M<C>`, Haskell's `>=>`.
+    ///
+    /// ```rust
+    /// # use awesome::monad::kleisli;
+    /// let parse = |s: &str| s.parse::<i32>().ok();
+    /// let half = |x: i32| if x % 2 == 0 { Some(x / 2) } else { None };
+    /// let mut parse_half = kleisli(parse, half);
+    ///
+    /// assert_eq!(parse_half("10"), Some(5));
+    /// assert_eq!(parse_half("3"), None);
+    /// ```
+    pub fn kleisli<A, B, C, M, F, G>(mut f: F, mut g: G) -> impl FnMut(A) -> M::Wrapped<C>
+    where
+        M: Monad<T = B>,
+        F: FnMut(A) -> M,
+        G: FnMut(B) -> M::Wrapped<C>,
+    {
+        move |x| f(x).bind(&mut g)
+    }
+
+    /// Monad implementation for `Option<T>`.
+    impl<T> Monad for Option<T> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            match self {
+                Some(x) => f(x),
+                None => None,
+            }
+        }
+    }
+
+    /// Monad implementation for `Result<T, E>`.
+    ///
+    /// `bind` short-circuits on `Err`, the error is passed through without calling `f`.
+    impl<T, E> Monad for Result<T, E> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            match self {
+                Ok(x) => f(x),
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    /// Monad implementation for `Vec<T>`, `bind` is `flat_map`.
+    ///
+    /// it models nondeterminism, every element is a possible result.
+    ///
+    /// ```rust
+    /// # use awesome::monad::Monad;
+    /// assert_eq!(vec![1, 2].bind(|x| vec![x, x * 10]), vec![1, 10, 2, 20]);
+    /// ```
+    impl<T: Clone> Monad for Vec<T> {
+        fn bind<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            self.into_iter().flat_map(f).collect()
+        }
+    }
+
+    /// Generic checks for the monad laws, any `Monad` implementation can call them in its tests.
+    ///
+    /// each check takes a generator of sample values (anything `IntoIterator`, like a range or a `Vec`),
+    /// and returns `true` if the law holds for every sample.
+    ///
+    /// ```rust
+    /// # use awesome::monad::laws::*;
+    /// assert!(check_left_identity::<Option<i32>, _>(0..100, |x| Some(x * 2)));
+    /// assert!(check_right_identity((0..100).map(Some)));
+    /// assert!(check_associativity((0..100).map(Some), |x| Some(x + 1), |x| Some(x * 2)));
+    /// ```
+    pub mod laws {
+        use super::Monad;
+
+        /// `pure(a).bind(f) == f(a)`
+        pub fn check_left_identity<M, U>(values: impl IntoIterator<Item = M::T>, mut f: impl FnMut(M::T) -> M::Wrapped<U>) -> bool
+        where
+            M: Monad,
+            M::T: Clone,
+            M::Wrapped<U>: PartialEq,
+        {
+            values.into_iter().all(|a| M::pure(a.clone()).bind(&mut f) == f(a))
+        }
+
+        /// `m.bind(pure) == m`
+        pub fn check_right_identity<M, A>(values: impl IntoIterator<Item = M>) -> bool
+        where
+            M: Monad<T = A, Wrapped<A> = M> + Clone + PartialEq,
+        {
+            values.into_iter().all(|m| m.clone().bind::<A, _>(M::pure) == m)
+        }
+
+        /// `m.bind(f).bind(g) == m.bind(|x| f(x).bind(g))`
+        pub fn check_associativity<M, U, V>(values: impl IntoIterator<Item = M>, mut f: impl FnMut(M::T) -> M::Wrapped<U>, mut g: impl FnMut(U) -> M::Wrapped<V>) -> bool
+        where
+            M: Monad + Clone,
+            M::Wrapped<U>: Monad<T = U, Wrapped<V> = M::Wrapped<V>>,
+            M::Wrapped<V>: PartialEq,
+        {
+            values
+                .into_iter()
+                .all(|m| m.clone().bind(&mut f).bind::<V, _>(&mut g) == m.bind(|x| f(x).bind::<V, _>(&mut g)))
+        }
+    }
+
+    /// Haskell-style `do` notation, desugars into nested `bind` calls.
+    ///
+    /// - `x <- m;` binds the inner value of `m` to `x`.
+    /// - `_ <- m;` runs `m` and discards its value.
+    /// - `let x = e;` is a plain `let` binding.
+    /// - `guard cond;` stops the computation with `Alternative::mzero` unless `cond` holds, so `guard` is reserved.
+    /// - `return e` wraps `e` using `Applicative::pure`.
+    /// - the last statement can also be a monadic expression.
+    ///
+    /// ```rust
+    /// # use awesome::do_monad;
+    /// let result = do_monad! {
+    ///     x <- Some(1);
+    ///     y <- Some(2);
+    ///     return x + y
+    /// };
+    ///
+    /// assert_eq!(result, Some(3));
+    /// ```
+    #[macro_export]
+    macro_rules! do_monad {
+        (return $e:expr) => {
+            $crate::applicative::Applicative::pure($e)
+        };
+        (guard $c:expr; $($rest:tt)*) => {
+            if $c {
+                $crate::do_monad!($($rest)*)
+            } else {
+                $crate::applicative::Alternative::mzero()
+            }
+        };
+        (_ <- $m:expr; $($rest:tt)*) => {
+            $crate::monad::Monad::bind($m, move |_| $crate::do_monad!($($rest)*))
+        };
+        ($x:ident <- $m:expr; $($rest:tt)*) => {
+            $crate::monad::Monad::bind($m, move |$x| $crate::do_monad!($($rest)*))
+        };
+        (let $p:pat = $e:expr; $($rest:tt)*) => {{
+            let $p = $e;
+            $crate::do_monad!($($rest)*)
+        }};
+        ($m:expr) => {
+            $m
+        };
+    }
+
+    /// The state monad, a computation that takes a state `S` and returns a value `A` with a new state.
+    ///
+    /// It cannot implement the `Monad` trait because the bound function has to be boxed (`'static`),
+    /// so `bind` and `pure` are inherent methods.
+    ///
+    /// ```rust
+    /// # use awesome::monad::State;
+    /// let program = State::get().bind(|n: usize| State::put(n + 1).bind(move |_| State::pure(n * 10)));
+    ///
+    /// assert_eq!(program.run_state(4), (40, 5));
+    /// ```
+    pub struct State<S, A> {
+        run: Box<dyn FnOnce(S) -> (A, S)>,
+    }
+
+    impl<S: 'static, A: 'static> State<S, A> {
+        /// Creates a state computation from a function.
+        pub fn new<F>(f: F) -> Self
+        where
+            F: FnOnce(S) -> (A, S) + 'static,
+        {
+            State { run: Box::new(f) }
+        }
+
+        /// Returns `x` without touching the state.
+        pub fn pure(x: A) -> Self {
+            State::new(move |s| (x, s))
+        }
+
+        /// Runs the computation with the initial state, returns the value and the final state.
+        pub fn run_state(self, s: S) -> (A, S) {
+            (self.run)(s)
+        }
+
+        /// Runs this computation, then passes its value to `f` and runs the returned computation with the new state.
+        pub fn bind<B, F>(self, f: F) -> State<S, B>
+        where
+            B: 'static,
+            F: FnOnce(A) -> State<S, B> + 'static,
+        {
+            State::new(move |s| {
+                let (x, s) = self.run_state(s);
+                f(x).run_state(s)
+            })
+        }
+    }
+
+    impl<S: Clone + 'static> State<S, S> {
+        /// Returns the current state as the value.
+        pub fn get() -> Self {
+            State::new(|s: S| (s.clone(), s))
+        }
+    }
+
+    impl<S: 'static> State<S, ()> {
+        /// Replaces the state.
+        pub fn put(s: S) -> Self {
+            State::new(move |_| ((), s))
+        }
+
+        /// Updates the state with `f`.
+        pub fn modify<F>(f: F) -> Self
+        where
+            F: FnOnce(S) -> S + 'static,
+        {
+            State::new(move |s| ((), f(s)))
+        }
+    }
+
+    /// The writer monad, a value `A` with a log `W` accumulated using [`Monoid`].
+    ///
+    /// ```rust
+    /// # use awesome::{monad::{Monad, Writer}, functor::Functor};
+    /// let program = Writer::tell(vec!["start"]).bind(|_| Writer::new(10, vec!["ten"])).fmap(|x| x * 2);
+    ///
+    /// assert_eq!(program.run_writer(), (20, vec!["start", "ten"]));
+    /// ```
+    pub struct Writer<W, A> {
+        value: A,
+        log: W,
+    }
+
+    impl<W, A> Writer<W, A> {
+        /// Creates a writer with a value and a log.
+        pub fn new(value: A, log: W) -> Self {
+            Writer { value, log }
+        }
+
+        /// Returns the value and the accumulated log.
+        pub fn run_writer(self) -> (A, W) {
+            (self.value, self.log)
+        }
+    }
+
+    impl<W> Writer<W, ()> {
+        /// Appends `log` without producing a value.
+        pub fn tell(log: W) -> Self {
+            Writer::new((), log)
+        }
+    }
+
+    /// Functor implementation for `Writer<W, A>`, the log is kept as it is.
+    impl<W, A> Functor for Writer<W, A> {
+        type T = A;
+        type Wrapped<U> = Writer<W, U>;
+
+        fn fmap<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            Writer::new(f(self.value), self.log)
+        }
+    }
+
+    /// Applicative implementation for `Writer<W, A>`, the log of the function comes first.
+    impl<W: Monoid, A> Applicative for Writer<W, A> {
+        fn pure(x: Self::T) -> Self {
+            Writer::new(x, W::mempty())
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            let (f, log) = f.run_writer();
+
+            Writer::new(f(self.value), log.combine(self.log))
+        }
+    }
+
+    /// Monad implementation for `Writer<W, A>`, the logs are combined in order.
+    impl<W: Monoid, A> Monad for Writer<W, A> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            let (value, log) = f(self.value).run_writer();
+
+            Writer::new(value, self.log.combine(log))
+        }
+    }
+
+    /// The reader monad, a computation that reads a shared environment `R`, useful for dependency injection.
+    ///
+    /// The environment is passed by reference, so it is shared by every computation without cloning.
+    /// like `State`, `bind` is an inherent method.
+    ///
+    /// ```rust
+    /// # use awesome::monad::Reader;
+    /// let greeting = Reader::asks(|name: &String| format!("hello, {name}"));
+    ///
+    /// assert_eq!(greeting.run_reader(&"world".to_string()), "hello, world");
+    /// ```
+    pub struct Reader<R, A> {
+        run: Box<dyn FnOnce(&R) -> A>,
+    }
+
+    impl<R: 'static, A: 'static> Reader<R, A> {
+        /// Creates a reader computation from a function.
+        pub fn new<F>(f: F) -> Self
+        where
+            F: FnOnce(&R) -> A + 'static,
+        {
+            Reader { run: Box::new(f) }
+        }
+
+        /// Returns `x` ignoring the environment.
+        pub fn pure(x: A) -> Self {
+            Reader::new(move |_| x)
+        }
+
+        /// Reads a part of the environment.
+        pub fn asks<F>(f: F) -> Self
+        where
+            F: FnOnce(&R) -> A + 'static,
+        {
+            Reader::new(f)
+        }
+
+        /// Runs the computation with the environment.
+        pub fn run_reader(self, r: &R) -> A {
+            (self.run)(r)
+        }
+
+        /// Runs this computation, then passes its value to `f` and runs the returned computation with the same environment.
+        pub fn bind<B, F>(self, f: F) -> Reader<R, B>
+        where
+            B: 'static,
+            F: FnOnce(A) -> Reader<R, B> + 'static,
+        {
+            Reader::new(move |r| f(self.run_reader(r)).run_reader(r))
+        }
+
+        /// Runs this computation with an environment modified by `f`.
+        pub fn local<F>(self, f: F) -> Self
+        where
+            F: FnOnce(&R) -> R + 'static,
+        {
+            Reader::new(move |r| self.run_reader(&f(r)))
+        }
+    }
+
+    impl<R: Clone + 'static> Reader<R, R> {
+        /// Returns the whole environment.
+        pub fn ask() -> Self {
+            Reader::new(R::clone)
+        }
+    }
+
+    /// A continuation that receives the value `A` and produces the final result `R`.
+    ///
+    /// it is reference counted because `call_cc` may call the same continuation from several places.
+    pub type Continuation<R, A> = Rc<dyn Fn(A) -> R>;
+
+    /// The continuation monad, a computation in continuation-passing style (CPS).
+    ///
+    /// Instead of returning `A`, the computation is given "the rest of the program" as a continuation and calls it with `A`.
+    ///
+    /// ```rust
+    /// # use awesome::monad::Cont;
+    /// let program = Cont::pure(20).bind(|x| Cont::pure(x + 1)).bind(|x| Cont::pure(x * 2));
+    ///
+    /// assert_eq!(program.run_cont(|x| x.to_string()), "42");
+    /// ```
+    pub struct Cont<R, A> {
+        run: Box<dyn FnOnce(Continuation<R, A>) -> R>,
+    }
+
+    impl<R: 'static, A: 'static> Cont<R, A> {
+        /// Creates a computation from a function that receives the continuation.
+        pub fn new<F>(f: F) -> Self
+        where
+            F: FnOnce(Continuation<R, A>) -> R + 'static,
+        {
+            Cont { run: Box::new(f) }
+        }
+
+        /// Passes `x` to the continuation.
+        pub fn pure(x: A) -> Self {
+            Cont::new(move |k| k(x))
+        }
+
+        /// Runs the computation with the final continuation `k`.
+        pub fn run_cont<K>(self, k: K) -> R
+        where
+            K: Fn(A) -> R + 'static,
+        {
+            (self.run)(Rc::new(k))
+        }
+
+        /// Continues with the computation returned by `f`.
+        ///
+        /// `f` is `Fn` because the continuation captured by `call_cc` can be resumed more than once.
+        pub fn bind<B, F>(self, f: F) -> Cont<R, B>
+        where
+            B: 'static,
+            F: Fn(A) -> Cont<R, B> + 'static,
+        {
+            Cont::new(move |k: Continuation<R, B>| (self.run)(Rc::new(move |x| (f(x).run)(k.clone()))))
+        }
+
+        /// Call with current continuation, `f` receives an [`Escape`] which jumps straight to the continuation of `call_cc`,
+        /// skipping the rest of the computation built inside `f`.
+        ///
+        /// ```rust
+        /// # use awesome::monad::Cont;
+        /// let program = Cont::call_cc(|escape| escape.exit(1).bind(|()| Cont::pure(2)));
+        ///
+        /// assert_eq!(program.eval_cont(), 1);
+        /// ```
+        pub fn call_cc<F>(f: F) -> Self
+        where
+            F: FnOnce(Escape<R, A>) -> Cont<R, A> + 'static,
+        {
+            Cont::new(move |k: Continuation<R, A>| (f(Escape(k.clone())).run)(k))
+        }
+    }
+
+    impl<A: 'static> Cont<A, A> {
+        /// Runs the computation with the identity continuation.
+        pub fn eval_cont(self) -> A {
+            self.run_cont(|x| x)
+        }
+    }
+
+    /// The escape continuation given by [`Cont::call_cc`].
+    pub struct Escape<R, A>(Continuation<R, A>);
+
+    impl<R: 'static, A: 'static> Escape<R, A> {
+        /// Exits with `x`, the continuation of this computation is discarded.
+        pub fn exit<B: 'static>(&self, x: A) -> Cont<R, B> {
+            let k = self.0.clone();
+
+            Cont::new(move |_| k(x))
+        }
+    }
+
+    /// A boxed future with monad-shaped methods, `bind` chains an asynchronous computation using `FutureExt::then`.
+    ///
+    /// it doesn't implement `Functor`, `Applicative` or `Monad`: the future stores the bound function, so it must be `Send + 'a`,
+    /// and the trait methods can't require that. like `State`, `pure`, `fmap` and `bind` are inherent methods instead.
+    /// requires the `async` feature.
+    ///
+    /// ```rust
+    /// # use awesome::monad::BoxFuture;
+    /// let future = BoxFuture::pure(20).bind(|x| BoxFuture::new(async move { x + 1 })).fmap(|x| x * 2);
+    ///
+    /// assert_eq!(futures::executor::block_on(future), 42);
+    /// ```
+    #[cfg(feature = "async")]
+    pub struct BoxFuture<'a, T>(Pin<Box<dyn Future<Output = T> + Send + 'a>>);
+
+    #[cfg(feature = "async")]
+    impl<'a, T: Send + 'a> BoxFuture<'a, T> {
+        pub fn new<F>(future: F) -> Self
+        where
+            F: Future<Output = T> + Send + 'a,
+        {
+            BoxFuture(Box::pin(future))
+        }
+
+        /// A future that is immediately ready with `x`.
+        pub fn pure(x: T) -> Self {
+            BoxFuture::new(future::ready(x))
+        }
+
+        /// Awaits this future, then awaits the future returned by `f`.
+        pub fn bind<U, F>(self, f: F) -> BoxFuture<'a, U>
+        where
+            U: Send + 'a,
+            F: FnOnce(T) -> BoxFuture<'a, U> + Send + 'a,
+        {
+            BoxFuture::new(self.then(f))
+        }
+
+        pub fn fmap<U, F>(self, f: F) -> BoxFuture<'a, U>
+        where
+            U: Send + 'a,
+            F: FnOnce(T) -> U + Send + 'a,
+        {
+            BoxFuture::new(self.map(f))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl<T> Future for BoxFuture<'_, T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.0.as_mut().poll(cx)
+        }
+    }
+
+    /// The identity monad, it just wraps a value without any effect.
+    ///
+    /// useful as the base of a monad transformer stack.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Identity<A>(pub A);
+
+    /// Functor implementation for `Identity<A>`.
+    impl<A> Functor for Identity<A> {
+        type T = A;
+        type Wrapped<U> = Identity<U>;
+
+        fn fmap<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            Identity(f(self.0))
+        }
+    }
+
+    /// Applicative implementation for `Identity<A>`.
+    impl<A> Applicative for Identity<A> {
+        fn pure(x: Self::T) -> Self {
+            Identity(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            Identity((f.0)(self.0))
+        }
+    }
+
+    /// Monad implementation for `Identity<A>`.
+    impl<A> Monad for Identity<A> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            f(self.0)
+        }
+    }
+
+    /// The option monad transformer, it adds the short-circuiting of `Option` to any monad `M`.
+    ///
+    /// `OptionT<M>` wraps `M<Option<A>>`, for example `OptionT<Identity<Option<A>>>` or `OptionT<Writer<W, Option<A>>>`.
+    /// the methods are inherent because they need extra bounds on `M::Wrapped<U>`, which the GAT of `Functor` cannot express.
+    ///
+    /// ```rust
+    /// # use awesome::monad::{Identity, OptionT};
+    /// let program = OptionT::lift(Identity(1)).bind(|x| OptionT(Identity(Some(x + 1))));
+    ///
+    /// assert_eq!(program.run_option_t(), Identity(Some(2)));
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OptionT<M>(pub M);
+
+    impl<M, A> OptionT<M>
+    where
+        M: Monad<T = Option<A>>,
+    {
+        /// Lifts a computation of the inner monad, the value is wrapped with `Some`.
+        pub fn lift<N>(m: N) -> Self
+        where
+            N: Functor<T = A, Wrapped<Option<A>> = M>,
+        {
+            OptionT(m.fmap(Some))
+        }
+
+        /// Wraps `x` as `pure(Some(x))`.
+        pub fn pure(x: A) -> Self {
+            OptionT(M::pure(Some(x)))
+        }
+
+        /// Returns the inner monad.
+        pub fn run_option_t(self) -> M {
+            self.0
+        }
+
+        /// Binds the value inside `Some`, `None` short-circuits while the effects of `M` are kept.
+        pub fn bind<U, F>(self, mut f: F) -> OptionT<M::Wrapped<Option<U>>>
+        where
+            F: FnMut(A) -> OptionT<M::Wrapped<Option<U>>>,
+        {
+            OptionT(self.0.bind(move |x| match x {
+                Some(x) => f(x).0,
+                // `M::Wrapped<Option<U>>` has no `pure`, so `pure(None)` is made from `M` and mapped.
+                None => M::pure(None).fmap(|_| None),
+            }))
+        }
+    }
+}
+
+/// # Comonad
+///
+/// A comonad is the dual of a monad, a value in a context where a value can always be extracted. See [here](https://hackage.haskell.org/package/comonad) for more details.
+pub mod comonad {
+    use crate::functor::Functor;
+
+    /// A simple comonad implementation.
+    ///
+    /// `extend` calls `f` with each "context" of the structure, for example each position of a zipper.
+    /// it must satisfy `w.extend(|w| w.extract().clone()) == w`.
+    pub trait Comonad: Functor {
+        /// Returns the value in focus, the dual of `pure`.
+        fn extract(&self) -> &Self::T;
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::comonad::{Comonad, NonEmptyVec};
+        /// let sums = NonEmptyVec::new(1, vec![2, 3]).extend(|w| w.iter().sum::<i32>());
+        ///
+        /// assert_eq!(sums, NonEmptyVec::new(6, vec![5, 3]));
+        /// ```
+        fn extend<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(&Self) -> U;
+    }
+
+    /// A vector with at least one element, so `extract` is always possible, it is [`NonEmpty`](crate::nonempty::NonEmpty).
+    pub use crate::nonempty::NonEmpty as NonEmptyVec;
+
+    /// Comonad implementation for `NonEmptyVec<T>`, `f` is called with every non-empty suffix.
+    ///
+    /// each suffix is a new `NonEmptyVec`, so the elements are cloned.
+    impl<T: Clone> Comonad for NonEmptyVec<T> {
+        fn extract(&self) -> &Self::T {
+            self.head()
+        }
+
+        fn extend<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(&Self) -> U,
+        {
+            let head = f(&self);
+            let tail = (0..self.len() - 1)
+                .map(|i| f(&NonEmptyVec::new(self.tail()[i].clone(), self.tail()[i + 1..].to_vec())))
+                .collect();
+
+            NonEmptyVec::new(head, tail)
+        }
+    }
+
+    /// A zipper over a slice, the elements with a focused position.
+    ///
+    /// `extend` moves the focus over every position, so `f` can look at the neighbours of each element.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Zipper<T> {
+        items: Vec<T>,
+        focus: usize,
+    }
+
+    impl<T> Zipper<T> {
+        /// Returns `None` if `focus` is out of bounds (including an empty vector).
+        pub fn new(items: Vec<T>, focus: usize) -> Option<Self> {
+            (focus < items.len()).then_some(Zipper { items, focus })
+        }
+
+        pub fn position(&self) -> usize {
+            self.focus
+        }
+
+        /// The elements before the focus.
+        pub fn left(&self) -> &[T] {
+            &self.items[..self.focus]
+        }
+
+        /// The elements after the focus.
+        pub fn right(&self) -> &[T] {
+            &self.items[self.focus + 1..]
+        }
+
+        /// The focus and up to `radius` elements on each side.
+        pub fn window(&self, radius: usize) -> &[T] {
+            &self.items[self.focus.saturating_sub(radius)..(self.focus + radius + 1).min(self.items.len())]
+        }
+
+        /// Moves the focus to the left, returns `None` at the start.
+        pub fn move_left(self) -> Option<Self> {
+            let focus = self.focus.checked_sub(1)?;
+            Zipper::new(self.items, focus)
+        }
+
+        /// Moves the focus to the right, returns `None` at the end.
+        pub fn move_right(self) -> Option<Self> {
+            let focus = self.focus + 1;
+            Zipper::new(self.items, focus)
+        }
+
+        pub fn into_vec(self) -> Vec<T> {
+            self.items
+        }
+    }
+
+    /// Functor implementation for `Zipper<T>`, the focus is kept.
+    impl<T> Functor for Zipper<T> {
+        type T = T;
+        type Wrapped<U> = Zipper<U>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            Zipper {
+                items: self.items.into_iter().map(f).collect(),
+                focus: self.focus,
+            }
+        }
+    }
+
+    /// Comonad implementation for `Zipper<T>`, `f` is called with the zipper focused on every position.
+    ///
+    /// ```rust
+    /// # use awesome::comonad::{Comonad, Zipper};
+    /// // moving average of three elements
+    /// let averages = Zipper::new(vec![3.0, 6.0, 9.0, 12.0], 0).unwrap().extend(|z| z.window(1).iter().sum::<f64>() / z.window(1).len() as f64);
+    ///
+    /// assert_eq!(averages.into_vec(), vec![4.5, 6.0, 9.0, 10.5]);
+    /// ```
+    impl<T> Comonad for Zipper<T> {
+        fn extract(&self) -> &Self::T {
+            &self.items[self.focus]
+        }
+
+        fn extend<U, F>(mut self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(&Self) -> U,
+        {
+            let focus = self.focus;
+            let items = (0..self.items.len())
+                .map(|i| {
+                    self.focus = i;
+                    f(&self)
+                })
+                .collect();
+
+            Zipper { items, focus }
+        }
+    }
+}
+
+/// # Free Monad
+///
+/// A free monad turns any functor into a monad, so a program can be built as data and run later by an interpreter. See [here](https://wiki.haskell.org/Free_monad) for more details.
+pub mod free {
+    /// A functor family used as the instruction set of [`Free`], for example `Of<N> = Command<N>`.
+    ///
+    /// unlike `Functor`, the mapping function is boxed (`'static`), so it can be stored inside continuations like `Get(key, Box<dyn FnOnce(Value) -> N>)`.
+    /// the trait is implemented by a marker type because `Free` needs the functor "for any `N`".
+    pub trait FreeFunctor: 'static {
+        /// The functor applied to `N`.
+        type Of<N>;
+
+        /// Maps the continuation `N` of an instruction.
+        fn map<A: 'static, B: 'static>(fa: Self::Of<A>, f: Box<dyn FnOnce(A) -> B>) -> Self::Of<B>;
+    }
+
+    /// The free monad over the functor `F`.
+    ///
+    /// - `Pure(a)` is a finished program returning `a`.
+    /// - `Roll(instruction)` is an instruction whose continuation is the rest of the program.
+    ///
+    /// this is the naive encoding, a long chain of left-nested `bind`s is resolved recursively while the program runs.
+    pub enum Free<F: FreeFunctor, A> {
+        Pure(A),
+        Roll(Box<F::Of<Free<F, A>>>),
+    }
+
+    impl<F: FreeFunctor, A: 'static> Free<F, A> {
+        pub fn pure(x: A) -> Self {
+            Free::Pure(x)
+        }
+
+        /// Lifts a single instruction into a program.
+        pub fn lift_f(instruction: F::Of<A>) -> Self {
+            Free::Roll(Box::new(F::map(instruction, Box::new(Free::Pure))))
+        }
+
+        /// Appends the program returned by `f` to the end of this program.
+        pub fn bind<B, G>(self, f: G) -> Free<F, B>
+        where
+            B: 'static,
+            G: FnOnce(A) -> Free<F, B> + 'static,
+        {
+            match self {
+                Free::Pure(x) => f(x),
+                Free::Roll(instruction) => Free::Roll(Box::new(F::map(*instruction, Box::new(move |next: Free<F, A>| next.bind(f))))),
+            }
+        }
+
+        pub fn map<B, G>(self, f: G) -> Free<F, B>
+        where
+            B: 'static,
+            G: FnOnce(A) -> B + 'static,
+        {
+            self.bind(move |x| Free::Pure(f(x)))
+        }
+    }
+
+    /// An example DSL, commands of a key-value store and two interpreters.
+    ///
+    /// ```rust
+    /// # use awesome::free::kv::*;
+    /// # use std::collections::HashMap;
+    /// let program = put("lang", "rust").bind(|()| get("lang"));
+    ///
+    /// assert_eq!(run_in_memory(program, &mut HashMap::new()), Some("rust".to_string()));
+    /// ```
+    pub mod kv {
+        use super::{Free, FreeFunctor};
+        use std::collections::HashMap;
+
+        /// The commands, `N` is the rest of the program.
+        pub enum Command<N> {
+            Put(String, String, N),
+            Get(String, Box<dyn FnOnce(Option<String>) -> N>),
+            Delete(String, N),
+        }
+
+        /// The marker type of the `Command` functor.
+        pub struct Kv;
+
+        impl FreeFunctor for Kv {
+            type Of<N> = Command<N>;
+
+            fn map<A: 'static, B: 'static>(fa: Self::Of<A>, f: Box<dyn FnOnce(A) -> B>) -> Self::Of<B> {
+                match fa {
+                    Command::Put(key, value, next) => Command::Put(key, value, f(next)),
+                    Command::Get(key, next) => Command::Get(key, Box::new(move |value| f(next(value)))),
+                    Command::Delete(key, next) => Command::Delete(key, f(next)),
+                }
+            }
+        }
+
+        /// A program of the key-value store.
+        pub type Program<A> = Free<Kv, A>;
+
+        pub fn put(key: &str, value: &str) -> Program<()> {
+            Free::lift_f(Command::Put(key.to_string(), value.to_string(), ()))
+        }
+
+        pub fn get(key: &str) -> Program<Option<String>> {
+            Free::lift_f(Command::Get(key.to_string(), Box::new(|value| value)))
+        }
+
+        pub fn delete(key: &str) -> Program<()> {
+            Free::lift_f(Command::Delete(key.to_string(), ()))
+        }
+
+        /// Runs the program against a `HashMap`.
+        pub fn run_in_memory<A>(program: Program<A>, store: &mut HashMap<String, String>) -> A {
+            run_logging(program, store).0
+        }
+
+        /// Runs the program against a `HashMap`, and records every executed command.
+        pub fn run_logging<A>(mut program: Program<A>, store: &mut HashMap<String, String>) -> (A, Vec<String>) {
+            let mut log = Vec::new();
+
+            // a loop instead of recursion, the interpreter itself does not grow the stack.
+            loop {
+                program = match program {
+                    Free::Pure(x) => return (x, log),
+                    Free::Roll(command) => match *command {
+                        Command::Put(key, value, next) => {
+                            log.push(format!("put {key} = {value}"));
+                            store.insert(key, value);
+                            next
+                        }
+                        Command::Get(key, next) => {
+                            let value = store.get(&key).cloned();
+                            log.push(format!("get {key} -> {value:?}"));
+                            next(value)
+                        }
+                        Command::Delete(key, next) => {
+                            log.push(format!("delete {key}"));
+                            store.remove(&key);
+                            next
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// # Traverse
+///
+/// `traverse` maps each element to an effect and collects the results inside the effect, `sequence` does the same without mapping.
+/// for example, a list of `Option`s becomes an `Option` of a list.
+pub mod traverse {
+    use crate::functor::Functor;
+
+    /// An applicative effect that can collect a sequence of itself, `[M<T>] -> M<[T]>`.
+    pub trait Sequence: Functor + Sized {
+        /// Collects the effects in order.
+        fn sequence_iter<I>(iter: I) -> Self::Wrapped<Vec<Self::T>>
+        where
+            I: IntoIterator<Item = Self>;
+    }
+
+    /// Stops at the first `None`.
+    impl<T> Sequence for Option<T> {
+        fn sequence_iter<I>(iter: I) -> Self::Wrapped<Vec<Self::T>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            iter.into_iter().collect()
+        }
+    }
+
+    /// Stops at the first `Err`.
+    impl<T, E> Sequence for Result<T, E> {
+        fn sequence_iter<I>(iter: I) -> Self::Wrapped<Vec<Self::T>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            iter.into_iter().collect()
+        }
+    }
+
+    /// Every combination of the elements (cartesian product), like the `Applicative` of `Vec<T>`.
+    impl<T: Clone> Sequence for Vec<T> {
+        fn sequence_iter<I>(iter: I) -> Self::Wrapped<Vec<Self::T>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            iter.into_iter().fold(vec![Vec::new()], |acc, xs| {
+                acc.into_iter()
+                    .flat_map(|prefix| {
+                        xs.iter().map(move |x| {
+                            let mut prefix = prefix.clone();
+                            prefix.push(x.clone());
+                            prefix
+                        })
+                    })
+                    .collect()
+            })
+        }
+    }
+
+    /// A trait consists of `traverse` and `sequence`, implemented for everything that can be iterated (`Vec`, arrays, iterators, etc.).
+    pub trait Traverse: IntoIterator + Sized {
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::traverse::Traverse;
+        /// assert_eq!(vec!["1", "2"].traverse(|s| s.parse::<i32>().ok()), Some(vec![1, 2]));
+        /// assert_eq!(vec!["1", "x"].traverse(|s| s.parse::<i32>().ok()), None);
+        /// ```
+        fn traverse<M, F>(self, f: F) -> M::Wrapped<Vec<M::T>>
+        where
+            M: Sequence,
+            F: FnMut(Self::Item) -> M,
+        {
+            M::sequence_iter(self.into_iter().map(f))
+        }
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::traverse::Traverse;
+        /// assert_eq!(vec![Some(1), Some(2)].sequence(), Some(vec![1, 2]));
+        /// ```
+        fn sequence<M>(self) -> M::Wrapped<Vec<M::T>>
+        where
+            Self: IntoIterator<Item = M>,
+            M: Sequence,
+        {
+            M::sequence_iter(self)
+        }
+    }
+
+    impl<I: IntoIterator> Traverse for I {}
+}
+
+/// # Validated
+///
+/// `Validated` is like `Result`, but combining values with `ap` / `map2` collects every error instead of stopping at the first one.
+/// useful to validate a form with multiple fields.
+pub mod validated {
+    use crate::{applicative::Applicative, functor::Functor, monoid::Monoid, traverse::Sequence};
+
+    /// The errors `E` are accumulated with [`Monoid`], for example `Vec<E>` or `String`.
+    ///
+    /// it is an `Applicative` but not a `Monad`, `bind` would need the first value, so it could not accumulate the errors of both sides.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Validated<E, A> {
+        Valid(A),
+        Invalid(E),
+    }
+
+    impl<E, A> Validated<E, A> {
+        pub fn is_valid(&self) -> bool {
+            matches!(self, Validated::Valid(_))
+        }
+
+        pub fn into_result(self) -> Result<A, E> {
+            self.into()
+        }
+    }
+
+    impl<E, A> Validated<Vec<E>, A> {
+        /// An invalid value with a single error.
+        pub fn fail(e: E) -> Self {
+            Validated::Invalid(vec![e])
+        }
+    }
+
+    impl<E: Monoid, A> Validated<E, A> {
+        /// Combines two values with `f`, if both are invalid, the errors are combined in order.
+        ///
+        /// ```rust
+        /// # use awesome::validated::Validated;
+        /// let a = Validated::<Vec<&str>, i32>::fail("a is invalid");
+        /// let b = Validated::<Vec<&str>, i32>::fail("b is invalid");
+        ///
+        /// assert_eq!(a.map2(b, |a, b| a + b), Validated::Invalid(vec!["a is invalid", "b is invalid"]));
+        /// ```
+        pub fn map2<B, C, F>(self, other: Validated<E, B>, f: F) -> Validated<E, C>
+        where
+            F: FnOnce(A, B) -> C,
+        {
+            match (self, other) {
+                (Validated::Valid(a), Validated::Valid(b)) => Validated::Valid(f(a, b)),
+                (Validated::Invalid(e1), Validated::Invalid(e2)) => Validated::Invalid(e1.combine(e2)),
+                (Validated::Invalid(e), _) | (_, Validated::Invalid(e)) => Validated::Invalid(e),
+            }
+        }
+    }
+
+    impl<E, A> From<Result<A, E>> for Validated<E, A> {
+        fn from(result: Result<A, E>) -> Self {
+            match result {
+                Ok(x) => Validated::Valid(x),
+                Err(e) => Validated::Invalid(e),
+            }
+        }
+    }
+
+    impl<E, A> From<Validated<E, A>> for Result<A, E> {
+        fn from(validated: Validated<E, A>) -> Self {
+            match validated {
+                Validated::Valid(x) => Ok(x),
+                Validated::Invalid(e) => Err(e),
+            }
+        }
+    }
+
+    /// Functor implementation for `Validated<E, A>`, only the valid value is mapped.
+    impl<E, A> Functor for Validated<E, A> {
+        type T = A;
+        type Wrapped<U> = Validated<E, U>;
+
+        fn fmap<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            match self {
+                Validated::Valid(x) => Validated::Valid(f(x)),
+                Validated::Invalid(e) => Validated::Invalid(e),
+            }
+        }
+    }
+
+    /// Applicative implementation for `Validated<E, A>`, the errors of the function come first.
+    impl<E: Monoid, A> Applicative for Validated<E, A> {
+        fn pure(x: Self::T) -> Self {
+            Validated::Valid(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            f.map2(self, |f, x| f(x))
+        }
+    }
+
+    /// Collects every value, or every error.
+    impl<E: Monoid, A> Sequence for Validated<E, A> {
+        fn sequence_iter<I>(iter: I) -> Self::Wrapped<Vec<Self::T>>
+        where
+            I: IntoIterator<Item = Self>,
+        {
+            iter.into_iter().fold(Validated::Valid(Vec::new()), |acc, x| {
+                acc.map2(x, |mut acc, x| {
+                    acc.push(x);
+                    acc
+                })
+            })
+        }
+    }
+}
+
+/// # Either
+///
+/// `Either<L, R>` is a value of one of two types. unlike `Result`, neither side means failure,
+/// but the `Functor` / `Monad` implementations are right-biased (`Right` is mapped, `Left` short-circuits) like Haskell's `Either`.
+pub mod either {
+    use crate::{applicative::Applicative, functor::Functor, monad::Monad};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Either<L, R> {
+        Left(L),
+        Right(R),
+    }
+
+    impl<L, R> Either<L, R> {
+        pub fn is_left(&self) -> bool {
+            matches!(self, Either::Left(_))
+        }
+
+        pub fn is_right(&self) -> bool {
+            matches!(self, Either::Right(_))
+        }
+
+        pub fn left(self) -> Option<L> {
+            match self {
+                Either::Left(l) => Some(l),
+                Either::Right(_) => None,
+            }
+        }
+
+        pub fn right(self) -> Option<R> {
+            match self {
+                Either::Left(_) => None,
+                Either::Right(r) => Some(r),
+            }
+        }
+
+        pub fn map_left<M, F>(self, f: F) -> Either<M, R>
+        where
+            F: FnOnce(L) -> M,
+        {
+            self.bimap(f, |r| r)
+        }
+
+        pub fn map_right<S, F>(self, f: F) -> Either<L, S>
+        where
+            F: FnOnce(R) -> S,
+        {
+            self.bimap(|l| l, f)
+        }
+
+        /// Maps both sides, each function is called for its own side.
+        ///
+        /// ```rust
+        /// # use awesome::either::Either;
+        /// let (left, right) = (Either::<i32, &str>::Left(1), Either::<i32, &str>::Right("a"));
+        ///
+        /// assert_eq!(left.bimap(|x| x + 1, str::len), Either::Left(2));
+        /// assert_eq!(right.bimap(|x| x + 1, str::len), Either::Right(1));
+        /// ```
+        pub fn bimap<M, S, F, G>(self, f: F, g: G) -> Either<M, S>
+        where
+            F: FnOnce(L) -> M,
+            G: FnOnce(R) -> S,
+        {
+            match self {
+                Either::Left(l) => Either::Left(f(l)),
+                Either::Right(r) => Either::Right(g(r)),
+            }
+        }
+
+        /// Applies `f` or `g` depending on the side, both must return the same type.
+        pub fn either<T, F, G>(self, f: F, g: G) -> T
+        where
+            F: FnOnce(L) -> T,
+            G: FnOnce(R) -> T,
+        {
+            match self {
+                Either::Left(l) => f(l),
+                Either::Right(r) => g(r),
+            }
+        }
+
+        /// Swaps the sides, useful to use the left-biased operations with the right-biased implementations.
+        pub fn swap(self) -> Either<R, L> {
+            match self {
+                Either::Left(l) => Either::Right(l),
+                Either::Right(r) => Either::Left(r),
+            }
+        }
+    }
+
+    /// `Ok` becomes `Right` and `Err` becomes `Left`, so the bias is kept.
+    impl<L, R> From<Result<R, L>> for Either<L, R> {
+        fn from(result: Result<R, L>) -> Self {
+            match result {
+                Ok(r) => Either::Right(r),
+                Err(l) => Either::Left(l),
+            }
+        }
+    }
+
+    impl<L, R> From<Either<L, R>> for Result<R, L> {
+        fn from(either: Either<L, R>) -> Self {
+            match either {
+                Either::Left(l) => Err(l),
+                Either::Right(r) => Ok(r),
+            }
+        }
+    }
+
+    /// Functor implementation for `Either<L, R>`, the right side is mapped.
+    impl<L, R> Functor for Either<L, R> {
+        type T = R;
+        type Wrapped<U> = Either<L, U>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.map_right(f)
+        }
+    }
+
+    /// Applicative implementation for `Either<L, R>`, the first `Left` (of the function) wins.
+    impl<L, R> Applicative for Either<L, R> {
+        fn pure(x: Self::T) -> Self {
+            Either::Right(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            match (f, self) {
+                (Either::Right(f), Either::Right(x)) => Either::Right(f(x)),
+                (Either::Left(l), _) | (_, Either::Left(l)) => Either::Left(l),
+            }
+        }
+    }
+
+    /// Monad implementation for `Either<L, R>`, `Left` short-circuits.
+    impl<L, R> Monad for Either<L, R> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            match self {
+                Either::Left(l) => Either::Left(l),
+                Either::Right(r) => f(r),
+            }
+        }
+    }
+}
+
+/// # Implementing a trait for a type with macro
+///
+/// Implement a repeated `impl` using a `macro_rules`.
+pub mod impl_macro {
+    /// `Foo` trait, generic `T`
+    pub trait Foo<T> {
+        /// The return type of the function.
+        type Output;
+        /// `foo` function, `x` is a parameter of type `T`. it returns `Output`.
+        fn foo(&self, x: T) -> Self::Output;
+    }
+
+    /// The derive version of `r#impl!`, the types are listed in an attribute.
+    ///
+    /// ```rust
+    /// # use awesome::impl_macro::{Foo, FooAll};
+    /// #[derive(FooAll)]
+    /// #[foo(types(u8, u16, f64))]
+    /// struct Adder<T>(T);
+    ///
+    /// assert_eq!(Adder(1u8).foo(2), 3);
+    /// assert_eq!(Adder(0.5).foo(0.25), 0.75);
+    /// ```
+    pub use awesome_macros::FooAll;
+
+    /// The operation passed to a `combine` method of `#[impl_ops]`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Op {
+        Add,
+        Sub,
+        Mul,
+        Div,
+    }
+
+    /// Implements the four arithmetic `std::ops` and their `*Assign` variants from one `combine` method.
+    ///
+    /// ```rust
+    /// # use awesome::impl_macro::{impl_ops, Op};
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// struct Money(i64);
+    ///
+    /// #[impl_ops]
+    /// impl Money {
+    ///     fn combine(self, rhs: Self, op: Op) -> Self {
+    ///         match op {
+    ///             Op::Add => Money(self.0 + rhs.0),
+    ///             Op::Sub => Money(self.0 - rhs.0),
+    ///             Op::Mul => Money(self.0 * rhs.0),
+    ///             Op::Div => Money(self.0 / rhs.0),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut m = Money(10) + Money(5) * Money(2);
+    /// m /= Money(4);
+    /// assert_eq!(m, Money(5));
+    /// ```
+    pub use awesome_macros::impl_ops;
+
+    /// `Bar` trait, generic `T`
+    pub trait Bar<T> {
+        /// `bar` function, it compares the inner value with `x`.
+        fn bar(&self, x: T) -> bool;
+    }
+
+    pub struct MyStruct<T>(pub T);
+
+    /// Keywords cannot be used as identifiers, but can be decalred using `r#` prefix.
+    ///
+    /// the operation comes first: an operator token (`+ : usize u8`), or a closure in parentheses (`(|a, b| a ^ b) : bool`),
+    /// it is applied to `self.0` and `x`. without an operation, it adds.
+    ///
+    /// every type also gets `Foo<&$t>` (the types are `Copy`) and `Foo<Option<$t>>`, which returns `Option<$t>`.
+    ///
+    /// several traits can be implemented at once, `(Foo, Bar) for usize u8`, `Foo` adds in this form.
+    /// the traits are taken one by one (recursion), a repetition of the types cannot be nested in a repetition of the traits.
+    ///
+    /// with the `ctors;` flag in front, every type also gets the inherent `MyStruct::<$t>::zero()` and `MyStruct::<$t>::from_inner($t)`,
+    /// a type can get them only once (they would be defined twice), and `MyStruct::from_inner` needs the type, `MyStruct::<u8>::from_inner`.
+    ///
+    /// the arms are ordered so that a fragment (`$t:ty`) is only parsed when the previous arms cannot match,
+    /// a fragment that fails to parse is a hard error, not a fall through to the next arm.
+    macro_rules! r#impl {
+        (ctors; $($rest:tt)*) => {
+            r#impl! { $($rest)* }
+            r#impl! { @ctors $($rest)* }
+        };
+        (@ctors (|$a:ident, $b:ident| $e:expr) : $($t:ty)*) => {
+            r#impl! { @ctors $($t)* }
+        };
+        (@ctors $op:tt : $($t:ty)*) => {
+            r#impl! { @ctors $($t)* }
+        };
+        (@ctors ($($tr:ident),* $(,)?) for $($t:ty)*) => {
+            r#impl! { @ctors $($t)* }
+        };
+        (@ctors $($t:ty)*) => {
+            $(
+                impl MyStruct<$t> {
+                    /// The default value of the inner type, `0`, `0.0`, `false`, ...
+                    pub fn zero() -> Self {
+                        MyStruct(<$t as Default>::default())
+                    }
+
+                    pub const fn from_inner(x: $t) -> Self {
+                        MyStruct(x)
+                    }
+                }
+            )*
+        };
+        ((|$a:ident, $b:ident| $e:expr) : $($t:ty)*) => {
+            $(
+                impl Foo<$t> for MyStruct<$t> {
+                    type Output = $t;
+
+                    fn foo(&self, x: $t) -> Self::Output {
+                        // the closure parameters are annotated, so methods can be called on them.
+                        let f = |$a: $t, $b: $t| -> $t { $e };
+                        f(self.0, x)
+                    }
+                }
+
+                impl Foo<&$t> for MyStruct<$t> {
+                    type Output = $t;
+
+                    fn foo(&self, x: &$t) -> Self::Output {
+                        <Self as Foo<$t>>::foo(self, *x)
+                    }
+                }
+
+                impl Foo<Option<$t>> for MyStruct<$t> {
+                    type Output = Option<$t>;
+
+                    fn foo(&self, x: Option<$t>) -> Self::Output {
+                        x.map(|x| <Self as Foo<$t>>::foo(self, x))
+                    }
+                }
+            )*
+        };
+        // an operator is the same as the closure `|a, b| a $op b`.
+        ($op:tt : $($t:ty)*) => {
+            r#impl! { (|a, b| a $op b) : $($t)* }
+        };
+        (() for $($t:ty)*) => {};
+        (($tr:ident $(, $rest:ident)* $(,)?) for $($t:ty)*) => {
+            r#impl! { @trait $tr $($t)* }
+            r#impl! { ($($rest),*) for $($t)* }
+        };
+        (@trait Foo $($t:ty)*) => {
+            r#impl! { + : $($t)* }
+        };
+        (@trait Bar $($t:ty)*) => {
+            $(
+                impl Bar<$t> for MyStruct<$t> {
+                    fn bar(&self, x: $t) -> bool {
+                        self.0 == x
+                    }
+                }
+            )*
+        };
+        ($($t:ty /* `ty` is `type` */)*) => {
+            r#impl! { + : $($t)* }
+        };
+    }
+
+    // `{ }` can omit the semicolon.
+    //
+    // https://play.rust-lang.org/?gist=dee6113358f0390a3a353b3dd95e411f
+    r#impl! { ctors; (Foo, Bar) for usize u8 u16 u32 u64 u128 isize i8 i16 i32 i64 i128 }
+    r#impl! { ctors; * : f32 f64 }
+    r#impl! { ctors; (|a, b| a ^ b) : bool }
+    r#impl! { (|a, b| a.max(b)) : char }
+    r#impl! { (Bar) for f32 f64 bool char }
+
+    pub fn x() -> impl Foo<usize, Output = usize> {
+        MyStruct(42)
+    }
+
+    /// Creates a wrapper struct with `From` conversions both ways and the listed `std::ops` impls on the inner value.
+    ///
+    /// `Add` is `Self + Self`, `Mul<f64>` is `Self * f64`, both return `Self`. supported: `Add`, `Sub`, `Mul`, `Div`, `Rem` and `Neg`.
+    /// the wrapper and the inner type don't mix unless an op is listed with that right-hand side, `Meters(1.0) + 1.0` does not compile.
+    ///
+    /// ```rust
+    /// # use awesome::newtype;
+    /// newtype!(#[derive(Debug, Clone, Copy, PartialEq)] pub Meters(f64): Add, Sub, Mul<f64>);
+    ///
+    /// let d = Meters(1.5) + Meters(2.0) - Meters(0.5);
+    /// assert_eq!(d * 2.0, Meters(6.0));
+    /// assert_eq!(f64::from(d), 3.0);
+    /// ```
+    #[macro_export]
+    macro_rules! newtype {
+        ($(#[$meta:meta])* $vis:vis $name:ident($inner:ty) $(: $($op:ident $(<$rhs:ty>)?),+ $(,)?)?) => {
+            $(#[$meta])*
+            $vis struct $name(pub $inner);
+
+            impl ::std::convert::From<$inner> for $name {
+                fn from(inner: $inner) -> Self {
+                    $name(inner)
+                }
+            }
+
+            impl ::std::convert::From<$name> for $inner {
+                fn from(outer: $name) -> Self {
+                    outer.0
+                }
+            }
+
+            $($(
+                $crate::newtype!(@op $name; $op $(<$rhs>)?);
+            )+)?
+        };
+        (@op $name:ident; Add $($rhs:tt)*) => { $crate::newtype!(@bin $name; Add add + $($rhs)*); };
+        (@op $name:ident; Sub $($rhs:tt)*) => { $crate::newtype!(@bin $name; Sub sub - $($rhs)*); };
+        (@op $name:ident; Mul $($rhs:tt)*) => { $crate::newtype!(@bin $name; Mul mul * $($rhs)*); };
+        (@op $name:ident; Div $($rhs:tt)*) => { $crate::newtype!(@bin $name; Div div / $($rhs)*); };
+        (@op $name:ident; Rem $($rhs:tt)*) => { $crate::newtype!(@bin $name; Rem rem % $($rhs)*); };
+        (@op $name:ident; Neg) => {
+            impl ::std::ops::Neg for $name {
+                type Output = $name;
+
+                fn neg(self) -> Self::Output {
+                    $name(-self.0)
+                }
+            }
+        };
+        (@bin $name:ident; $tr:ident $method:ident $o:tt) => {
+            impl ::std::ops::$tr for $name {
+                type Output = $name;
+
+                fn $method(self, rhs: $name) -> Self::Output {
+                    $name(self.0 $o rhs.0)
+                }
+            }
+        };
+        (@bin $name:ident; $tr:ident $method:ident $o:tt <$rhs:ty>) => {
+            impl ::std::ops::$tr<$rhs> for $name {
+                type Output = $name;
+
+                fn $method(self, rhs: $rhs) -> Self::Output {
+                    $name(self.0 $o rhs)
+                }
+            }
+        };
+    }
+
+    /// Forwards trait impls of a wrapper to one of its fields, `impl<generics> Traits for Wrapper => field: FieldType;`.
+    ///
+    /// every impl is bounded by `FieldType: Trait`, extra bounds go in `where`, one path per bound (`T: A, T: B`).
+    /// supported traits: `Display`, `Debug`, `LowerHex`, `UpperHex`, `Octal`, `Binary`, `Clone`, `Default`, `PartialEq`, `Eq`,
+    /// `PartialOrd`, `Ord`, `Hash`, `AsRef` and `Deref`. `Clone` and `Default` build `Self { field: .. }`, so the wrapper must have only that field.
+    ///
+    /// generics are passed down in brackets (`[T]`) and the traits one by one,
+    /// a repetition of the traits cannot contain another repetition of the generics.
+    ///
+    /// ```rust
+    /// # use awesome::delegate;
+    /// struct Name {
+    ///     inner: String,
+    /// }
+    ///
+    /// struct Wrapper<T>(T);
+    ///
+    /// delegate! {
+    ///     impl Display, PartialEq for Name => inner: String;
+    ///     impl<T> Display, Debug, Clone for Wrapper<T> => 0: T;
+    /// }
+    ///
+    /// assert_eq!(Name { inner: "a".to_string() }.to_string(), "a");
+    /// assert_eq!(format!("{:?}", Wrapper("b").clone()), "\"b\"");
+    /// ```
+    #[macro_export]
+    macro_rules! delegate {
+        ($(impl $(<$($g:ident $(: $gb:path)?),* $(,)?>)? $($tr:ident),+ for $ty:ty => $f:tt : $fty:ty $(where $($wt:ty : $wb:path),+ $(,)?)?;)+) => {
+            $(
+                $crate::delegate!(@traits [$($($g $(: $gb)?),*)?] [$($($wt: $wb),+)?] [$ty] [$f: $fty] $($tr)+);
+            )+
+        };
+        (@traits $gen:tt $wc:tt $ty:tt $field:tt $($tr:ident)+) => {
+            $(
+                $crate::delegate!(@impl $tr $gen $wc $ty $field);
+            )+
+        };
+        (@impl Display $($rest:tt)*) => { $crate::delegate!(@fmt Display $($rest)*); };
+        (@impl Debug $($rest:tt)*) => { $crate::delegate!(@fmt Debug $($rest)*); };
+        (@impl LowerHex $($rest:tt)*) => { $crate::delegate!(@fmt LowerHex $($rest)*); };
+        (@impl UpperHex $($rest:tt)*) => { $crate::delegate!(@fmt UpperHex $($rest)*); };
+        (@impl Octal $($rest:tt)*) => { $crate::delegate!(@fmt Octal $($rest)*); };
+        (@impl Binary $($rest:tt)*) => { $crate::delegate!(@fmt Binary $($rest)*); };
+        (@fmt $tr:ident [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::fmt::$tr for $ty
+            where
+                $fty: ::std::fmt::$tr,
+                $($wc)*
+            {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    ::std::fmt::$tr::fmt(&self.$f, f)
+                }
+            }
+        };
+        (@impl Clone [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::clone::Clone for $ty
+            where
+                $fty: ::std::clone::Clone,
+                $($wc)*
+            {
+                fn clone(&self) -> Self {
+                    Self { $f: ::std::clone::Clone::clone(&self.$f) }
+                }
+            }
+        };
+        (@impl Default [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::default::Default for $ty
+            where
+                $fty: ::std::default::Default,
+                $($wc)*
+            {
+                fn default() -> Self {
+                    Self { $f: ::std::default::Default::default() }
+                }
+            }
+        };
+        (@impl PartialEq [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::cmp::PartialEq for $ty
+            where
+                $fty: ::std::cmp::PartialEq,
+                $($wc)*
+            {
+                fn eq(&self, other: &Self) -> bool {
+                    self.$f == other.$f
+                }
+            }
+        };
+        (@impl Eq [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::cmp::Eq for $ty
+            where
+                $fty: ::std::cmp::Eq,
+                $($wc)*
+            {
+            }
+        };
+        (@impl PartialOrd [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::cmp::PartialOrd for $ty
+            where
+                $fty: ::std::cmp::PartialOrd,
+                $($wc)*
+            {
+                fn partial_cmp(&self, other: &Self) -> ::std::option::Option<::std::cmp::Ordering> {
+                    ::std::cmp::PartialOrd::partial_cmp(&self.$f, &other.$f)
+                }
+            }
+        };
+        (@impl Ord [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::cmp::Ord for $ty
+            where
+                $fty: ::std::cmp::Ord,
+                $($wc)*
+            {
+                fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                    ::std::cmp::Ord::cmp(&self.$f, &other.$f)
+                }
+            }
+        };
+        (@impl Hash [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::hash::Hash for $ty
+            where
+                $fty: ::std::hash::Hash,
+                $($wc)*
+            {
+                fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                    ::std::hash::Hash::hash(&self.$f, state)
+                }
+            }
+        };
+        (@impl AsRef [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::convert::AsRef<$fty> for $ty
+            where
+                $($wc)*
+            {
+                fn as_ref(&self) -> &$fty {
+                    &self.$f
+                }
+            }
+        };
+        (@impl Deref [$($g:tt)*] [$($wc:tt)*] [$ty:ty] [$f:tt : $fty:ty]) => {
+            impl<$($g)*> ::std::ops::Deref for $ty
+            where
+                $($wc)*
+            {
+                type Target = $fty;
+
+                fn deref(&self) -> &Self::Target {
+                    &self.$f
+                }
+            }
+        };
+    }
+}
+
+/// # LINQ Iterators
+///
+/// The iterator adapters used by `linq!`, they can be used without the macro too.
+pub mod linq {
+    use std::collections::hash_map::Entry;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::Hash;
+
+    /// Deduplication adapters, for every `Iterator`.
+    pub trait DistinctExt: Iterator + Sized {
+        /// Yields each item once, the first time it is seen.
+        ///
+        /// ```rust
+        /// # use awesome::linq::DistinctExt;
+        /// assert_eq!([1, 2, 1, 3, 2].into_iter().distinct().collect::<Vec<_>>(), vec![1, 2, 3]);
+        /// ```
+        fn distinct(self) -> Distinct<Self>
+        where
+            Self::Item: Hash + Eq + Clone,
+        {
+            Distinct {
+                iter: self,
+                seen: HashSet::new(),
+            }
+        }
+
+        /// Yields the first item of each key.
+        ///
+        /// ```rust
+        /// # use awesome::linq::DistinctExt;
+        /// let words = ["apple", "avocado", "banana", "blueberry", "cherry"];
+        /// let firsts: Vec<_> = words.into_iter().distinct_by(|w| w.chars().next()).collect();
+        ///
+        /// assert_eq!(firsts, vec!["apple", "banana", "cherry"]);
+        /// ```
+        fn distinct_by<K, F>(self, key: F) -> DistinctBy<Self, K, F>
+        where
+            K: Hash + Eq,
+            F: FnMut(&Self::Item) -> K,
+        {
+            DistinctBy {
+                iter: self,
+                key,
+                seen: HashSet::new(),
+            }
+        }
+    }
+
+    impl<I: Iterator> DistinctExt for I {}
+
+    /// The LINQ operators as methods, for every `Iterator`, the same queries as `linq!` without the macro.
+    ///
+    /// `distinct_by` is also in `DistinctExt`, the call is ambiguous if both traits are imported (`use awesome::linq::*;`).
+    ///
+    /// ```rust
+    /// # use awesome::linq::QueryIteratorExt;
+    /// let words = ["pear", "fig", "apple", "kiwi", "plum"];
+    /// let result: Vec<_> = words
+    ///     .into_iter()
+    ///     .where_(|w| w.len() > 3)
+    ///     .order_by(|w| w.len())
+    ///     .select(|w| w.to_uppercase())
+    ///     .collect();
+    ///
+    /// assert_eq!(result, vec!["PEAR", "KIWI", "PLUM", "APPLE"]);
+    /// ```
+    pub trait QueryIteratorExt: Iterator + Sized {
+        /// Keeps the items matching `predicate`, `Iterator::filter`.
+        fn where_<P>(self, predicate: P) -> std::iter::Filter<Self, P>
+        where
+            P: FnMut(&Self::Item) -> bool,
+        {
+            self.filter(predicate)
+        }
+
+        /// Projects each item, `Iterator::map`.
+        fn select<U, F>(self, selector: F) -> std::iter::Map<Self, F>
+        where
+            F: FnMut(Self::Item) -> U,
+        {
+            self.map(selector)
+        }
+
+        /// Sorts the items by `key` (stable), like `orderby key;`. the items are collected first, `key` only needs `PartialOrd`,
+        /// `NaN` keys sort last (see `compare_keys`).
+        fn order_by<K, F>(self, key: F) -> std::vec::IntoIter<Self::Item>
+        where
+            K: PartialOrd,
+            F: FnMut(&Self::Item) -> K,
+        {
+            sort_by_key(self, key, |ordering| ordering)
+        }
+
+        /// Sorts the items by `key` (stable), largest first, like `orderby descending key;`.
+        fn order_by_descending<K, F>(self, key: F) -> std::vec::IntoIter<Self::Item>
+        where
+            K: PartialOrd,
+            F: FnMut(&Self::Item) -> K,
+        {
+            sort_by_key(self, key, std::cmp::Ordering::reverse)
+        }
+
+        /// Groups the items by `key`, like `group x by key;`, the groups are in the order their keys first appear.
+        ///
+        /// ```rust
+        /// # use awesome::linq::QueryIteratorExt;
+        /// let groups: Vec<_> = [1, 2, 3, 4, 5].into_iter().group_by(|n| n % 3).collect();
+        ///
+        /// assert_eq!(groups, vec![(1, vec![1, 4]), (2, vec![2, 5]), (0, vec![3])]);
+        /// ```
+        fn group_by<K, F>(self, mut key: F) -> std::vec::IntoIter<(K, Vec<Self::Item>)>
+        where
+            K: Hash + Eq,
+            F: FnMut(&Self::Item) -> K,
+        {
+            group_pairs(self.map(|item| (key(&item), item)))
+        }
+
+        /// Yields the first item of each key, `DistinctExt::distinct_by`.
+        fn distinct_by<K, F>(self, key: F) -> DistinctBy<Self, K, F>
+        where
+            K: Hash + Eq,
+            F: FnMut(&Self::Item) -> K,
+        {
+            DistinctExt::distinct_by(self, key)
+        }
+
+        /// The only item, see `single`.
+        fn single(self) -> Result<Self::Item, SingleError> {
+            single(self)
+        }
+    }
+
+    impl<I: Iterator> QueryIteratorExt for I {}
+
+    fn sort_by_key<I, K, F>(iter: I, mut key: F, direction: fn(std::cmp::Ordering) -> std::cmp::Ordering) -> std::vec::IntoIter<I::Item>
+    where
+        I: Iterator,
+        K: PartialOrd,
+        F: FnMut(&I::Item) -> K,
+    {
+        let mut items: Vec<_> = iter.collect();
+        items.sort_by(|a, b| direction(compare_keys(&key(a), &key(b))));
+        items.into_iter()
+    }
+
+    /// Compares two sort keys, `orderby` expands to it and `order_by` uses it.
+    ///
+    /// it stays a total order with floats, which `sort_by` requires: a key that is not comparable with itself (`NaN`) sorts after
+    /// every other key, and those keys are equal to each other. the other keys must be comparable with each other, like floats,
+    /// or tuples and `Option`s of them.
+    pub fn compare_keys<K: PartialOrd>(a: &K, b: &K) -> std::cmp::Ordering {
+        #[allow(clippy::eq_op)]
+        match (a.partial_cmp(a).is_some(), b.partial_cmp(b).is_some()) {
+            (true, true) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
+            (a_ordered, b_ordered) => b_ordered.cmp(&a_ordered),
+        }
+    }
+
+    /// Groups `(key, value)` pairs by key, the groups are in the order their keys first appear. `group ... by` expands to it.
+    pub fn group_pairs<K, V, I>(pairs: I) -> std::vec::IntoIter<(K, Vec<V>)>
+    where
+        K: Hash + Eq,
+        I: Iterator<Item = (K, V)>,
+    {
+        // the keys map to the index of their group, so the groups keep the order of the source.
+        let mut keys: HashMap<K, usize> = HashMap::new();
+        let mut groups: Vec<Vec<V>> = Vec::new();
+        for (key, value) in pairs {
+            match keys.entry(key) {
+                Entry::Occupied(entry) => groups[*entry.get()].push(value),
+                Entry::Vacant(entry) => {
+                    entry.insert(groups.len());
+                    groups.push(vec![value]);
+                }
+            }
+        }
+        let mut keys: Vec<_> = keys.into_iter().collect();
+        keys.sort_by_key(|(_, index)| *index);
+        keys.into_iter().map(|(key, _)| key).zip(groups).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Why `single` did not find exactly one item.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SingleError {
+        Empty,
+        MoreThanOne,
+    }
+
+    impl std::fmt::Display for SingleError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                SingleError::Empty => write!(f, "the sequence is empty"),
+                SingleError::MoreThanOne => write!(f, "the sequence has more than one item"),
+            }
+        }
+    }
+
+    impl std::error::Error for SingleError {}
+
+    /// The only item, an error if there are none or more than one. it stops at the second item.
+    ///
+    /// ```rust
+    /// # use awesome::linq::{single, SingleError};
+    /// assert_eq!(single([1].into_iter()), Ok(1));
+    /// assert_eq!(single([1, 2, 3].into_iter()), Err(SingleError::MoreThanOne));
+    /// assert_eq!(single(std::iter::empty::<i32>()), Err(SingleError::Empty));
+    /// ```
+    pub fn single<I: Iterator>(mut iter: I) -> Result<I::Item, SingleError> {
+        match (iter.next(), iter.next()) {
+            (Some(item), None) => Ok(item),
+            (None, _) => Err(SingleError::Empty),
+            (Some(_), Some(_)) => Err(SingleError::MoreThanOne),
+        }
+    }
+
+    /// The sum of the items, with the item type as the result, `Iterator::sum` needs an annotation.
+    pub fn sum<I>(iter: I) -> I::Item
+    where
+        I: Iterator,
+        I::Item: std::iter::Sum,
+    {
+        iter.sum()
+    }
+
+    /// The mean of the items, `None` if there are none.
+    pub fn average<I>(iter: I) -> Option<f64>
+    where
+        I: Iterator,
+        I::Item: Into<f64>,
+    {
+        let (total, count) = iter.fold((0.0, 0usize), |(total, count), item| (total + item.into(), count + 1));
+
+        (count > 0).then(|| total / count as f64)
+    }
+
+    /// The smallest item, only `PartialOrd` is needed, so it works with floats. the first one wins on ties.
+    pub fn min<I>(iter: I) -> Option<I::Item>
+    where
+        I: Iterator,
+        I::Item: PartialOrd,
+    {
+        iter.reduce(|a, b| if b < a { b } else { a })
+    }
+
+    /// The largest item, only `PartialOrd` is needed, so it works with floats. the first one wins on ties.
+    pub fn max<I>(iter: I) -> Option<I::Item>
+    where
+        I: Iterator,
+        I::Item: PartialOrd,
+    {
+        iter.reduce(|a, b| if b > a { b } else { a })
+    }
+
+    /// The iterator of `DistinctExt::distinct`, it keeps a clone of every item it yields.
+    pub struct Distinct<I: Iterator> {
+        iter: I,
+        seen: HashSet<I::Item>,
+    }
+
+    impl<I> Iterator for Distinct<I>
+    where
+        I: Iterator,
+        I::Item: Hash + Eq + Clone,
+    {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let seen = &mut self.seen;
+            self.iter.find(|item| seen.insert(item.clone()))
+        }
+    }
+
+    /// The iterator of `DistinctExt::distinct_by`, it keeps the keys it has seen.
+    pub struct DistinctBy<I, K, F> {
+        iter: I,
+        key: F,
+        seen: HashSet<K>,
+    }
+
+    impl<I, K, F> Iterator for DistinctBy<I, K, F>
+    where
+        I: Iterator,
+        K: Hash + Eq,
+        F: FnMut(&I::Item) -> K,
+    {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let (seen, key) = (&mut self.seen, &mut self.key);
+            self.iter.find(|item| seen.insert(key(item)))
+        }
+    }
+}
+
+/// # LINQ
+///
+/// A query over an iterator, `from x in iter;` followed by clauses and `select`, like C#'s LINQ.
+///
+/// the clauses are applied in order (a tt muncher): each one wraps the iterator built so far, `select` finishes it.
+/// a range variable (of `from`, `join` and `let`) is an identifier or a tuple pattern of them, e.g. `from (k, v) in map.iter();`,
+/// it must be one token tree, and it is also used as an expression, so `_` and `mut` cannot be used.
+/// the clauses see the range variable by value, like `select`. `where` and the paging clauses rebuild the item from it afterwards
+/// (`filter_map`), so the items do not have to be `Copy`. `orderby` and `distinct by` only borrow the items,
+/// they see a single range variable as a reference (`&x`, fields auto-deref) and the parts of a tuple one (after `join`, `let`, ...) by reference.
+///
+/// - `where cond;` keeps the items matching `cond`. there may be any number of them, they short-circuit like `&&`.
+/// - `orderby key;`, `orderby descending key;` sort by `key` (stable). the items are collected into a `Vec` first,
+///   so the query is not lazy from there. `key` is borrowed (`&key`), so `x.name` does not move, and it only needs `PartialOrd`:
+///   with floats, `NaN` keys sort last (see `linq::compare_keys`).
+/// - `group elem by key;` ends the query instead of `select`, it yields `(key, Vec<elem>)` in the order the keys first appear.
+///   `elem` is one token tree (`x` or `(x.name)`), an expression cannot be followed by `by`. `key` needs `Hash + Eq`.
+/// - `join y in ys on key equals key;` is an equi-join (inner), the range variable becomes `(x, y)` for the next clauses.
+///   `x` and `y` are cloned for each match, so they need `Clone` (a source of references, `iter()`, only copies them).
+///   it is a hash join, `ys` is put in a `HashMap` first, so it takes `O(n + m)`. the source and the first key are taken token by token
+///   until `on` and `equals`, an expression cannot be followed by them either.
+/// - `group join y in ys on key equals key into g;` (or `join ... into g;`) is a group join, `g` is the `Vec` of the matches of `x`, maybe empty,
+///   and the range variable becomes `(x, g)`. the matches keep the order of `ys`, they are cloned for each `x`, so `y` needs `Clone`.
+/// - another `from y in ys;` is a cross join (`flat_map`), `ys` is evaluated for each `x` and can use it (`from y in x.children.iter();`).
+///   the range variable becomes `(x, y)`. `x` is cloned for each `y`, so it needs `Clone` (iterate by reference to avoid copies).
+/// - `zip y in ys;` pairs the items with `ys` by position, it stops at the shorter one. the range variable becomes `(x, y)`.
+/// - `let v = expr;` binds a value for the next clauses, the range variable becomes `(x, v)`.
+/// - `skip n;`, `take n;`, `skip_while cond;` and `take_while cond;` page the items, they are lazy.
+/// - `reverse;` reverses the items, `chunk n into c;` groups them into `Vec`s of `n` items (the last one may be shorter),
+///   and the range variable becomes the chunk `c`. they collect the items first, only where they appear.
+///   `chunk n;` at the end of a query yields the chunks.
+/// - `distinct;` and `distinct by key;` drop the items (or the keys) seen before, with `linq::DistinctExt`.
+///   the items need `Hash + Eq + Clone`, the key needs `Hash + Eq` and is kept, so it must not borrow from the item (`x.name.clone()`).
+///
+/// instead of `select`, the query can end with an aggregate, which returns a value instead of an iterator:
+/// `count;`, `sum expr;`, `min expr;`, `max expr;` (`Option`, `PartialOrd`), `average expr;` (`Option<f64>`, `Into<f64>`)
+/// and `fold init, acc => expr;`.
+///
+/// or with an element operator: `first;` / `first expr;` (`Option`), `single;` / `single expr;` (`Result<_, linq::SingleError>`),
+/// `any cond;` and `all cond;` (`bool`), they stop as soon as the answer is known.
+///
+/// `select Person { name: x.name.clone(), age: x.age };` builds a struct, it is just an expression. `select { name = x.name, age = x.age };`
+/// builds an anonymous record instead: a struct defined by the macro, generic over the types of the fields, with the fields
+/// in the written order (`Debug`, `Clone`, `Copy`, `PartialEq`, `Eq`, `PartialOrd`, `Ord` and `Hash` are derived, so they compare in that order).
+/// its name cannot be written, but the fields can be read (`r.name`).
+///
+/// `into Collection;` after `select` or `group` collects the items, e.g. `into Vec<_>;`, `into HashSet<_>;` or `into HashMap<_, _>;`.
+///
+/// ```rust
+/// # use awesome::linq;
+/// let words = ["pear", "fig", "apple", "kiwi"];
+/// let result = linq!(
+///     from w in words.iter();
+///     where w.len() > 3;
+///     orderby w.len();
+///     select w.to_uppercase();
+/// );
+///
+/// assert_eq!(result.collect::<Vec<_>>(), vec!["PEAR", "KIWI", "APPLE"]);
+/// ```
+#[macro_export]
+macro_rules! linq {
+    (from $r:tt in $d:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($d); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); select { $($f:ident = $e:expr),+ $(,)? }; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it); select $crate::linq!(@record $($f = $e),+); $($rest)*)
+    };
+    (@record $($f:ident = $e:expr),+) => {{
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct Record<$($f),+> {
+            $($f: $f),+
+        }
+
+        Record { $($f: $e),+ }
+    }};
+    (@query $r:tt; ($it:expr); select $s:expr;) => {
+        $it.map(|item| {
+            $crate::linq!(@bind $r = item);
+            $s
+        })
+    };
+    (@query $r:tt; ($it:expr); select $s:expr; into $c:ty $(;)?) => {
+        $crate::linq!(@query $r; ($it); select $s;).collect::<$c>()
+    };
+    (@query $r:tt; ($it:expr); group $e:tt by $k:expr; into $c:ty $(;)?) => {
+        $crate::linq!(@query $r; ($it); group $e by $k;).collect::<$c>()
+    };
+    (@query $r:tt; ($it:expr); first;) => {
+        $crate::linq!(@query $r; ($it); select $r;).next()
+    };
+    (@query $r:tt; ($it:expr); first $s:expr;) => {
+        $crate::linq!(@query $r; ($it); select $s;).next()
+    };
+    (@query $r:tt; ($it:expr); single;) => {
+        $crate::linq::single($crate::linq!(@query $r; ($it); select $r;))
+    };
+    (@query $r:tt; ($it:expr); single $s:expr;) => {
+        $crate::linq::single($crate::linq!(@query $r; ($it); select $s;))
+    };
+    (@query $r:tt; ($it:expr); any $w:expr;) => {
+        $crate::linq!(@query $r; ($it); select $w;).any(|ok| ok)
+    };
+    (@query $r:tt; ($it:expr); all $w:expr;) => {
+        $crate::linq!(@query $r; ($it); select $w;).all(|ok| ok)
+    };
+    (@query $r:tt; ($it:expr); count;) => {
+        $it.count()
+    };
+    (@query $r:tt; ($it:expr); sum $s:expr;) => {
+        $crate::linq::sum($crate::linq!(@query $r; ($it); select $s;))
+    };
+    (@query $r:tt; ($it:expr); min $s:expr;) => {
+        $crate::linq::min($crate::linq!(@query $r; ($it); select $s;))
+    };
+    (@query $r:tt; ($it:expr); max $s:expr;) => {
+        $crate::linq::max($crate::linq!(@query $r; ($it); select $s;))
+    };
+    (@query $r:tt; ($it:expr); average $s:expr;) => {
+        $crate::linq::average($crate::linq!(@query $r; ($it); select $s;))
+    };
+    (@query $r:tt; ($it:expr); fold $init:expr, $acc:ident => $e:expr;) => {
+        $it.fold($init, |$acc, item| {
+            $crate::linq!(@bind $r = item);
+            $e
+        })
+    };
+    // after a `join`, a clause may use only some of the range variables.
+    (@bind $r:tt = $item:expr) => {
+        #[allow(unused_variables)]
+        let $r = $item;
+    };
+    // a copy of the range variable for each inner item of a cross join, a tuple one is cloned part by part.
+    (@clone ($($x:tt),+)) => {
+        ($($crate::linq!(@clone $x)),+)
+    };
+    (@clone $x:ident) => {
+        ::std::clone::Clone::clone(&$x)
+    };
+    // binds from `&item`, a single range variable is the reference, the parts of a tuple are borrowed (binding modes).
+    (@bind_ref $r:tt = $item:expr) => {
+        #[allow(unused_variables)]
+        let $r = $item;
+    };
+    (@query $r:tt; ($it:expr); group $e:tt by $k:expr;) => {
+        $crate::linq::group_pairs($it.map(|item| {
+            $crate::linq!(@bind $r = item);
+            #[allow(unused_parens)]
+            let value = $e;
+            ($k, value)
+        }))
+    };
+    // every `where` is its own filter, so a predicate only runs for the items the previous ones kept (like `&&`).
+    // the range variable is an expression too (an ident or a tuple of them), so the item is rebuilt from it.
+    (@query $r:tt; ($it:expr); where $w:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.filter_map(|item| {
+            $crate::linq!(@bind $r = item);
+            if $w {
+                ::std::option::Option::Some($r)
+            } else {
+                ::std::option::Option::None
+            }
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); from $y:tt in $src:expr; $($rest:tt)*) => {
+        $crate::linq!(@query ($r, $y); ($it.flat_map(|item| {
+            $crate::linq!(@bind $r = item);
+            ::std::iter::IntoIterator::into_iter($src).map(move |$y| ($crate::linq!(@clone $r), $y))
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); reverse; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.collect::<::std::vec::Vec<_>>().into_iter().rev()); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); chunk $n:tt into $c:tt; $($rest:tt)*) => {
+        $crate::linq!(@query $c; ($crate::linq!(@chunk $it; $n)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); chunk $n:expr;) => {
+        $crate::linq!(@chunk $it; $n)
+    };
+    (@chunk $it:expr; $n:expr) => {{
+        let size: usize = $n;
+        assert!(size > 0, "the chunk size must be positive");
+        let mut chunks: ::std::vec::Vec<::std::vec::Vec<_>> = ::std::vec::Vec::new();
+        for item in $it {
+            match chunks.last_mut() {
+                ::std::option::Option::Some(chunk) if chunk.len() < size => chunk.push(item),
+                _ => chunks.push(::std::vec![item]),
+            }
+        }
+        chunks.into_iter()
+    }};
+    (@query $r:tt; ($it:expr); zip $y:tt in $src:expr; $($rest:tt)*) => {
+        $crate::linq!(@query ($r, $y); ($it.zip($src)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); let $v:tt = $e:expr; $($rest:tt)*) => {
+        $crate::linq!(@query ($r, $v); ($it.map(|item| {
+            $crate::linq!(@bind $r = item);
+            let $v = $e;
+            ($r, $v)
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); skip $n:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.skip($n)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); take $n:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.take($n)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); skip_while $w:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.scan(true, |skipping, item| {
+            $crate::linq!(@bind $r = item);
+            if *skipping && $w {
+                ::std::option::Option::Some(::std::option::Option::None)
+            } else {
+                *skipping = false;
+                ::std::option::Option::Some(::std::option::Option::Some($r))
+            }
+        }).flatten()); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); take_while $w:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it.map_while(|item| {
+            $crate::linq!(@bind $r = item);
+            if $w {
+                ::std::option::Option::Some($r)
+            } else {
+                ::std::option::Option::None
+            }
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); distinct; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($crate::linq::DistinctExt::distinct($it)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); distinct by $k:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($crate::linq::DistinctExt::distinct_by($it, |item: &_| {
+            $crate::linq!(@bind_ref $r = item);
+            $k
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); group join $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it); join $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); join $j:tt in $($rest:tt)*) => {
+        $crate::linq!(@join $r; ($it); $j; []; $($rest)*)
+    };
+    (@join $r:tt; ($it:expr); $j:tt; [$($src:tt)*]; on $($rest:tt)*) => {
+        $crate::linq!(@join_on $r; ($it); $j; ($($src)*); []; $($rest)*)
+    };
+    (@join $r:tt; ($it:expr); $j:tt; [$($src:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::linq!(@join $r; ($it); $j; [$($src)* $t]; $($rest)*)
+    };
+    (@join_on $r:tt; ($it:expr); $j:tt; ($src:expr); [$($k:tt)*]; equals $($rest:tt)*) => {
+        $crate::linq!(@join_eq $r; ($it); $j; ($src); ($($k)*); []; $($rest)*)
+    };
+    (@join_on $r:tt; ($it:expr); $j:tt; ($src:expr); [$($k:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::linq!(@join_on $r; ($it); $j; ($src); [$($k)* $t]; $($rest)*)
+    };
+    (@join_eq $r:tt; ($it:expr); $j:tt; ($src:expr); ($k:expr); [$($jk:tt)*]; into $g:ident; $($rest:tt)*) => {
+        $crate::linq!(@query ($r, $g); ({
+            let table = $crate::linq!(@join_table $j; $src; ($($jk)*));
+            $it.map(move |item| {
+                $crate::linq!(@bind $r = item);
+                let $g = table.get(&$k).cloned().unwrap_or_default();
+                ($r, $g)
+            })
+        }); $($rest)*)
+    };
+    (@join_eq $r:tt; ($it:expr); $j:tt; ($src:expr); ($k:expr); [$($jk:tt)*]; ; $($rest:tt)*) => {
+        $crate::linq!(@query ($r, $j); ({
+            let table = $crate::linq!(@join_table $j; $src; ($($jk)*));
+            // the matches are borrowed from the table, each one is cloned with a clone of the outer item.
+            $it.flat_map(move |$r| {
+                let matches = table.get(&$k).map_or(&[][..], ::std::vec::Vec::as_slice);
+                matches.iter().cloned().map(|$j| ($crate::linq!(@clone $r), $j)).collect::<::std::vec::Vec<_>>()
+            })
+        }); $($rest)*)
+    };
+    (@join_eq $r:tt; ($it:expr); $j:tt; ($src:expr); ($k:expr); [$($jk:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::linq!(@join_eq $r; ($it); $j; ($src); ($k); [$($jk)* $t]; $($rest)*)
+    };
+    // the build side of a join, the items of `src` by their key, in order.
+    (@join_table $j:tt; $src:expr; ($jk:expr)) => {{
+        let mut table: ::std::collections::HashMap<_, ::std::vec::Vec<_>> = ::std::collections::HashMap::new();
+        for $j in $src {
+            table.entry($jk).or_default().push($j);
+        }
+        table
+    }};
+    (@query $r:tt; ($it:expr); orderby descending $k:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($crate::linq!(@sort $r; $it; $k; |ordering: ::std::cmp::Ordering| ordering.reverse())); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); orderby ascending $k:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($it); orderby $k; $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); orderby $k:expr; $($rest:tt)*) => {
+        $crate::linq!(@query $r; ($crate::linq!(@sort $r; $it; $k; |ordering: ::std::cmp::Ordering| ordering)); $($rest)*)
+    };
+    (@sort $r:tt; $it:expr; $k:expr; $direction:expr) => {{
+        let mut items: ::std::vec::Vec<_> = $it.collect();
+        let direction = $direction;
+        items.sort_by(|a, b| {
+            // the key of `b` shadows the range variable in an inner block, so the key of `a` can still borrow it.
+            $crate::linq!(@bind_ref $r = a);
+            let ka = &$k;
+            {
+                $crate::linq!(@bind_ref $r = b);
+                let kb = &$k;
+                direction($crate::linq::compare_keys(ka, kb))
+            }
+        });
+        items.into_iter()
+    }};
+}
+
+/// The same query as `linq!`, with its plan: it returns `(query, &'static str)`.
+///
+/// the plan names the steps the clauses expand to, in order, joined by `" -> "`, e.g. `"filter -> map -> take(5)"`.
+/// the arguments of `skip`, `take` and `chunk` are shown as written. the steps that collect the items first are
+/// `sort_by` (`orderby`), `rev` (`reverse`), `chunks(n)` and `collect` (`into`). the aggregates and the element operators
+/// keep their own names. it is built with `concat!`, so it costs nothing at runtime.
+///
+/// ```rust
+/// # use awesome::linq_explain;
+/// let (query, plan) = linq_explain!(
+///     from n in 1..;
+///     where n % 3 == 0;
+///     take 3;
+///     select n * n;
+/// );
+///
+/// assert_eq!(plan, "filter -> take(3) -> map");
+/// assert_eq!(query.collect::<Vec<_>>(), vec![9, 36, 81]);
+/// ```
+#[macro_export]
+macro_rules! linq_explain {
+    (from $r:tt in $d:expr; $($rest:tt)*) => {
+        ($crate::linq!(from $r in $d; $($rest)*), $crate::linq_explain!(@plan []; $($rest)*))
+    };
+    (@plan []; ) => {
+        ""
+    };
+    (@plan [$first:expr $(, $step:expr)*]; ) => {
+        ::std::concat!($first $(, " -> ", $step)*)
+    };
+    (@plan [$($step:expr),*]; where $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "filter"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; select $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "map"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; let $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "map"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; from $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "flat_map"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; zip $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "zip"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; into $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "collect"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; group join $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "group_join"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; join $($rest:tt)*) => {
+        $crate::linq_explain!(@join [$($step),*]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; group $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "group_by"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; orderby $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "sort_by"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; reverse; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step,)* "rev"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; distinct; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step,)* "distinct"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; distinct by $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "distinct_by"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; skip_while $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "skip_while"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; take_while $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* "take_while"]; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; skip $($rest:tt)*) => {
+        $crate::linq_explain!(@arg [$($step),*] "skip" []; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; take $($rest:tt)*) => {
+        $crate::linq_explain!(@arg [$($step),*] "take" []; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; chunk $($rest:tt)*) => {
+        $crate::linq_explain!(@arg [$($step),*] "chunks" []; $($rest)*)
+    };
+    (@plan [$($step:expr),*]; $terminal:ident $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step,)* ::std::stringify!($terminal)]; $($rest)*)
+    };
+    // the rest of a clause, until `;` (or the end, after `into`).
+    (@skip [$($step:expr),*]; ) => {
+        $crate::linq_explain!(@plan [$($step),*]; )
+    };
+    (@skip [$($step:expr),*]; ; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step),*]; $($rest)*)
+    };
+    (@skip [$($step:expr),*]; $t:tt $($rest:tt)*) => {
+        $crate::linq_explain!(@skip [$($step),*]; $($rest)*)
+    };
+    // `join ... into g;` is a group join.
+    (@join [$($step:expr),*]; into $g:ident; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step,)* "group_join"]; $($rest)*)
+    };
+    (@join [$($step:expr),*]; ; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step,)* "join"]; $($rest)*)
+    };
+    (@join [$($step:expr),*]; $t:tt $($rest:tt)*) => {
+        $crate::linq_explain!(@join [$($step),*]; $($rest)*)
+    };
+    // the argument of `skip`, `take` and `chunk`, until `;`.
+    (@arg [$($step:expr),*] $name:literal [$($a:tt)*]; into $c:tt; $($rest:tt)*) => {
+        $crate::linq_explain!(@arg [$($step),*] $name [$($a)*]; ; $($rest)*)
+    };
+    (@arg [$($step:expr),*] $name:literal [$($a:tt)*]; ; $($rest:tt)*) => {
+        $crate::linq_explain!(@plan [$($step,)* ::std::concat!($name, "(", ::std::stringify!($($a)*), ")")]; $($rest)*)
+    };
+    (@arg [$($step:expr),*] $name:literal [$($a:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::linq_explain!(@arg [$($step),*] $name [$($a)* $t]; $($rest)*)
+    };
+}
+
+/// A query over fallible items, the source yields `Result<T, E>`, and it returns `Result<Vec<U>, E>`.
+///
+/// it stops at the first `Err` (`try_fold`), the items after it are not pulled. the clauses are `where`, `let` and `select`,
+/// they run inside the fold, so they can use `?` too (the error is converted with `From`, like in a function).
+/// the range variable is the `Ok` value itself, not a reference.
+///
+/// ```rust
+/// # use awesome::try_linq;
+/// let parse = |input: &[&str]| {
+///     try_linq!(
+///         from n in input.iter().map(|s| s.parse::<i32>());
+///         where n % 2 == 0;
+///         select n * 10;
+///     )
+/// };
+///
+/// assert_eq!(parse(&["1", "2", "4"]), Ok(vec![20, 40]));
+/// assert!(parse(&["2", "x", "4"]).is_err());
+/// ```
+#[macro_export]
+macro_rules! try_linq {
+    (from $r:tt in $d:expr; $($rest:tt)*) => {
+        ::std::iter::IntoIterator::into_iter($d).try_fold(::std::vec::Vec::new(), |mut output, item| {
+            let $r = match item {
+                ::std::result::Result::Ok(value) => value,
+                ::std::result::Result::Err(error) => return ::std::result::Result::Err(error),
+            };
+            $crate::try_linq!(@clauses output; $($rest)*)
+        })
+    };
+    (@clauses $output:ident; where $w:expr; $($rest:tt)*) => {{
+        if !($w) {
+            return ::std::result::Result::Ok($output);
+        }
+        $crate::try_linq!(@clauses $output; $($rest)*)
+    }};
+    (@clauses $output:ident; let $v:pat = $e:expr; $($rest:tt)*) => {{
+        let $v = $e;
+        $crate::try_linq!(@clauses $output; $($rest)*)
+    }};
+    (@clauses $output:ident; select $s:expr;) => {{
+        $output.push($s);
+        ::std::result::Result::Ok($output)
+    }};
+}
+
+// the async macros use `futures` through this path, so the users don't need to depend on it.
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub use futures as __futures;
+
+/// The `linq!` of `Stream`s (the `async` feature), the clauses are `where`, `let`, `skip`, `take` and `select`.
+///
+/// it returns a `Stream`, the clauses become `StreamExt` adapters, so the query is as lazy as the stream.
+///
+/// ```rust
+/// # use awesome::alinq;
+/// use futures::{executor::block_on, stream, StreamExt};
+///
+/// let query = alinq!(
+///     from n in stream::iter(1..=10);
+///     where n % 3 == 0;
+///     select n * 2;
+/// );
+///
+/// assert_eq!(block_on(query.collect::<Vec<_>>()), vec![6, 12, 18]);
+/// ```
+#[cfg(feature = "async")]
+#[macro_export]
+macro_rules! alinq {
+    (from $r:tt in $d:expr; $($rest:tt)*) => {
+        $crate::alinq!(@query $r; ($d); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); select $s:expr;) => {
+        $crate::__futures::StreamExt::map($it, |item| {
+            $crate::linq!(@bind $r = item);
+            $s
+        })
+    };
+    (@query $r:tt; ($it:expr); where $w:expr; $($rest:tt)*) => {
+        $crate::alinq!(@query $r; ($crate::__futures::StreamExt::filter_map($it, |item| {
+            $crate::linq!(@bind $r = item);
+            $crate::__futures::future::ready(if $w {
+                ::std::option::Option::Some($r)
+            } else {
+                ::std::option::Option::None
+            })
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); let $v:tt = $e:expr; $($rest:tt)*) => {
+        $crate::alinq!(@query ($r, $v); ($crate::__futures::StreamExt::map($it, |item| {
+            $crate::linq!(@bind $r = item);
+            let $v = $e;
+            ($r, $v)
+        })); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); skip $n:expr; $($rest:tt)*) => {
+        $crate::alinq!(@query $r; ($crate::__futures::StreamExt::skip($it, $n)); $($rest)*)
+    };
+    (@query $r:tt; ($it:expr); take $n:expr; $($rest:tt)*) => {
+        $crate::alinq!(@query $r; ($crate::__futures::StreamExt::take($it, $n)); $($rest)*)
+    };
+}
+
+/// # Function Composition
+///
+/// Building functions out of functions: `compose(f, g)` is a function doing `f` then `g`, and `pipe!` writes a chain of calls
+/// in the order they run, like the `|>` operator of F# or Elixir.
+pub mod compose {
+    /// `f` then `g`, `compose(f, g)(x) == g(f(x))`. the order is the order of the calls (like `pipe!`), not `g ∘ f`.
+    ///
+    /// ```rust
+    /// # use awesome::compose::compose;
+    /// let shout = compose(|s: &str| s.to_uppercase(), |s| s + "!");
+    ///
+    /// assert_eq!(shout("hello"), "HELLO!");
+    /// ```
+    pub fn compose<A, B, C>(f: impl Fn(A) -> B, g: impl Fn(B) -> C) -> impl Fn(A) -> C {
+        move |x| g(f(x))
+    }
+
+    /// `f` then `g` for fallible functions, `g` only runs if `f` succeeds (`Result::and_then`).
+    ///
+    /// ```rust
+    /// # use awesome::compose::try_compose;
+    /// let parse_even = try_compose(|s: &str| s.parse::<i32>().map_err(|e| e.to_string()), |n| {
+    ///     if n % 2 == 0 { Ok(n) } else { Err(format!("{n} is odd")) }
+    /// });
+    ///
+    /// assert_eq!(parse_even("4"), Ok(4));
+    /// assert_eq!(parse_even("3"), Err("3 is odd".to_string()));
+    /// ```
+    pub fn try_compose<A, B, C, E>(f: impl Fn(A) -> Result<B, E>, g: impl Fn(B) -> Result<C, E>) -> impl Fn(A) -> Result<C, E> {
+        move |x| f(x).and_then(&g)
+    }
+
+    /// Passes `value` through the functions in order. they all have the same type, `T -> T`,
+    /// so an array of closures works if they capture nothing (they become `fn` pointers), or use `&dyn Fn(T) -> T`.
+    ///
+    /// ```rust
+    /// # use awesome::compose::pipe;
+    /// assert_eq!(pipe(3, [|x| x + 1, |x| x * 10, |x| x - 2]), 38);
+    /// ```
+    pub fn pipe<T, F>(value: T, fs: impl IntoIterator<Item = F>) -> T
+    where
+        F: FnOnce(T) -> T,
+    {
+        fs.into_iter().fold(value, |value, f| f(value))
+    }
+
+    /// `pipe!(x => f => g => h)` is `h(g(f(x)))`, each step is a function, a closure or anything callable.
+    ///
+    /// unlike `pipe`, the steps can change the type of the value.
+    ///
+    /// ```rust
+    /// # use awesome::pipe;
+    /// let len = pipe!("  awesome " => str::trim => str::len => |n| n * 2);
+    ///
+    /// assert_eq!(len, 14);
+    /// ```
+    #[macro_export]
+    macro_rules! pipe {
+        ($x:expr $(=> $f:expr)* $(,)?) => {{
+            let value = $x;
+            $(let value = ($f)(value);)*
+            value
+        }};
+    }
+
+    /// `try_pipe!(x => f => g)` is `pipe!` for steps returning `Result`, it stops at the first `Err` and returns it,
+    /// otherwise `Ok` of the last value. the steps must have the same error type, it is not converted.
+    ///
+    /// ```rust
+    /// # use awesome::try_pipe;
+    /// let parse = |s: &str| s.parse::<u8>().map_err(|e| e.to_string());
+    /// let double = |n: u8| n.checked_mul(2).ok_or(format!("{n} * 2 overflows"));
+    ///
+    /// assert_eq!(try_pipe!("21" => parse => double), Ok(42));
+    /// assert_eq!(try_pipe!("200" => parse => double), Err("200 * 2 overflows".to_string()));
+    /// assert!(try_pipe!("x" => parse => double).is_err());
+    /// ```
+    #[macro_export]
+    macro_rules! try_pipe {
+        ($x:expr $(=> $f:expr)* $(,)?) => {
+            'pipe: {
+                let value = $x;
+                $(let value = match ($f)(value) {
+                    ::std::result::Result::Ok(value) => value,
+                    ::std::result::Result::Err(e) => break 'pipe ::std::result::Result::Err(e),
+                };)*
+                ::std::result::Result::Ok(value)
+            }
+        };
+    }
+}
+
+/// # Currying
+///
+/// Turning a function of `n` arguments into `n` functions of one argument, so it can be applied partially:
+/// `curry!(add)(5)` is a function waiting for the second argument.
+pub mod curry {
+    use std::rc::Rc;
+
+    /// Currying for the functions and closures of 2 to 5 arguments, `Args` is the tuple of the argument types.
+    ///
+    /// the curried function is boxed (`Box<dyn Fn(A) -> Box<dyn Fn(B) -> R>>`), so it has a name and can be stored,
+    /// and the function is shared (`Rc`), so every step can be called many times. the arguments given to a step are cloned
+    /// for each call of the next one, so all but the last need `Clone`. `curry2!` to `curry5!` do the same without boxing.
+    ///
+    /// ```rust
+    /// # use awesome::curry::Curry;
+    /// fn volume(w: u32, h: u32, d: u32) -> u32 {
+    ///     w * h * d
+    /// }
+    ///
+    /// let flat = volume.curry()(10)(10);
+    ///
+    /// assert_eq!((flat(1), flat(2)), (100, 200));
+    /// ```
+    pub trait Curry<Args> {
+        type Curried;
+
+        fn curry(self) -> Self::Curried;
+    }
+
+    // `curried!(R; A B)` is `Box<dyn Fn(A) -> Box<dyn Fn(B) -> R>>`.
+    macro_rules! curried {
+        ($r:ty;) => {
+            $r
+        };
+        ($r:ty; $t:ident $($ts:ident)*) => {
+            Box<dyn Fn($t) -> curried!($r; $($ts)*)>
+        };
+    }
+
+    // the closures need their return types, a `Box` of a closure is only coerced to `Box<dyn Fn>` where the type is known.
+    macro_rules! nest {
+        ($f:ident; $r:ty; [$($done:ident)*]; $x:ident: $t:ident) => {
+            Box::new(move |$x: $t| -> $r { $f($($done.clone(),)* $x) })
+        };
+        ($f:ident; $r:ty; [$($done:ident)*]; $x:ident: $t:ident, $($xs:ident: $ts:ident),+) => {
+            Box::new(move |$x: $t| -> curried!($r; $($ts)+) {
+                let $f = Rc::clone(&$f);
+                $(let $done = $done.clone();)*
+                nest!($f; $r; [$($done)* $x]; $($xs: $ts),+)
+            })
+        };
+    }
+
+    macro_rules! impl_curry {
+        ($($x:ident: $t:ident),+; $last_x:ident: $last:ident) => {
+            impl<F, $($t,)+ $last, R> Curry<($($t,)+ $last)> for F
+            where
+                F: Fn($($t,)+ $last) -> R + 'static,
+                $($t: Clone + 'static,)+
+                $last: 'static,
+                R: 'static,
+            {
+                type Curried = curried!(R; $($t)+ $last);
+
+                fn curry(self) -> Self::Curried {
+                    let f = Rc::new(self);
+                    nest!(f; R; []; $($x: $t,)+ $last_x: $last)
+                }
+            }
+        };
+    }
+
+    impl_curry!(a: A; b: B);
+    impl_curry!(a: A, b: B; c: C);
+    impl_curry!(a: A, b: B, c: C; d: D);
+    impl_curry!(a: A, b: B, c: C, d: D; e: E);
+
+    /// `curry!(f)` is `Curry::curry(f)`, for any arity from 2 to 5.
+    ///
+    /// ```rust
+    /// # use awesome::curry;
+    /// let add = |a: i32, b: i32| a + b;
+    /// let add5 = curry!(add)(5);
+    ///
+    /// assert_eq!(add5(3), 8);
+    /// ```
+    #[macro_export]
+    macro_rules! curry {
+        // the steps of `curry2!` to `curry5!`, their closures clone `f` and the arguments so far, like `Curry`.
+        (@plain $f:ident; [$($done:ident)*]; $x:ident) => {
+            move |$x| $f($(::std::clone::Clone::clone(&$done),)* $x)
+        };
+        (@plain $f:ident; [$($done:ident)*]; $x:ident, $($xs:ident),+) => {
+            move |$x| {
+                let $f = ::std::clone::Clone::clone(&$f);
+                $(let $done = ::std::clone::Clone::clone(&$done);)*
+                $crate::curry!(@plain $f; [$($done)* $x]; $($xs),+)
+            }
+        };
+        ($f:expr) => {
+            $crate::curry::Curry::curry($f)
+        };
+    }
+
+    /// `curry2!(f)` is `|a| |b| f(a, b)`, with closures instead of boxes, so `f` only needs `Clone` and does not need to be `'static`.
+    ///
+    /// ```rust
+    /// # use awesome::curry2;
+    /// let greeting = String::from("hello");
+    /// let greet = curry2!(|name: &str, mark: char| format!("{greeting}, {name}{mark}"));
+    ///
+    /// assert_eq!(greet("kim")('!'), "hello, kim!");
+    /// ```
+    #[macro_export]
+    macro_rules! curry2 {
+        ($f:expr) => {{
+            let f = $f;
+            $crate::curry!(@plain f; []; a, b)
+        }};
+    }
+
+    /// `curry3!(f)` is `|a| |b| |c| f(a, b, c)`, see `curry2!`.
+    #[macro_export]
+    macro_rules! curry3 {
+        ($f:expr) => {{
+            let f = $f;
+            $crate::curry!(@plain f; []; a, b, c)
+        }};
+    }
+
+    /// `curry4!(f)` is `|a| |b| |c| |d| f(a, b, c, d)`, see `curry2!`.
+    #[macro_export]
+    macro_rules! curry4 {
+        ($f:expr) => {{
+            let f = $f;
+            $crate::curry!(@plain f; []; a, b, c, d)
+        }};
+    }
+
+    /// `curry5!(f)` is `|a| |b| |c| |d| |e| f(a, b, c, d, e)`, see `curry2!`.
+    #[macro_export]
+    macro_rules! curry5 {
+        ($f:expr) => {{
+            let f = $f;
+            $crate::curry!(@plain f; []; a, b, c, d, e)
+        }};
+    }
+
+    /// Partial application with placeholders, `partial!(f, 1, _, 3)` is `|x| f(1, x, 3)`.
+    ///
+    /// the closure takes one argument per `_`, in order. the other arguments are evaluated once, when the closure is made,
+    /// and cloned for each call.
+    ///
+    /// ```rust
+    /// # use awesome::partial;
+    /// fn clamp(low: i32, x: i32, high: i32) -> i32 {
+    ///     x.max(low).min(high)
+    /// }
+    ///
+    /// let percent = partial!(clamp, 0, _, 100);
+    ///
+    /// assert_eq!((percent(-5), percent(42), percent(250)), (0, 42, 100));
+    /// ```
+    #[macro_export]
+    macro_rules! partial {
+        // each step makes its own `x` or `v`, macro hygiene keeps them apart.
+        (@munch $f:ident; [$($p:ident)*]; [$($a:expr),*]; [$($v:ident = $e:expr;)*];) => {{
+            $(let $v = $e;)*
+            move |$($p),*| $f($($a),*)
+        }};
+        (@munch $f:ident; [$($p:ident)*]; [$($a:expr),*]; [$($lets:tt)*]; _ $(, $($rest:tt)*)?) => {
+            $crate::partial!(@munch $f; [$($p)* x]; [$($a,)* x]; [$($lets)*]; $($($rest)*)?)
+        };
+        (@munch $f:ident; [$($p:ident)*]; [$($a:expr),*]; [$($lets:tt)*]; $e:expr $(, $($rest:tt)*)?) => {
+            $crate::partial!(@munch $f; [$($p)*]; [$($a,)* ::std::clone::Clone::clone(&v)]; [$($lets)* v = $e;]; $($($rest)*)?)
+        };
+        ($f:expr $(, $($args:tt)*)?) => {{
+            let f = $f;
+            $crate::partial!(@munch f; []; []; []; $($($args)*)?)
+        }};
+    }
+}
+
+/// # Lazy Evaluation
+///
+/// Call-by-need: a `Lazy` value is computed the first time it is needed, and never again. a plain closure is call-by-name,
+/// it runs every time it is called.
+pub mod lazy {
+    use std::cell::{Cell, OnceCell};
+    use std::fmt;
+
+    /// A thunk, a value computed on the first `force` and then kept.
+    ///
+    /// ```rust
+    /// # use awesome::lazy::Lazy;
+    /// let answer = Lazy::new(|| (1..=6).product::<u32>() / 24 + 12);
+    /// assert!(!answer.is_forced());
+    ///
+    /// assert_eq!(*answer.force(), 42);
+    /// assert!(answer.is_forced());
+    /// ```
+    pub struct Lazy<'a, T> {
+        value: OnceCell<T>,
+        init: Cell<Option<Box<dyn FnOnce() -> T + 'a>>>,
+    }
+
+    impl<'a, T> Lazy<'a, T> {
+        pub fn new(f: impl FnOnce() -> T + 'a) -> Self {
+            Lazy {
+                value: OnceCell::new(),
+                init: Cell::new(Some(Box::new(f))),
+            }
+        }
+
+        /// An already evaluated value.
+        pub fn ready(value: T) -> Self {
+            Lazy {
+                value: OnceCell::from(value),
+                init: Cell::new(None),
+            }
+        }
+
+        /// Evaluates the value if it is not yet, the closure runs at most once.
+        ///
+        /// # Panics
+        ///
+        /// if the closure forces its own `Lazy`, the value would depend on itself.
+        pub fn force(&self) -> &T {
+            self.value.get_or_init(|| {
+                let init = self.init.take().expect("a `Lazy` was forced during its own evaluation");
+                init()
+            })
+        }
+
+        pub fn is_forced(&self) -> bool {
+            self.value.get().is_some()
+        }
+
+        /// Forces the value and takes it.
+        pub fn into_inner(self) -> T {
+            self.force();
+            self.value.into_inner().expect("the value was just forced")
+        }
+
+        /// The value if it was forced, it is not evaluated otherwise.
+        pub fn into_forced(self) -> Option<T> {
+            self.value.into_inner()
+        }
+
+        /// A lazy value computed from this one, nothing is evaluated until the result is forced.
+        ///
+        /// ```rust
+        /// # use awesome::lazy::Lazy;
+        /// let len = Lazy::new(|| "hello".to_string()).map(|s| s.len());
+        ///
+        /// assert_eq!(len.into_inner(), 5);
+        /// ```
+        pub fn map<U>(self, f: impl FnOnce(T) -> U + 'a) -> Lazy<'a, U>
+        where
+            T: 'a,
+        {
+            Lazy::new(move || f(self.into_inner()))
+        }
+
+        /// Chains a lazy computation depending on this value, like `bind`.
+        pub fn and_then<U>(self, f: impl FnOnce(T) -> Lazy<'a, U> + 'a) -> Lazy<'a, U>
+        where
+            T: 'a,
+        {
+            Lazy::new(move || f(self.into_inner()).into_inner())
+        }
+    }
+
+    impl<T: fmt::Debug> fmt::Debug for Lazy<'_, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.value.get() {
+                Some(value) => f.debug_tuple("Lazy").field(value).finish(),
+                None => f.write_str("Lazy(<unevaluated>)"),
+            }
+        }
+    }
+}
+
+/// # Lazy Stream
+///
+/// An infinite (or finite) lazy list: a head and a thunk of the tail, the cells are built when they are first needed
+/// and shared after that, like Haskell's lists. cloning a stream clones the head and shares the rest.
+pub mod stream {
+    use crate::lazy::Lazy;
+    use std::rc::Rc;
+
+    /// A lazy linked list, `Cons(head, thunk of the tail)`. the tail is computed once (see [`Lazy`]), even if the stream is walked many times.
+    ///
+    /// ```rust
+    /// # use awesome::stream::Stream;
+    /// let odd_squares = Stream::iterate(1u32, |n| n + 1).map(|n| n * n).filter(|n| n % 2 == 1);
+    ///
+    /// assert_eq!(odd_squares.take(4).iter().collect::<Vec<_>>(), vec![1, 9, 25, 49]);
+    /// ```
+    #[derive(Clone)]
+    pub enum Stream<T> {
+        Nil,
+        Cons(T, Rc<Lazy<'static, Stream<T>>>),
+    }
+
+    impl<T: Clone + 'static> Stream<T> {
+        pub fn empty() -> Self {
+            Stream::Nil
+        }
+
+        /// A stream starting with `head`, `tail` is called the first time the rest is needed.
+        pub fn cons(head: T, tail: impl FnOnce() -> Stream<T> + 'static) -> Self {
+            Stream::Cons(head, Rc::new(Lazy::new(tail)))
+        }
+
+        pub fn head(&self) -> Option<&T> {
+            match self {
+                Stream::Nil => None,
+                Stream::Cons(head, _) => Some(head),
+            }
+        }
+
+        /// The rest of the stream, it is forced.
+        pub fn tail(&self) -> Option<Stream<T>> {
+            match self {
+                Stream::Nil => None,
+                Stream::Cons(_, tail) => Some(tail.force().clone()),
+            }
+        }
+
+        /// `seed, f(seed), f(f(seed)), ...`, infinite.
+        pub fn iterate(seed: T, f: impl Fn(&T) -> T + 'static) -> Self {
+            Self::iterate_rc(seed, Rc::new(f))
+        }
+
+        fn iterate_rc(seed: T, f: Rc<dyn Fn(&T) -> T>) -> Self {
+            let next = f(&seed);
+            Stream::cons(seed, move || Self::iterate_rc(next, f))
+        }
+
+        /// Builds a stream from a state, `f` returns the next item and state, or `None` to end it.
+        ///
+        /// ```rust
+        /// # use awesome::stream::Stream;
+        /// let digits = Stream::unfold(1234u32, |n| (n > 0).then(|| (n % 10, n / 10)));
+        ///
+        /// assert_eq!(digits.iter().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+        /// ```
+        pub fn unfold<S: 'static>(seed: S, f: impl Fn(S) -> Option<(T, S)> + 'static) -> Self {
+            Self::unfold_rc(seed, Rc::new(f))
+        }
+
+        fn unfold_rc<S: 'static>(seed: S, f: Rc<dyn Fn(S) -> Option<(T, S)>>) -> Self {
+            match f(seed) {
+                None => Stream::Nil,
+                Some((head, next)) => Stream::cons(head, move || Self::unfold_rc(next, f)),
+            }
+        }
+
+        /// The first `n` items, lazily.
+        pub fn take(&self, n: usize) -> Self {
+            match self {
+                Stream::Cons(head, tail) if n > 0 => {
+                    let tail = Rc::clone(tail);
+                    Stream::cons(head.clone(), move || tail.force().take(n - 1))
+                }
+                _ => Stream::Nil,
+            }
+        }
+
+        pub fn map<U: Clone + 'static>(&self, f: impl Fn(&T) -> U + 'static) -> Stream<U> {
+            self.map_rc(Rc::new(f))
+        }
+
+        fn map_rc<U: Clone + 'static>(&self, f: Rc<dyn Fn(&T) -> U>) -> Stream<U> {
+            match self {
+                Stream::Nil => Stream::Nil,
+                Stream::Cons(head, tail) => {
+                    let tail = Rc::clone(tail);
+                    Stream::cons(f(head), move || tail.force().map_rc(f))
+                }
+            }
+        }
+
+        /// The items matching `predicate`. it looks for the next match when it is needed,
+        /// so on an infinite stream without one more match, that step never ends.
+        pub fn filter(&self, predicate: impl Fn(&T) -> bool + 'static) -> Self {
+            self.filter_rc(Rc::new(predicate))
+        }
+
+        fn filter_rc(&self, predicate: Rc<dyn Fn(&T) -> bool>) -> Self {
+            let mut stream = self.clone();
+            loop {
+                stream = match &stream {
+                    Stream::Nil => return Stream::Nil,
+                    Stream::Cons(head, tail) if predicate(head) => {
+                        let tail = Rc::clone(tail);
+                        return Stream::cons(head.clone(), move || tail.force().filter_rc(predicate));
+                    }
+                    Stream::Cons(_, tail) => tail.force().clone(),
+                }
+            }
+        }
+
+        /// An `Iterator` over the items, cloned. it forces the cells as it goes.
+        pub fn iter(&self) -> Iter<T> {
+            Iter(self.clone())
+        }
+    }
+
+    impl<T> Stream<T> {
+        // takes the rest of the stream out of this cell if it is forced and not shared, the cell keeps an empty tail.
+        fn take_forced_tail(&mut self) -> Option<Stream<T>> {
+            match self {
+                Stream::Nil => None,
+                Stream::Cons(_, tail) => Rc::get_mut(tail).and_then(|lazy| std::mem::replace(lazy, Lazy::ready(Stream::Nil)).into_forced()),
+            }
+        }
+    }
+
+    /// The forced cells are dropped in a loop, a recursive drop would overflow the stack after a long walk.
+    /// it stops at a cell that is shared with another stream or not forced yet.
+    impl<T> Drop for Stream<T> {
+        fn drop(&mut self) {
+            let mut next = self.take_forced_tail();
+            while let Some(mut stream) = next {
+                next = stream.take_forced_tail();
+            }
+        }
+    }
+
+    impl<T: Clone + 'static> IntoIterator for Stream<T> {
+        type Item = T;
+        type IntoIter = Iter<T>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            Iter(self)
+        }
+    }
+
+    /// The iterator of a [`Stream`].
+    pub struct Iter<T>(Stream<T>);
+
+    impl<T: Clone + 'static> Iterator for Iter<T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let (head, rest) = match &self.0 {
+                Stream::Nil => return None,
+                Stream::Cons(head, tail) => (head.clone(), tail.force().clone()),
+            };
+            self.0 = rest;
+            Some(head)
+        }
+    }
+
+    /// The Fibonacci numbers `0, 1, 1, 2, 3, ...`, all that fit in a `u64` (94 of them).
+    ///
+    /// ```rust
+    /// # use awesome::stream::fibs;
+    /// assert_eq!(fibs().take(10).iter().collect::<Vec<_>>(), vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+    /// ```
+    pub fn fibs() -> Stream<u64> {
+        // the state is the next two numbers, the second one is `None` once it overflows.
+        Stream::unfold(Some((0u64, Some(1u64))), |state| {
+            let (a, b) = state?;
+            Some((a, b.map(|b| (b, a.checked_add(b)))))
+        })
+    }
+}
+
+/// # Memoization
+///
+/// Caching the results of a pure function by argument, so each one is computed once. `memoize` and `memoize_rec` are
+/// for one thread (`RefCell`), `memoize_sync` and `memoize_rec_sync` can be shared between threads (`Mutex`).
+pub mod memo {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::hash::Hash;
+    use std::sync::{Mutex, PoisonError};
+
+    /// Where the results are kept.
+    trait Store<K, V> {
+        fn get(&self, key: &K) -> Option<V>;
+        fn insert(&self, key: K, value: V);
+    }
+
+    impl<K: Hash + Eq, V: Clone> Store<K, V> for RefCell<HashMap<K, V>> {
+        fn get(&self, key: &K) -> Option<V> {
+            self.borrow().get(key).cloned()
+        }
+
+        fn insert(&self, key: K, value: V) {
+            self.borrow_mut().insert(key, value);
+        }
+    }
+
+    impl<K: Hash + Eq, V: Clone> Store<K, V> for Mutex<HashMap<K, V>> {
+        // a panic in `f` happens outside of the lock, but a poisoned map is still consistent, so it is used anyway.
+        fn get(&self, key: &K) -> Option<V> {
+            self.lock().unwrap_or_else(PoisonError::into_inner).get(key).cloned()
+        }
+
+        fn insert(&self, key: K, value: V) {
+            self.lock().unwrap_or_else(PoisonError::into_inner).insert(key, value);
+        }
+    }
+
+    struct Memo<S, F> {
+        store: S,
+        f: F,
+    }
+
+    impl<S, F> Memo<S, F> {
+        // the store is not borrowed (or locked) while `f` runs, so `f` can call back into it.
+        fn call<K, V>(&self, key: K) -> V
+        where
+            K: Clone,
+            V: Clone,
+            S: Store<K, V>,
+            F: Fn(&dyn Fn(K) -> V, K) -> V,
+        {
+            if let Some(value) = self.store.get(&key) {
+                return value;
+            }
+            let value = (self.f)(&|key| self.call(key), key.clone());
+            self.store.insert(key, value.clone());
+            value
+        }
+    }
+
+    /// A cached version of `f`, for one thread.
+    ///
+    /// ```rust
+    /// # use awesome::memo::memoize;
+    /// let calls = std::cell::Cell::new(0);
+    /// let square = memoize(|n: u64| {
+    ///     calls.set(calls.get() + 1);
+    ///     n * n
+    /// });
+    ///
+    /// assert_eq!(square(12) + square(12), 288);
+    /// assert_eq!(calls.get(), 1);
+    /// ```
+    pub fn memoize<K, V>(f: impl Fn(K) -> V) -> impl Fn(K) -> V
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+    {
+        memoize_rec(move |_, key| f(key))
+    }
+
+    /// A cached recursive function, `f` gets the memoized function itself to make the recursive calls through the cache.
+    ///
+    /// ```rust
+    /// # use awesome::memo::memoize_rec;
+    /// let fib = memoize_rec(|fib, n: u64| if n < 2 { n } else { fib(n - 1) + fib(n - 2) });
+    ///
+    /// assert_eq!(fib(90), 2_880_067_194_370_816_120);
+    /// ```
+    pub fn memoize_rec<K, V>(f: impl Fn(&dyn Fn(K) -> V, K) -> V) -> impl Fn(K) -> V
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+    {
+        let memo = Memo {
+            store: RefCell::new(HashMap::new()),
+            f,
+        };
+        move |key| memo.call(key)
+    }
+
+    /// `memoize` for many threads, the cache is behind a `Mutex`. it is not locked while `f` runs,
+    /// so two threads asking for the same new key at once may both compute it.
+    pub fn memoize_sync<K, V>(f: impl Fn(K) -> V + Send + Sync) -> impl Fn(K) -> V + Send + Sync
+    where
+        K: Hash + Eq + Clone + Send,
+        V: Clone + Send,
+    {
+        memoize_rec_sync(move |_, key| f(key))
+    }
+
+    /// `memoize_rec` for many threads, see `memoize_sync`.
+    pub fn memoize_rec_sync<K, V>(f: impl Fn(&dyn Fn(K) -> V, K) -> V + Send + Sync) -> impl Fn(K) -> V + Send + Sync
+    where
+        K: Hash + Eq + Clone + Send,
+        V: Clone + Send,
+    {
+        let memo = Memo {
+            store: Mutex::new(HashMap::new()),
+            f,
+        };
+        move |key| memo.call(key)
+    }
+
+    /// Defines a memoized function, the recursive calls go through the cache too.
+    ///
+    /// the cache is a `thread_local!` inside the function, keyed by the tuple of the arguments,
+    /// so the arguments need `Hash + Eq + Clone`, the result needs `Clone`, and the function cannot be generic.
+    ///
+    /// ```rust
+    /// # use awesome::memoize;
+    /// memoize! {
+    ///     /// The number of paths from the top left to the bottom right of a grid, moving right or down.
+    ///     fn paths(width: u32, height: u32) -> u64 {
+    ///         if width == 0 || height == 0 { 1 } else { paths(width - 1, height) + paths(width, height - 1) }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(paths(16, 16), 601_080_390);
+    /// ```
+    #[macro_export]
+    macro_rules! memoize {
+        ($(#[$meta:meta])* $vis:vis fn $name:ident($($arg:ident : $ty:ty),* $(,)?) -> $ret:ty $body:block) => {
+            $(#[$meta])*
+            $vis fn $name($($arg: $ty),*) -> $ret {
+                ::std::thread_local! {
+                    static CACHE: ::std::cell::RefCell<::std::collections::HashMap<($($ty,)*), $ret>> =
+                        ::std::cell::RefCell::new(::std::collections::HashMap::new());
+                }
+
+                fn compute($($arg: $ty),*) -> $ret $body
+
+                let key = ($(::std::clone::Clone::clone(&$arg),)*);
+                if let ::std::option::Option::Some(value) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+                    return value;
+                }
+                let value = compute($($arg),*);
+                CACHE.with(|cache| cache.borrow_mut().insert(key, ::std::clone::Clone::clone(&value)));
+                value
+            }
+        };
+    }
+}
+
+/// # Active Patterns
+///
+/// F#'s active patterns in Rust: a pattern is a value that tries to take a value apart, `extract` returns the parts (the bindings)
+/// or `None`, and `match_with!` tries patterns in order like a `match`. unlike the built-in patterns, they can run any code,
+/// e.g. parse a number or strip a prefix.
+pub mod pattern {
+    use std::ops::{Div, Rem};
+
+    /// A pattern matching values of type `T`, `'a` is how long the value is borrowed, so the bindings can borrow from it.
+    ///
+    /// any `Fn(&T) -> Option<B>` is a pattern, F#'s partial active patterns (`(|Int|_|)`) are such functions.
+    pub trait Pattern<'a, T: ?Sized> {
+        type Bindings;
+
+        fn extract(&self, value: &'a T) -> Option<Self::Bindings>;
+    }
+
+    impl<'a, T, B, F> Pattern<'a, T> for F
+    where
+        T: ?Sized + 'a,
+        F: Fn(&'a T) -> Option<B>,
+    {
+        type Bindings = B;
+
+        fn extract(&self, value: &'a T) -> Option<B> {
+            self(value)
+        }
+    }
+
+    /// Matches the strings starting with the prefix, the binding is the rest.
+    pub struct Prefix<'p>(pub &'p str);
+
+    impl<'a, S: AsRef<str> + ?Sized> Pattern<'a, S> for Prefix<'_> {
+        type Bindings = &'a str;
+
+        fn extract(&self, value: &'a S) -> Option<&'a str> {
+            value.as_ref().strip_prefix(self.0)
+        }
+    }
+
+    /// Matches the strings containing the separator, the bindings are the parts before and after its first occurrence.
+    pub struct Split(pub char);
+
+    impl<'a, S: AsRef<str> + ?Sized> Pattern<'a, S> for Split {
+        type Bindings = (&'a str, &'a str);
+
+        fn extract(&self, value: &'a S) -> Option<(&'a str, &'a str)> {
+            value.as_ref().split_once(self.0)
+        }
+    }
+
+    /// Matches the multiples of the number, the binding is the quotient.
+    pub struct DivisibleBy<T>(pub T);
+
+    impl<'a, T> Pattern<'a, T> for DivisibleBy<T>
+    where
+        T: Copy + PartialEq + Default + Rem<Output = T> + Div<Output = T>,
+    {
+        type Bindings = T;
+
+        fn extract(&self, value: &'a T) -> Option<T> {
+            (self.0 != T::default() && *value % self.0 == T::default()).then(|| *value / self.0)
+        }
+    }
+
+    /// `match_with!(value; bindings in pattern => expr, ..., _ => expr)` tries the patterns in order,
+    /// the first one whose `extract` returns `Some(bindings)` is taken, `bindings` is a Rust pattern for them.
+    ///
+    /// the value is borrowed (`&value`). if the bindings don't match the Rust pattern (e.g. `(x, 0) in ...`), the next pattern is tried.
+    /// the patterns are not checked for exhaustiveness, so the last arm must be `_`.
+    ///
+    /// ```rust
+    /// # use awesome::match_with;
+    /// use awesome::pattern::{DivisibleBy, Prefix};
+    ///
+    /// let describe = |command: &str| {
+    ///     match_with!(*command;
+    ///         name in Prefix("--") => format!("flag {name}"),
+    ///         n in |s: &str| s.parse::<u32>().ok() => match_with!(n;
+    ///             q in DivisibleBy(2) => format!("{n} is twice {q}"),
+    ///             _ => format!("{n} is odd"),
+    ///         ),
+    ///         _ => "unknown".to_string(),
+    ///     )
+    /// };
+    ///
+    /// assert_eq!(describe("--verbose"), "flag verbose");
+    /// assert_eq!(describe("42"), "42 is twice 21");
+    /// assert_eq!(describe("7"), "7 is odd");
+    /// assert_eq!(describe("?"), "unknown");
+    /// ```
+    #[macro_export]
+    macro_rules! match_with {
+        (@arms $value:ident; _ => $default:expr $(,)?) => {
+            $default
+        };
+        (@arms $value:ident; $bindings:pat in $pattern:expr => $body:expr, $($rest:tt)*) => {
+            if let ::std::option::Option::Some($bindings) = $crate::pattern::Pattern::extract(&$pattern, $value) {
+                $body
+            } else {
+                $crate::match_with!(@arms $value; $($rest)*)
+            }
+        };
+        ($value:expr; $($arms:tt)*) => {{
+            let value = &$value;
+            $crate::match_with!(@arms value; $($arms)*)
+        }};
+    }
+}
+
+/// # Optics
+///
+/// Functional references into a structure: a `Lens` focuses on a part that is always there (a field), a `Prism` on a part that
+/// may not be (an enum variant). updating through them returns a new structure, so nested updates don't need to be written out.
+pub mod optics {
+    use std::rc::Rc;
+
+    /// `#[derive(Lenses)]` on a struct with named fields adds `Type::field_lens()` for each field.
+    ///
+    /// ```rust
+    /// # use awesome::optics::Lenses;
+    /// #[derive(Debug, Clone, PartialEq, Lenses)]
+    /// struct Pair<T> {
+    ///     left: T,
+    ///     right: T,
+    /// }
+    ///
+    /// let pair = Pair { left: 1, right: 2 };
+    ///
+    /// assert_eq!(Pair::right_lens().set(pair, 5), Pair { left: 1, right: 5 });
+    /// ```
+    pub use awesome_macros::Lenses;
+
+    /// A getter and a setter for a part `A` of `S`.
+    ///
+    /// a lawful lens satisfies `view(&set(s, a)) == a`, `set(s, view(&s)) == s` and `set(set(s, a), b) == set(s, b)`.
+    ///
+    /// ```rust
+    /// # use awesome::{lens, optics::Lens};
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let x = lens!(Point, x);
+    /// let p = Point { x: 1, y: 2 };
+    ///
+    /// assert_eq!(x.view(&p), 1);
+    /// assert_eq!(x.over(p, |x| x + 10), Point { x: 11, y: 2 });
+    /// ```
+    pub struct Lens<S, A> {
+        get: Rc<dyn Fn(&S) -> A>,
+        set: Rc<dyn Fn(S, A) -> S>,
+    }
+
+    impl<S, A> Clone for Lens<S, A> {
+        fn clone(&self) -> Self {
+            Lens {
+                get: Rc::clone(&self.get),
+                set: Rc::clone(&self.set),
+            }
+        }
+    }
+
+    impl<S: 'static, A: 'static> Lens<S, A> {
+        pub fn new(get: impl Fn(&S) -> A + 'static, set: impl Fn(S, A) -> S + 'static) -> Self {
+            Lens {
+                get: Rc::new(get),
+                set: Rc::new(set),
+            }
+        }
+
+        pub fn view(&self, s: &S) -> A {
+            (self.get)(s)
+        }
+
+        pub fn set(&self, s: S, a: A) -> S {
+            (self.set)(s, a)
+        }
+
+        /// Replaces the part with `f` of it.
+        pub fn over(&self, s: S, f: impl FnOnce(A) -> A) -> S {
+            let a = self.view(&s);
+            self.set(s, f(a))
+        }
+
+        /// The lens on the part `B` of the part `A`, `self` then `other`.
+        pub fn compose<B: 'static>(self, other: Lens<A, B>) -> Lens<S, B> {
+            let (outer, inner) = (self.clone(), other.clone());
+            Lens::new(
+                move |s| other.view(&self.view(s)),
+                move |s, b| {
+                    let a = outer.view(&s);
+                    outer.set(s, inner.set(a, b))
+                },
+            )
+        }
+    }
+
+    /// A lens on a field (or a path of fields) of a struct, the field is cloned by `view`.
+    ///
+    /// `lens!(Type, a.b.c)` is one lens for `s.a.b.c`, the same as composing the lenses of each field.
+    #[macro_export]
+    macro_rules! lens {
+        ($ty:ty, $($field:tt).+) => {
+            $crate::optics::Lens::new(
+                |s: &$ty| ::std::clone::Clone::clone(&s.$($field).+),
+                |mut s: $ty, value| {
+                    s.$($field).+ = value;
+                    s
+                },
+            )
+        };
+    }
+
+    /// A part `A` of `S` that may not be there, with a way back: `preview` extracts it, `review` builds an `S` from it.
+    ///
+    /// a lawful prism satisfies `preview(&review(a)) == Some(a)`.
+    ///
+    /// ```rust
+    /// # use awesome::{prism, optics::Prism};
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum Shape {
+    ///     Circle(f64),
+    ///     Square(f64),
+    /// }
+    ///
+    /// let circle = prism!(Shape::Circle);
+    ///
+    /// assert_eq!(circle.preview(&Shape::Circle(1.0)), Some(1.0));
+    /// assert_eq!(circle.over(Shape::Circle(1.0), |r| r * 2.0), Shape::Circle(2.0));
+    /// assert_eq!(circle.over(Shape::Square(1.0), |r| r * 2.0), Shape::Square(1.0));
+    /// ```
+    pub struct Prism<S, A> {
+        preview: Preview<S, A>,
+        review: Rc<dyn Fn(A) -> S>,
+    }
+
+    type Preview<S, A> = Rc<dyn Fn(&S) -> Option<A>>;
+
+    impl<S, A> Clone for Prism<S, A> {
+        fn clone(&self) -> Self {
+            Prism {
+                preview: Rc::clone(&self.preview),
+                review: Rc::clone(&self.review),
+            }
+        }
+    }
+
+    impl<S: 'static, A: 'static> Prism<S, A> {
+        pub fn new(preview: impl Fn(&S) -> Option<A> + 'static, review: impl Fn(A) -> S + 'static) -> Self {
+            Prism {
+                preview: Rc::new(preview),
+                review: Rc::new(review),
+            }
+        }
+
+        pub fn preview(&self, s: &S) -> Option<A> {
+            (self.preview)(s)
+        }
+
+        pub fn review(&self, a: A) -> S {
+            (self.review)(a)
+        }
+
+        /// Replaces the part with `f` of it if it is there, otherwise `s` is returned as is.
+        pub fn over(&self, s: S, f: impl FnOnce(A) -> A) -> S {
+            match self.preview(&s) {
+                Some(a) => self.review(f(a)),
+                None => s,
+            }
+        }
+
+        /// The prism on the part `B` of the part `A`, `self` then `other`.
+        pub fn compose<B: 'static>(self, other: Prism<A, B>) -> Prism<S, B> {
+            let (outer, inner) = (self.clone(), other.clone());
+            Prism::new(move |s| self.preview(s).and_then(|a| other.preview(&a)), move |b| outer.review(inner.review(b)))
+        }
+    }
+
+    /// A prism on a variant with one field, `prism!(Enum::Variant)`, the field is cloned by `preview`.
+    #[macro_export]
+    macro_rules! prism {
+        ($($variant:ident)::+) => {
+            $crate::optics::Prism::new(
+                |s| match s {
+                    $($variant)::+(a) => ::std::option::Option::Some(::std::clone::Clone::clone(a)),
+                    #[allow(unreachable_patterns)]
+                    _ => ::std::option::Option::None,
+                },
+                $($variant)::+,
+            )
+        };
+    }
+}
+
+/// # Zipper
+///
+/// A zipper is a tree with a focus: the subtree in focus and the path back to the root, with everything that was passed on the way.
+/// moving and editing at the focus are `O(1)`, and rebuilding the tree only touches the path, the rest is reused as is.
+pub mod zipper {
+    /// A binary tree.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Tree<T> {
+        Leaf,
+        Node(Box<Tree<T>>, T, Box<Tree<T>>),
+    }
+
+    impl<T> Tree<T> {
+        pub fn node(left: Tree<T>, value: T, right: Tree<T>) -> Self {
+            Tree::Node(Box::new(left), value, Box::new(right))
+        }
+
+        /// A node without children.
+        pub fn single(value: T) -> Self {
+            Tree::node(Tree::Leaf, value, Tree::Leaf)
+        }
+
+        pub fn value(&self) -> Option<&T> {
+            match self {
+                Tree::Leaf => None,
+                Tree::Node(_, value, _) => Some(value),
+            }
+        }
+
+        /// The values from left to right.
+        pub fn in_order(&self) -> Vec<&T> {
+            match self {
+                Tree::Leaf => vec![],
+                Tree::Node(left, value, right) => {
+                    let mut values = left.in_order();
+                    values.push(value);
+                    values.extend(right.in_order());
+                    values
+                }
+            }
+        }
+    }
+
+    /// A step of the path to the focus, with what is needed to rebuild the parent.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Crumb<T> {
+        /// went to the left child, the parent had this value and right child.
+        Left(T, Tree<T>),
+        /// went to the right child, the parent had this left child and value.
+        Right(Tree<T>, T),
+    }
+
+    /// A position in a `Tree`. the moves take the zipper and return `None` if they are not possible (at a leaf, or at the top).
+    ///
+    /// ```rust
+    /// # use awesome::zipper::{Tree, Zipper};
+    /// let tree = Tree::node(Tree::single(1), 2, Tree::single(3));
+    ///
+    /// let edited = Zipper::new(tree).down_right().unwrap().modify(|n| n * 10).to_tree();
+    ///
+    /// assert_eq!(edited, Tree::node(Tree::single(1), 2, Tree::single(30)));
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Zipper<T> {
+        focus: Tree<T>,
+        path: Vec<Crumb<T>>,
+    }
+
+    impl<T> Zipper<T> {
+        /// A zipper focused on the root.
+        pub fn new(tree: Tree<T>) -> Self {
+            Zipper { focus: tree, path: Vec::new() }
+        }
+
+        pub fn focus(&self) -> &Tree<T> {
+            &self.focus
+        }
+
+        pub fn value(&self) -> Option<&T> {
+            self.focus.value()
+        }
+
+        /// The number of moves up to the root.
+        pub fn depth(&self) -> usize {
+            self.path.len()
+        }
+
+        pub fn is_top(&self) -> bool {
+            self.path.is_empty()
+        }
+
+        pub fn down_left(mut self) -> Option<Self> {
+            match self.focus {
+                Tree::Leaf => None,
+                Tree::Node(left, value, right) => {
+                    self.path.push(Crumb::Left(value, *right));
+                    Some(Zipper { focus: *left, path: self.path })
+                }
+            }
+        }
+
+        pub fn down_right(mut self) -> Option<Self> {
+            match self.focus {
+                Tree::Leaf => None,
+                Tree::Node(left, value, right) => {
+                    self.path.push(Crumb::Right(*left, value));
+                    Some(Zipper { focus: *right, path: self.path })
+                }
+            }
+        }
+
+        /// Moves to the parent, rebuilding it from the focus, which may have been edited.
+        pub fn up(mut self) -> Option<Self> {
+            let focus = match self.path.pop()? {
+                Crumb::Left(value, right) => Tree::node(self.focus, value, right),
+                Crumb::Right(left, value) => Tree::node(left, value, self.focus),
+            };
+            Some(Zipper { focus, path: self.path })
+        }
+
+        /// Moves up to the root.
+        pub fn top(mut self) -> Self {
+            while !self.is_top() {
+                self = self.up().expect("not at the top");
+            }
+            self
+        }
+
+        /// Replaces the value in focus with `f` of it, nothing happens at a leaf.
+        pub fn modify(self, f: impl FnOnce(T) -> T) -> Self {
+            let focus = match self.focus {
+                Tree::Leaf => Tree::Leaf,
+                Tree::Node(left, value, right) => Tree::Node(left, f(value), right),
+            };
+            Zipper { focus, path: self.path }
+        }
+
+        /// Replaces the subtree in focus.
+        pub fn replace(self, tree: Tree<T>) -> Self {
+            Zipper { focus: tree, path: self.path }
+        }
+
+        /// The whole tree, with the edits.
+        pub fn to_tree(self) -> Tree<T> {
+            self.top().focus
+        }
+    }
+}
+
+/// # Persistent List
+///
+/// An immutable singly linked list, the `cons` list of functional languages. `push_front` and `tail` are `O(1)` and never copy:
+/// the new list shares its nodes with the old one (`Rc`), so every version stays valid.
+pub mod list {
+    use crate::{applicative::Applicative, functor::Functor, monad::Monad};
+    use std::fmt;
+    use std::rc::Rc;
+
+    // the value is behind its own `Rc` so that `bind` can put it in a new list without moving it out of a shared node.
+    struct Node<T> {
+        value: Rc<T>,
+        next: Option<Rc<Node<T>>>,
+    }
+
+    /// A persistent list, cloning it is `O(1)` (it shares every node).
+    ///
+    /// ```rust
+    /// # use awesome::list::List;
+    /// let tail = List::new().push_front(3).push_front(2);
+    /// let a = tail.push_front(1);
+    /// let b = tail.push_front(10);
+    ///
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    /// assert_eq!(b.iter().collect::<Vec<_>>(), vec![&10, &2, &3]);
+    /// assert_eq!(a.tail(), b.tail());
+    /// ```
+    pub struct List<T> {
+        head: Option<Rc<Node<T>>>,
+        len: usize,
+    }
+
+    impl<T> List<T> {
+        pub fn new() -> Self {
+            List { head: None, len: 0 }
+        }
+
+        /// A new list with `value` in front of this one, which is shared, not copied.
+        pub fn push_front(&self, value: T) -> Self {
+            List {
+                head: Some(Rc::new(Node {
+                    value: Rc::new(value),
+                    next: self.head.clone(),
+                })),
+                len: self.len + 1,
+            }
+        }
+
+        pub fn head(&self) -> Option<&T> {
+            self.head.as_ref().map(|node| &*node.value)
+        }
+
+        /// The list without its first element, it shares the nodes of this one. `None` if the list is empty.
+        pub fn tail(&self) -> Option<Self> {
+            self.head.as_ref().map(|node| List {
+                head: node.next.clone(),
+                len: self.len - 1,
+            })
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.head.is_none()
+        }
+
+        /// How many lists (and nodes of other lists) share the first node, `0` for the empty list.
+        pub fn strong_count(&self) -> usize {
+            self.head.as_ref().map_or(0, Rc::strong_count)
+        }
+
+        pub fn iter(&self) -> Iter<'_, T> {
+            Iter { next: self.head.as_deref() }
+        }
+
+        /// Folds from the front.
+        pub fn fold<B>(&self, init: B, f: impl FnMut(B, &T) -> B) -> B {
+            self.iter().fold(init, f)
+        }
+
+        /// A new list with `f` of each element, in the same order.
+        pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> List<U> {
+            self.iter().map(f).collect()
+        }
+
+        /// A new list in the reverse order, the elements are cloned.
+        pub fn reverse(&self) -> Self
+        where
+            T: Clone,
+        {
+            self.fold(List::new(), |list, x| list.push_front(x.clone()))
+        }
+    }
+
+    impl<T> List<T> {
+        // the values of the nodes, shared (not cloned), in order.
+        fn shared_values(&self) -> impl Iterator<Item = Rc<T>> + '_ {
+            let mut next = self.head.as_deref();
+            std::iter::from_fn(move || {
+                next.map(|node| {
+                    next = node.next.as_deref();
+                    node.value.clone()
+                })
+            })
+        }
+
+        fn from_shared(values: Vec<Rc<T>>) -> Self {
+            let len = values.len();
+            let head = values.into_iter().rev().fold(None, |next, value| Some(Rc::new(Node { value, next })));
+            List { head, len }
+        }
+    }
+
+    impl<T> Default for List<T> {
+        fn default() -> Self {
+            List::new()
+        }
+    }
+
+    impl<T> Clone for List<T> {
+        fn clone(&self) -> Self {
+            List {
+                head: self.head.clone(),
+                len: self.len,
+            }
+        }
+    }
+
+    /// The nodes are dropped in a loop, a recursive drop would overflow the stack on a long list.
+    /// it stops at the first node that is shared with another list.
+    impl<T> Drop for List<T> {
+        fn drop(&mut self) {
+            let mut next = self.head.take();
+            while let Some(node) = next {
+                match Rc::try_unwrap(node) {
+                    Ok(mut node) => next = node.next.take(),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    impl<T: PartialEq> PartialEq for List<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.iter().eq(other.iter())
+        }
+    }
+
+    impl<T: Eq> Eq for List<T> {}
+
+    impl<T: fmt::Debug> fmt::Debug for List<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    /// Collects in order, the first item is the head.
+    impl<T> FromIterator<T> for List<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let items: Vec<T> = iter.into_iter().collect();
+            items.into_iter().rev().fold(List::new(), |list, x| list.push_front(x))
+        }
+    }
+
+    /// The iterator of `List::iter`.
+    pub struct Iter<'a, T> {
+        next: Option<&'a Node<T>>,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.next.map(|node| {
+                self.next = node.next.as_deref();
+                &*node.value
+            })
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a List<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
+
+    /// Functor implementation for `List<T>`, the elements are cloned out of the shared nodes.
+    impl<T: Clone> Functor for List<T> {
+        type T = T;
+        type Wrapped<U> = List<U>;
+
+        fn fmap<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.map(|x| f(x.clone()))
+        }
+    }
+
+    /// Applicative implementation for `List<T>`, like `Vec<T>`: every function applied to every element.
+    ///
+    /// the functions are called by reference (`Fn`), so the function list can be shared with other lists.
+    impl<T: Clone> Applicative for List<T> {
+        fn pure(x: Self::T) -> Self {
+            List::new().push_front(x)
+        }
+
+        fn ap<U, F>(self, f: Self::Wrapped<F>) -> Self::Wrapped<U>
+        where
+            F: Fn(Self::T) -> U,
+        {
+            f.iter().flat_map(|f| self.iter().map(|x| f(x.clone())).collect::<Vec<_>>()).collect()
+        }
+    }
+
+    /// Monad implementation for `List<T>`, `bind` is `flat_map`.
+    ///
+    /// the values of the lists returned by `f` are shared with the result, not moved or cloned, so `f` can return a list
+    /// that is still used somewhere else.
+    ///
+    /// ```rust
+    /// # use awesome::{functor::Functor, list::List, monad::Monad};
+    /// let xs: List<i32> = (1..=2).collect();
+    /// let pairs = xs.bind(|x| "ab".chars().collect::<List<_>>().fmap(move |c| (x, c)));
+    ///
+    /// assert_eq!(pairs, List::from_iter([(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]));
+    /// ```
+    impl<T: Clone> Monad for List<T> {
+        fn bind<U, F>(self, mut f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> Self::Wrapped<U>,
+        {
+            let values = self.iter().flat_map(|x| f(x.clone()).shared_values().collect::<Vec<_>>()).collect();
+            List::from_shared(values)
+        }
+    }
+}
+
+/// # Persistent Map
+///
+/// An immutable sorted map, `insert` and `remove` return a new version and leave the old one as it was. it is an AVL tree with path copying:
+/// only the `O(log n)` nodes on the path to the change are copied, the new version shares all the other nodes (`Rc`) with the old one.
+pub mod persistent_map {
+    use std::cmp::Ordering;
+    use std::fmt;
+    use std::rc::Rc;
+
+    type Tree<K, V> = Option<Rc<Node<K, V>>>;
+
+    struct Node<K, V> {
+        key: K,
+        value: V,
+        left: Tree<K, V>,
+        right: Tree<K, V>,
+        height: usize,
+    }
+
+    fn height<K, V>(tree: &Tree<K, V>) -> usize {
+        tree.as_ref().map_or(0, |node| node.height)
+    }
+
+    fn node<K, V>(key: K, value: V, left: Tree<K, V>, right: Tree<K, V>) -> Rc<Node<K, V>> {
+        let height = 1 + height(&left).max(height(&right));
+        Rc::new(Node { key, value, left, right, height })
+    }
+
+    // builds a node whose subtrees differ in height by at most 2, rotating it back to a difference of at most 1.
+    fn balance<K: Clone, V: Clone>(key: K, value: V, left: Tree<K, V>, right: Tree<K, V>) -> Rc<Node<K, V>> {
+        let (hl, hr) = (height(&left), height(&right));
+        if hl > hr + 1 {
+            let l = left.expect("the higher side is not empty");
+            if height(&l.left) >= height(&l.right) {
+                node(l.key.clone(), l.value.clone(), l.left.clone(), Some(node(key, value, l.right.clone(), right)))
+            } else {
+                let lr = l.right.as_ref().expect("the higher side is not empty");
+                let new_left = node(l.key.clone(), l.value.clone(), l.left.clone(), lr.left.clone());
+                node(lr.key.clone(), lr.value.clone(), Some(new_left), Some(node(key, value, lr.right.clone(), right)))
+            }
+        } else if hr > hl + 1 {
+            let r = right.expect("the higher side is not empty");
+            if height(&r.right) >= height(&r.left) {
+                node(r.key.clone(), r.value.clone(), Some(node(key, value, left, r.left.clone())), r.right.clone())
+            } else {
+                let rl = r.left.as_ref().expect("the higher side is not empty");
+                let new_right = node(r.key.clone(), r.value.clone(), rl.right.clone(), r.right.clone());
+                node(rl.key.clone(), rl.value.clone(), Some(node(key, value, left, rl.left.clone())), Some(new_right))
+            }
+        } else {
+            node(key, value, left, right)
+        }
+    }
+
+    fn insert<K: Ord + Clone, V: Clone>(tree: &Tree<K, V>, key: K, value: V, added: &mut bool) -> Rc<Node<K, V>> {
+        let Some(n) = tree else {
+            *added = true;
+            return node(key, value, None, None);
+        };
+        match key.cmp(&n.key) {
+            Ordering::Less => balance(n.key.clone(), n.value.clone(), Some(insert(&n.left, key, value, added)), n.right.clone()),
+            Ordering::Greater => balance(n.key.clone(), n.value.clone(), n.left.clone(), Some(insert(&n.right, key, value, added))),
+            Ordering::Equal => node(key, value, n.left.clone(), n.right.clone()),
+        }
+    }
+
+    // `None` if the key is not there, so the caller can keep the whole tree.
+    fn remove<K: Ord + Clone, V: Clone>(tree: &Tree<K, V>, key: &K) -> Option<Tree<K, V>> {
+        let n = tree.as_ref()?;
+        match key.cmp(&n.key) {
+            Ordering::Less => remove(&n.left, key).map(|left| Some(balance(n.key.clone(), n.value.clone(), left, n.right.clone()))),
+            Ordering::Greater => remove(&n.right, key).map(|right| Some(balance(n.key.clone(), n.value.clone(), n.left.clone(), right))),
+            Ordering::Equal => Some(match (&n.left, &n.right) {
+                (None, _) => n.right.clone(),
+                (_, None) => n.left.clone(),
+                (_, Some(right)) => {
+                    let (key, value, right) = remove_min(right);
+                    Some(balance(key, value, n.left.clone(), right))
+                }
+            }),
+        }
+    }
+
+    fn remove_min<K: Clone, V: Clone>(n: &Rc<Node<K, V>>) -> (K, V, Tree<K, V>) {
+        match &n.left {
+            None => (n.key.clone(), n.value.clone(), n.right.clone()),
+            Some(left) => {
+                let (key, value, left) = remove_min(left);
+                (key, value, Some(balance(n.key.clone(), n.value.clone(), left, n.right.clone())))
+            }
+        }
+    }
+
+    /// A persistent sorted map, cloning it is `O(1)`.
+    ///
+    /// ```rust
+    /// # use awesome::persistent_map::PersistentMap;
+    /// let v1 = PersistentMap::new().insert("a", 1).insert("b", 2);
+    /// let v2 = v1.insert("c", 3).remove(&"a");
+    ///
+    /// assert_eq!(v1.entries().collect::<Vec<_>>(), vec![(&"a", &1), (&"b", &2)]);
+    /// assert_eq!(v2.entries().collect::<Vec<_>>(), vec![(&"b", &2), (&"c", &3)]);
+    /// ```
+    pub struct PersistentMap<K, V> {
+        root: Tree<K, V>,
+        len: usize,
+    }
+
+    impl<K, V> PersistentMap<K, V> {
+        pub fn new() -> Self {
+            PersistentMap { root: None, len: 0 }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        /// The entries sorted by key.
+        pub fn entries(&self) -> Entries<'_, K, V> {
+            let mut entries = Entries { stack: Vec::new() };
+            entries.push_left(&self.root);
+            entries
+        }
+    }
+
+    impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+        pub fn get(&self, key: &K) -> Option<&V> {
+            let mut tree = &self.root;
+            while let Some(n) = tree {
+                tree = match key.cmp(&n.key) {
+                    Ordering::Less => &n.left,
+                    Ordering::Greater => &n.right,
+                    Ordering::Equal => return Some(&n.value),
+                };
+            }
+            None
+        }
+
+        pub fn contains_key(&self, key: &K) -> bool {
+            self.get(key).is_some()
+        }
+
+        /// A new version with `key` set to `value`, the keys and values on the path are cloned.
+        pub fn insert(&self, key: K, value: V) -> Self {
+            let mut added = false;
+            let root = insert(&self.root, key, value, &mut added);
+            PersistentMap {
+                root: Some(root),
+                len: self.len + usize::from(added),
+            }
+        }
+
+        /// A new version without `key`, it shares everything with this one if `key` is not there.
+        pub fn remove(&self, key: &K) -> Self {
+            match remove(&self.root, key) {
+                Some(root) => PersistentMap { root, len: self.len - 1 },
+                None => self.clone(),
+            }
+        }
+    }
+
+    impl<K, V> Clone for PersistentMap<K, V> {
+        fn clone(&self) -> Self {
+            PersistentMap {
+                root: self.root.clone(),
+                len: self.len,
+            }
+        }
+    }
+
+    impl<K, V> Default for PersistentMap<K, V> {
+        fn default() -> Self {
+            PersistentMap::new()
+        }
+    }
+
+    impl<K: PartialEq, V: PartialEq> PartialEq for PersistentMap<K, V> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.entries().eq(other.entries())
+        }
+    }
+
+    impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for PersistentMap<K, V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_map().entries(self.entries()).finish()
+        }
+    }
+
+    impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for PersistentMap<K, V> {
+        fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+            iter.into_iter().fold(PersistentMap::new(), |map, (k, v)| map.insert(k, v))
+        }
+    }
+
+    /// The iterator of `PersistentMap::entries`, in order, with a stack of the nodes left to visit.
+    pub struct Entries<'a, K, V> {
+        stack: Vec<&'a Node<K, V>>,
+    }
+
+    impl<'a, K, V> Entries<'a, K, V> {
+        fn push_left(&mut self, mut tree: &'a Tree<K, V>) {
+            while let Some(n) = tree {
+                self.stack.push(n);
+                tree = &n.left;
+            }
+        }
+    }
+
+    impl<'a, K, V> Iterator for Entries<'a, K, V> {
+        type Item = (&'a K, &'a V);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let n = self.stack.pop()?;
+            self.push_left(&n.right);
+            Some((&n.key, &n.value))
+        }
+    }
+}
+
+/// # Non-Empty Vector
+///
+/// A vector that cannot be empty, by construction: it is a first element and a `Vec` of the others. so `head`, `max` or `fold1`
+/// always have an answer, and don't need an `Option` like their `Vec` versions.
+pub mod nonempty {
+    use crate::functor::Functor;
+    use std::{iter, slice, vec};
+
+    /// A vector with at least one element.
+    ///
+    /// ```rust
+    /// # use awesome::nonempty;
+    /// let scores = nonempty![3, 9, 4];
+    ///
+    /// assert_eq!((scores.head(), scores.max(), scores.last()), (&3, &9, &4));
+    /// assert_eq!(scores.map(|s| s * 10).fold1(|a, b| a + b), 160);
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct NonEmpty<T> {
+        head: T,
+        tail: Vec<T>,
+    }
+
+    impl<T> NonEmpty<T> {
+        pub fn new(head: T, tail: Vec<T>) -> Self {
+            NonEmpty { head, tail }
+        }
+
+        /// A vector of one element.
+        pub fn singleton(head: T) -> Self {
+            NonEmpty::new(head, Vec::new())
+        }
+
+        /// Returns `None` if `items` is empty.
+        pub fn from_vec(mut items: Vec<T>) -> Option<Self> {
+            if items.is_empty() {
+                None
+            } else {
+                let head = items.remove(0);
+                Some(NonEmpty::new(head, items))
+            }
+        }
+
+        pub fn head(&self) -> &T {
+            &self.head
+        }
+
+        /// The elements after the head, maybe none.
+        pub fn tail(&self) -> &[T] {
+            &self.tail
+        }
+
+        pub fn last(&self) -> &T {
+            self.tail.last().unwrap_or(&self.head)
+        }
+
+        pub fn len(&self) -> usize {
+            self.tail.len() + 1
+        }
+
+        /// Always `false`, it exists for consistency with `len`.
+        pub fn is_empty(&self) -> bool {
+            false
+        }
+
+        pub fn push(&mut self, value: T) {
+            self.tail.push(value);
+        }
+
+        pub fn iter(&self) -> iter::Chain<iter::Once<&T>, slice::Iter<'_, T>> {
+            iter::once(&self.head).chain(self.tail.iter())
+        }
+
+        /// Maps every element, the result is not empty either.
+        pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> NonEmpty<U> {
+            NonEmpty::new(f(self.head), self.tail.into_iter().map(f).collect())
+        }
+
+        /// Folds the elements with the first one as the initial value, `Iterator::reduce` without the `Option`.
+        pub fn fold1(self, f: impl FnMut(T, T) -> T) -> T {
+            self.tail.into_iter().fold(self.head, f)
+        }
+
+        /// The largest element, the last one on ties, like `Iterator::max`.
+        pub fn max(&self) -> &T
+        where
+            T: Ord,
+        {
+            self.tail.iter().fold(&self.head, |max, x| if x >= max { x } else { max })
+        }
+
+        /// The smallest element, the first one on ties, like `Iterator::min`.
+        pub fn min(&self) -> &T
+        where
+            T: Ord,
+        {
+            self.tail.iter().fold(&self.head, |min, x| if x < min { x } else { min })
+        }
+
+        pub fn into_vec(self) -> Vec<T> {
+            let mut items = Vec::with_capacity(self.len());
+            items.push(self.head);
+            items.extend(self.tail);
+            items
+        }
+    }
+
+    impl<T> From<(T, Vec<T>)> for NonEmpty<T> {
+        fn from((head, tail): (T, Vec<T>)) -> Self {
+            NonEmpty::new(head, tail)
+        }
+    }
+
+    impl<T> From<NonEmpty<T>> for Vec<T> {
+        fn from(items: NonEmpty<T>) -> Self {
+            items.into_vec()
+        }
+    }
+
+    impl<T> IntoIterator for NonEmpty<T> {
+        type Item = T;
+        type IntoIter = iter::Chain<iter::Once<T>, vec::IntoIter<T>>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            iter::once(self.head).chain(self.tail)
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a NonEmpty<T> {
+        type Item = &'a T;
+        type IntoIter = iter::Chain<iter::Once<&'a T>, slice::Iter<'a, T>>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
+
+    impl<T> Extend<T> for NonEmpty<T> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            self.tail.extend(iter);
+        }
+    }
+
+    /// Functor implementation for `NonEmpty<T>`.
+    impl<T> Functor for NonEmpty<T> {
+        type T = T;
+        type Wrapped<U> = NonEmpty<U>;
+
+        fn fmap<U, F>(self, f: F) -> Self::Wrapped<U>
+        where
+            F: FnMut(Self::T) -> U,
+        {
+            self.map(f)
+        }
+    }
+
+    /// `nonempty![a, b, c]` is `NonEmpty::new(a, vec![b, c])`, like `vec!`. `nonempty![]` does not compile.
+    #[macro_export]
+    macro_rules! nonempty {
+        () => {
+            ::std::compile_error!("a `NonEmpty` needs at least one element")
+        };
+        ($head:expr $(, $tail:expr)* $(,)?) => {
+            $crate::nonempty::NonEmpty::new($head, ::std::vec![$($tail),*])
+        };
+    }
+}
+
+/// # Foldable
+///
+/// Structures that can be reduced to a single value, element by element from left to right.
+/// See [here](https://wiki.haskell.org/Foldable_and_Traversable) for more details.
+pub mod foldable {
+    use crate::list::List;
+    use crate::monoid::Monoid;
+    use crate::nonempty::NonEmpty;
+    use crate::zipper::Tree;
+
+    /// A simple foldable implementation, only `fold_left` is required.
+    pub trait Foldable {
+        type Item;
+
+        /// Folds the elements from left to right, `f(f(f(init, a), b), c)`.
+        fn fold_left<'a, B>(&'a self, init: B, f: impl FnMut(B, &'a Self::Item) -> B) -> B;
+
+        /// Folds the elements from right to left, `f(a, f(b, f(c, init)))`.
+        fn fold_right<B>(&self, init: B, mut f: impl FnMut(&Self::Item, B) -> B) -> B {
+            let items = self.fold_left(Vec::new(), |mut items, item| {
+                items.push(item);
+                items
+            });
+            items.into_iter().rev().fold(init, |acc, item| f(item, acc))
+        }
+
+        /// Maps every element into a monoid and combines them in order.
+        ///
+        /// ```rust
+        /// # use awesome::foldable::Foldable;
+        /// # use awesome::monoid::{Max, Sum};
+        /// let xs = vec![3, 1, 4, 1, 5];
+        ///
+        /// assert_eq!(xs.fold_map(|&x| Sum(x)), Sum(14));
+        /// assert_eq!(xs.fold_map(|&x| Some(Max(x))), Some(Max(5)));
+        /// assert_eq!(xs.fold_map(|x| x.to_string()), "31415");
+        /// ```
+        fn fold_map<M: Monoid>(&self, mut f: impl FnMut(&Self::Item) -> M) -> M {
+            self.fold_left(M::mempty(), |acc, item| acc.combine(f(item)))
+        }
+
+        /// The number of elements.
+        fn length(&self) -> usize {
+            self.fold_left(0, |n, _| n + 1)
+        }
+
+        /// The elements in order.
+        fn to_vec(&self) -> Vec<Self::Item>
+        where
+            Self::Item: Clone,
+        {
+            self.fold_left(Vec::new(), |mut items, item| {
+                items.push(item.clone());
+                items
+            })
+        }
+    }
+
+    /// Zero or one element.
+    impl<T> Foldable for Option<T> {
+        type Item = T;
+
+        fn fold_left<'a, B>(&'a self, init: B, mut f: impl FnMut(B, &'a T) -> B) -> B {
+            match self {
+                Some(value) => f(init, value),
+                None => init,
+            }
+        }
+    }
+
+    impl<T> Foldable for Vec<T> {
+        type Item = T;
+
+        fn fold_left<'a, B>(&'a self, init: B, f: impl FnMut(B, &'a T) -> B) -> B {
+            self.iter().fold(init, f)
+        }
+
+        fn fold_right<B>(&self, init: B, mut f: impl FnMut(&T, B) -> B) -> B {
+            self.iter().rfold(init, |acc, item| f(item, acc))
+        }
+
+        fn length(&self) -> usize {
+            self.len()
+        }
+    }
+
+    impl<T> Foldable for List<T> {
+        type Item = T;
+
+        fn fold_left<'a, B>(&'a self, init: B, f: impl FnMut(B, &'a T) -> B) -> B {
+            self.iter().fold(init, f)
+        }
+
+        fn length(&self) -> usize {
+            self.len()
+        }
+    }
+
+    impl<T> Foldable for NonEmpty<T> {
+        type Item = T;
+
+        fn fold_left<'a, B>(&'a self, init: B, f: impl FnMut(B, &'a T) -> B) -> B {
+            self.iter().fold(init, f)
+        }
+
+        fn length(&self) -> usize {
+            self.len()
+        }
+    }
+
+    /// In order, the left subtree, the value and then the right subtree.
+    impl<T> Foldable for Tree<T> {
+        type Item = T;
+
+        fn fold_left<'a, B>(&'a self, init: B, mut f: impl FnMut(B, &'a T) -> B) -> B {
+            fn go<'a, T, B>(tree: &'a Tree<T>, acc: B, f: &mut impl FnMut(B, &'a T) -> B) -> B {
+                match tree {
+                    Tree::Leaf => acc,
+                    Tree::Node(left, value, right) => {
+                        let acc = go(left, acc, f);
+                        let acc = f(acc, value);
+                        go(right, acc, f)
+                    }
+                }
+            }
+
+            go(self, init, &mut f)
+        }
+
+        fn fold_right<B>(&self, init: B, mut f: impl FnMut(&T, B) -> B) -> B {
+            fn go<T, B>(tree: &Tree<T>, acc: B, f: &mut impl FnMut(&T, B) -> B) -> B {
+                match tree {
+                    Tree::Leaf => acc,
+                    Tree::Node(left, value, right) => {
+                        let acc = go(right, acc, f);
+                        let acc = f(value, acc);
+                        go(left, acc, f)
+                    }
+                }
+            }
+
+            go(self, init, &mut f)
+        }
+    }
+}
+
+/// # Bifunctor
+///
+/// A functor over two type parameters, both sides can be mapped at once.
+/// See [here](https://hackage.haskell.org/package/base/docs/Data-Bifunctor.html) for more details.
+pub mod bifunctor {
+    use crate::either::Either;
+
+    /// A bifunctor implementation using generic associated types, like `Functor`.
+    ///
+    /// `bimap` must satisfy the bifunctor laws:
+    ///
+    /// - identity: `x.bimap(|a| a, |b| b) == x`
+    /// - composition: `x.bimap(|a| f2(f1(a)), |b| g2(g1(b))) == x.bimap(f1, g1).bimap(f2, g2)`
+    pub trait Bifunctor: Sized {
+        /// The first inner type.
+        type L;
+        /// The second inner type.
+        type R;
+        /// The same bifunctor, wrapping `M` and `S` instead of `L` and `R`.
+        type Wrapped<M, S>;
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::bifunctor::Bifunctor;
+        /// assert_eq!((1, "two").bimap(|x| x + 1, str::len), (2, 3));
+        /// assert_eq!(Err::<i32, &str>("oops").bimap(|x| x + 1, str::len), Err(4));
+        /// ```
+        fn bimap<M, S, F, G>(self, f: F, g: G) -> Self::Wrapped<M, S>
+        where
+            F: FnOnce(Self::L) -> M,
+            G: FnOnce(Self::R) -> S;
+
+        /// Maps only the first side.
+        fn first<M, F>(self, f: F) -> Self::Wrapped<M, Self::R>
+        where
+            F: FnOnce(Self::L) -> M,
+        {
+            self.bimap(f, |r| r)
+        }
+
+        /// Maps only the second side.
+        fn second<S, G>(self, g: G) -> Self::Wrapped<Self::L, S>
+        where
+            G: FnOnce(Self::R) -> S,
+        {
+            self.bimap(|l| l, g)
+        }
+    }
+
+    /// Both elements of the pair are mapped.
+    impl<A, B> Bifunctor for (A, B) {
+        type L = A;
+        type R = B;
+        type Wrapped<M, S> = (M, S);
+
+        fn bimap<M, S, F, G>(self, f: F, g: G) -> Self::Wrapped<M, S>
+        where
+            F: FnOnce(Self::L) -> M,
+            G: FnOnce(Self::R) -> S,
+        {
+            (f(self.0), g(self.1))
+        }
+    }
+
+    /// The first side is the `Ok` value and the second side is the `Err` value, in the order of the type parameters.
+    impl<T, E> Bifunctor for Result<T, E> {
+        type L = T;
+        type R = E;
+        type Wrapped<M, S> = Result<M, S>;
+
+        fn bimap<M, S, F, G>(self, f: F, g: G) -> Self::Wrapped<M, S>
+        where
+            F: FnOnce(Self::L) -> M,
+            G: FnOnce(Self::R) -> S,
+        {
+            self.map(f).map_err(g)
+        }
+    }
+
+    /// Same as the inherent `Either::bimap`.
+    impl<L, R> Bifunctor for Either<L, R> {
+        type L = L;
+        type R = R;
+        type Wrapped<M, S> = Either<M, S>;
+
+        fn bimap<M, S, F, G>(self, f: F, g: G) -> Self::Wrapped<M, S>
+        where
+            F: FnOnce(Self::L) -> M,
+            G: FnOnce(Self::R) -> S,
+        {
+            Either::bimap(self, f, g)
+        }
+    }
+}
+
+/// # Profunctor
+///
+/// A bifunctor that is contravariant in its first parameter, the input can be pre-processed and the output post-processed.
+/// See [here](https://hackage.haskell.org/package/profunctors/docs/Data-Profunctor.html) for more details.
+pub mod profunctor {
+    /// A simple profunctor implementation.
+    ///
+    /// `dimap` must satisfy the profunctor laws:
+    ///
+    /// - identity: `p.dimap(|a| a, |b| b) == p`
+    /// - composition: `p.dimap(|a| f1(f2(a)), |b| g2(g1(b))) == p.dimap(f1, g1).dimap(f2, g2)`
+    pub trait Profunctor: Sized {
+        /// The input type.
+        type A;
+        /// The output type.
+        type B;
+        /// The same profunctor, taking `C` and producing `D`.
+        type Wrapped<C, D>;
+
+        /// `f` runs before and `g` runs after, `C -> A -> B -> D`.
+        fn dimap<C, D, F, G>(self, f: F, g: G) -> Self::Wrapped<C, D>
+        where
+            F: Fn(C) -> Self::A + 'static,
+            G: Fn(Self::B) -> D + 'static;
+
+        /// Pre-processes the input only.
+        fn lmap<C, F>(self, f: F) -> Self::Wrapped<C, Self::B>
+        where
+            F: Fn(C) -> Self::A + 'static,
+        {
+            self.dimap(f, |b| b)
+        }
+
+        /// Post-processes the output only.
+        fn rmap<D, G>(self, g: G) -> Self::Wrapped<Self::A, D>
+        where
+            G: Fn(Self::B) -> D + 'static,
+        {
+            self.dimap(|a| a, g)
+        }
+    }
+
+    /// A boxed function `A -> B`, closures have no nameable type so they need a wrapper.
+    pub struct Function<A, B>(Box<dyn Fn(A) -> B>);
+
+    impl<A, B> Function<A, B> {
+        pub fn new(f: impl Fn(A) -> B + 'static) -> Self {
+            Function(Box::new(f))
+        }
+
+        pub fn call(&self, a: A) -> B {
+            (self.0)(a)
+        }
+    }
+
+    /// for example, reusing a function on another input and output:
+    ///
+    /// ```rust
+    /// # use awesome::profunctor::{Function, Profunctor};
+    /// let count_words = Function::new(|s: String| s.split_whitespace().count());
+    ///
+    /// // trims a `&str` before and describes the count after.
+    /// let describe = count_words.dimap(|s: &'static str| s.trim().to_string(), |n| format!("{n} words"));
+    ///
+    /// assert_eq!(describe.call("  the quick brown fox  "), "4 words");
+    /// ```
+    impl<A: 'static, B: 'static> Profunctor for Function<A, B> {
+        type A = A;
+        type B = B;
+        type Wrapped<C, D> = Function<C, D>;
+
+        fn dimap<C, D, F, G>(self, f: F, g: G) -> Self::Wrapped<C, D>
+        where
+            F: Fn(C) -> Self::A + 'static,
+            G: Fn(Self::B) -> D + 'static,
+        {
+            Function::new(move |c| g((self.0)(f(c))))
+        }
+    }
+}
+
+/// # Arrow
+///
+/// Functions as composable arrows, values are threaded through pairs instead of named variables.
+/// See [here](https://wiki.haskell.org/Arrow_tutorial) for more details.
+pub mod arrow {
+    /// The arrow combinators for every function `A -> B`, the names follow Haskell's `Control.Arrow`.
+    ///
+    /// a small pipeline, by combining arrows only:
+    ///
+    /// ```rust
+    /// # use awesome::arrow::Arrow;
+    /// let words = |line: &'static str| line.split_whitespace().collect::<Vec<_>>();
+    /// let longest = |words: Vec<&'static str>| words.into_iter().max_by_key(|w| w.len()).unwrap_or("");
+    ///
+    /// let shout = |word: &'static str| word.to_uppercase();
+    ///
+    /// // (word count, longest word), then shout the longest word.
+    /// let stats = words.then(|w| w.len()).fanout(words.then(longest)).then(shout.second());
+    ///
+    /// assert_eq!(stats("the quickest brown fox"), (4, "QUICKEST".to_string()));
+    /// ```
+    pub trait Arrow<A, B>: Fn(A) -> B + Sized {
+        /// Left to right composition, Haskell's `>>>`.
+        fn then<C>(self, g: impl Fn(B) -> C) -> impl Fn(A) -> C {
+            move |a| g(self(a))
+        }
+
+        /// Runs on the first element of a pair, the second one passes through.
+        fn first<C>(self) -> impl Fn((A, C)) -> (B, C) {
+            move |(a, c)| (self(a), c)
+        }
+
+        /// Runs on the second element of a pair, the first one passes through.
+        fn second<C>(self) -> impl Fn((C, A)) -> (C, B) {
+            move |(c, a)| (c, self(a))
+        }
+
+        /// Runs both functions side by side, each on its own element of the pair, Haskell's `***`.
+        fn split<C, D>(self, g: impl Fn(C) -> D) -> impl Fn((A, C)) -> (B, D) {
+            move |(a, c)| (self(a), g(c))
+        }
+
+        /// Runs both functions on the same input, Haskell's `&&&`.
+        fn fanout<C>(self, g: impl Fn(A) -> C) -> impl Fn(A) -> (B, C)
+        where
+            A: Clone,
+        {
+            move |a| (self(a.clone()), g(a))
+        }
+    }
+
+    impl<A, B, F: Fn(A) -> B> Arrow<A, B> for F {}
+
+    /// Composes two Kleisli arrows `A -> M<B>` and `B -> M<C>`, Haskell's `>=>`, it is [`monad::kleisli`](crate::monad::kleisli).
+    pub use crate::monad::kleisli;
+}
+
+/// # Type-Level Numbers
+///
+/// Peano numbers encoded in types, the arithmetic is done by the trait solver at compile time.
+/// See [here](https://en.wikipedia.org/wiki/Peano_axioms) for more details.
+pub mod typelevel {
+    use std::marker::PhantomData;
+
+    /// The number zero.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Zero;
+
+    /// The successor of `N`, `N + 1`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Succ<N>(PhantomData<N>);
+
+    /// A type-level natural number, `VALUE` brings it back to a value.
+    pub trait Nat {
+        const VALUE: usize;
+    }
+
+    impl Nat for Zero {
+        const VALUE: usize = 0;
+    }
+
+    impl<N: Nat> Nat for Succ<N> {
+        const VALUE: usize = N::VALUE + 1;
+    }
+
+    /// Type-level addition, by recursion on the left operand.
+    ///
+    /// - `0 + n = n`
+    /// - `(m + 1) + n = (m + n) + 1`
+    pub trait AddNat<N: Nat>: Nat {
+        type Output: Nat;
+    }
+
+    impl<N: Nat> AddNat<N> for Zero {
+        type Output = N;
+    }
+
+    impl<M: AddNat<N>, N: Nat> AddNat<N> for Succ<M> {
+        type Output = Succ<M::Output>;
+    }
+
+    /// Type-level multiplication, by recursion on the left operand.
+    ///
+    /// - `0 * n = 0`
+    /// - `(m + 1) * n = n + m * n`
+    pub trait MulNat<N: Nat>: Nat {
+        type Output: Nat;
+    }
+
+    impl<N: Nat> MulNat<N> for Zero {
+        type Output = Zero;
+    }
+
+    impl<M: MulNat<N>, N: AddNat<M::Output>> MulNat<N> for Succ<M> {
+        type Output = <N as AddNat<M::Output>>::Output;
+    }
+
+    /// `A + B`, for example:
+    ///
+    /// ```rust
+    /// # use awesome::typelevel::{Add, Nat, S2, S3, S5};
+    /// const _: () = assert!(<Add<S2, S3> as Nat>::VALUE == 5);
+    ///
+    /// // it is the very same type, not only the same value.
+    /// let _five: S5 = <Add<S2, S3>>::default();
+    /// ```
+    pub type Add<A, B> = <A as AddNat<B>>::Output;
+
+    /// `A * B`.
+    pub type Mul<A, B> = <A as MulNat<B>>::Output;
+
+    pub type S0 = Zero;
+    pub type S1 = Succ<S0>;
+    pub type S2 = Succ<S1>;
+    pub type S3 = Succ<S2>;
+    pub type S4 = Succ<S3>;
+    pub type S5 = Succ<S4>;
+    pub type S6 = Succ<S5>;
+    pub type S7 = Succ<S6>;
+    pub type S8 = Succ<S7>;
+    pub type S9 = Succ<S8>;
+    pub type S10 = Succ<S9>;
+
+    /// Implemented only when both types are the same, `where A: Same<B>` is a compile-time type equality.
+    pub trait Same<T> {}
+
+    impl<T> Same<T> for T {}
+
+    /// Compiles only if `A` and `B` are the same type.
+    pub const fn assert_same<A: Same<B>, B>() {}
+}
+
+/// # Heterogeneous List
+///
+/// A list whose elements can all have different types, the types of the elements are part of the type of the list.
+/// See [here](https://docs.rs/frunk/latest/frunk/hlist/index.html) for more details.
+pub mod hlist {
+    use std::marker::PhantomData;
+
+    /// The empty list.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct HNil;
+
+    /// A `head` in front of the list `tail`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct HCons<H, T> {
+        pub head: H,
+        pub tail: T,
+    }
+
+    /// Implemented by `HNil` and `HCons` only, the length is known at compile time.
+    pub trait HList: Sized {
+        const LEN: usize;
+
+        fn len(&self) -> usize {
+            Self::LEN
+        }
+
+        fn is_empty(&self) -> bool {
+            Self::LEN == 0
+        }
+
+        /// Puts `head` in front, the opposite of destructuring with `head` and `tail`.
+        fn prepend<H>(self, head: H) -> HCons<H, Self> {
+            HCons { head, tail: self }
+        }
+    }
+
+    impl HList for HNil {
+        const LEN: usize = 0;
+    }
+
+    impl<H, T: HList> HList for HCons<H, T> {
+        const LEN: usize = 1 + T::LEN;
+    }
+
+    /// Builds a heterogeneous list, `hlist![1, "a", 'b']`.
+    #[macro_export]
+    macro_rules! hlist {
+        () => {
+            $crate::hlist::HNil
+        };
+        ($head:expr $(, $tail:expr)* $(,)?) => {
+            $crate::hlist::HCons { head: $head, tail: $crate::hlist!($($tail),*) }
+        };
+    }
+
+    /// Destructures a heterogeneous list in a pattern, `let hlist_pat![a, _, c] = list;`.
+    #[macro_export]
+    macro_rules! hlist_pat {
+        () => {
+            $crate::hlist::HNil
+        };
+        ($head:pat $(, $tail:pat)* $(,)?) => {
+            $crate::hlist::HCons { head: $head, tail: $crate::hlist_pat!($($tail),*) }
+        };
+    }
+
+    /// The type of a heterogeneous list, `HList![i32, &str, char]`.
+    #[macro_export]
+    macro_rules! HList {
+        () => {
+            $crate::hlist::HNil
+        };
+        ($head:ty $(, $tail:ty)* $(,)?) => {
+            $crate::hlist::HCons<$head, $crate::HList!($($tail),*)>
+        };
+    }
+
+    /// The index of the element that was found, `Here` is the head.
+    pub struct Here;
+
+    /// The element is in the tail, at index `I`.
+    pub struct There<I>(PhantomData<I>);
+
+    /// Finds the element of type `T`, the index `I` is inferred so it does not need to be written.
+    ///
+    /// the index keeps the two impls from overlapping, if `T` appears twice the index is ambiguous and it does not compile.
+    pub trait Selector<T, I> {
+        fn get(&self) -> &T;
+
+        fn get_mut(&mut self) -> &mut T;
+    }
+
+    impl<T, Tail> Selector<T, Here> for HCons<T, Tail> {
+        fn get(&self) -> &T {
+            &self.head
+        }
+
+        fn get_mut(&mut self) -> &mut T {
+            &mut self.head
+        }
+    }
+
+    impl<H, Tail: Selector<T, I>, T, I> Selector<T, There<I>> for HCons<H, Tail> {
+        fn get(&self) -> &T {
+            self.tail.get()
+        }
+
+        fn get_mut(&mut self) -> &mut T {
+            self.tail.get_mut()
+        }
+    }
+
+    impl<H, T> HCons<H, T> {
+        /// The element of type `U`, the index is left to inference with `_`:
+        ///
+        /// ```rust
+        /// # use awesome::hlist;
+        /// let list = hlist![1, "two", 3.0];
+        ///
+        /// assert_eq!(*list.get::<&str, _>(), "two");
+        /// assert_eq!(*list.get::<f64, _>(), 3.0);
+        /// ```
+        pub fn get<U, I>(&self) -> &U
+        where
+            Self: Selector<U, I>,
+        {
+            Selector::get(self)
+        }
+
+        pub fn get_mut<U, I>(&mut self) -> &mut U
+        where
+            Self: Selector<U, I>,
+        {
+            Selector::get_mut(self)
+        }
+    }
+
+    /// A function that works for more than one type, a closure can only take a single type.
+    pub trait Poly<T> {
+        type Output;
+
+        fn call(&mut self, value: T) -> Self::Output;
+    }
+
+    /// Maps every element with a `Poly` function, each element can map to another type.
+    ///
+    /// ```rust
+    /// # use awesome::hlist;
+    /// # use awesome::hlist::{HMap, Poly};
+    /// struct Describe;
+    ///
+    /// impl Poly<i32> for Describe {
+    ///     type Output = String;
+    ///
+    ///     fn call(&mut self, value: i32) -> String {
+    ///         format!("the number {value}")
+    ///     }
+    /// }
+    ///
+    /// impl Poly<bool> for Describe {
+    ///     type Output = &'static str;
+    ///
+    ///     fn call(&mut self, value: bool) -> &'static str {
+    ///         if value { "yes" } else { "no" }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(hlist![1, true].map(Describe), hlist!["the number 1".to_string(), "yes"]);
+    /// ```
+    pub trait HMap<F> {
+        type Output;
+
+        fn map(self, f: F) -> Self::Output;
+    }
+
+    impl<F> HMap<F> for HNil {
+        type Output = HNil;
+
+        fn map(self, _: F) -> Self::Output {
+            HNil
+        }
+    }
+
+    impl<F: Poly<H>, H, T: HMap<F>> HMap<F> for HCons<H, T> {
+        type Output = HCons<F::Output, T::Output>;
+
+        fn map(self, mut f: F) -> Self::Output {
+            let head = f.call(self.head);
+            HCons { head, tail: self.tail.map(f) }
+        }
+    }
+
+    /// Converts a heterogeneous list into the tuple of the same elements, tuples are the other way with `From`.
+    pub trait IntoTuple {
+        type Tuple;
+
+        fn into_tuple(self) -> Self::Tuple;
+    }
+
+    impl IntoTuple for HNil {
+        type Tuple = ();
+
+        fn into_tuple(self) -> Self::Tuple {}
+    }
+
+    impl From<()> for HNil {
+        fn from(_: ()) -> Self {
+            HNil
+        }
+    }
+
+    macro_rules! tuple {
+        ($($t:ident $v:ident),+) => {
+            impl<$($t),+> IntoTuple for HList![$($t),+] {
+                type Tuple = ($($t,)+);
+
+                fn into_tuple(self) -> Self::Tuple {
+                    let hlist_pat![$($v),+] = self;
+                    ($($v,)+)
+                }
+            }
+
+            impl<$($t),+> From<($($t,)+)> for HList![$($t),+] {
+                fn from(($($v,)+): ($($t,)+)) -> Self {
+                    hlist![$($v),+]
+                }
+            }
+
+            impl<$($t),+> From<HList![$($t),+]> for ($($t,)+) {
+                fn from(list: HList![$($t),+]) -> Self {
+                    list.into_tuple()
+                }
+            }
+        };
+    }
+
+    tuple!(A a);
+    tuple!(A a, B b);
+    tuple!(A a, B b, C c);
+    tuple!(A a, B b, C c, D d);
+    tuple!(A a, B b, C c, D d, E e);
+    tuple!(A a, B b, C c, D d, E e, F f);
+    tuple!(A a, B b, C c, D d, E e, F f, G g);
+    tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+}
+
+/// # Type-State Builder
+///
+/// The state of a builder is a type parameter, so a method that needs some state only exists in that state and misuse is a compile error.
+/// See [here](https://cliffle.com/blog/rust-typestate/) for more details.
+pub mod typestate {
+    use std::marker::PhantomData;
+
+    /// The url is not set yet, `send` does not exist.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct NoUrl;
+
+    /// The url is set, the request can be sent.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct HasUrl;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Method {
+        #[default]
+        Get,
+        Post,
+        Put,
+        Delete,
+    }
+
+    /// A finished request, only `RequestBuilder<HasUrl>::send` can make one.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Request {
+        pub method: Method,
+        pub url: String,
+        pub headers: Vec<(String, String)>,
+        pub body: Option<String>,
+    }
+
+    impl Request {
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::typestate::{Method, Request};
+        /// let request = Request::builder().method(Method::Post).url("https://example.com").body("hello").send();
+        ///
+        /// assert_eq!(request.url, "https://example.com");
+        /// assert_eq!(request.body.as_deref(), Some("hello"));
+        /// ```
+        pub fn builder() -> RequestBuilder<NoUrl> {
+            RequestBuilder {
+                method: Method::default(),
+                url: String::new(),
+                headers: Vec::new(),
+                body: None,
+                state: PhantomData,
+            }
+        }
+    }
+
+    /// Builds a `Request`, `S` is `NoUrl` or `HasUrl`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RequestBuilder<S> {
+        method: Method,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Option<String>,
+        state: PhantomData<S>,
+    }
+
+    /// Available in every state, the state stays the same.
+    impl<S> RequestBuilder<S> {
+        pub fn method(self, method: Method) -> Self {
+            RequestBuilder { method, ..self }
+        }
+
+        pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+            self.headers.push((name.into(), value.into()));
+            self
+        }
+
+        pub fn body(self, body: impl Into<String>) -> Self {
+            RequestBuilder { body: Some(body.into()), ..self }
+        }
+    }
+
+    impl RequestBuilder<NoUrl> {
+        /// Sets the url, the builder moves to `HasUrl`.
+        pub fn url(self, url: impl Into<String>) -> RequestBuilder<HasUrl> {
+            RequestBuilder {
+                method: self.method,
+                url: url.into(),
+                headers: self.headers,
+                body: self.body,
+                state: PhantomData,
+            }
+        }
+    }
+
+    impl RequestBuilder<HasUrl> {
+        /// Finishes the request, there is no network here so it is only handed back.
+        pub fn send(self) -> Request {
+            Request {
+                method: self.method,
+                url: self.url,
+                headers: self.headers,
+                body: self.body,
+            }
+        }
+    }
+}
+
+/// # Units of Measure
+///
+/// Quantities tagged with a phantom unit type, mixing up units is a compile error and the tag costs nothing at runtime.
+/// See [here](https://doc.rust-lang.org/rust-by-example/generics/phantom/testcase_units.html) for more details.
+pub mod phantom {
+    use std::fmt;
+    use std::marker::PhantomData;
+    use std::ops::{Add, Div, Mul, Sub};
+
+    /// A unit of measure, only used as a type parameter.
+    pub trait Unit {
+        const SYMBOL: &'static str;
+    }
+
+    macro_rules! units {
+        ($($name:ident = $symbol:literal;)+) => {
+            $(
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                pub struct $name;
+
+                impl Unit for $name {
+                    const SYMBOL: &'static str = $symbol;
+                }
+            )+
+        };
+    }
+
+    units! {
+        Meters = "m";
+        Seconds = "s";
+        SquareMeters = "m²";
+        MetersPerSecond = "m/s";
+        MetersPerSecondSquared = "m/s²";
+    }
+
+    /// The unit of `Self * Rhs`.
+    pub trait UnitMul<Rhs: Unit>: Unit {
+        type Output: Unit;
+    }
+
+    /// The unit of `Self / Rhs`.
+    pub trait UnitDiv<Rhs: Unit>: Unit {
+        type Output: Unit;
+    }
+
+    macro_rules! derived {
+        ($($a:ident $op:tt $b:ident = $c:ident;)+) => {
+            $(derived!(@one $a $op $b = $c);)+
+        };
+        (@one $a:ident * $b:ident = $c:ident) => {
+            impl UnitMul<$b> for $a {
+                type Output = $c;
+            }
+        };
+        (@one $a:ident / $b:ident = $c:ident) => {
+            impl UnitDiv<$b> for $a {
+                type Output = $c;
+            }
+        };
+    }
+
+    derived! {
+        Meters * Meters = SquareMeters;
+        Meters / Seconds = MetersPerSecond;
+        MetersPerSecond / Seconds = MetersPerSecondSquared;
+        MetersPerSecond * Seconds = Meters;
+        Seconds * MetersPerSecond = Meters;
+        MetersPerSecondSquared * Seconds = MetersPerSecond;
+        SquareMeters / Meters = Meters;
+    }
+
+    /// A value of `T` in the unit `U`, it has the same size as `T`.
+    ///
+    /// ```rust
+    /// # use awesome::phantom::{meters, seconds, MetersPerSecond, Quantity};
+    /// let speed: Quantity<f64, MetersPerSecond> = meters(100.0) / seconds(8.0);
+    ///
+    /// assert_eq!(speed.value(), 12.5);
+    /// assert_eq!(speed.to_string(), "12.5 m/s");
+    /// ```
+    pub struct Quantity<T, U> {
+        value: T,
+        unit: PhantomData<U>,
+    }
+
+    impl<T, U> Quantity<T, U> {
+        pub fn new(value: T) -> Self {
+            Quantity { value, unit: PhantomData }
+        }
+
+        pub fn value(self) -> T {
+            self.value
+        }
+    }
+
+    pub fn meters<T>(value: T) -> Quantity<T, Meters> {
+        Quantity::new(value)
+    }
+
+    pub fn seconds<T>(value: T) -> Quantity<T, Seconds> {
+        Quantity::new(value)
+    }
+
+    // implemented by hand, deriving would require the unit markers to implement them too.
+    impl<T: Clone, U> Clone for Quantity<T, U> {
+        fn clone(&self) -> Self {
+            Quantity::new(self.value.clone())
+        }
+    }
+
+    impl<T: Copy, U> Copy for Quantity<T, U> {}
+
+    impl<T: PartialEq, U> PartialEq for Quantity<T, U> {
+        fn eq(&self, other: &Self) -> bool {
+            self.value == other.value
+        }
+    }
+
+    impl<T: PartialOrd, U> PartialOrd for Quantity<T, U> {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            self.value.partial_cmp(&other.value)
+        }
+    }
+
+    impl<T: fmt::Debug, U: Unit> fmt::Debug for Quantity<T, U> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Quantity({:?} {})", self.value, U::SYMBOL)
+        }
+    }
+
+    impl<T: fmt::Display, U: Unit> fmt::Display for Quantity<T, U> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} {}", self.value, U::SYMBOL)
+        }
+    }
+
+    /// Only quantities of the same unit can be added.
+    impl<T: Add<Output = T>, U> Add for Quantity<T, U> {
+        type Output = Self;
+
+        fn add(self, other: Self) -> Self {
+            Quantity::new(self.value + other.value)
+        }
+    }
+
+    impl<T: Sub<Output = T>, U> Sub for Quantity<T, U> {
+        type Output = Self;
+
+        fn sub(self, other: Self) -> Self {
+            Quantity::new(self.value - other.value)
+        }
+    }
+
+    /// Multiplying by a plain number keeps the unit.
+    impl<T: Mul<Output = T>, U> Mul<T> for Quantity<T, U> {
+        type Output = Self;
+
+        fn mul(self, factor: T) -> Self {
+            Quantity::new(self.value * factor)
+        }
+    }
+
+    impl<T: Div<Output = T>, U> Div<T> for Quantity<T, U> {
+        type Output = Self;
+
+        fn div(self, divisor: T) -> Self {
+            Quantity::new(self.value / divisor)
+        }
+    }
+
+    /// Multiplying two quantities multiplies their units, see `UnitMul`.
+    impl<T: Mul<Output = T>, U: UnitMul<V>, V: Unit> Mul<Quantity<T, V>> for Quantity<T, U> {
+        type Output = Quantity<T, U::Output>;
+
+        fn mul(self, other: Quantity<T, V>) -> Self::Output {
+            Quantity::new(self.value * other.value)
+        }
+    }
+
+    /// Dividing two quantities divides their units, see `UnitDiv`.
+    impl<T: Div<Output = T>, U: UnitDiv<V>, V: Unit> Div<Quantity<T, V>> for Quantity<T, U> {
+        type Output = Quantity<T, U::Output>;
+
+        fn div(self, other: Quantity<T, V>) -> Self::Output {
+            Quantity::new(self.value / other.value)
+        }
+    }
+}
+
+/// # Branded Indices
+///
+/// Every vector gets its own unique lifetime brand, an index checked once against a vector can only be used with that vector, so it never needs a bounds check again.
+/// See [here](https://plv.mpi-sws.org/rustbelt/ghostcell/) for more details (generativity).
+pub mod branded {
+    use std::marker::PhantomData;
+
+    /// The brand, invariant in `'id` so that one brand can never be turned into another.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    struct Brand<'id>(PhantomData<fn(&'id ()) -> &'id ()>);
+
+    /// A vector with the brand `'id`, it can grow but never shrink so its indices stay valid.
+    pub struct BrandedVec<'id, T> {
+        items: Vec<T>,
+        brand: Brand<'id>,
+    }
+
+    /// An index of a `BrandedVec` with the brand `'id`, it is known to be in bounds.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct Index<'id> {
+        index: usize,
+        brand: Brand<'id>,
+    }
+
+    impl<'id> Index<'id> {
+        pub fn value(self) -> usize {
+            self.index
+        }
+    }
+
+    impl std::fmt::Debug for Index<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Index({})", self.index)
+        }
+    }
+
+    /// Brands `items` and passes it to `f`, the only way to get a `BrandedVec`.
+    ///
+    /// `f` must work for any `'id`, so the brand is new and different from all the others and neither the vector nor its indices can leave `f`.
+    ///
+    /// ```rust
+    /// # use awesome::branded::scope;
+    /// let total = scope(vec![10, 20, 30], |v| {
+    ///     let first = v.index(0).unwrap();
+    ///     let last = v.index(2).unwrap();
+    ///     v[first] + v[last]
+    /// });
+    ///
+    /// assert_eq!(total, 40);
+    /// ```
+    pub fn scope<T, R>(items: Vec<T>, f: impl for<'id> FnOnce(BrandedVec<'id, T>) -> R) -> R {
+        f(BrandedVec {
+            items,
+            brand: Brand(PhantomData),
+        })
+    }
+
+    impl<'id, T> BrandedVec<'id, T> {
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        /// Checks `index` once, the returned `Index` is then always valid.
+        pub fn index(&self, index: usize) -> Option<Index<'id>> {
+            (index < self.items.len()).then_some(Index { index, brand: self.brand })
+        }
+
+        /// All the indices in order.
+        pub fn indices(&self) -> impl Iterator<Item = Index<'id>> + use<'id, T> {
+            let brand = self.brand;
+            (0..self.items.len()).map(move |index| Index { index, brand })
+        }
+
+        /// Appends `value` and returns its index, the old indices stay valid.
+        pub fn push(&mut self, value: T) -> Index<'id> {
+            self.items.push(value);
+            Index {
+                index: self.items.len() - 1,
+                brand: self.brand,
+            }
+        }
+
+        pub fn get(&self, index: Index<'id>) -> &T {
+            // SAFETY: an `Index<'id>` is only made in bounds of the vector with the brand `'id`, there is only one such
+            // vector since `scope` makes a new brand every time, and it never shrinks.
+            unsafe { self.items.get_unchecked(index.index) }
+        }
+
+        pub fn get_mut(&mut self, index: Index<'id>) -> &mut T {
+            // SAFETY: same as `get`.
+            unsafe { self.items.get_unchecked_mut(index.index) }
+        }
+
+        pub fn swap(&mut self, a: Index<'id>, b: Index<'id>) {
+            self.items.swap(a.index, b.index)
+        }
+
+        /// Removes the brand, the indices can not be used anymore since they can not leave the scope.
+        pub fn into_inner(self) -> Vec<T> {
+            self.items
+        }
+    }
+
+    impl<'id, T> std::ops::Index<Index<'id>> for BrandedVec<'id, T> {
+        type Output = T;
+
+        fn index(&self, index: Index<'id>) -> &T {
+            self.get(index)
+        }
+    }
+
+    impl<'id, T> std::ops::IndexMut<Index<'id>> for BrandedVec<'id, T> {
+        fn index_mut(&mut self, index: Index<'id>) -> &mut T {
+            self.get_mut(index)
+        }
+    }
+}
+
+/// # GADT Expressions
+///
+/// An expression language where every expression knows the type of its value, `if 1 then ...` does not even compile so `eval` needs no runtime type checks.
+/// Rust has no GADTs, the type index is emulated with an associated type. See [here](https://en.wikipedia.org/wiki/Generalized_algebraic_data_type) for more details.
+pub mod gadt {
+    /// A typed expression, `Output` is the type of its value, like `Expr<i64>` or `Expr<bool>` in Haskell.
+    ///
+    /// ```rust
+    /// # use awesome::gadt::*;
+    /// // if 1 + 2 == 3 then 10 else 20
+    /// let expr = if_(equal(add(int(1), int(2)), int(3)), int(10), int(20));
+    /// let value: i64 = expr.eval();
+    ///
+    /// assert_eq!(value, 10);
+    /// assert_eq!(expr.show(), "if ((1 + 2) == 3) then 10 else 20");
+    /// ```
+    pub trait Expr {
+        type Output;
+
+        fn eval(&self) -> Self::Output;
+
+        /// Another interpretation of the same expression, as source code.
+        fn show(&self) -> String;
+    }
+
+    /// An expression of type `T` built at runtime, the type is still checked at compile time.
+    pub type Boxed<T> = Box<dyn Expr<Output = T>>;
+
+    impl<E: Expr + ?Sized> Expr for Box<E> {
+        type Output = E::Output;
+
+        fn eval(&self) -> Self::Output {
+            (**self).eval()
+        }
+
+        fn show(&self) -> String {
+            (**self).show()
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct IntLit(pub i64);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct BoolLit(pub bool);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Add<A, B>(pub A, pub B);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Mul<A, B>(pub A, pub B);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Less<A, B>(pub A, pub B);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Equal<A, B>(pub A, pub B);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Not<A>(pub A);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct If<C, A, B>(pub C, pub A, pub B);
+
+    /// A pair of two expressions of any types.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Pair<A, B>(pub A, pub B);
+
+    /// The first element of a pair expression.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Fst<P>(pub P);
+
+    impl Expr for IntLit {
+        type Output = i64;
+
+        fn eval(&self) -> i64 {
+            self.0
+        }
+
+        fn show(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    impl Expr for BoolLit {
+        type Output = bool;
+
+        fn eval(&self) -> bool {
+            self.0
+        }
+
+        fn show(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    macro_rules! binary {
+        ($($name:ident: $input:ty => $output:ty, $op:tt;)+) => {
+            $(
+                impl<A: Expr<Output = $input>, B: Expr<Output = $input>> Expr for $name<A, B> {
+                    type Output = $output;
+
+                    fn eval(&self) -> $output {
+                        self.0.eval() $op self.1.eval()
+                    }
+
+                    fn show(&self) -> String {
+                        format!("({} {} {})", self.0.show(), stringify!($op), self.1.show())
+                    }
+                }
+            )+
+        };
+    }
+
+    binary! {
+        Add: i64 => i64, +;
+        Mul: i64 => i64, *;
+        Less: i64 => bool, <;
+    }
+
+    /// Both sides must have the same type, any type that can be compared.
+    impl<T: PartialEq, A: Expr<Output = T>, B: Expr<Output = T>> Expr for Equal<A, B> {
+        type Output = bool;
+
+        fn eval(&self) -> bool {
+            self.0.eval() == self.1.eval()
+        }
+
+        fn show(&self) -> String {
+            format!("({} == {})", self.0.show(), self.1.show())
+        }
+    }
+
+    impl<A: Expr<Output = bool>> Expr for Not<A> {
+        type Output = bool;
+
+        fn eval(&self) -> bool {
+            !self.0.eval()
+        }
+
+        fn show(&self) -> String {
+            format!("!{}", self.0.show())
+        }
+    }
+
+    /// The condition must be a `bool` and both branches must have the same type, only the taken branch is evaluated.
+    impl<T, C: Expr<Output = bool>, A: Expr<Output = T>, B: Expr<Output = T>> Expr for If<C, A, B> {
+        type Output = T;
+
+        fn eval(&self) -> T {
+            if self.0.eval() {
+                self.1.eval()
+            } else {
+                self.2.eval()
+            }
+        }
+
+        fn show(&self) -> String {
+            format!("if {} then {} else {}", self.0.show(), self.1.show(), self.2.show())
+        }
+    }
+
+    impl<A: Expr, B: Expr> Expr for Pair<A, B> {
+        type Output = (A::Output, B::Output);
+
+        fn eval(&self) -> Self::Output {
+            (self.0.eval(), self.1.eval())
+        }
+
+        fn show(&self) -> String {
+            format!("({}, {})", self.0.show(), self.1.show())
+        }
+    }
+
+    impl<A, B, P: Expr<Output = (A, B)>> Expr for Fst<P> {
+        type Output = A;
+
+        fn eval(&self) -> A {
+            self.0.eval().0
+        }
+
+        fn show(&self) -> String {
+            format!("fst {}", self.0.show())
+        }
+    }
+
+    pub fn int(value: i64) -> IntLit {
+        IntLit(value)
+    }
+
+    pub fn boolean(value: bool) -> BoolLit {
+        BoolLit(value)
+    }
+
+    pub fn add<A: Expr<Output = i64>, B: Expr<Output = i64>>(a: A, b: B) -> Add<A, B> {
+        Add(a, b)
+    }
+
+    pub fn mul<A: Expr<Output = i64>, B: Expr<Output = i64>>(a: A, b: B) -> Mul<A, B> {
+        Mul(a, b)
+    }
+
+    pub fn less<A: Expr<Output = i64>, B: Expr<Output = i64>>(a: A, b: B) -> Less<A, B> {
+        Less(a, b)
+    }
+
+    pub fn equal<T: PartialEq, A: Expr<Output = T>, B: Expr<Output = T>>(a: A, b: B) -> Equal<A, B> {
+        Equal(a, b)
+    }
+
+    pub fn not<A: Expr<Output = bool>>(a: A) -> Not<A> {
+        Not(a)
+    }
+
+    pub fn if_<T, C: Expr<Output = bool>, A: Expr<Output = T>, B: Expr<Output = T>>(condition: C, then: A, otherwise: B) -> If<C, A, B> {
+        If(condition, then, otherwise)
+    }
+
+    pub fn pair<A: Expr, B: Expr>(a: A, b: B) -> Pair<A, B> {
+        Pair(a, b)
+    }
+
+    pub fn fst<A, B, P: Expr<Output = (A, B)>>(p: P) -> Fst<P> {
+        Fst(p)
+    }
+
+    /// Boxes an expression, to store expressions of different shapes but the same type together.
+    pub fn boxed<T, E: Expr<Output = T> + 'static>(expr: E) -> Boxed<T> {
+        Box::new(expr)
+    }
+}
+
+/// # Visitor
+///
+/// A closed set of shapes behind a sealed trait, new operations are added as visitors without touching the shapes.
+/// See [here](https://rust-unofficial.github.io/patterns/patterns/behavioural/visitor.html) for more details.
+pub mod visitor {
+    use std::f64::consts::PI;
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Circle {
+        pub radius: f64,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Rect {
+        pub width: f64,
+        pub height: f64,
+    }
+
+    /// A triangle given by the lengths of its sides.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Triangle {
+        pub a: f64,
+        pub b: f64,
+        pub c: f64,
+    }
+
+    /// One method per shape, `Output` is what the operation returns.
+    pub trait Visitor {
+        type Output;
+
+        fn visit_circle(&mut self, circle: &Circle) -> Self::Output;
+
+        fn visit_rect(&mut self, rect: &Rect) -> Self::Output;
+
+        fn visit_triangle(&mut self, triangle: &Triangle) -> Self::Output;
+    }
+
+    /// A shape, it is sealed, so the set of shapes is closed and every `Visitor` handles all of them.
+    ///
+    /// `accept` is the first dispatch (on the shape), the `visit_*` call is the second one (on the visitor).
+    pub trait Shape: sealed::Sealed {
+        fn accept<V: Visitor>(&self, visitor: &mut V) -> V::Output;
+    }
+
+    /// Generates the `accept` impls, and `AnyShape` with one variant per shape for mixed collections.
+    macro_rules! visitor {
+        ($($shape:ident => $visit:ident),+ $(,)?) => {
+            $(
+                impl sealed::Sealed for $shape {}
+
+                impl Shape for $shape {
+                    fn accept<V: Visitor>(&self, visitor: &mut V) -> V::Output {
+                        visitor.$visit(self)
+                    }
+                }
+
+                impl From<$shape> for AnyShape {
+                    fn from(shape: $shape) -> Self {
+                        AnyShape::$shape(shape)
+                    }
+                }
+            )+
+
+            /// Any of the shapes, `accept` is forwarded to the one inside.
+            #[derive(Debug, Clone, Copy, PartialEq)]
+            pub enum AnyShape {
+                $($shape($shape)),+
+            }
+
+            impl sealed::Sealed for AnyShape {}
+
+            impl Shape for AnyShape {
+                fn accept<V: Visitor>(&self, visitor: &mut V) -> V::Output {
+                    match self {
+                        $(AnyShape::$shape(shape) => visitor.$visit(shape)),+
+                    }
+                }
+            }
+        };
+    }
+
+    visitor! {
+        Circle => visit_circle,
+        Rect => visit_rect,
+        Triangle => visit_triangle,
+    }
+
+    /// for example:
+    ///
+    /// ```rust
+    /// # use awesome::visitor::*;
+    /// let shapes: Vec<AnyShape> = vec![Rect { width: 2.0, height: 3.0 }.into(), Triangle { a: 3.0, b: 4.0, c: 5.0 }.into()];
+    ///
+    /// let total: f64 = shapes.iter().map(|shape| shape.accept(&mut Area)).sum();
+    /// assert_eq!(total, 12.0);
+    /// ```
+    pub struct Area;
+
+    impl Visitor for Area {
+        type Output = f64;
+
+        fn visit_circle(&mut self, circle: &Circle) -> f64 {
+            PI * circle.radius * circle.radius
+        }
+
+        fn visit_rect(&mut self, rect: &Rect) -> f64 {
+            rect.width * rect.height
+        }
+
+        // Heron's formula.
+        fn visit_triangle(&mut self, &Triangle { a, b, c }: &Triangle) -> f64 {
+            let s = (a + b + c) / 2.0;
+            (s * (s - a) * (s - b) * (s - c)).sqrt()
+        }
+    }
+
+    pub struct Perimeter;
+
+    impl Visitor for Perimeter {
+        type Output = f64;
+
+        fn visit_circle(&mut self, circle: &Circle) -> f64 {
+            2.0 * PI * circle.radius
+        }
+
+        fn visit_rect(&mut self, rect: &Rect) -> f64 {
+            2.0 * (rect.width + rect.height)
+        }
+
+        fn visit_triangle(&mut self, triangle: &Triangle) -> f64 {
+            triangle.a + triangle.b + triangle.c
+        }
+    }
+
+    /// Builds a new shape, scaled by the factor.
+    pub struct Scale(pub f64);
+
+    impl Visitor for Scale {
+        type Output = AnyShape;
+
+        fn visit_circle(&mut self, circle: &Circle) -> AnyShape {
+            Circle { radius: circle.radius * self.0 }.into()
+        }
+
+        fn visit_rect(&mut self, rect: &Rect) -> AnyShape {
+            Rect {
+                width: rect.width * self.0,
+                height: rect.height * self.0,
+            }
+            .into()
+        }
+
+        fn visit_triangle(&mut self, triangle: &Triangle) -> AnyShape {
+            Triangle {
+                a: triangle.a * self.0,
+                b: triangle.b * self.0,
+                c: triangle.c * self.0,
+            }
+            .into()
+        }
+    }
+
+    /// A visitor with state and no result, it counts the shapes by kind.
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct Count {
+        pub circles: usize,
+        pub rects: usize,
+        pub triangles: usize,
+    }
+
+    impl Visitor for Count {
+        type Output = ();
+
+        fn visit_circle(&mut self, _: &Circle) {
+            self.circles += 1;
+        }
+
+        fn visit_rect(&mut self, _: &Rect) {
+            self.rects += 1;
+        }
+
+        fn visit_triangle(&mut self, _: &Triangle) {
+            self.triangles += 1;
+        }
+    }
+}
+
+/// # Parser Combinators
+///
+/// Small parsers glued together into bigger ones, in the style of Haskell's `parsec`.
+/// See [here](https://en.wikipedia.org/wiki/Parser_combinator) for more details.
+pub mod parser {
+    use std::fmt;
+    use std::rc::Rc;
+
+    /// Why and where a parser failed.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseError {
+        /// What the parser was looking for, like `'('` or `a digit`.
+        pub expected: String,
+        /// The char that was there instead, `None` at the end of the input.
+        pub found: Option<char>,
+        /// How much of the input was left, in bytes.
+        pub remaining: usize,
+    }
+
+    impl ParseError {
+        pub fn new(expected: impl Into<String>, rest: &str) -> Self {
+            ParseError {
+                expected: expected.into(),
+                found: rest.chars().next(),
+                remaining: rest.len(),
+            }
+        }
+
+        /// The byte offset in `input` where the parser failed.
+        pub fn offset(&self, input: &str) -> usize {
+            input.len() - self.remaining
+        }
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.found {
+                Some(c) => write!(f, "expected {}, found {c:?}", self.expected),
+                None => write!(f, "expected {}, found the end of the input", self.expected),
+            }
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    type ParseFn<'a, T> = dyn Fn(&'a str) -> Result<(T, &'a str), ParseError> + 'a;
+
+    /// A parser of `T`, it returns the value and the rest of the input.
+    ///
+    /// it is reference counted, so cloning is cheap and a parser can be used in several places.
+    pub struct Parser<'a, T>(Rc<ParseFn<'a, T>>);
+
+    impl<T> Clone for Parser<'_, T> {
+        fn clone(&self) -> Self {
+            Parser(Rc::clone(&self.0))
+        }
+    }
+
+    impl<'a, T: 'a> Parser<'a, T> {
+        pub fn new(f: impl Fn(&'a str) -> Result<(T, &'a str), ParseError> + 'a) -> Self {
+            Parser(Rc::new(f))
+        }
+
+        pub fn parse(&self, input: &'a str) -> Result<(T, &'a str), ParseError> {
+            (self.0)(input)
+        }
+
+        /// Like `parse`, but the whole input must be consumed.
+        ///
+        /// ```rust
+        /// # use awesome::parser::{char_, digit};
+        /// let digits = digit().sep_by(char_(','));
+        ///
+        /// assert_eq!(digits.parse_all("1,2,3"), Ok(vec!['1', '2', '3']));
+        /// assert_eq!(digits.parse_all("1,2;3").unwrap_err().to_string(), "expected the end of the input, found ';'");
+        /// ```
+        pub fn parse_all(&self, input: &'a str) -> Result<T, ParseError> {
+            match self.parse(input)? {
+                (value, "") => Ok(value),
+                (_, rest) => Err(ParseError::new("the end of the input", rest)),
+            }
+        }
+
+        pub fn map<U: 'a>(self, f: impl Fn(T) -> U + 'a) -> Parser<'a, U> {
+            Parser::new(move |input| self.parse(input).map(|(value, rest)| (f(value), rest)))
+        }
+
+        /// Picks the next parser from the value of this one, the monadic bind.
+        pub fn and_then<U: 'a>(self, f: impl Fn(T) -> Parser<'a, U> + 'a) -> Parser<'a, U> {
+            Parser::new(move |input| {
+                let (value, rest) = self.parse(input)?;
+                f(value).parse(rest)
+            })
+        }
+
+        /// Both in sequence, keeping both values.
+        pub fn zip<U: 'a>(self, next: Parser<'a, U>) -> Parser<'a, (T, U)> {
+            Parser::new(move |input| {
+                let (a, rest) = self.parse(input)?;
+                let (b, rest) = next.parse(rest)?;
+                Ok(((a, b), rest))
+            })
+        }
+
+        /// Both in sequence, keeping the value of `next`, Haskell's `*>`.
+        pub fn then<U: 'a>(self, next: Parser<'a, U>) -> Parser<'a, U> {
+            self.zip(next).map(|(_, b)| b)
+        }
+
+        /// Both in sequence, keeping the value of this one, Haskell's `<*`.
+        pub fn skip<U: 'a>(self, next: Parser<'a, U>) -> Parser<'a, T> {
+            self.zip(next).map(|(a, _)| a)
+        }
+
+        /// Tries `other` if this one fails, always backtracking.
+        ///
+        /// if both fail at the same place, the error expects either one, otherwise the error that got further wins.
+        pub fn or(self, other: Parser<'a, T>) -> Parser<'a, T> {
+            Parser::new(move |input| {
+                self.parse(input).or_else(|first| {
+                    other.parse(input).map_err(|second| match first.remaining.cmp(&second.remaining) {
+                        std::cmp::Ordering::Less => first,
+                        std::cmp::Ordering::Greater => second,
+                        std::cmp::Ordering::Equal => ParseError {
+                            expected: format!("{} or {}", first.expected, second.expected),
+                            ..second
+                        },
+                    })
+                })
+            })
+        }
+
+        /// Zero or more times, it stops when the parser fails or consumes nothing.
+        ///
+        /// like in parsec, a failure after consuming some input is an error and not the end of the repetition,
+        /// so `1 + * 2` reports the `*` instead of stopping before the `+`.
+        pub fn many(self) -> Parser<'a, Vec<T>> {
+            Parser::new(move |mut input: &'a str| {
+                let mut values = Vec::new();
+                loop {
+                    match self.parse(input) {
+                        Ok((value, rest)) if rest.len() < input.len() => {
+                            values.push(value);
+                            input = rest;
+                        }
+                        Err(e) if e.remaining < input.len() => return Err(e),
+                        _ => return Ok((values, input)),
+                    }
+                }
+            })
+        }
+
+        /// One or more times.
+        pub fn many1(self) -> Parser<'a, Vec<T>> {
+            self.clone().zip(self.many()).map(|(first, mut rest)| {
+                rest.insert(0, first);
+                rest
+            })
+        }
+
+        /// Zero or more times, separated by `separator`. like `optional`, a failure after consuming some input is still an error.
+        pub fn sep_by<S: 'a>(self, separator: Parser<'a, S>) -> Parser<'a, Vec<T>> {
+            self.sep_by1(separator).optional().map(Option::unwrap_or_default)
+        }
+
+        /// One or more times, separated by `separator`.
+        pub fn sep_by1<S: 'a>(self, separator: Parser<'a, S>) -> Parser<'a, Vec<T>> {
+            let more = separator.then(self.clone()).many();
+            self.zip(more).map(|(first, mut rest)| {
+                rest.insert(0, first);
+                rest
+            })
+        }
+
+        /// Between `open` and `close`, keeping the value in the middle.
+        pub fn between<L: 'a, R: 'a>(self, open: Parser<'a, L>, close: Parser<'a, R>) -> Parser<'a, T> {
+            open.then(self).skip(close)
+        }
+
+        /// `None` instead of failing, a failure after consuming some input is still an error like in `many`.
+        pub fn optional(self) -> Parser<'a, Option<T>> {
+            Parser::new(move |input: &'a str| match self.parse(input) {
+                Ok((value, rest)) => Ok((Some(value), rest)),
+                Err(e) if e.remaining < input.len() => Err(e),
+                Err(_) => Ok((None, input)),
+            })
+        }
+
+        /// Skips the whitespace after the value.
+        pub fn token(self) -> Parser<'a, T> {
+            self.skip(spaces())
+        }
+
+        /// Replaces the expectation in the error, for a friendlier message, Haskell's `<?>`.
+        ///
+        /// only a failure at the start is relabeled, an error deeper inside is more precise and kept as it is.
+        pub fn label(self, expected: &'a str) -> Parser<'a, T> {
+            Parser::new(move |input: &'a str| {
+                self.parse(input).map_err(|e| match e.remaining == input.len() {
+                    true => ParseError::new(expected, input),
+                    false => e,
+                })
+            })
+        }
+
+        /// The part of the input that was consumed, instead of the value.
+        pub fn recognize(self) -> Parser<'a, &'a str> {
+            Parser::new(move |input: &'a str| {
+                let (_, rest) = self.parse(input)?;
+                Ok((&input[..input.len() - rest.len()], rest))
+            })
+        }
+
+        /// One or more values separated by `op`, combined from the left, `1 - 2 - 3` is `(1 - 2) - 3`.
+        pub fn chain_left<F: Fn(T, T) -> T + 'a>(self, op: Parser<'a, F>) -> Parser<'a, T> {
+            let rest = op.zip(self.clone()).many();
+            self.zip(rest).map(|(first, rest)| rest.into_iter().fold(first, |acc, (f, value)| f(acc, value)))
+        }
+    }
+
+    /// Succeeds without consuming anything.
+    pub fn pure<'a, T: Clone + 'a>(value: T) -> Parser<'a, T> {
+        pure_with(move || value.clone())
+    }
+
+    fn pure_with<'a, T: 'a>(value: impl Fn() -> T + 'a) -> Parser<'a, T> {
+        Parser::new(move |input| Ok((value(), input)))
+    }
+
+    /// Always fails.
+    pub fn fail<'a, T: 'a>(expected: &'a str) -> Parser<'a, T> {
+        Parser::new(move |input| Err(ParseError::new(expected, input)))
+    }
+
+    /// One char matching `predicate`.
+    pub fn satisfy<'a>(expected: &'a str, predicate: impl Fn(char) -> bool + 'a) -> Parser<'a, char> {
+        Parser::new(move |input: &'a str| match input.chars().next() {
+            Some(c) if predicate(c) => Ok((c, &input[c.len_utf8()..])),
+            _ => Err(ParseError::new(expected, input)),
+        })
+    }
+
+    pub fn char_<'a>(expected: char) -> Parser<'a, char> {
+        Parser::new(move |input: &'a str| match input.strip_prefix(expected) {
+            Some(rest) => Ok((expected, rest)),
+            None => Err(ParseError::new(format!("{expected:?}"), input)),
+        })
+    }
+
+    pub fn digit<'a>() -> Parser<'a, char> {
+        satisfy("a digit", |c| c.is_ascii_digit())
+    }
+
+    pub fn string<'a>(expected: &'a str) -> Parser<'a, &'a str> {
+        Parser::new(move |input: &'a str| match input.strip_prefix(expected) {
+            Some(rest) => Ok((expected, rest)),
+            None => Err(ParseError::new(format!("{expected:?}"), input)),
+        })
+    }
+
+    /// Zero or more whitespace chars.
+    pub fn spaces<'a>() -> Parser<'a, ()> {
+        Parser::new(|input: &'a str| Ok(((), input.trim_start())))
+    }
+
+    /// Succeeds only at the end of the input.
+    pub fn eof<'a>() -> Parser<'a, ()> {
+        Parser::new(|input: &'a str| match input {
+            "" => Ok(((), input)),
+            _ => Err(ParseError::new("the end of the input", input)),
+        })
+    }
+
+    /// Builds the parser only when it runs, for recursive grammars.
+    pub fn lazy<'a, T: 'a>(f: impl Fn() -> Parser<'a, T> + 'a) -> Parser<'a, T> {
+        Parser::new(move |input| f().parse(input))
+    }
+
+    /// Arithmetic expressions parsed into an AST, with the usual precedence.
+    ///
+    /// ```text
+    /// expr   = term (('+' | '-') term)*
+    /// term   = factor (('*' | '/') factor)*
+    /// factor = '-' factor | number | '(' expr ')'
+    /// ```
+    pub mod arithmetic {
+        use super::{char_, digit, fail, lazy, pure, spaces, ParseError, Parser};
+        use crate::arena::Arena;
+
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum Expr {
+            Num(i64),
+            Neg(Box<Expr>),
+            Add(Box<Expr>, Box<Expr>),
+            Sub(Box<Expr>, Box<Expr>),
+            Mul(Box<Expr>, Box<Expr>),
+            Div(Box<Expr>, Box<Expr>),
+        }
+
+        impl Expr {
+            /// `None` on overflow or a division by zero.
+            pub fn eval(&self) -> Option<i64> {
+                match self {
+                    Expr::Num(n) => Some(*n),
+                    Expr::Neg(e) => e.eval()?.checked_neg(),
+                    Expr::Add(a, b) => a.eval()?.checked_add(b.eval()?),
+                    Expr::Sub(a, b) => a.eval()?.checked_sub(b.eval()?),
+                    Expr::Mul(a, b) => a.eval()?.checked_mul(b.eval()?),
+                    Expr::Div(a, b) => a.eval()?.checked_div(b.eval()?),
+                }
+            }
+        }
+
+        /// An expression allocated in an [`Arena`], see [`parse_in`].
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Node<'a> {
+            Num(i64),
+            Neg(&'a Node<'a>),
+            Add(&'a Node<'a>, &'a Node<'a>),
+            Sub(&'a Node<'a>, &'a Node<'a>),
+            Mul(&'a Node<'a>, &'a Node<'a>),
+            Div(&'a Node<'a>, &'a Node<'a>),
+        }
+
+        impl Node<'_> {
+            /// `None` on overflow or a division by zero.
+            pub fn eval(&self) -> Option<i64> {
+                match self {
+                    Node::Num(n) => Some(*n),
+                    Node::Neg(e) => e.eval()?.checked_neg(),
+                    Node::Add(a, b) => a.eval()?.checked_add(b.eval()?),
+                    Node::Sub(a, b) => a.eval()?.checked_sub(b.eval()?),
+                    Node::Mul(a, b) => a.eval()?.checked_mul(b.eval()?),
+                    Node::Div(a, b) => a.eval()?.checked_div(b.eval()?),
+                }
+            }
+        }
+
+        /// Makes the nodes of the AST, so the same grammar builds boxed [`Expr`]s or arena allocated [`Node`]s.
+        pub trait Build<'a>: Copy + 'a {
+            type Expr: Clone + 'a;
+
+            fn num(self, n: i64) -> Self::Expr;
+            fn neg(self, e: Self::Expr) -> Self::Expr;
+            fn add(self, a: Self::Expr, b: Self::Expr) -> Self::Expr;
+            fn sub(self, a: Self::Expr, b: Self::Expr) -> Self::Expr;
+            fn mul(self, a: Self::Expr, b: Self::Expr) -> Self::Expr;
+            fn div(self, a: Self::Expr, b: Self::Expr) -> Self::Expr;
+        }
+
+        /// Builds [`Expr`]s.
+        #[derive(Debug, Clone, Copy)]
+        pub struct Boxed;
+
+        impl Build<'_> for Boxed {
+            type Expr = Expr;
+
+            fn num(self, n: i64) -> Expr {
+                Expr::Num(n)
+            }
+
+            fn neg(self, e: Expr) -> Expr {
+                Expr::Neg(Box::new(e))
+            }
+
+            fn add(self, a: Expr, b: Expr) -> Expr {
+                Expr::Add(Box::new(a), Box::new(b))
+            }
+
+            fn sub(self, a: Expr, b: Expr) -> Expr {
+                Expr::Sub(Box::new(a), Box::new(b))
+            }
+
+            fn mul(self, a: Expr, b: Expr) -> Expr {
+                Expr::Mul(Box::new(a), Box::new(b))
+            }
+
+            fn div(self, a: Expr, b: Expr) -> Expr {
+                Expr::Div(Box::new(a), Box::new(b))
+            }
+        }
+
+        impl<'a> Build<'a> for &'a Arena<Node<'a>> {
+            type Expr = &'a Node<'a>;
+
+            fn num(self, n: i64) -> &'a Node<'a> {
+                self.alloc(Node::Num(n))
+            }
+
+            fn neg(self, e: &'a Node<'a>) -> &'a Node<'a> {
+                self.alloc(Node::Neg(e))
+            }
+
+            fn add(self, a: &'a Node<'a>, b: &'a Node<'a>) -> &'a Node<'a> {
+                self.alloc(Node::Add(a, b))
+            }
+
+            fn sub(self, a: &'a Node<'a>, b: &'a Node<'a>) -> &'a Node<'a> {
+                self.alloc(Node::Sub(a, b))
+            }
+
+            fn mul(self, a: &'a Node<'a>, b: &'a Node<'a>) -> &'a Node<'a> {
+                self.alloc(Node::Mul(a, b))
+            }
+
+            fn div(self, a: &'a Node<'a>, b: &'a Node<'a>) -> &'a Node<'a> {
+                self.alloc(Node::Div(a, b))
+            }
+        }
+
+        fn symbol<'a>(c: char) -> Parser<'a, char> {
+            char_(c).token()
+        }
+
+        fn binary<'a, B: Build<'a>>(c: char, build: B, op: fn(B, B::Expr, B::Expr) -> B::Expr) -> Parser<'a, impl Fn(B::Expr, B::Expr) -> B::Expr> {
+            symbol(c).map(move |_| move |a, b| op(build, a, b))
+        }
+
+        fn number<'a, B: Build<'a>>(build: B) -> Parser<'a, B::Expr> {
+            digit()
+                .many1()
+                .and_then(move |digits| match digits.into_iter().collect::<String>().parse() {
+                    Ok(n) => pure(build.num(n)),
+                    Err(_) => fail("a smaller number"),
+                })
+                .token()
+                .label("a number")
+        }
+
+        fn factor<'a, B: Build<'a>>(build: B) -> Parser<'a, B::Expr> {
+            let neg = symbol('-').then(lazy(move || factor(build))).map(move |e| build.neg(e));
+            let parens = lazy(move || expr_with(build)).between(symbol('('), symbol(')'));
+            neg.or(number(build)).or(parens)
+        }
+
+        fn term<'a, B: Build<'a>>(build: B) -> Parser<'a, B::Expr> {
+            factor(build).chain_left(binary('*', build, B::mul).or(binary('/', build, B::div)))
+        }
+
+        /// The grammar, making the nodes with `build`.
+        pub fn expr_with<'a, B: Build<'a>>(build: B) -> Parser<'a, B::Expr> {
+            term(build).chain_left(binary('+', build, B::add).or(binary('-', build, B::sub)))
+        }
+
+        pub fn expr<'a>() -> Parser<'a, Expr> {
+            expr_with(Boxed)
+        }
+
+        /// for example:
+        ///
+        /// ```rust
+        /// # use awesome::parser::arithmetic::{parse, Expr};
+        /// let e = parse("1 + 2 * 3").unwrap();
+        ///
+        /// assert_eq!(e, Expr::Add(Box::new(Expr::Num(1)), Box::new(Expr::Mul(Box::new(Expr::Num(2)), Box::new(Expr::Num(3))))));
+        /// assert_eq!(e.eval(), Some(7));
+        /// ```
+        pub fn parse(input: &str) -> Result<Expr, ParseError> {
+            spaces().then(expr()).parse_all(input)
+        }
+
+        /// Like [`parse`], but every node is allocated in `arena`, so the tree is freed in one go with it.
+        ///
+        /// ```rust
+        /// # use awesome::arena::Arena;
+        /// # use awesome::parser::arithmetic::{parse_in, Node};
+        /// let arena = Arena::new();
+        /// let e = parse_in(&arena, "(1 + 2) * -3").unwrap();
+        ///
+        /// assert!(matches!(e, Node::Mul(Node::Add(..), Node::Neg(Node::Num(3)))));
+        /// assert_eq!(e.eval(), Some(-9));
+        /// assert_eq!(arena.len(), 6);
+        /// ```
+        pub fn parse_in<'a>(arena: &'a Arena<Node<'a>>, input: &'a str) -> Result<&'a Node<'a>, ParseError> {
+            spaces().then(expr_with(arena)).parse_all(input)
+        }
+    }
+}
+
+/// # JSON Parser
+///
+/// A JSON parser made only of the parser combinators of [`parser`](crate::parser).
+/// See [here](https://www.json.org/) for the grammar.
+pub mod json {
+    use crate::parser::{char_, digit, fail, lazy, pure, satisfy, string, ParseError, Parser};
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Json {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(BTreeMap<String, Json>),
+    }
+
+    impl Json {
+        /// The value of `key`, if this is an object.
+        pub fn get(&self, key: &str) -> Option<&Json> {
+            match self {
+                Json::Object(members) => members.get(key),
+                _ => None,
+            }
+        }
+    }
+
+    /// Compact JSON, parsing it gives the same value back (except for the numbers that are not finite, they become `null`).
+    impl fmt::Display for Json {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Json::Null => write!(f, "null"),
+                Json::Bool(b) => write!(f, "{b}"),
+                Json::Number(n) if n.is_finite() => write!(f, "{n}"),
+                Json::Number(_) => write!(f, "null"),
+                Json::String(s) => write_string(f, s),
+                Json::Array(values) => {
+                    write!(f, "[")?;
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{value}")?;
+                    }
+                    write!(f, "]")
+                }
+                Json::Object(members) => {
+                    write!(f, "{{")?;
+                    for (i, (key, value)) in members.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ",")?;
+                        }
+                        write_string(f, key)?;
+                        write!(f, ":{value}")?;
+                    }
+                    write!(f, "}}")
+                }
+            }
+        }
+    }
+
+    fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in s.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        write!(f, "\"")
+    }
+
+    /// The JSON whitespace, it is fewer chars than `char::is_whitespace`.
+    fn ws<'a>() -> Parser<'a, ()> {
+        satisfy("whitespace", |c| matches!(c, ' ' | '\t' | '\n' | '\r')).many().map(|_| ())
+    }
+
+    fn symbol<'a>(c: char) -> Parser<'a, char> {
+        char_(c).skip(ws())
+    }
+
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+    fn number<'a>() -> Parser<'a, Json> {
+        let integer = char_('0')
+            .map(|_| ())
+            .or(satisfy("a digit", |c| matches!(c, '1'..='9')).then(digit().many()).map(|_| ()));
+        let fraction = char_('.').then(digit().many1()).optional();
+        let exponent = satisfy("'e'", |c| c == 'e' || c == 'E')
+            .then(satisfy("a sign", |c| c == '+' || c == '-').optional())
+            .then(digit().many1())
+            .optional();
+
+        char_('-')
+            .optional()
+            .zip(integer)
+            .zip(fraction)
+            .zip(exponent)
+            .recognize()
+            .map(|number| Json::Number(number.parse().expect("a JSON number is a valid f64")))
+    }
+
+    fn hex4<'a>() -> Parser<'a, u32> {
+        let hex = satisfy("a hex digit", |c| c.is_ascii_hexdigit());
+        hex.clone()
+            .zip(hex.clone())
+            .zip(hex.clone())
+            .zip(hex)
+            .recognize()
+            .map(|digits| u32::from_str_radix(digits, 16).expect("four hex digits"))
+    }
+
+    /// `\n`, `é`, or a surrogate pair like `😀` for the chars outside of the basic plane.
+    fn escape<'a>() -> Parser<'a, char> {
+        let simple = satisfy("an escape char", |c| "\"\\/bfnrt".contains(c)).map(|c| match c {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            c => c,
+        });
+        let unicode = char_('u').then(hex4()).and_then(|unit| match unit {
+            0xD800..=0xDBFF => string("\\u").then(hex4()).label("a low surrogate").and_then(move |low| match low {
+                0xDC00..=0xDFFF => pure(char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)).expect("a valid surrogate pair")),
+                _ => fail("a low surrogate"),
+            }),
+            0xDC00..=0xDFFF => fail("a high surrogate before the low one"),
+            _ => pure(char::from_u32(unit).expect("not a surrogate")),
+        });
+
+        char_('\\').then(simple.or(unicode))
+    }
+
+    fn json_string<'a>() -> Parser<'a, String> {
+        // the control chars must be escaped.
+        let plain = satisfy("a string char", |c| c != '"' && c != '\\' && c >= ' ');
+        plain.or(escape()).many().map(|chars| chars.into_iter().collect()).between(char_('"'), char_('"'))
+    }
+
+    /// A list of `item`s between `open` and `close`, without trailing whitespace.
+    fn list<'a, T: 'a>(open: char, item: Parser<'a, T>, close: char) -> Parser<'a, Vec<T>> {
+        let empty = char_(close).map(|_| Vec::new());
+        let items = item.skip(ws()).sep_by1(symbol(',')).skip(char_(close));
+        symbol(open).then(empty.or(items))
+    }
+
+    fn value<'a>() -> Parser<'a, Json> {
+        let null = string("null").map(|_| Json::Null);
+        let boolean = string("true").map(|_| Json::Bool(true)).or(string("false").map(|_| Json::Bool(false)));
+        let array = list('[', lazy(value), ']').map(Json::Array);
+        let member = json_string().skip(ws()).skip(symbol(':')).zip(lazy(value));
+        let object = list('{', member, '}').map(|members| Json::Object(members.into_iter().collect()));
+
+        null.or(boolean)
+            .or(number())
+            .or(json_string().map(Json::String))
+            .or(array)
+            .or(object)
+            .label("a JSON value")
+    }
+
+    /// Parses a whole JSON document, whitespace is allowed around it.
+    ///
+    /// ```rust
+    /// # use awesome::json::{parse_json, Json};
+    /// let json = parse_json(r#"{"name": "awesome", "tags": ["rust", "fp"], "stars": 4.5}"#).unwrap();
+    ///
+    /// assert_eq!(json.get("stars"), Some(&Json::Number(4.5)));
+    /// assert_eq!(json.to_string(), r#"{"name":"awesome","stars":4.5,"tags":["rust","fp"]}"#);
+    ///
+    /// let error = parse_json("[1, 2,]").unwrap_err();
+    /// assert_eq!(error.to_string(), "expected a JSON value, found ']'");
+    /// assert_eq!(error.offset("[1, 2,]"), 6);
+    /// ```
+    pub fn parse_json(input: &str) -> Result<Json, ParseError> {
+        ws().then(value()).skip(ws()).parse_all(input)
+    }
+}
+
+/// # Pratt Parser
+///
+/// Operator precedence parsing driven by a table built at runtime, the grammar does not change when an operator is added.
+/// See [here](https://matklad.github.io/2020/04/13/simple-but-powerful-pratt-parsing.html) for more details.
+pub mod pratt {
+    use std::collections::HashMap;
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Assoc {
+        /// `a - b - c` is `(a - b) - c`.
+        Left,
+        /// `a ^ b ^ c` is `a ^ (b ^ c)`.
+        Right,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Expr {
+        Num(i64),
+        Var(String),
+        Prefix(String, Box<Expr>),
+        Postfix(String, Box<Expr>),
+        Binary(String, Box<Expr>, Box<Expr>),
+        /// `f(a, b)`
+        Call(String, Vec<Expr>),
+        /// `let x = value in body`, or `let f(x, y) = value in body` with parameters.
+        ///
+        /// the body is optional for a definition on its own, like `let x = 1` in a repl.
+        Let(String, Vec<String>, Box<Expr>, Option<Box<Expr>>),
+    }
+
+    /// Fully parenthesized, in prefix notation, so the shape of the tree is visible: `1 + 2 * 3` is `(+ 1 (* 2 3))`.
+    impl fmt::Display for Expr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Expr::Num(n) => write!(f, "{n}"),
+                Expr::Var(name) => write!(f, "{name}"),
+                Expr::Prefix(op, operand) | Expr::Postfix(op, operand) => write!(f, "({op} {operand})"),
+                Expr::Binary(op, left, right) => write!(f, "({op} {left} {right})"),
+                Expr::Call(name, args) => {
+                    write!(f, "({name}")?;
+                    args.iter().try_for_each(|arg| write!(f, " {arg}"))?;
+                    write!(f, ")")
+                }
+                Expr::Let(name, params, value, body) => {
+                    match params.is_empty() {
+                        true => write!(f, "(let {name} {value}")?,
+                        false => write!(f, "(let ({name} {}) {value}", params.join(" "))?,
+                    }
+                    match body {
+                        Some(body) => write!(f, " {body})"),
+                        None => write!(f, ")"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// The error and the byte offset where it happened.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum PrattError {
+        UnexpectedChar(char, usize),
+        UnexpectedToken(String, usize),
+        UnexpectedEnd,
+        NumberTooLarge(usize),
+    }
+
+    impl fmt::Display for PrattError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                PrattError::UnexpectedChar(c, at) => write!(f, "unexpected char {c:?} at {at}"),
+                PrattError::UnexpectedToken(token, at) => write!(f, "unexpected {token:?} at {at}"),
+                PrattError::UnexpectedEnd => write!(f, "unexpected end of the input"),
+                PrattError::NumberTooLarge(at) => write!(f, "number too large at {at}"),
+            }
+        }
+    }
+
+    impl std::error::Error for PrattError {}
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Token {
+        Num(i64),
+        Ident(String),
+        Op(String),
+        Open,
+        Close,
+        Comma,
+    }
+
+    impl Token {
+        fn text(&self) -> String {
+            match self {
+                Token::Num(n) => n.to_string(),
+                Token::Ident(s) | Token::Op(s) => s.clone(),
+                Token::Open => "(".to_string(),
+                Token::Close => ")".to_string(),
+                Token::Comma => ",".to_string(),
+            }
+        }
+    }
+
+    /// The operators with their precedences, a higher precedence binds tighter.
+    ///
+    /// the same symbol can be a prefix and a binary (or postfix) operator, like `-`, the position tells which one it is.
+    ///
+    /// besides the operators, a name followed by `(` is a call and `let` and `in` are keywords for the bindings.
+    #[derive(Debug, Clone, Default)]
+    pub struct OperatorTable {
+        prefix: HashMap<String, u8>,
+        postfix: HashMap<String, u8>,
+        binary: HashMap<String, (u8, Assoc)>,
+    }
+
+    impl OperatorTable {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// `+ -`, then `* / %`, then prefix `-`, then `^` (right associative), then postfix `!`.
+        ///
+        /// ```rust
+        /// # use awesome::pratt::OperatorTable;
+        /// let table = OperatorTable::arithmetic();
+        ///
+        /// assert_eq!(table.parse("1 + 2 * 3").unwrap().to_string(), "(+ 1 (* 2 3))");
+        /// assert_eq!(table.parse("-2 ^ 3 ^ 2").unwrap().to_string(), "(- (^ 2 (^ 3 2)))");
+        /// assert_eq!(table.parse("(a - b)! * c").unwrap().to_string(), "(* (! (- a b)) c)");
+        /// ```
+        pub fn arithmetic() -> Self {
+            OperatorTable::new()
+                .binary("+", 10, Assoc::Left)
+                .binary("-", 10, Assoc::Left)
+                .binary("*", 20, Assoc::Left)
+                .binary("/", 20, Assoc::Left)
+                .binary("%", 20, Assoc::Left)
+                .prefix("-", 25)
+                .binary("^", 30, Assoc::Right)
+                .postfix("!", 40)
+        }
+
+        /// Adds (or replaces) a binary operator, the precedence must be at least 1.
+        pub fn binary(mut self, op: &str, precedence: u8, assoc: Assoc) -> Self {
+            self.binary.insert(op.to_string(), (precedence.max(1), assoc));
+            self
+        }
+
+        pub fn prefix(mut self, op: &str, precedence: u8) -> Self {
+            self.prefix.insert(op.to_string(), precedence.max(1));
+            self
+        }
+
+        /// A postfix operator wins over a binary operator with the same symbol.
+        pub fn postfix(mut self, op: &str, precedence: u8) -> Self {
+            self.postfix.insert(op.to_string(), precedence.max(1));
+            self
+        }
+
+        pub fn parse(&self, input: &str) -> Result<Expr, PrattError> {
+            let tokens = self.tokenize(input)?;
+            let mut parser = Parser { table: self, tokens, next: 0 };
+            let expr = parser.expr(0)?;
+            match parser.tokens.get(parser.next) {
+                None => Ok(expr),
+                Some((token, at)) => Err(PrattError::UnexpectedToken(token.text(), *at)),
+            }
+        }
+
+        /// The symbols of the table are matched longest first, so `**` is not read as two `*`.
+        fn tokenize(&self, input: &str) -> Result<Vec<(Token, usize)>, PrattError> {
+            let mut symbols: Vec<&str> = self.prefix.keys().chain(self.postfix.keys()).chain(self.binary.keys()).map(String::as_str).collect();
+            symbols.sort_by_key(|s| std::cmp::Reverse(s.len()));
+
+            let mut tokens = Vec::new();
+            let mut rest = input;
+            while let Some(c) = rest.chars().next() {
+                let at = input.len() - rest.len();
+                let len = if c.is_whitespace() {
+                    c.len_utf8()
+                } else if let Some(token) = [('(', Token::Open), (')', Token::Close), (',', Token::Comma)].into_iter().find(|(p, _)| *p == c) {
+                    tokens.push((token.1, at));
+                    1
+                } else if c.is_ascii_digit() {
+                    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                    let n = rest[..digits].parse().map_err(|_| PrattError::NumberTooLarge(at))?;
+                    tokens.push((Token::Num(n), at));
+                    digits
+                } else if c.is_alphabetic() || c == '_' {
+                    let name = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+                    tokens.push((Token::Ident(rest[..name].to_string()), at));
+                    name
+                } else if let Some(symbol) = symbols.iter().find(|s| rest.starts_with(**s)) {
+                    tokens.push((Token::Op(symbol.to_string()), at));
+                    symbol.len()
+                } else if c == '=' {
+                    // the `=` of `let`, when it is not an operator of the table.
+                    tokens.push((Token::Op("=".to_string()), at));
+                    1
+                } else {
+                    return Err(PrattError::UnexpectedChar(c, at));
+                };
+                rest = &rest[len..];
+            }
+            Ok(tokens)
+        }
+    }
+
+    struct Parser<'t> {
+        table: &'t OperatorTable,
+        tokens: Vec<(Token, usize)>,
+        next: usize,
+    }
+
+    impl Parser<'_> {
+        fn advance(&mut self) -> Result<(Token, usize), PrattError> {
+            let token = self.tokens.get(self.next).cloned().ok_or(PrattError::UnexpectedEnd)?;
+            self.next += 1;
+            Ok(token)
+        }
+
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.next).map(|(token, _)| token)
+        }
+
+        fn expect(&mut self, expected: Token) -> Result<(), PrattError> {
+            match self.advance()? {
+                (token, _) if token == expected => Ok(()),
+                (token, at) => Err(PrattError::UnexpectedToken(token.text(), at)),
+            }
+        }
+
+        fn name(&mut self) -> Result<String, PrattError> {
+            match self.advance()? {
+                (Token::Ident(name), _) => Ok(name),
+                (token, at) => Err(PrattError::UnexpectedToken(token.text(), at)),
+            }
+        }
+
+        /// Zero or more of `item` separated by `,` and closed by `)`, the `(` is already consumed.
+        fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, PrattError>) -> Result<Vec<T>, PrattError> {
+            let mut items = Vec::new();
+            if self.peek() == Some(&Token::Close) {
+                self.next += 1;
+                return Ok(items);
+            }
+            loop {
+                items.push(item(self)?);
+                match self.advance()? {
+                    (Token::Comma, _) => continue,
+                    (Token::Close, _) => return Ok(items),
+                    (token, at) => return Err(PrattError::UnexpectedToken(token.text(), at)),
+                }
+            }
+        }
+
+        /// `let name = value in body` or `let name(params) = value in body`, the `let` is already consumed.
+        fn binding(&mut self) -> Result<Expr, PrattError> {
+            let name = self.name()?;
+            let params = match self.peek() {
+                Some(Token::Open) => {
+                    self.next += 1;
+                    self.list(Self::name)?
+                }
+                _ => Vec::new(),
+            };
+            self.expect(Token::Op("=".to_string()))?;
+            let value = self.expr(0)?;
+            let body = match self.peek() {
+                Some(Token::Ident(word)) if word == "in" => {
+                    self.next += 1;
+                    Some(Box::new(self.expr(0)?))
+                }
+                _ => None,
+            };
+            Ok(Expr::Let(name, params, Box::new(value), body))
+        }
+
+        /// Parses while the operators bind at least as tight as `min`.
+        ///
+        /// `min` is wider than a precedence, the right side of a left associative operator at `u8::MAX` needs one more.
+        fn expr(&mut self, min: u16) -> Result<Expr, PrattError> {
+            let mut left = match self.advance()? {
+                (Token::Num(n), _) => Expr::Num(n),
+                (Token::Ident(word), _) if word == "let" => self.binding()?,
+                (Token::Ident(name), _) if self.peek() == Some(&Token::Open) => {
+                    self.next += 1;
+                    Expr::Call(name, self.list(|parser| parser.expr(0))?)
+                }
+                (Token::Ident(name), _) => Expr::Var(name),
+                (Token::Open, _) => {
+                    let inner = self.expr(0)?;
+                    match self.advance()? {
+                        (Token::Close, _) => inner,
+                        (token, at) => return Err(PrattError::UnexpectedToken(token.text(), at)),
+                    }
+                }
+                (Token::Op(op), at) => match self.table.prefix.get(&op) {
+                    Some(&precedence) => Expr::Prefix(op, Box::new(self.expr(precedence.into())?)),
+                    None => return Err(PrattError::UnexpectedToken(op, at)),
+                },
+                (token @ (Token::Close | Token::Comma), at) => return Err(PrattError::UnexpectedToken(token.text(), at)),
+            };
+
+            while let Some((token, at)) = self.tokens.get(self.next).cloned() {
+                let op = match token {
+                    Token::Op(op) => op,
+                    Token::Close | Token::Comma => break,
+                    Token::Ident(word) if word == "in" => break,
+                    token => return Err(PrattError::UnexpectedToken(token.text(), at)),
+                };
+
+                if let Some(&precedence) = self.table.postfix.get(&op) {
+                    if u16::from(precedence) < min {
+                        break;
+                    }
+                    self.next += 1;
+                    left = Expr::Postfix(op, Box::new(left));
+                } else if let Some(&(precedence, assoc)) = self.table.binary.get(&op) {
+                    let precedence = u16::from(precedence);
+                    if precedence < min {
+                        break;
+                    }
+                    self.next += 1;
+                    // the right side of a left associative operator must bind tighter, so the next one is not taken.
+                    let next = match assoc {
+                        Assoc::Left => precedence + 1,
+                        Assoc::Right => precedence,
+                    };
+                    let right = self.expr(next)?;
+                    left = Expr::Binary(op, Box::new(left), Box::new(right));
+                } else {
+                    return Err(PrattError::UnexpectedToken(op, at));
+                }
+            }
+
+            Ok(left)
+        }
+    }
+}
+
+/// # Interpreter
+///
+/// A tree-walking interpreter for the expressions of the Pratt parser, with integers, booleans, `let` bindings and functions.
+/// See [here](https://craftinginterpreters.com/a-tree-walk-interpreter.html) for more details.
+pub mod interpreter {
+    use crate::persistent_map::PersistentMap;
+    use crate::pratt::{Assoc, Expr, OperatorTable, PrattError};
+    use std::fmt;
+    use std::rc::Rc;
+
+    type BuiltinFn = dyn Fn(&[Value]) -> Result<Value, EvalError>;
+
+    #[derive(Clone)]
+    pub enum Value {
+        Int(i64),
+        Bool(bool),
+        /// A function defined with `let`, with the environment it was defined in.
+        Function(Rc<Function>),
+        /// A function of the host, with its name and arity.
+        Builtin(&'static str, usize, Rc<BuiltinFn>),
+    }
+
+    pub struct Function {
+        pub name: String,
+        pub params: Vec<String>,
+        pub body: Expr,
+        pub env: Env,
+    }
+
+    impl Value {
+        fn type_name(&self) -> &'static str {
+            match self {
+                Value::Int(_) => "int",
+                Value::Bool(_) => "bool",
+                Value::Function(_) | Value::Builtin(..) => "function",
+            }
+        }
+
+        fn int(self) -> Result<i64, EvalError> {
+            match self {
+                Value::Int(n) => Ok(n),
+                other => Err(EvalError::TypeMismatch("int", other.type_name())),
+            }
+        }
+
+        fn bool(self) -> Result<bool, EvalError> {
+            match self {
+                Value::Bool(b) => Ok(b),
+                other => Err(EvalError::TypeMismatch("bool", other.type_name())),
+            }
+        }
+    }
+
+    /// The functions are equal only to themselves.
+    impl PartialEq for Value {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Value::Int(a), Value::Int(b)) => a == b,
+                (Value::Bool(a), Value::Bool(b)) => a == b,
+                (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+                (Value::Builtin(_, _, a), Value::Builtin(_, _, b)) => Rc::ptr_eq(a, b),
+                _ => false,
+            }
+        }
+    }
+
+    impl fmt::Display for Value {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Value::Int(n) => write!(f, "{n}"),
+                Value::Bool(b) => write!(f, "{b}"),
+                Value::Function(function) => write!(f, "<fn {}({})>", function.name, function.params.join(", ")),
+                Value::Builtin(name, ..) => write!(f, "<builtin {name}>"),
+            }
+        }
+    }
+
+    impl fmt::Debug for Value {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{self}")
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum EvalError {
+        Parse(PrattError),
+        UnboundVariable(String),
+        /// the expected and the found type.
+        TypeMismatch(&'static str, &'static str),
+        /// the function, the number of parameters and the number of arguments.
+        Arity(String, usize, usize),
+        NotAFunction(String),
+        UnknownOperator(String),
+        DivisionByZero,
+        Overflow,
+    }
+
+    impl fmt::Display for EvalError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                EvalError::Parse(e) => write!(f, "{e}"),
+                EvalError::UnboundVariable(name) => write!(f, "unbound variable `{name}`"),
+                EvalError::TypeMismatch(expected, found) => write!(f, "expected {expected}, found {found}"),
+                EvalError::Arity(name, expected, found) => write!(f, "`{name}` takes {expected} arguments, {found} given"),
+                EvalError::NotAFunction(name) => write!(f, "`{name}` is not a function"),
+                EvalError::UnknownOperator(op) => write!(f, "unknown operator `{op}`"),
+                EvalError::DivisionByZero => write!(f, "division by zero"),
+                EvalError::Overflow => write!(f, "overflow"),
+            }
+        }
+    }
+
+    impl std::error::Error for EvalError {}
+
+    impl From<PrattError> for EvalError {
+        fn from(e: PrattError) -> Self {
+            EvalError::Parse(e)
+        }
+    }
+
+    /// The variables in scope, it is persistent so a closure keeps its environment cheaply.
+    #[derive(Clone, Default)]
+    pub struct Env {
+        vars: PersistentMap<String, Value>,
+    }
+
+    impl Env {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// `true`, `false` and the builtins `abs`, `min` and `max`.
+        pub fn standard() -> Self {
+            Env::new()
+                .bind("true", Value::Bool(true))
+                .bind("false", Value::Bool(false))
+                .builtin("abs", 1, |args| args[0].clone().int()?.checked_abs().map(Value::Int).ok_or(EvalError::Overflow))
+                .builtin("min", 2, |args| Ok(Value::Int(args[0].clone().int()?.min(args[1].clone().int()?))))
+                .builtin("max", 2, |args| Ok(Value::Int(args[0].clone().int()?.max(args[1].clone().int()?))))
+        }
+
+        /// A new environment with `name` bound, this one is unchanged.
+        pub fn bind(&self, name: &str, value: Value) -> Self {
+            Env {
+                vars: self.vars.insert(name.to_string(), value),
+            }
+        }
+
+        /// Binds a function of the host, it is only called with `arity` arguments.
+        pub fn builtin(&self, name: &'static str, arity: usize, f: impl Fn(&[Value]) -> Result<Value, EvalError> + 'static) -> Self {
+            self.bind(name, Value::Builtin(name, arity, Rc::new(f)))
+        }
+
+        pub fn get(&self, name: &str) -> Option<&Value> {
+            self.vars.get(&name.to_string())
+        }
+    }
+
+    /// The operators of the language, the arithmetic ones with the comparisons and the logic ones below them.
+    pub fn table() -> OperatorTable {
+        let table = OperatorTable::arithmetic().binary("&&", 3, Assoc::Left).binary("||", 2, Assoc::Left).prefix("!", 25);
+        ["==", "!=", "<", "<=", ">", ">="]
+            .into_iter()
+            .fold(table, |table, op| table.binary(op, 5, Assoc::Left))
+    }
+
+    /// Evaluates `expr` in `env`.
+    ///
+    /// `if(condition, then, otherwise)` is a special form, only the branch that is taken is evaluated,
+    /// so a function can call itself recursively:
+    ///
+    /// ```rust
+    /// # use awesome::interpreter::{eval, table, Env, Value};
+    /// let program = table().parse("let fact(n) = if(n <= 1, 1, n * fact(n - 1)) in fact(10)").unwrap();
+    ///
+    /// assert_eq!(eval(&program, &Env::standard()), Ok(Value::Int(3628800)));
+    /// ```
+    pub fn eval(expr: &Expr, env: &Env) -> Result<Value, EvalError> {
+        match expr {
+            Expr::Num(n) => Ok(Value::Int(*n)),
+            Expr::Var(name) => env.get(name).cloned().ok_or_else(|| EvalError::UnboundVariable(name.clone())),
+            Expr::Prefix(op, operand) => {
+                let value = eval(operand, env)?;
+                match op.as_str() {
+                    "-" => value.int()?.checked_neg().map(Value::Int).ok_or(EvalError::Overflow),
+                    "!" => Ok(Value::Bool(!value.bool()?)),
+                    op => Err(EvalError::UnknownOperator(op.to_string())),
+                }
+            }
+            Expr::Postfix(op, operand) => match op.as_str() {
+                "!" => {
+                    let n = eval(operand, env)?.int()?;
+                    (1..=n).try_fold(1i64, |acc, k| acc.checked_mul(k)).map(Value::Int).ok_or(EvalError::Overflow)
+                }
+                op => Err(EvalError::UnknownOperator(op.to_string())),
+            },
+            // the logic operators short-circuit.
+            Expr::Binary(op, left, right) if op == "&&" || op == "||" => {
+                let left = eval(left, env)?.bool()?;
+                match (op.as_str(), left) {
+                    ("&&", false) => Ok(Value::Bool(false)),
+                    ("||", true) => Ok(Value::Bool(true)),
+                    _ => Ok(Value::Bool(eval(right, env)?.bool()?)),
+                }
+            }
+            Expr::Binary(op, left, right) => binary(op, eval(left, env)?, eval(right, env)?),
+            Expr::Call(name, args) if name == "if" && env.get(name).is_none() => match args.as_slice() {
+                [condition, then, otherwise] => match eval(condition, env)?.bool()? {
+                    true => eval(then, env),
+                    false => eval(otherwise, env),
+                },
+                _ => Err(EvalError::Arity(name.clone(), 3, args.len())),
+            },
+            Expr::Call(name, args) => {
+                let function = env.get(name).cloned().ok_or_else(|| EvalError::UnboundVariable(name.clone()))?;
+                let args = args.iter().map(|arg| eval(arg, env)).collect::<Result<Vec<_>, _>>()?;
+                call(name, function, args)
+            }
+            Expr::Let(name, params, value, body) => {
+                let value = define(name, params, value, env)?;
+                match body {
+                    Some(body) => eval(body, &env.bind(name, value)),
+                    None => Ok(value),
+                }
+            }
+        }
+    }
+
+    /// The value of a `let`, a function if there are parameters.
+    fn define(name: &str, params: &[String], value: &Expr, env: &Env) -> Result<Value, EvalError> {
+        match params.is_empty() {
+            true => eval(value, env),
+            false => Ok(Value::Function(Rc::new(Function {
+                name: name.to_string(),
+                params: params.to_vec(),
+                body: value.clone(),
+                env: env.clone(),
+            }))),
+        }
+    }
+
+    fn call(name: &str, function: Value, args: Vec<Value>) -> Result<Value, EvalError> {
+        match function {
+            Value::Function(function) => {
+                if function.params.len() != args.len() {
+                    return Err(EvalError::Arity(name.to_string(), function.params.len(), args.len()));
+                }
+                // the function itself is in scope too, for the recursion.
+                let env = function.env.bind(&function.name, Value::Function(Rc::clone(&function)));
+                let env = function.params.iter().zip(args).fold(env, |env, (param, arg)| env.bind(param, arg));
+                eval(&function.body, &env)
+            }
+            Value::Builtin(_, arity, f) if arity == args.len() => f(&args),
+            Value::Builtin(_, arity, _) => Err(EvalError::Arity(name.to_string(), arity, args.len())),
+            _ => Err(EvalError::NotAFunction(name.to_string())),
+        }
+    }
+
+    fn binary(op: &str, left: Value, right: Value) -> Result<Value, EvalError> {
+        match op {
+            "==" => return Ok(Value::Bool(left == right)),
+            "!=" => return Ok(Value::Bool(left != right)),
+            _ => {}
+        }
+
+        let (a, b) = (left.int()?, right.int()?);
+        let checked = |result: Option<i64>| result.map(Value::Int).ok_or(EvalError::Overflow);
+        match op {
+            "+" => checked(a.checked_add(b)),
+            "-" => checked(a.checked_sub(b)),
+            "*" => checked(a.checked_mul(b)),
+            "/" | "%" if b == 0 => Err(EvalError::DivisionByZero),
+            "/" => checked(a.checked_div(b)),
+            "%" => checked(a.checked_rem(b)),
+            "^" => checked(u32::try_from(b).ok().and_then(|b| a.checked_pow(b))),
+            "<" => Ok(Value::Bool(a < b)),
+            "<=" => Ok(Value::Bool(a <= b)),
+            ">" => Ok(Value::Bool(a > b)),
+            ">=" => Ok(Value::Bool(a >= b)),
+            op => Err(EvalError::UnknownOperator(op.to_string())),
+        }
+    }
+
+    /// Evaluates line after line, a `let` without `in` stays defined for the next lines.
+    ///
+    /// ```rust
+    /// # use awesome::interpreter::{Repl, Value};
+    /// let mut repl = Repl::new();
+    ///
+    /// repl.eval("let double(x) = x * 2").unwrap();
+    /// assert_eq!(repl.eval("double(21)"), Ok(Value::Int(42)));
+    /// ```
+    pub struct Repl {
+        table: OperatorTable,
+        env: Env,
+    }
+
+    impl Default for Repl {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Repl {
+        pub fn new() -> Self {
+            Repl {
+                table: table(),
+                env: Env::standard(),
+            }
+        }
+
+        pub fn eval(&mut self, line: &str) -> Result<Value, EvalError> {
+            let expr = self.table.parse(line)?;
+            match &expr {
+                Expr::Let(name, params, value, None) => {
+                    let value = define(name, params, value, &self.env)?;
+                    self.env = self.env.bind(name, value.clone());
+                    Ok(value)
+                }
+                expr => eval(expr, &self.env),
+            }
+        }
+    }
+}
+
+/// # Tiny Regex
+///
+/// A small regex subset (literals, `.`, `*`, `+`, `?`, `|` and groups) compiled to an NFA with Thompson's construction,
+/// matched by simulating all the states at once, so it is linear in the text and never backtracks.
+/// See [here](https://swtch.com/~rsc/regexp/regexp1.html) for more details.
+pub mod regexlite {
+    use std::fmt;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum RegexError {
+        /// a `(` without `)`, or a `)` without `(`, at this char index.
+        UnbalancedParen(usize),
+        /// a `*`, `+` or `?` with nothing before it.
+        NothingToRepeat(usize),
+        /// a `\` at the end of the pattern.
+        TrailingEscape,
+    }
+
+    impl fmt::Display for RegexError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                RegexError::UnbalancedParen(at) => write!(f, "unbalanced parenthesis at {at}"),
+                RegexError::NothingToRepeat(at) => write!(f, "nothing to repeat at {at}"),
+                RegexError::TrailingEscape => write!(f, "trailing backslash"),
+            }
+        }
+    }
+
+    impl std::error::Error for RegexError {}
+
+    /// The syntax tree of a pattern.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Node {
+        Empty,
+        Char(char),
+        Any,
+        Concat(Vec<Node>),
+        Alt(Vec<Node>),
+        Star(Box<Node>),
+        Plus(Box<Node>),
+        Question(Box<Node>),
+    }
+
+    /// A recursive descent parser, one function per precedence level.
+    ///
+    /// ```text
+    /// alt    = concat ('|' concat)*
+    /// concat = repeat*
+    /// repeat = atom ('*' | '+' | '?')*
+    /// atom   = char | '.' | '\' char | '(' alt ')'
+    /// ```
+    struct Parser {
+        chars: Vec<char>,
+        next: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.next).copied()
+        }
+
+        fn alt(&mut self) -> Result<Node, RegexError> {
+            let mut branches = vec![self.concat()?];
+            while self.peek() == Some('|') {
+                self.next += 1;
+                branches.push(self.concat()?);
+            }
+            Ok(match branches.len() {
+                1 => branches.remove(0),
+                _ => Node::Alt(branches),
+            })
+        }
+
+        fn concat(&mut self) -> Result<Node, RegexError> {
+            let mut items = Vec::new();
+            while !matches!(self.peek(), None | Some('|') | Some(')')) {
+                items.push(self.repeat()?);
+            }
+            Ok(match items.len() {
+                0 => Node::Empty,
+                1 => items.remove(0),
+                _ => Node::Concat(items),
+            })
+        }
+
+        fn repeat(&mut self) -> Result<Node, RegexError> {
+            let mut node = self.atom()?;
+            while let Some(op @ ('*' | '+' | '?')) = self.peek() {
+                self.next += 1;
+                node = match op {
+                    '*' => Node::Star(Box::new(node)),
+                    '+' => Node::Plus(Box::new(node)),
+                    _ => Node::Question(Box::new(node)),
+                };
+            }
+            Ok(node)
+        }
+
+        fn atom(&mut self) -> Result<Node, RegexError> {
+            let at = self.next;
+            self.next += 1;
+            match self.chars[at] {
+                '.' => Ok(Node::Any),
+                '*' | '+' | '?' => Err(RegexError::NothingToRepeat(at)),
+                '\\' => match self.peek() {
+                    Some(c) => {
+                        self.next += 1;
+                        Ok(Node::Char(c))
+                    }
+                    None => Err(RegexError::TrailingEscape),
+                },
+                '(' => {
+                    let inner = self.alt()?;
+                    match self.peek() {
+                        Some(')') => {
+                            self.next += 1;
+                            Ok(inner)
+                        }
+                        _ => Err(RegexError::UnbalancedParen(at)),
+                    }
+                }
+                c => Ok(Node::Char(c)),
+            }
+        }
+    }
+
+    /// A state of the NFA, the indices point to other states.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Char(char, usize),
+        Any(usize),
+        /// an epsilon transition to both states.
+        Split(usize, usize),
+        Match,
+    }
+
+    /// A compiled pattern.
+    ///
+    /// ```rust
+    /// # use awesome::regexlite::Regex;
+    /// let re = Regex::new("(ab|cd)+e?")?;
+    ///
+    /// assert!(re.is_match("xxcdabx"));
+    /// assert!(re.is_full_match("abcde"));
+    /// assert!(!re.is_full_match("abc"));
+    /// # Ok::<(), awesome::regexlite::RegexError>(())
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct Regex {
+        states: Vec<State>,
+        start: usize,
+    }
+
+    impl Regex {
+        pub fn new(pattern: &str) -> Result<Self, RegexError> {
+            let mut parser = Parser {
+                chars: pattern.chars().collect(),
+                next: 0,
+            };
+            let node = parser.alt()?;
+            if parser.next < parser.chars.len() {
+                // the only char that stops `alt` before the end is an unmatched `)`.
+                return Err(RegexError::UnbalancedParen(parser.next));
+            }
+
+            let mut states = vec![State::Match];
+            let start = compile(&node, 0, &mut states);
+            Ok(Regex { states, start })
+        }
+
+        /// The number of states of the NFA, it grows linearly with the pattern.
+        pub fn state_count(&self) -> usize {
+            self.states.len()
+        }
+
+        /// Whether the pattern matches anywhere in `text`.
+        pub fn is_match(&self, text: &str) -> bool {
+            self.run(text, false)
+        }
+
+        /// Whether the pattern matches all of `text`.
+        pub fn is_full_match(&self, text: &str) -> bool {
+            self.run(text, true)
+        }
+
+        /// Steps all the current states at once over every char.
+        fn run(&self, text: &str, anchored: bool) -> bool {
+            let mut current = Vec::new();
+            let mut seen = vec![false; self.states.len()];
+            self.add(self.start, &mut current, &mut seen);
+
+            for c in text.chars() {
+                if !anchored && current.contains(&0) {
+                    return true;
+                }
+                let mut next = Vec::new();
+                seen.iter_mut().for_each(|s| *s = false);
+                for &state in &current {
+                    match self.states[state] {
+                        State::Char(expected, out) if expected == c => self.add(out, &mut next, &mut seen),
+                        State::Any(out) => self.add(out, &mut next, &mut seen),
+                        _ => {}
+                    }
+                }
+                // a match can start at every position when it is not anchored.
+                if !anchored {
+                    self.add(self.start, &mut next, &mut seen);
+                }
+                current = next;
+            }
+            current.contains(&0)
+        }
+
+        /// Adds `state` and everything reachable from it through the epsilon transitions.
+        fn add(&self, state: usize, list: &mut Vec<usize>, seen: &mut [bool]) {
+            if std::mem::replace(&mut seen[state], true) {
+                return;
+            }
+            match self.states[state] {
+                State::Split(a, b) => {
+                    self.add(a, list, seen);
+                    self.add(b, list, seen);
+                }
+                _ => list.push(state),
+            }
+        }
+    }
+
+    /// Thompson's construction, backwards: compiles `node` so that it continues to `out`, and returns where it starts.
+    fn compile(node: &Node, out: usize, states: &mut Vec<State>) -> usize {
+        fn push(states: &mut Vec<State>, state: State) -> usize {
+            states.push(state);
+            states.len() - 1
+        }
+
+        match node {
+            Node::Empty => out,
+            Node::Char(c) => push(states, State::Char(*c, out)),
+            Node::Any => push(states, State::Any(out)),
+            Node::Concat(items) => items.iter().rev().fold(out, |out, item| compile(item, out, states)),
+            Node::Alt(branches) => {
+                let starts: Vec<usize> = branches.iter().map(|branch| compile(branch, out, states)).collect();
+                starts.into_iter().reduce(|a, b| push(states, State::Split(a, b))).expect("at least two branches")
+            }
+            // the loop state goes into the body or out, and the body comes back to it.
+            Node::Star(body) => {
+                let split = push(states, State::Split(usize::MAX, out));
+                let start = compile(body, split, states);
+                states[split] = State::Split(start, out);
+                split
+            }
+            // like `*`, but the body comes first.
+            Node::Plus(body) => {
+                let split = push(states, State::Split(usize::MAX, out));
+                let start = compile(body, split, states);
+                states[split] = State::Split(start, out);
+                start
+            }
+            Node::Question(body) => {
+                let start = compile(body, out, states);
+                push(states, State::Split(start, out))
+            }
+        }
+    }
+}
+
+/// # State Machine
+///
+/// `state_machine!` turns a list of transitions into typed states and events,
+/// so an event which isn't allowed in the current state is a value you have to handle, not a forgotten `if`.
+/// See [here](https://en.wikipedia.org/wiki/Finite-state_machine) for more details.
+pub mod fsm {
+    use std::fmt;
+
+    /// Generates the state machine `Name` from `From --event--> To` transitions.
+    ///
+    /// ```
+    /// use awesome::fsm::{state_machine, InvalidTransition, StateMachine};
+    ///
+    /// state_machine! {
+    ///     pub Door {
+    ///         Closed --open--> Open,
+    ///         Open --close--> Closed,
+    ///         Closed --lock--> Locked,
+    ///         Locked --unlock--> Closed,
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(DoorState::default(), DoorState::Closed);
+    /// assert_eq!(Door::transition(DoorState::Closed, DoorEvent::Open), Ok(DoorState::Open));
+    /// assert_eq!(
+    ///     Door::transition(DoorState::Locked, DoorEvent::Open),
+    ///     Err(InvalidTransition { state: DoorState::Locked, event: DoorEvent::Open })
+    /// );
+    /// assert_eq!(Door::TRANSITIONS.len(), 4);
+    /// ```
+    pub use awesome_macros::state_machine;
+
+    /// `event` isn't allowed in `state`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct InvalidTransition<S, E> {
+        pub state: S,
+        pub event: E,
+    }
+
+    impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for InvalidTransition<S, E> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "no transition from {:?} on {:?}", self.state, self.event)
+        }
+    }
+
+    impl<S: fmt::Debug, E: fmt::Debug> std::error::Error for InvalidTransition<S, E> {}
+
+    /// Implemented by `state_machine!`.
+    pub trait StateMachine {
+        type State: Copy + Eq + fmt::Debug + 'static;
+        type Event: Copy + Eq + fmt::Debug + 'static;
+
+        /// Every `(from, event, to)` in the order they were written.
+        const TRANSITIONS: &'static [(Self::State, Self::Event, Self::State)];
+
+        fn transition(state: Self::State, event: Self::Event) -> Result<Self::State, InvalidTransition<Self::State, Self::Event>>;
+    }
+
+    /// Entry and exit hooks of a [`Machine`], both do nothing by default.
+    pub trait Hooks<S, E> {
+        /// Called before leaving `state` because of `event`.
+        fn on_exit(&mut self, state: S, event: E) {
+            let _ = (state, event);
+        }
+
+        /// Called after entering `state` because of `event`.
+        fn on_enter(&mut self, state: S, event: E) {
+            let _ = (state, event);
+        }
+    }
+
+    impl<S, E> Hooks<S, E> for () {}
+
+    /// A running state machine `M`, which calls `H` around each transition.
+    ///
+    /// ```
+    /// use awesome::fsm::{state_machine, Hooks, Machine};
+    ///
+    /// state_machine! {
+    ///     Light { Off --toggle--> On, On --toggle--> Off }
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Log(Vec<String>);
+    ///
+    /// impl Hooks<LightState, LightEvent> for Log {
+    ///     fn on_enter(&mut self, state: LightState, _: LightEvent) {
+    ///         self.0.push(format!("{state:?}"));
+    ///     }
+    /// }
+    ///
+    /// let mut light = Machine::<Light, _>::with_hooks(LightState::Off, Log::default());
+    /// light.fire(LightEvent::Toggle).unwrap();
+    /// light.fire(LightEvent::Toggle).unwrap();
+    /// assert_eq!(light.state(), LightState::Off);
+    /// assert_eq!(light.hooks().0, ["On", "Off"]);
+    /// ```
+    pub struct Machine<M: StateMachine, H = ()> {
+        state: M::State,
+        hooks: H,
+    }
+
+    impl<M: StateMachine> Machine<M> {
+        pub fn new(initial: M::State) -> Self {
+            Machine::with_hooks(initial, ())
+        }
+    }
+
+    impl<M: StateMachine, H: Hooks<M::State, M::Event>> Machine<M, H> {
+        pub fn with_hooks(initial: M::State, hooks: H) -> Self {
+            Machine { state: initial, hooks }
+        }
+
+        pub fn state(&self) -> M::State {
+            self.state
+        }
+
+        pub fn hooks(&self) -> &H {
+            &self.hooks
+        }
+
+        /// Moves to the next state, calling the exit hook of the old one and the entry hook of the new one.
+        /// an invalid event leaves the state as it is and calls no hooks.
+        pub fn fire(&mut self, event: M::Event) -> Result<M::State, InvalidTransition<M::State, M::Event>> {
+            let next = M::transition(self.state, event)?;
+            self.hooks.on_exit(self.state, event);
+            self.state = next;
+            self.hooks.on_enter(next, event);
+            Ok(next)
+        }
+    }
+
+    impl<M: StateMachine, H: fmt::Debug> fmt::Debug for Machine<M, H> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Machine").field("state", &self.state).field("hooks", &self.hooks).finish()
+        }
+    }
+}
+
+/// # Event Bus
+///
+/// A publish/subscribe bus where the handlers are keyed by the `TypeId` of their event,
+/// so one bus carries any number of event types and each handler only sees the one it asked for.
+/// See [here](https://en.wikipedia.org/wiki/Publish%E2%80%93subscribe_pattern) for more details.
+pub mod event_bus {
+    use std::any::{Any, TypeId};
+    use std::collections::HashMap;
+    use std::fmt;
+
+    /// Handles events of type `E`, implemented for every `FnMut(&E)`.
+    ///
+    /// ```
+    /// use awesome::event_bus::{Bus, Handler};
+    ///
+    /// struct Counter(usize);
+    ///
+    /// impl Handler<String> for Counter {
+    ///     fn handle(&mut self, _: &String) {
+    ///         self.0 += 1;
+    ///         println!("seen {} strings", self.0);
+    ///     }
+    /// }
+    ///
+    /// let mut bus = Bus::new();
+    /// let handler: Box<dyn Handler<String>> = Box::new(Counter(0));
+    /// bus.subscribe_boxed(handler);
+    /// assert_eq!(bus.publish("hello".to_string()), 1);
+    /// ```
+    pub trait Handler<E> {
+        fn handle(&mut self, event: &E);
+    }
+
+    impl<E, F: FnMut(&E)> Handler<E> for F {
+        fn handle(&mut self, event: &E) {
+            self(event)
+        }
+    }
+
+    /// Returned by [`Bus::subscribe`], pass it to [`Bus::unsubscribe`] to remove the handler.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Subscription {
+        event: TypeId,
+        id: usize,
+    }
+
+    /// The handlers of `E`, in subscription order.
+    struct Handlers<E>(Vec<(usize, Box<dyn Handler<E>>)>);
+
+    /// What the bus needs from the handlers without knowing their event type, the rest goes through `Any`.
+    trait Slot: Any {
+        fn remove(&mut self, id: usize) -> bool;
+        fn len(&self) -> usize;
+    }
+
+    impl<E: 'static> Slot for Handlers<E> {
+        fn remove(&mut self, id: usize) -> bool {
+            let before = self.0.len();
+            self.0.retain(|(other, _)| *other != id);
+            self.0.len() != before
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    /// The bus itself, handlers run in the order they subscribed.
+    ///
+    /// ```
+    /// use awesome::event_bus::Bus;
+    ///
+    /// struct Joined(&'static str);
+    ///
+    /// let mut bus = Bus::new();
+    /// bus.subscribe(|Joined(name): &Joined| println!("{name} joined"));
+    /// let subscription = bus.subscribe(|_: &u32| println!("a number"));
+    ///
+    /// assert_eq!(bus.publish(Joined("ferris")), 1);
+    /// assert!(bus.unsubscribe(subscription));
+    /// assert_eq!(bus.publish(42u32), 0);
+    /// ```
+    #[derive(Default)]
+    pub struct Bus {
+        handlers: HashMap<TypeId, Box<dyn Slot>>,
+        next_id: usize,
+    }
+
+    impl Bus {
+        pub fn new() -> Self {
+            Bus::default()
+        }
+
+        pub fn subscribe<E: 'static>(&mut self, handler: impl Handler<E> + 'static) -> Subscription {
+            self.subscribe_boxed(Box::new(handler))
+        }
+
+        pub fn subscribe_boxed<E: 'static>(&mut self, handler: Box<dyn Handler<E>>) -> Subscription {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.handlers_mut::<E>().push((id, handler));
+            Subscription { event: TypeId::of::<E>(), id }
+        }
+
+        /// Removes the handler, `false` if it was already removed.
+        pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+            let Some(handlers) = self.handlers.get_mut(&subscription.event) else {
+                return false;
+            };
+            let removed = handlers.remove(subscription.id);
+            if handlers.len() == 0 {
+                self.handlers.remove(&subscription.event);
+            }
+            removed
+        }
+
+        /// Calls every handler of `E` with `event`, returns how many there were.
+        pub fn publish<E: 'static>(&mut self, event: E) -> usize {
+            let Some(handlers) = self.handlers.get_mut(&TypeId::of::<E>()) else {
+                return 0;
+            };
+            let handlers = (handlers.as_mut() as &mut dyn Any)
+                .downcast_mut::<Handlers<E>>()
+                .expect("handlers are keyed by their event type");
+            for (_, handler) in &mut handlers.0 {
+                handler.handle(&event);
+            }
+            handlers.0.len()
+        }
+
+        /// How many handlers `E` has.
+        pub fn subscribers<E: 'static>(&self) -> usize {
+            self.handlers.get(&TypeId::of::<E>()).map_or(0, |handlers| handlers.len())
+        }
+
+        fn handlers_mut<E: 'static>(&mut self) -> &mut Vec<(usize, Box<dyn Handler<E>>)> {
+            let handlers = self.handlers.entry(TypeId::of::<E>()).or_insert_with(|| Box::new(Handlers::<E>(Vec::new())));
+            &mut (handlers.as_mut() as &mut dyn Any)
+                .downcast_mut::<Handlers<E>>()
+                .expect("handlers are keyed by their event type")
+                .0
+        }
+    }
+
+    impl fmt::Debug for Bus {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Bus").field("event_types", &self.handlers.len()).finish_non_exhaustive()
+        }
+    }
+}
+
+/// # Actors
+///
+/// Each actor owns its state on its own thread and is only reached through messages in its mailbox,
+/// so there is no lock around the state, only `std::sync::mpsc` channels.
+/// See [here](https://en.wikipedia.org/wiki/Actor_model) for more details.
+pub mod actor {
+    use std::fmt;
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+
+    /// State that handles messages one at a time, `handle` returns the reply to [`Addr::ask`].
+    pub trait Actor: Send + 'static {
+        type Msg: Send + 'static;
+        type Reply: Send + 'static;
+
+        fn handle(&mut self, msg: Self::Msg) -> Self::Reply;
+
+        /// Called on the actor's thread before the first message.
+        fn started(&mut self) {}
+
+        /// Called on the actor's thread after the last message.
+        fn stopped(&mut self) {}
+    }
+
+    /// The actor is no longer running, so the message was not handled.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Stopped;
+
+    impl fmt::Display for Stopped {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "the actor has stopped")
+        }
+    }
+
+    impl std::error::Error for Stopped {}
+
+    enum Envelope<A: Actor> {
+        /// A message, with the sender of the oneshot reply channel if it was asked.
+        Message(A::Msg, Option<Sender<A::Reply>>),
+        Stop,
+    }
+
+    /// A handle to a running actor, cheap to clone and to send to other threads.
+    pub struct Addr<A: Actor> {
+        mailbox: Sender<Envelope<A>>,
+        thread: Arc<Mutex<Option<JoinHandle<A>>>>,
+    }
+
+    impl<A: Actor> Clone for Addr<A> {
+        fn clone(&self) -> Self {
+            Addr {
+                mailbox: self.mailbox.clone(),
+                thread: self.thread.clone(),
+            }
+        }
+    }
+
+    impl<A: Actor> fmt::Debug for Addr<A> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Addr").finish_non_exhaustive()
+        }
+    }
+
+    /// Starts `actor` on a new thread.
+    ///
+    /// the actor runs until [`Addr::shutdown`] or until every `Addr` is dropped,
+    /// either way the messages already in its mailbox are handled first.
+    ///
+    /// ```
+    /// use awesome::actor::{spawn_actor, Actor};
+    ///
+    /// struct Counter(i64);
+    ///
+    /// enum Msg {
+    ///     Add(i64),
+    ///     Get,
+    /// }
+    ///
+    /// impl Actor for Counter {
+    ///     type Msg = Msg;
+    ///     type Reply = i64;
+    ///
+    ///     fn handle(&mut self, msg: Msg) -> i64 {
+    ///         if let Msg::Add(n) = msg {
+    ///             self.0 += n;
+    ///         }
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// let counter = spawn_actor(Counter(0));
+    /// counter.send(Msg::Add(2)).unwrap();
+    /// counter.send(Msg::Add(3)).unwrap();
+    /// assert_eq!(counter.ask(Msg::Get), Ok(5));
+    /// assert_eq!(counter.shutdown().map(|counter| counter.0), Some(5));
+    /// ```
+    pub fn spawn_actor<A: Actor>(mut actor: A) -> Addr<A> {
+        let (mailbox, messages) = mpsc::channel::<Envelope<A>>();
+        let thread = thread::spawn(move || {
+            actor.started();
+            // ends on `Stop`, or when all the senders are gone.
+            while let Ok(Envelope::Message(msg, reply)) = messages.recv() {
+                let answer = actor.handle(msg);
+                if let Some(reply) = reply {
+                    // nobody waiting for the answer is fine.
+                    let _ = reply.send(answer);
+                }
+            }
+            actor.stopped();
+            actor
+        });
+        Addr {
+            mailbox,
+            thread: Arc::new(Mutex::new(Some(thread))),
+        }
+    }
+
+    impl<A: Actor> Addr<A> {
+        /// Puts `msg` in the mailbox without waiting for it to be handled.
+        pub fn send(&self, msg: A::Msg) -> Result<(), Stopped> {
+            self.mailbox.send(Envelope::Message(msg, None)).map_err(|_| Stopped)
+        }
+
+        /// Sends `msg` and blocks until the actor replies.
+        pub fn ask(&self, msg: A::Msg) -> Result<A::Reply, Stopped> {
+            let (reply, answer) = mpsc::channel();
+            self.mailbox.send(Envelope::Message(msg, Some(reply))).map_err(|_| Stopped)?;
+            // the reply sender is dropped unanswered if the actor stops or panics first.
+            answer.recv().map_err(|_| Stopped)
+        }
+
+        /// Stops the actor after the messages sent before, and gives it back.
+        ///
+        /// `None` if another `Addr` has already shut it down, or it panicked.
+        pub fn shutdown(&self) -> Option<A> {
+            let _ = self.mailbox.send(Envelope::Stop);
+            let thread = self.thread.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()?;
+            thread.join().ok()
+        }
+    }
+}
+
+/// # Channel Combinators
+///
+/// `std::sync::mpsc` has no `select!`, but a forwarding thread per input gets most of the way:
+/// each combinator returns a new receiver which ends when its inputs end. a thread can only see that its receiver was dropped
+/// when a send fails, so it stops at the first value it forwards after that.
+/// See [here](https://doc.rust-lang.org/std/sync/mpsc/index.html) for more details.
+pub mod channel_ext {
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+
+    /// Runs `forward` for each value of `input` on a new thread, until `input` ends or `forward` returns `false`.
+    fn spawn_forward<T: Send + 'static, U: Send + 'static>(input: Receiver<T>, mut forward: impl FnMut(T, &Sender<U>) -> bool + Send + 'static) -> Receiver<U> {
+        let (output, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for value in input {
+                if !forward(value, &output) {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    /// Every value of both receivers, in the order they arrive; values of one input keep their order.
+    ///
+    /// ```
+    /// use awesome::channel_ext::merge;
+    /// use std::sync::mpsc;
+    ///
+    /// let (a, ra) = mpsc::channel();
+    /// let (b, rb) = mpsc::channel();
+    /// a.send(1).unwrap();
+    /// b.send(2).unwrap();
+    /// drop((a, b));
+    ///
+    /// let mut merged: Vec<_> = merge(ra, rb).iter().collect();
+    /// merged.sort();
+    /// assert_eq!(merged, [1, 2]);
+    /// ```
+    pub fn merge<T: Send + 'static>(a: Receiver<T>, b: Receiver<T>) -> Receiver<T> {
+        let (output, receiver) = mpsc::channel();
+        for input in [a, b] {
+            let output = output.clone();
+            thread::spawn(move || {
+                for value in input {
+                    if output.send(value).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        receiver
+    }
+
+    /// `f` applied to every value.
+    ///
+    /// ```
+    /// use awesome::channel_ext::map_channel;
+    /// use std::sync::mpsc;
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// (1..=3).for_each(|i| tx.send(i).unwrap());
+    /// drop(tx);
+    /// assert_eq!(map_channel(rx, |i| i * 10).iter().collect::<Vec<_>>(), [10, 20, 30]);
+    /// ```
+    pub fn map_channel<T: Send + 'static, U: Send + 'static>(input: Receiver<T>, mut f: impl FnMut(T) -> U + Send + 'static) -> Receiver<U> {
+        spawn_forward(input, move |value, output| output.send(f(value)).is_ok())
+    }
+
+    /// Only the values matching `predicate`.
+    ///
+    /// the rejected values are never sent, so after the receiver is dropped the thread keeps reading until a value matches.
+    pub fn filter_channel<T: Send + 'static>(input: Receiver<T>, mut predicate: impl FnMut(&T) -> bool + Send + 'static) -> Receiver<T> {
+        spawn_forward(input, move |value, output| !predicate(&value) || output.send(value).is_ok())
+    }
+
+    /// Deals the values to `n` receivers in turn, skipping the dropped ones.
+    ///
+    /// forwarding stops when `input` ends or every receiver is dropped.
+    ///
+    /// ```
+    /// use awesome::channel_ext::fan_out;
+    /// use std::sync::mpsc;
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// (0..6).for_each(|i| tx.send(i).unwrap());
+    /// drop(tx);
+    ///
+    /// let workers = fan_out(rx, 2);
+    /// assert_eq!(workers[0].iter().collect::<Vec<_>>(), [0, 2, 4]);
+    /// assert_eq!(workers[1].iter().collect::<Vec<_>>(), [1, 3, 5]);
+    /// ```
+    pub fn fan_out<T: Send + 'static>(input: Receiver<T>, n: usize) -> Vec<Receiver<T>> {
+        assert!(n > 0, "fan_out needs at least one output");
+        let (mut outputs, receivers): (Vec<Sender<T>>, Vec<Receiver<T>>) = (0..n).map(|_| mpsc::channel()).unzip();
+        thread::spawn(move || {
+            let mut next = 0;
+            for mut value in input {
+                // a failed send gives the value back, so it goes to the next receiver instead.
+                loop {
+                    if outputs.is_empty() {
+                        return;
+                    }
+                    next %= outputs.len();
+                    match outputs[next].send(value) {
+                        Ok(()) => {
+                            next += 1;
+                            break;
+                        }
+                        Err(mpsc::SendError(back)) => {
+                            outputs.remove(next);
+                            value = back;
+                        }
+                    }
+                }
+            }
+        });
+        receivers
+    }
+}
+
+/// # Thread Pool
+///
+/// A fixed set of worker threads, each with its own deque: a worker pops its newest job from the back,
+/// and when it runs dry it takes the oldest job from the shared queue or steals one from the front of another worker's deque.
+/// threads waiting on a scope or a task run jobs meanwhile, so nested `join`s don't deadlock the pool.
+/// See [here](https://en.wikipedia.org/wiki/Work_stealing) for more details.
+pub mod pool {
+    use std::any::Any;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::marker::PhantomData;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    type Job = Box<dyn FnOnce() + Send + 'static>;
+
+    thread_local! {
+        /// The pool (by address) and the index of the worker running on this thread.
+        static WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+    }
+
+    /// Poisoning only means a job panicked, the queues themselves are still fine.
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A job's panic has already been reported by the panic hook, the worker just carries on.
+    fn run(job: Job) {
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+
+    struct Shared {
+        injector: Mutex<VecDeque<Job>>,
+        locals: Vec<Mutex<VecDeque<Job>>>,
+        /// Queued jobs, checked under `shutdown`'s lock before sleeping so no wakeup is lost.
+        pending: AtomicUsize,
+        shutdown: Mutex<bool>,
+        wake: Condvar,
+    }
+
+    impl Shared {
+        /// The worker index of the current thread in this pool.
+        fn current_worker(&self) -> Option<usize> {
+            let id = self as *const Shared as usize;
+            WORKER.with(Cell::get).and_then(|(pool, index)| (pool == id).then_some(index))
+        }
+
+        /// Workers push to their own deque, other threads to the shared queue.
+        fn push(&self, job: Job) {
+            match self.current_worker() {
+                Some(index) => lock(&self.locals[index]).push_back(job),
+                None => lock(&self.injector).push_back(job),
+            }
+            self.pending.fetch_add(1, Ordering::SeqCst);
+            let _guard = lock(&self.shutdown);
+            self.wake.notify_one();
+        }
+
+        fn find_job(&self) -> Option<Job> {
+            let me = self.current_worker();
+            let job = me
+                .and_then(|index| lock(&self.locals[index]).pop_back())
+                .or_else(|| lock(&self.injector).pop_front())
+                .or_else(|| {
+                    let start = me.map_or(0, |index| index + 1);
+                    (0..self.locals.len())
+                        .map(|offset| (start + offset) % self.locals.len())
+                        .filter(|&victim| Some(victim) != me)
+                        .find_map(|victim| lock(&self.locals[victim]).pop_front())
+                })?;
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            Some(job)
+        }
+
+        fn work(&self, index: usize) {
+            WORKER.with(|worker| worker.set(Some((self as *const Shared as usize, index))));
+            loop {
+                if let Some(job) = self.find_job() {
+                    run(job);
+                    continue;
+                }
+                let mut shutdown = lock(&self.shutdown);
+                while self.pending.load(Ordering::SeqCst) == 0 && !*shutdown {
+                    shutdown = self.wake.wait(shutdown).unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+                // the queued jobs are done before leaving.
+                if *shutdown && self.pending.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+            }
+        }
+
+        /// Runs other jobs until `done`, so waiting doesn't take a worker away from the pool.
+        fn help_until(&self, done: impl Fn() -> bool, notify: &Notify) {
+            while !done() {
+                match self.find_job() {
+                    Some(job) => run(job),
+                    // the missing job is running somewhere else.
+                    None => notify.wait_timeout(&done, Duration::from_millis(1)),
+                }
+            }
+        }
+    }
+
+    /// Wakes the threads waiting for some condition, which is changed before `notify`.
+    #[derive(Default)]
+    struct Notify {
+        lock: Mutex<()>,
+        cond: Condvar,
+    }
+
+    impl Notify {
+        fn notify(&self) {
+            let _guard = lock(&self.lock);
+            self.cond.notify_all();
+        }
+
+        /// Checks `done` under the lock, so a `notify` after the check is never missed.
+        fn wait_timeout(&self, done: impl Fn() -> bool, timeout: Duration) {
+            let guard = lock(&self.lock);
+            if !done() {
+                let _ = self.cond.wait_timeout(guard, timeout);
+            }
+        }
+    }
+
+    /// The pool, dropping it finishes the queued jobs and joins the workers.
+    ///
+    /// ```
+    /// use awesome::pool::Pool;
+    ///
+    /// let pool = Pool::new(4);
+    /// let mut squares = vec![0; 8];
+    /// pool.scope(|s| {
+    ///     for (i, square) in squares.iter_mut().enumerate() {
+    ///         s.spawn(move || *square = i * i);
+    ///     }
+    /// });
+    /// assert_eq!(squares, [0, 1, 4, 9, 16, 25, 36, 49]);
+    ///
+    /// let (a, b) = pool.join(|| (1..=10).sum::<u32>(), || (1..=10).product::<u32>());
+    /// assert_eq!((a, b), (55, 3628800));
+    /// ```
+    pub struct Pool {
+        shared: Arc<Shared>,
+        workers: Vec<JoinHandle<()>>,
+    }
+
+    impl Pool {
+        pub fn new(threads: usize) -> Self {
+            assert!(threads > 0, "a pool needs at least one thread");
+            let shared = Arc::new(Shared {
+                injector: Mutex::new(VecDeque::new()),
+                locals: (0..threads).map(|_| Mutex::new(VecDeque::new())).collect(),
+                pending: AtomicUsize::new(0),
+                shutdown: Mutex::new(false),
+                wake: Condvar::new(),
+            });
+            let workers = (0..threads)
+                .map(|index| {
+                    let shared = shared.clone();
+                    thread::Builder::new()
+                        .name(format!("pool-worker-{index}"))
+                        .spawn(move || shared.work(index))
+                        .expect("failed to spawn a worker thread")
+                })
+                .collect();
+            Pool { shared, workers }
+        }
+
+        pub fn threads(&self) -> usize {
+            self.workers.len()
+        }
+
+        /// Runs `job` on some worker, a panic in it is reported and otherwise ignored.
+        pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+            self.shared.push(Box::new(job));
+        }
+
+        /// Runs `f` on some worker, its result comes from [`Task::join`].
+        pub fn spawn<T: Send + 'static>(&self, f: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+            let state = Arc::new(TaskState {
+                result: Mutex::new(None),
+                done: Notify::default(),
+            });
+            let task = state.clone();
+            self.execute(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                *lock(&task.result) = Some(result);
+                task.done.notify();
+            });
+            Task {
+                state,
+                shared: self.shared.clone(),
+            }
+        }
+
+        /// Lets `f` spawn jobs borrowing from the caller's stack, all of them are finished when this returns.
+        ///
+        /// if `f` or any job panics, the panic is resumed here after the others are done.
+        pub fn scope<'scope, R>(&self, f: impl FnOnce(&Scope<'_, 'scope>) -> R) -> R {
+            let scope = Scope {
+                pool: self,
+                state: Arc::new(ScopeState {
+                    running: AtomicUsize::new(0),
+                    panic: Mutex::new(None),
+                    done: Notify::default(),
+                }),
+                scope: PhantomData,
+            };
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+            let state = &scope.state;
+            self.shared.help_until(|| state.running.load(Ordering::SeqCst) == 0, &state.done);
+            if let Some(payload) = lock(&scope.state.panic).take() {
+                panic::resume_unwind(payload);
+            }
+            result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+        }
+
+        /// Runs `a` here and `b` on the pool, unless this thread gets to it first.
+        pub fn join<A: Send, B: Send>(&self, a: impl FnOnce() -> A + Send, b: impl FnOnce() -> B + Send) -> (A, B) {
+            let mut right = None;
+            let left = self.scope(|s| {
+                s.spawn(|| right = Some(b()));
+                a()
+            });
+            (left, right.expect("the scope waits for its jobs"))
+        }
+    }
+
+    impl Drop for Pool {
+        fn drop(&mut self) {
+            *lock(&self.shared.shutdown) = true;
+            self.shared.wake.notify_all();
+            for worker in self.workers.drain(..) {
+                let _ = worker.join();
+            }
+        }
+    }
+
+    impl fmt::Debug for Pool {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Pool").field("threads", &self.threads()).finish_non_exhaustive()
+        }
+    }
+
+    struct TaskState<T> {
+        result: Mutex<Option<thread::Result<T>>>,
+        done: Notify,
+    }
+
+    /// The result of [`Pool::spawn`].
+    pub struct Task<T> {
+        state: Arc<TaskState<T>>,
+        shared: Arc<Shared>,
+    }
+
+    impl<T> Task<T> {
+        /// Waits for the result, running other jobs meanwhile, and resumes the job's panic if it had one.
+        pub fn join(self) -> T {
+            let state = &self.state;
+            self.shared.help_until(|| lock(&state.result).is_some(), &state.done);
+            match lock(&state.result).take().expect("the result is set before the notification") {
+                Ok(value) => value,
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+    }
+
+    impl<T> fmt::Debug for Task<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Task").field("done", &lock(&self.state.result).is_some()).finish()
+        }
+    }
+
+    struct ScopeState {
+        running: AtomicUsize,
+        panic: Mutex<Option<Box<dyn Any + Send>>>,
+        done: Notify,
+    }
+
+    /// Given to the closure of [`Pool::scope`], `'scope` is invariant so jobs can't borrow anything shorter.
+    pub struct Scope<'pool, 'scope> {
+        pool: &'pool Pool,
+        state: Arc<ScopeState>,
+        scope: PhantomData<&'scope mut &'scope ()>,
+    }
+
+    impl<'scope> Scope<'_, 'scope> {
+        pub fn spawn(&self, f: impl FnOnce() + Send + 'scope) {
+            self.state.running.fetch_add(1, Ordering::SeqCst);
+            let state = self.state.clone();
+            let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+                if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+                    lock(&state.panic).get_or_insert(payload);
+                }
+                if state.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    state.done.notify();
+                }
+            });
+            // SAFETY: `Pool::scope` doesn't return (or unwind) before `running` is back to zero,
+            // which happens after `f` has run, so nothing borrowed for `'scope` is used after it ends.
+            let job = unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job) };
+            self.pool.shared.push(job);
+        }
+    }
+
+    impl fmt::Debug for Scope<'_, '_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Scope").field("running", &self.state.running.load(Ordering::SeqCst)).finish()
+        }
+    }
+}
+
+/// # Software Transactional Memory
+///
+/// Transactions read and write `TVar`s optimistically: reads remember the version they saw, writes are buffered,
+/// and the commit only goes through if nothing read has changed since, otherwise the whole transaction runs again.
+/// a global version clock makes every attempt see a consistent snapshot, even one that is going to be retried.
+/// See [here](https://en.wikipedia.org/wiki/Software_transactional_memory) for more details.
+pub mod stm {
+    use std::any::Any;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::thread;
+
+    /// Incremented by every commit that writes, under `COMMIT`.
+    static CLOCK: AtomicU64 = AtomicU64::new(0);
+    static COMMIT: Mutex<()> = Mutex::new(());
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The transaction has to start over, because of a conflict or [`Transaction::retry`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Retry;
+
+    struct Slot<T> {
+        version: u64,
+        value: T,
+    }
+
+    struct Var<T> {
+        id: usize,
+        slot: Mutex<Slot<T>>,
+    }
+
+    /// What a commit needs from a variable without knowing its type.
+    trait AnyVar: Send + Sync {
+        fn version(&self) -> u64;
+        fn store(&self, value: Box<dyn Any + Send>, version: u64);
+    }
+
+    impl<T: Send + 'static> AnyVar for Var<T> {
+        fn version(&self) -> u64 {
+            lock(&self.slot).version
+        }
+
+        fn store(&self, value: Box<dyn Any + Send>, version: u64) {
+            let value = *value.downcast::<T>().expect("writes are keyed by their variable");
+            *lock(&self.slot) = Slot { version, value };
+        }
+    }
+
+    /// A transactional variable, clones share the same value.
+    pub struct TVar<T> {
+        var: Arc<Var<T>>,
+    }
+
+    impl<T> Clone for TVar<T> {
+        fn clone(&self) -> Self {
+            TVar { var: self.var.clone() }
+        }
+    }
+
+    impl<T: Clone + Send + 'static> TVar<T> {
+        pub fn new(value: T) -> Self {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            TVar {
+                var: Arc::new(Var {
+                    id,
+                    slot: Mutex::new(Slot { version: 0, value }),
+                }),
+            }
+        }
+
+        /// The current value, as its own transaction.
+        pub fn get(&self) -> T {
+            atomically(|tx| tx.read(self))
+        }
+    }
+
+    impl<T: Clone + Send + fmt::Debug + 'static> fmt::Debug for TVar<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("TVar").field(&lock(&self.var.slot).value).finish()
+        }
+    }
+
+    /// A buffered write, the value is the `T` of the variable.
+    type Write = (Arc<dyn AnyVar>, Box<dyn Any + Send>);
+
+    /// One attempt of a transaction.
+    pub struct Transaction {
+        /// The clock when the attempt started, anything newer was written after the snapshot.
+        start: u64,
+        reads: HashMap<usize, (u64, Arc<dyn AnyVar>)>,
+        writes: HashMap<usize, Write>,
+    }
+
+    impl Transaction {
+        fn new() -> Self {
+            Transaction {
+                start: CLOCK.load(Ordering::SeqCst),
+                reads: HashMap::new(),
+                writes: HashMap::new(),
+            }
+        }
+
+        /// The value as of the snapshot, or as written earlier in this transaction.
+        pub fn read<T: Clone + Send + 'static>(&mut self, tvar: &TVar<T>) -> Result<T, Retry> {
+            if let Some((_, value)) = self.writes.get(&tvar.var.id) {
+                return Ok(value.downcast_ref::<T>().expect("writes are keyed by their variable").clone());
+            }
+            let slot = lock(&tvar.var.slot);
+            if slot.version > self.start {
+                return Err(Retry);
+            }
+            self.reads.insert(tvar.var.id, (slot.version, tvar.var.clone()));
+            Ok(slot.value.clone())
+        }
+
+        /// Buffers `value` until the commit.
+        pub fn write<T: Clone + Send + 'static>(&mut self, tvar: &TVar<T>, value: T) -> Result<(), Retry> {
+            self.writes.insert(tvar.var.id, (tvar.var.clone(), Box::new(value)));
+            Ok(())
+        }
+
+        /// Gives up this attempt, for when the transaction can't go on with what it read yet.
+        pub fn retry<T>(&mut self) -> Result<T, Retry> {
+            Err(Retry)
+        }
+
+        fn commit(self) -> bool {
+            let _guard = lock(&COMMIT);
+            if self.reads.values().any(|(version, var)| var.version() != *version) {
+                return false;
+            }
+            if self.writes.is_empty() {
+                return true;
+            }
+            let version = CLOCK.load(Ordering::SeqCst) + 1;
+            for (var, value) in self.writes.into_values() {
+                var.store(value, version);
+            }
+            // published after the writes, so a snapshot taken before sees them all as too new.
+            CLOCK.store(version, Ordering::SeqCst);
+            true
+        }
+    }
+
+    impl fmt::Debug for Transaction {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Transaction")
+                .field("reads", &self.reads.len())
+                .field("writes", &self.writes.len())
+                .finish()
+        }
+    }
+
+    /// Runs `f` until it commits, so `f` should have no effects besides the transaction.
+    ///
+    /// ```
+    /// use awesome::stm::{atomically, TVar};
+    ///
+    /// let (from, to) = (TVar::new(100), TVar::new(0));
+    /// let moved = atomically(|tx| {
+    ///     let balance = tx.read(&from)?;
+    ///     if balance < 30 {
+    ///         return Ok(false);
+    ///     }
+    ///     tx.write(&from, balance - 30)?;
+    ///     let other = tx.read(&to)?;
+    ///     tx.write(&to, other + 30)?;
+    ///     Ok(true)
+    /// });
+    /// assert!(moved);
+    /// assert_eq!((from.get(), to.get()), (70, 30));
+    /// ```
+    pub fn atomically<R>(mut f: impl FnMut(&mut Transaction) -> Result<R, Retry>) -> R {
+        loop {
+            let mut tx = Transaction::new();
+            if let Ok(result) = f(&mut tx) {
+                if tx.commit() {
+                    return result;
+                }
+            }
+            thread::yield_now();
+        }
+    }
+}
+
+/// # Rate Limiter
+///
+/// A token bucket and a sliding window behind one `Limiter` trait, so they compose with `and` and wrap closures.
+/// the state lives in `Cell`s so acquiring only needs `&self`, and time comes from a `Clock` so tests don't sleep.
+/// See [here](https://en.wikipedia.org/wiki/Token_bucket) for more details.
+pub mod rate_limiter {
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Where the time comes from, `now` is measured from any fixed origin.
+    pub trait Clock {
+        fn now(&self) -> Duration;
+        fn sleep(&self, duration: Duration);
+    }
+
+    /// The real time.
+    #[derive(Debug, Clone, Copy)]
+    pub struct SystemClock {
+        origin: Instant,
+    }
+
+    impl Default for SystemClock {
+        fn default() -> Self {
+            SystemClock { origin: Instant::now() }
+        }
+    }
+
+    impl Clock for SystemClock {
+        fn now(&self) -> Duration {
+            self.origin.elapsed()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            thread::sleep(duration)
+        }
+    }
+
+    /// Time that only moves by `advance` or `sleep`, clones share it.
+    #[derive(Debug, Clone, Default)]
+    pub struct ManualClock {
+        now: Rc<Cell<Duration>>,
+    }
+
+    impl ManualClock {
+        pub fn new() -> Self {
+            ManualClock::default()
+        }
+
+        pub fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration)
+        }
+    }
+
+    /// Something that lets calls through at some rate.
+    pub trait Limiter {
+        /// How long until the next call is allowed, zero if it is now.
+        fn available_in(&self) -> Duration;
+
+        /// Counts a call, only called when `available_in` is zero.
+        fn consume(&self);
+
+        /// Waits for `duration` on the limiter's clock.
+        fn sleep(&self, duration: Duration);
+
+        fn try_acquire(&self) -> bool {
+            let allowed = self.available_in().is_zero();
+            if allowed {
+                self.consume();
+            }
+            allowed
+        }
+
+        fn acquire_blocking(&self) {
+            loop {
+                let wait = self.available_in();
+                if wait.is_zero() {
+                    return self.consume();
+                }
+                self.sleep(wait);
+            }
+        }
+
+        /// Allows a call only when both limiters do, and counts it in both.
+        fn and<L: Limiter>(self, other: L) -> And<Self, L>
+        where
+            Self: Sized,
+        {
+            And(self, other)
+        }
+    }
+
+    /// `capacity` tokens refilled at `rate` per second, each call takes one.
+    ///
+    /// ```
+    /// use awesome::rate_limiter::{Limiter, ManualClock, TokenBucket};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let bucket = TokenBucket::with_clock(2.0, 3, clock.clone());
+    /// assert_eq!((0..5).filter(|_| bucket.try_acquire()).count(), 3);
+    /// assert_eq!(bucket.available_in(), Duration::from_millis(500));
+    ///
+    /// clock.advance(Duration::from_secs(1));
+    /// assert_eq!((0..5).filter(|_| bucket.try_acquire()).count(), 2);
+    /// ```
+    pub struct TokenBucket<C: Clock = SystemClock> {
+        rate: f64,
+        capacity: f64,
+        /// The tokens at `updated`.
+        tokens: Cell<f64>,
+        updated: Cell<Duration>,
+        clock: C,
+    }
+
+    impl TokenBucket {
+        /// A full bucket of `burst` tokens, refilled at `rate` per second.
+        pub fn new(rate: f64, burst: u32) -> Self {
+            TokenBucket::with_clock(rate, burst, SystemClock::default())
+        }
+    }
+
+    impl<C: Clock> TokenBucket<C> {
+        pub fn with_clock(rate: f64, burst: u32, clock: C) -> Self {
+            assert!(rate > 0.0 && burst > 0, "a token bucket needs a positive rate and burst");
+            let updated = Cell::new(clock.now());
+            TokenBucket {
+                rate,
+                capacity: burst.into(),
+                tokens: Cell::new(burst.into()),
+                updated,
+                clock,
+            }
+        }
+
+        pub fn tokens(&self) -> f64 {
+            self.refill();
+            self.tokens.get()
+        }
+
+        fn refill(&self) {
+            let now = self.clock.now();
+            let elapsed = now.saturating_sub(self.updated.get()).as_secs_f64();
+            self.tokens.set((self.tokens.get() + elapsed * self.rate).min(self.capacity));
+            self.updated.set(now);
+        }
+    }
+
+    impl<C: Clock> Limiter for TokenBucket<C> {
+        fn available_in(&self) -> Duration {
+            let missing = 1.0 - self.tokens();
+            if missing <= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(missing / self.rate)
+            }
+        }
+
+        fn consume(&self) {
+            self.tokens.set(self.tokens.get() - 1.0);
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.clock.sleep(duration)
+        }
+    }
+
+    impl<C: Clock> fmt::Debug for TokenBucket<C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("TokenBucket")
+                .field("rate", &self.rate)
+                .field("capacity", &self.capacity)
+                .field("tokens", &self.tokens.get())
+                .finish()
+        }
+    }
+
+    /// At most `limit` calls in any `window`, remembering the time of each call.
+    ///
+    /// ```
+    /// use awesome::rate_limiter::{Limiter, ManualClock, SlidingWindow};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let window = SlidingWindow::with_clock(2, Duration::from_secs(10), clock.clone());
+    /// assert!(window.try_acquire());
+    /// clock.advance(Duration::from_secs(4));
+    /// assert!(window.try_acquire());
+    /// assert!(!window.try_acquire());
+    ///
+    /// // the first call leaves the window at 10s.
+    /// assert_eq!(window.available_in(), Duration::from_secs(6));
+    /// ```
+    pub struct SlidingWindow<C: Clock = SystemClock> {
+        limit: usize,
+        window: Duration,
+        calls: RefCell<VecDeque<Duration>>,
+        clock: C,
+    }
+
+    impl SlidingWindow {
+        pub fn new(limit: usize, window: Duration) -> Self {
+            SlidingWindow::with_clock(limit, window, SystemClock::default())
+        }
+    }
+
+    impl<C: Clock> SlidingWindow<C> {
+        pub fn with_clock(limit: usize, window: Duration, clock: C) -> Self {
+            assert!(limit > 0, "a sliding window needs a positive limit");
+            SlidingWindow {
+                limit,
+                window,
+                calls: RefCell::new(VecDeque::with_capacity(limit)),
+                clock,
+            }
+        }
+    }
+
+    impl<C: Clock> Limiter for SlidingWindow<C> {
+        fn available_in(&self) -> Duration {
+            let now = self.clock.now();
+            let mut calls = self.calls.borrow_mut();
+            while calls.front().is_some_and(|&call| call + self.window <= now) {
+                calls.pop_front();
+            }
+            match calls.front() {
+                Some(&oldest) if calls.len() >= self.limit => oldest + self.window - now,
+                _ => Duration::ZERO,
+            }
+        }
+
+        fn consume(&self) {
+            self.calls.borrow_mut().push_back(self.clock.now());
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.clock.sleep(duration)
+        }
+    }
+
+    impl<C: Clock> fmt::Debug for SlidingWindow<C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("SlidingWindow")
+                .field("limit", &self.limit)
+                .field("window", &self.window)
+                .field("calls", &self.calls.borrow().len())
+                .finish()
+        }
+    }
+
+    /// Both limiters, see [`Limiter::and`].
+    #[derive(Debug)]
+    pub struct And<A, B>(A, B);
+
+    impl<A: Limiter, B: Limiter> Limiter for And<A, B> {
+        fn available_in(&self) -> Duration {
+            self.0.available_in().max(self.1.available_in())
+        }
+
+        fn consume(&self) {
+            self.0.consume();
+            self.1.consume();
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.0.sleep(duration)
+        }
+    }
+
+    /// `f` behind `limiter`, a call over the limit returns `None` without calling `f`.
+    ///
+    /// ```
+    /// use awesome::rate_limiter::{rate_limited, ManualClock, TokenBucket};
+    ///
+    /// let mut shout = rate_limited(TokenBucket::with_clock(1.0, 2, ManualClock::new()), |s: &str| s.to_uppercase());
+    /// assert_eq!(shout("a"), Some("A".to_string()));
+    /// assert_eq!(shout("b"), Some("B".to_string()));
+    /// assert_eq!(shout("c"), None);
+    /// ```
+    pub fn rate_limited<A, R>(limiter: impl Limiter, mut f: impl FnMut(A) -> R) -> impl FnMut(A) -> Option<R> {
+        move |arg| limiter.try_acquire().then(|| f(arg))
+    }
+
+    /// `f` behind `limiter`, a call over the limit waits for its turn.
+    pub fn throttled<A, R>(limiter: impl Limiter, mut f: impl FnMut(A) -> R) -> impl FnMut(A) -> R {
+        move |arg| {
+            limiter.acquire_blocking();
+            f(arg)
+        }
+    }
+}
+
+/// # Retry
+///
+/// Runs a fallible operation again until it succeeds, the `Policy` decides how long to wait before each new attempt
+/// and when to give up, so `Fixed`, `Exponential` and `Jitter` combine freely with `max_attempts`.
+/// the waiting goes through `rate_limiter::Clock`, or an async `sleep` with the `async` feature.
+/// See [here](https://en.wikipedia.org/wiki/Exponential_backoff) for more details.
+pub mod retry {
+    use crate::rate_limiter::{Clock, SystemClock};
+    use std::fmt;
+    #[cfg(feature = "async")]
+    use std::future::Future;
+    use std::time::Duration;
+
+    /// How long to wait after the `failures`th failure, `None` to give up.
+    pub trait Policy {
+        fn delay(&mut self, failures: u32) -> Option<Duration>;
+
+        /// Gives up after `attempts` attempts in total, the first one included.
+        fn max_attempts(self, attempts: u32) -> MaxAttempts<Self>
+        where
+            Self: Sized,
+        {
+            MaxAttempts { policy: self, attempts }
+        }
+
+        /// Randomizes each delay, see [`Jitter`].
+        fn jitter(self, seed: u64) -> Jitter<Self>
+        where
+            Self: Sized,
+        {
+            Jitter::new(self, seed)
+        }
+    }
+
+    /// The same delay every time.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Fixed(pub Duration);
+
+    impl Policy for Fixed {
+        fn delay(&mut self, _: u32) -> Option<Duration> {
+            Some(self.0)
+        }
+    }
+
+    /// `initial`, then multiplied by `factor` after each failure, up to `max`.
+    ///
+    /// ```
+    /// use awesome::retry::{Exponential, Policy};
+    /// use std::time::Duration;
+    ///
+    /// let mut policy = Exponential::new(Duration::from_millis(100)).max_delay(Duration::from_millis(500));
+    /// let delays: Vec<_> = (1..=5).map(|failures| policy.delay(failures).unwrap().as_millis()).collect();
+    /// assert_eq!(delays, [100, 200, 400, 500, 500]);
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    }
+
+    impl Exponential {
+        /// Doubling from `initial`, without a maximum.
+        pub fn new(initial: Duration) -> Self {
+            Exponential {
+                initial,
+                factor: 2.0,
+                max: Duration::MAX,
+            }
+        }
+
+        pub fn factor(mut self, factor: f64) -> Self {
+            self.factor = factor;
+            self
+        }
+
+        pub fn max_delay(mut self, max: Duration) -> Self {
+            self.max = max;
+            self
+        }
+    }
+
+    impl Policy for Exponential {
+        fn delay(&mut self, failures: u32) -> Option<Duration> {
+            let exponent = failures.saturating_sub(1).min(i32::MAX as u32) as i32;
+            let delay = self.initial.as_secs_f64() * self.factor.powi(exponent);
+            Some(Duration::try_from_secs_f64(delay).map_or(self.max, |delay| delay.min(self.max)))
+        }
+    }
+
+    /// See [`Policy::max_attempts`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MaxAttempts<P> {
+        policy: P,
+        attempts: u32,
+    }
+
+    impl<P: Policy> Policy for MaxAttempts<P> {
+        fn delay(&mut self, failures: u32) -> Option<Duration> {
+            if failures >= self.attempts {
+                None
+            } else {
+                self.policy.delay(failures)
+            }
+        }
+    }
+
+    /// Each delay of `P` replaced by a random one between zero and it ("full jitter"),
+    /// so clients failing together don't retry together. the randomness is a seeded xorshift, so it's repeatable.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Jitter<P> {
+        policy: P,
+        state: u64,
+    }
+
+    impl<P: Policy> Jitter<P> {
+        pub fn new(policy: P, seed: u64) -> Self {
+            // xorshift is stuck at zero.
+            Jitter { policy, state: seed.max(1) }
+        }
+
+        fn next(&mut self) -> f64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            (self.state >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    impl<P: Policy> Policy for Jitter<P> {
+        fn delay(&mut self, failures: u32) -> Option<Duration> {
+            let delay = self.policy.delay(failures)?;
+            Some(delay.mul_f64(self.next()))
+        }
+    }
+
+    /// A retry loop, `retry_if` filters the errors worth another attempt.
+    ///
+    /// ```
+    /// use awesome::rate_limiter::{Clock, ManualClock};
+    /// use awesome::retry::{Fixed, Policy, Retry};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut calls = 0;
+    /// let result = Retry::new(Fixed(Duration::from_secs(1)).max_attempts(5))
+    ///     .retry_if(|e: &&str| *e != "fatal")
+    ///     .clock(clock.clone())
+    ///     .run(|| {
+    ///         calls += 1;
+    ///         if calls < 3 { Err("busy") } else { Ok(calls) }
+    ///     });
+    ///
+    /// assert_eq!(result, Ok(3));
+    /// assert_eq!(clock.now(), Duration::from_secs(2));
+    /// ```
+    pub struct Retry<'a, P, E, C = SystemClock> {
+        policy: P,
+        clock: C,
+        retry_if: Box<dyn FnMut(&E) -> bool + 'a>,
+    }
+
+    impl<'a, P: Policy, E> Retry<'a, P, E> {
+        /// Retries every error, sleeping for real.
+        pub fn new(policy: P) -> Self {
+            Retry {
+                policy,
+                clock: SystemClock::default(),
+                retry_if: Box::new(|_| true),
+            }
+        }
+    }
+
+    impl<'a, P: Policy, E, C> Retry<'a, P, E, C> {
+        pub fn retry_if(mut self, predicate: impl FnMut(&E) -> bool + 'a) -> Self {
+            self.retry_if = Box::new(predicate);
+            self
+        }
+
+        pub fn clock<D: Clock>(self, clock: D) -> Retry<'a, P, E, D> {
+            Retry {
+                policy: self.policy,
+                clock,
+                retry_if: self.retry_if,
+            }
+        }
+
+        /// The delay before the next attempt, or `None` if `error` is final.
+        fn next_delay(&mut self, failures: u32, error: &E) -> Option<Duration> {
+            if (self.retry_if)(error) {
+                self.policy.delay(failures)
+            } else {
+                None
+            }
+        }
+
+        /// Calls `op` until it succeeds, or returns its last error.
+        pub fn run<T>(mut self, mut op: impl FnMut() -> Result<T, E>) -> Result<T, E>
+        where
+            C: Clock,
+        {
+            let mut failures = 0;
+            loop {
+                let error = match op() {
+                    Ok(value) => return Ok(value),
+                    Err(error) => error,
+                };
+                failures += 1;
+                match self.next_delay(failures, &error) {
+                    Some(delay) => self.clock.sleep(delay),
+                    None => return Err(error),
+                }
+            }
+        }
+
+        /// [`Retry::run`] for an async `op`, waiting with the runtime's `sleep` (`tokio::time::sleep` for example).
+        #[cfg(feature = "async")]
+        pub async fn run_async<T, Op, S>(mut self, mut sleep: impl FnMut(Duration) -> S, mut op: impl FnMut() -> Op) -> Result<T, E>
+        where
+            Op: Future<Output = Result<T, E>>,
+            S: Future<Output = ()>,
+        {
+            let mut failures = 0;
+            loop {
+                let error = match op().await {
+                    Ok(value) => return Ok(value),
+                    Err(error) => error,
+                };
+                failures += 1;
+                match self.next_delay(failures, &error) {
+                    Some(delay) => sleep(delay).await,
+                    None => return Err(error),
+                }
+            }
+        }
+    }
+
+    impl<P: fmt::Debug, E, C: fmt::Debug> fmt::Debug for Retry<'_, P, E, C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Retry")
+                .field("policy", &self.policy)
+                .field("clock", &self.clock)
+                .finish_non_exhaustive()
+        }
+    }
+
+    /// Calls `op` until it succeeds or `policy` gives up, sleeping between the attempts.
+    pub fn retry<T, E>(policy: impl Policy, op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        Retry::new(policy).run(op)
+    }
+}
+
+/// # Circuit Breaker
+///
+/// Stops calling something that keeps failing: after enough failures in a row the circuit opens and calls fail fast,
+/// after a cooldown it lets trial calls through (half-open), which close it again or reopen it.
+/// the states come from `fsm::state_machine!`, and the time from `rate_limiter::Clock`.
+/// See [here](https://martinfowler.com/bliki/CircuitBreaker.html) for more details.
+pub mod circuit_breaker {
+    use crate::fsm::state_machine;
+    use crate::rate_limiter::{Clock, SystemClock};
+    use std::cell::Cell;
+    use std::fmt;
+    use std::time::Duration;
+
+    state_machine! {
+        /// The transitions of a [`CircuitBreaker`].
+        pub Circuit {
+            Closed --trip--> Open,
+            Open --cool_down--> HalfOpen,
+            HalfOpen --close--> Closed,
+            HalfOpen --trip--> Open,
+        }
+    }
+
+    /// The error of [`CircuitBreaker::call`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BreakerError<E> {
+        /// The circuit is open, the call wasn't made.
+        Open { retry_in: Duration },
+        /// The call was made and failed.
+        Failed(E),
+    }
+
+    impl<E: fmt::Display> fmt::Display for BreakerError<E> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                BreakerError::Open { retry_in } => write!(f, "the circuit is open, retry in {retry_in:?}"),
+                BreakerError::Failed(error) => write!(f, "the call failed: {error}"),
+            }
+        }
+    }
+
+    impl<E: std::error::Error + 'static> std::error::Error for BreakerError<E> {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                BreakerError::Open { .. } => None,
+                BreakerError::Failed(error) => Some(error),
+            }
+        }
+    }
+
+    /// Wraps fallible calls, see the module docs.
+    ///
+    /// ```
+    /// use awesome::circuit_breaker::{BreakerError, CircuitBreaker, CircuitState};
+    /// use awesome::rate_limiter::ManualClock;
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let breaker = CircuitBreaker::with_clock(2, Duration::from_secs(30), clock.clone());
+    /// let failing = || Err::<(), _>("connection refused");
+    ///
+    /// assert_eq!(breaker.call(failing), Err(BreakerError::Failed("connection refused")));
+    /// assert_eq!(breaker.call(failing), Err(BreakerError::Failed("connection refused")));
+    /// assert_eq!(breaker.state(), CircuitState::Open);
+    /// assert_eq!(breaker.call(|| Ok::<_, &str>(1)), Err(BreakerError::Open { retry_in: Duration::from_secs(30) }));
+    ///
+    /// clock.advance(Duration::from_secs(30));
+    /// assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    /// assert_eq!(breaker.call(|| Ok::<_, &str>(1)), Ok(1));
+    /// assert_eq!(breaker.state(), CircuitState::Closed);
+    /// ```
+    pub struct CircuitBreaker<C: Clock = SystemClock> {
+        failure_threshold: u32,
+        success_threshold: u32,
+        cooldown: Duration,
+        state: Cell<CircuitState>,
+        /// Failures in a row while closed, successes in a row while half-open.
+        count: Cell<u32>,
+        opened_at: Cell<Duration>,
+        clock: C,
+    }
+
+    impl CircuitBreaker {
+        /// Opens after `failure_threshold` failures in a row, for `cooldown`.
+        pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+            CircuitBreaker::with_clock(failure_threshold, cooldown, SystemClock::default())
+        }
+    }
+
+    impl<C: Clock> CircuitBreaker<C> {
+        pub fn with_clock(failure_threshold: u32, cooldown: Duration, clock: C) -> Self {
+            assert!(failure_threshold > 0, "a circuit breaker needs a positive failure threshold");
+            CircuitBreaker {
+                failure_threshold,
+                success_threshold: 1,
+                cooldown,
+                state: Cell::new(CircuitState::Closed),
+                count: Cell::new(0),
+                opened_at: Cell::new(Duration::ZERO),
+                clock,
+            }
+        }
+
+        /// How many successes in a row close a half-open circuit, one by default.
+        pub fn success_threshold(mut self, successes: u32) -> Self {
+            assert!(successes > 0, "a circuit breaker needs a positive success threshold");
+            self.success_threshold = successes;
+            self
+        }
+
+        /// The state now, an open circuit whose cooldown is over is half-open.
+        pub fn state(&self) -> CircuitState {
+            if self.state.get() == CircuitState::Open && self.clock.now().saturating_sub(self.opened_at.get()) >= self.cooldown {
+                self.fire(CircuitEvent::CoolDown);
+            }
+            self.state.get()
+        }
+
+        /// Calls `f` unless the circuit is open, and counts how it went.
+        pub fn call<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, BreakerError<E>> {
+            if self.state() == CircuitState::Open {
+                let retry_in = self.cooldown - self.clock.now().saturating_sub(self.opened_at.get());
+                return Err(BreakerError::Open { retry_in });
+            }
+            match f() {
+                Ok(value) => {
+                    self.succeeded();
+                    Ok(value)
+                }
+                Err(error) => {
+                    self.failed();
+                    Err(BreakerError::Failed(error))
+                }
+            }
+        }
+
+        fn succeeded(&self) {
+            match self.state.get() {
+                CircuitState::HalfOpen if self.count.get() + 1 >= self.success_threshold => self.fire(CircuitEvent::Close),
+                CircuitState::HalfOpen => self.count.set(self.count.get() + 1),
+                _ => self.count.set(0),
+            }
+        }
+
+        fn failed(&self) {
+            match self.state.get() {
+                CircuitState::Closed if self.count.get() + 1 < self.failure_threshold => self.count.set(self.count.get() + 1),
+                _ => self.fire(CircuitEvent::Trip),
+            }
+        }
+
+        /// Moves to the next state, which starts with a fresh count.
+        fn fire(&self, event: CircuitEvent) {
+            let next = Circuit::transition(self.state.get(), event).expect("the breaker only fires valid events");
+            if next == CircuitState::Open {
+                self.opened_at.set(self.clock.now());
+            }
+            self.state.set(next);
+            self.count.set(0);
+        }
+    }
+
+    impl<C: Clock> fmt::Debug for CircuitBreaker<C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("CircuitBreaker")
+                .field("state", &self.state.get())
+                .field("count", &self.count.get())
+                .finish_non_exhaustive()
+        }
+    }
+}
+
+/// # Cache
+///
+/// A bounded map whose eviction is a pluggable `Policy`: the cache keeps the values, the policy only tracks the keys
+/// and picks which one goes when it's full, or which ones are stale.
+/// `Lru` keeps an intrusive doubly linked list in a `Vec`, `Lfu` a `BTreeMap` ordered by use count, `Ttl` the insertion times.
+/// See [here](https://en.wikipedia.org/wiki/Cache_replacement_policies) for more details.
+pub mod cache {
+    use crate::rate_limiter::{Clock, SystemClock};
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt;
+    use std::hash::Hash;
+    use std::time::Duration;
+
+    /// Tracks the keys of a [`Cache`] and chooses what to evict.
+    pub trait Policy<K> {
+        /// `key` was inserted, or its value replaced.
+        fn on_insert(&mut self, key: &K);
+        fn on_access(&mut self, key: &K);
+        fn on_remove(&mut self, key: &K);
+
+        /// The key to evict to make room, `None` if there are none.
+        fn victim(&self) -> Option<K>;
+
+        /// Whether `key` is stale and should be treated as missing.
+        fn is_expired(&self, key: &K) -> bool {
+            let _ = key;
+            false
+        }
+    }
+
+    /// Evicts the least recently used key.
+    pub struct Lru<K> {
+        nodes: Vec<LruNode<K>>,
+        slots: HashMap<K, usize>,
+        /// The most recently used node.
+        head: Option<usize>,
+        tail: Option<usize>,
+        /// Nodes of removed keys, reused by the next insert.
+        free: Vec<usize>,
+    }
+
+    struct LruNode<K> {
+        key: K,
+        prev: Option<usize>,
+        next: Option<usize>,
+    }
+
+    impl<K> Default for Lru<K> {
+        fn default() -> Self {
+            Lru {
+                nodes: Vec::new(),
+                slots: HashMap::new(),
+                head: None,
+                tail: None,
+                free: Vec::new(),
+            }
+        }
+    }
+
+    impl<K: Clone + Eq + Hash> Lru<K> {
+        pub fn new() -> Self {
+            Lru::default()
+        }
+
+        /// From the most to the least recently used.
+        pub fn keys(&self) -> impl Iterator<Item = &K> {
+            std::iter::successors(self.head, |&i| self.nodes[i].next).map(|i| &self.nodes[i].key)
+        }
+
+        fn unlink(&mut self, i: usize) {
+            let (prev, next) = (self.nodes[i].prev, self.nodes[i].next);
+            match prev {
+                Some(prev) => self.nodes[prev].next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => self.nodes[next].prev = prev,
+                None => self.tail = prev,
+            }
+        }
+
+        fn push_front(&mut self, i: usize) {
+            self.nodes[i].prev = None;
+            self.nodes[i].next = self.head;
+            match self.head {
+                Some(head) => self.nodes[head].prev = Some(i),
+                None => self.tail = Some(i),
+            }
+            self.head = Some(i);
+        }
+    }
+
+    impl<K: Clone + Eq + Hash> Policy<K> for Lru<K> {
+        fn on_insert(&mut self, key: &K) {
+            if self.slots.contains_key(key) {
+                return self.on_access(key);
+            }
+            let node = LruNode {
+                key: key.clone(),
+                prev: None,
+                next: None,
+            };
+            let i = match self.free.pop() {
+                Some(i) => {
+                    self.nodes[i] = node;
+                    i
+                }
+                None => {
+                    self.nodes.push(node);
+                    self.nodes.len() - 1
+                }
+            };
+            self.slots.insert(key.clone(), i);
+            self.push_front(i);
+        }
+
+        fn on_access(&mut self, key: &K) {
+            if let Some(&i) = self.slots.get(key) {
+                self.unlink(i);
+                self.push_front(i);
+            }
+        }
+
+        fn on_remove(&mut self, key: &K) {
+            if let Some(i) = self.slots.remove(key) {
+                self.unlink(i);
+                self.free.push(i);
+            }
+        }
+
+        fn victim(&self) -> Option<K> {
+            self.tail.map(|i| self.nodes[i].key.clone())
+        }
+    }
+
+    impl<K: fmt::Debug + Clone + Eq + Hash> fmt::Debug for Lru<K> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Lru").field(&self.keys().collect::<Vec<_>>()).finish()
+        }
+    }
+
+    /// Evicts the least frequently used key, the least recently used of those on a tie.
+    #[derive(Debug)]
+    pub struct Lfu<K> {
+        /// The use count and the tick of the last use of each key.
+        uses: HashMap<K, (u64, u64)>,
+        order: BTreeMap<(u64, u64), K>,
+        tick: u64,
+    }
+
+    impl<K> Default for Lfu<K> {
+        fn default() -> Self {
+            Lfu {
+                uses: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+            }
+        }
+    }
+
+    impl<K: Clone + Eq + Hash> Lfu<K> {
+        pub fn new() -> Self {
+            Lfu::default()
+        }
+
+        /// How many times `key` was inserted or accessed.
+        pub fn uses(&self, key: &K) -> u64 {
+            self.uses.get(key).map_or(0, |&(count, _)| count)
+        }
+
+        fn bump(&mut self, key: &K) {
+            let count = match self.uses.get(key) {
+                Some(&(count, tick)) => {
+                    self.order.remove(&(count, tick));
+                    count + 1
+                }
+                None => 1,
+            };
+            self.tick += 1;
+            self.uses.insert(key.clone(), (count, self.tick));
+            self.order.insert((count, self.tick), key.clone());
+        }
+    }
+
+    impl<K: Clone + Eq + Hash> Policy<K> for Lfu<K> {
+        fn on_insert(&mut self, key: &K) {
+            self.bump(key)
+        }
+
+        fn on_access(&mut self, key: &K) {
+            self.bump(key)
+        }
+
+        fn on_remove(&mut self, key: &K) {
+            if let Some(place) = self.uses.remove(key) {
+                self.order.remove(&place);
+            }
+        }
+
+        fn victim(&self) -> Option<K> {
+            self.order.values().next().cloned()
+        }
+    }
+
+    /// Entries expire `ttl` after they were inserted, the oldest one is evicted first.
+    pub struct Ttl<K, C: Clock = SystemClock> {
+        ttl: Duration,
+        inserted: HashMap<K, (Duration, u64)>,
+        /// Keyed by time and a counter, so entries inserted at the same time keep their order.
+        order: BTreeMap<(Duration, u64), K>,
+        counter: u64,
+        clock: C,
+    }
+
+    impl<K: Clone + Eq + Hash> Ttl<K> {
+        pub fn new(ttl: Duration) -> Self {
+            Ttl::with_clock(ttl, SystemClock::default())
+        }
+    }
+
+    impl<K: Clone + Eq + Hash, C: Clock> Ttl<K, C> {
+        pub fn with_clock(ttl: Duration, clock: C) -> Self {
+            Ttl {
+                ttl,
+                inserted: HashMap::new(),
+                order: BTreeMap::new(),
+                counter: 0,
+                clock,
+            }
+        }
+    }
+
+    impl<K: Clone + Eq + Hash, C: Clock> Policy<K> for Ttl<K, C> {
+        fn on_insert(&mut self, key: &K) {
+            self.on_remove(key);
+            self.counter += 1;
+            let place = (self.clock.now(), self.counter);
+            self.inserted.insert(key.clone(), place);
+            self.order.insert(place, key.clone());
+        }
+
+        fn on_access(&mut self, _: &K) {}
+
+        fn on_remove(&mut self, key: &K) {
+            if let Some(place) = self.inserted.remove(key) {
+                self.order.remove(&place);
+            }
+        }
+
+        fn victim(&self) -> Option<K> {
+            self.order.values().next().cloned()
+        }
+
+        fn is_expired(&self, key: &K) -> bool {
+            self.inserted.get(key).is_some_and(|&(at, _)| self.clock.now().saturating_sub(at) >= self.ttl)
+        }
+    }
+
+    impl<K: fmt::Debug, C: Clock> fmt::Debug for Ttl<K, C> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Ttl")
+                .field("ttl", &self.ttl)
+                .field("keys", &self.order.values().collect::<Vec<_>>())
+                .finish()
+        }
+    }
+
+    /// At most `capacity` entries, evicted by `P`.
+    ///
+    /// ```
+    /// use awesome::cache::Cache;
+    ///
+    /// let mut cache = Cache::lru(2);
+    /// cache.insert("a", 1);
+    /// cache.insert("b", 2);
+    /// cache.get(&"a");
+    /// cache.insert("c", 3);
+    ///
+    /// // "b" was the least recently used.
+    /// assert_eq!(cache.get(&"b"), None);
+    /// assert_eq!(cache.get_or_insert_with("b", || 20), &20);
+    /// assert_eq!(cache.get(&"a"), None);
+    /// ```
+    pub struct Cache<K, V, P: Policy<K>> {
+        entries: HashMap<K, V>,
+        policy: P,
+        capacity: usize,
+    }
+
+    impl<K: Clone + Eq + Hash, V> Cache<K, V, Lru<K>> {
+        pub fn lru(capacity: usize) -> Self {
+            Cache::new(capacity, Lru::new())
+        }
+    }
+
+    impl<K: Clone + Eq + Hash, V> Cache<K, V, Lfu<K>> {
+        pub fn lfu(capacity: usize) -> Self {
+            Cache::new(capacity, Lfu::new())
+        }
+    }
+
+    impl<K: Clone + Eq + Hash, V, C: Clock> Cache<K, V, Ttl<K, C>> {
+        pub fn ttl(capacity: usize, ttl: Duration, clock: C) -> Self {
+            Cache::new(capacity, Ttl::with_clock(ttl, clock))
+        }
+    }
+
+    impl<K: Clone + Eq + Hash, V, P: Policy<K>> Cache<K, V, P> {
+        pub fn new(capacity: usize, policy: P) -> Self {
+            assert!(capacity > 0, "a cache needs a positive capacity");
+            Cache {
+                entries: HashMap::with_capacity(capacity),
+                policy,
+                capacity,
+            }
+        }
+
+        pub fn policy(&self) -> &P {
+            &self.policy
+        }
+
+        pub fn capacity(&self) -> usize {
+            self.capacity
+        }
+
+        /// Entries, expired ones included until they are looked up or purged.
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        /// The value of `key`, which counts as a use.
+        pub fn get(&mut self, key: &K) -> Option<&V> {
+            if self.remove_expired(key) {
+                return None;
+            }
+            let value = self.entries.get(key)?;
+            self.policy.on_access(key);
+            Some(value)
+        }
+
+        /// [`Cache::get`], but not counted as a use.
+        pub fn peek(&self, key: &K) -> Option<&V> {
+            self.entries.get(key).filter(|_| !self.policy.is_expired(key))
+        }
+
+        pub fn contains_key(&self, key: &K) -> bool {
+            self.peek(key).is_some()
+        }
+
+        /// Inserts `value`, evicting an entry if the cache is full, and returns the value it replaced.
+        pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+            self.remove_expired(&key);
+            if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+                self.evict();
+            }
+            self.policy.on_insert(&key);
+            self.entries.insert(key, value)
+        }
+
+        /// The value of `key`, computed by `f` and inserted if it is missing.
+        pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce() -> V) -> &V {
+            if self.get(&key).is_none() {
+                self.insert(key.clone(), f());
+            }
+            &self.entries[&key]
+        }
+
+        pub fn remove(&mut self, key: &K) -> Option<V> {
+            let value = self.entries.remove(key)?;
+            self.policy.on_remove(key);
+            Some(value)
+        }
+
+        /// Removes the expired entries, returns how many there were.
+        pub fn purge(&mut self) -> usize {
+            let expired: Vec<K> = self.entries.keys().filter(|key| self.policy.is_expired(key)).cloned().collect();
+            expired.iter().for_each(|key| {
+                self.remove(key);
+            });
+            expired.len()
+        }
+
+        /// The fresh entries in no particular order, without counting as uses.
+        pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+            self.entries.iter().filter(|(key, _)| !self.policy.is_expired(key))
+        }
+
+        fn evict(&mut self) {
+            if let Some(victim) = self.policy.victim() {
+                self.remove(&victim);
+            }
+        }
+
+        fn remove_expired(&mut self, key: &K) -> bool {
+            let expired = self.policy.is_expired(key);
+            if expired {
+                self.remove(key);
+            }
+            expired
+        }
+    }
+
+    impl<K: fmt::Debug, V: fmt::Debug, P: Policy<K>> fmt::Debug for Cache<K, V, P> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_map().entries(&self.entries).finish()
+        }
+    }
+}
+
+/// # String Interner
+///
+/// Each distinct string is stored once and named by a `Symbol`, a `u32` index,
+/// so comparing or hashing names is comparing integers and the strings are only looked at to print them.
+/// See [here](https://en.wikipedia.org/wiki/String_interning) for more details.
+pub mod interner {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::hash::{BuildHasherDefault, DefaultHasher, Hash, Hasher};
+
+    /// An interned string, only meaningful to the [`Interner`] that made it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Symbol(u32);
+
+    impl Symbol {
+        /// Used by `static_symbols!`, the symbols are numbered in the order they are interned.
+        #[doc(hidden)]
+        pub const fn __from_index(index: u32) -> Self {
+            Symbol(index)
+        }
+
+        pub const fn index(self) -> usize {
+            self.0 as usize
+        }
+    }
+
+    /// A string with its hash computed once, to look it up in any number of interners.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Prehashed<'a> {
+        string: &'a str,
+        hash: u64,
+    }
+
+    impl<'a> Prehashed<'a> {
+        pub fn new(string: &'a str) -> Self {
+            let mut hasher = DefaultHasher::new();
+            string.hash(&mut hasher);
+            Prehashed { string, hash: hasher.finish() }
+        }
+
+        pub fn hash(&self) -> u64 {
+            self.hash
+        }
+    }
+
+    /// The buckets are keyed by the hash already, so hashing them again would be wasted.
+    #[derive(Default)]
+    struct IdentityHasher(u64);
+
+    impl Hasher for IdentityHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            bytes.iter().for_each(|&byte| self.0 = self.0.rotate_left(8) ^ u64::from(byte));
+        }
+
+        fn write_u64(&mut self, hash: u64) {
+            self.0 = hash;
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    /// The strings, and the symbols of each string hash.
+    ///
+    /// ```
+    /// use awesome::interner::Interner;
+    ///
+    /// let mut interner = Interner::new();
+    /// let a = interner.intern("apple");
+    /// let b = interner.intern("banana");
+    /// assert_eq!(interner.intern("apple"), a);
+    /// assert_ne!(a, b);
+    /// assert_eq!(interner.resolve(b), "banana");
+    /// assert_eq!(interner.get("cherry"), None);
+    /// assert_eq!(interner.len(), 2);
+    /// ```
+    #[derive(Default)]
+    pub struct Interner {
+        strings: Vec<Box<str>>,
+        buckets: HashMap<u64, Vec<Symbol>, BuildHasherDefault<IdentityHasher>>,
+    }
+
+    impl Interner {
+        pub fn new() -> Self {
+            Interner::default()
+        }
+
+        /// An interner where `strings[i]` is the symbol `i`, for `static_symbols!`.
+        pub fn with_symbols(strings: &[&str]) -> Self {
+            let mut interner = Interner::new();
+            for (index, string) in strings.iter().enumerate() {
+                assert_eq!(interner.intern(string).index(), index, "{string:?} is in the symbols twice");
+            }
+            interner
+        }
+
+        pub fn intern(&mut self, string: &str) -> Symbol {
+            self.intern_prehashed(Prehashed::new(string))
+        }
+
+        pub fn intern_prehashed(&mut self, string: Prehashed<'_>) -> Symbol {
+            if let Some(symbol) = self.get_prehashed(string) {
+                return symbol;
+            }
+            let symbol = Symbol(u32::try_from(self.strings.len()).expect("more than u32::MAX symbols"));
+            self.strings.push(string.string.into());
+            self.buckets.entry(string.hash).or_default().push(symbol);
+            symbol
+        }
+
+        /// The symbol of `string` if it was interned, without interning it.
+        pub fn get(&self, string: &str) -> Option<Symbol> {
+            self.get_prehashed(Prehashed::new(string))
+        }
+
+        pub fn get_prehashed(&self, string: Prehashed<'_>) -> Option<Symbol> {
+            let bucket = self.buckets.get(&string.hash)?;
+            bucket.iter().copied().find(|symbol| *self.strings[symbol.index()] == *string.string)
+        }
+
+        /// The string of `symbol`, panics if it comes from another interner with more strings.
+        pub fn resolve(&self, symbol: Symbol) -> &str {
+            &self.strings[symbol.index()]
+        }
+
+        pub fn len(&self) -> usize {
+            self.strings.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.strings.is_empty()
+        }
+
+        /// Every symbol with its string, in the order they were interned.
+        pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+            self.strings.iter().enumerate().map(|(index, string)| (Symbol(index as u32), &**string))
+        }
+    }
+
+    impl fmt::Debug for Interner {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.strings.iter()).finish()
+        }
+    }
+
+    /// Declares a module of well-known symbols as constants, with `interner()` making an interner they belong to.
+    ///
+    /// the constants are numbered by a hidden `enum`, so they are known at compile time and can be matched on.
+    ///
+    /// ```
+    /// use awesome::interner::Symbol;
+    /// use awesome::static_symbols;
+    ///
+    /// static_symbols! {
+    ///     pub mod keywords {
+    ///         LET = "let",
+    ///         IN = "in",
+    ///         IF = "if",
+    ///     }
+    /// }
+    ///
+    /// let mut interner = keywords::interner();
+    /// assert_eq!(interner.intern("in"), keywords::IN);
+    /// assert_eq!(interner.resolve(keywords::IF), "if");
+    ///
+    /// let describe = |symbol: Symbol| match symbol {
+    ///     keywords::LET | keywords::IN | keywords::IF => "keyword",
+    ///     _ => "identifier",
+    /// };
+    /// assert_eq!(describe(interner.intern("x")), "identifier");
+    /// assert_eq!(describe(interner.intern("let")), "keyword");
+    /// ```
+    #[macro_export]
+    macro_rules! static_symbols {
+        ($(#[$meta:meta])* $vis:vis mod $name:ident { $($symbol:ident = $string:literal),* $(,)? }) => {
+            $(#[$meta])*
+            $vis mod $name {
+                // only ever cast to its discriminant.
+                #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+                #[repr(u32)]
+                enum Index {
+                    $($symbol,)*
+                }
+
+                $(pub const $symbol: $crate::interner::Symbol = $crate::interner::Symbol::__from_index(Index::$symbol as u32);)*
+
+                /// The strings, in the order of their symbols.
+                pub const STRINGS: &[&str] = &[$($string),*];
+
+                /// An interner already holding the symbols of this module.
+                pub fn interner() -> $crate::interner::Interner {
+                    $crate::interner::Interner::with_symbols(STRINGS)
+                }
+            }
+        };
+    }
+}
+
+/// # Arena
+///
+/// Allocates values that all live as long as the arena and are freed together with it,
+/// which suits trees like ASTs: nodes point at each other with plain references, with no `Box` or `Rc` per node.
+/// `Arena<T>` is for one type and runs the destructors, `Bump` holds any `Copy` type in raw byte chunks.
+/// See [here](https://en.wikipedia.org/wiki/Region-based_memory_management) for more details.
+pub mod arena {
+    use std::alloc::{self, Layout};
+    use std::cell::{Cell, RefCell};
+    use std::fmt;
+    use std::mem;
+    use std::ptr::{self, NonNull};
+
+    /// How much an arena holds.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Stats {
+        /// Values in an `Arena`, bytes in a `Bump`, padding included.
+        pub allocated: usize,
+        pub chunks: usize,
+        /// Room in all the chunks, in the same unit as `allocated`.
+        pub capacity: usize,
+    }
+
+    struct Chunks<T> {
+        /// Only pushed to while there is room, so the values never move.
+        current: Vec<T>,
+        full: Vec<Vec<T>>,
+    }
+
+    impl<T> Chunks<T> {
+        /// Starts a new chunk with room for at least `additional` values.
+        fn grow(&mut self, additional: usize) {
+            let capacity = (self.current.capacity() * 2).max(additional);
+            let full = mem::replace(&mut self.current, Vec::with_capacity(capacity));
+            self.full.push(full);
+        }
+    }
+
+    /// A typed arena, the values are dropped with it.
+    ///
+    /// ```
+    /// use awesome::arena::Arena;
+    ///
+    /// struct Person<'a> {
+    ///     name: &'a str,
+    ///     friend: Option<&'a Person<'a>>,
+    /// }
+    ///
+    /// let arena = Arena::new();
+    /// let alice = arena.alloc(Person { name: "alice", friend: None });
+    /// let bob = arena.alloc(Person { name: "bob", friend: Some(alice) });
+    /// assert_eq!(bob.friend.map(|friend| friend.name), Some("alice"));
+    ///
+    /// let numbers = Arena::new();
+    /// let digits = numbers.alloc_iter(0..10);
+    /// digits.reverse();
+    /// assert_eq!(digits[0], 9);
+    /// assert_eq!(numbers.len(), 10);
+    /// ```
+    pub struct Arena<T> {
+        chunks: RefCell<Chunks<T>>,
+    }
+
+    impl<T> Default for Arena<T> {
+        fn default() -> Self {
+            Arena::new()
+        }
+    }
+
+    // the `&mut` results of `&self` are sound, every allocation is a fresh place that no other reference points to.
+    impl<T> Arena<T> {
+        pub fn new() -> Self {
+            Arena::with_capacity(8)
+        }
+
+        pub fn with_capacity(capacity: usize) -> Self {
+            Arena {
+                chunks: RefCell::new(Chunks {
+                    current: Vec::with_capacity(capacity.max(1)),
+                    full: Vec::new(),
+                }),
+            }
+        }
+
+        #[allow(clippy::mut_from_ref)]
+        pub fn alloc(&self, value: T) -> &mut T {
+            let mut chunks = self.chunks.borrow_mut();
+            if chunks.current.len() == chunks.current.capacity() {
+                chunks.grow(1);
+            }
+            chunks.current.push(value);
+            let index = chunks.current.len() - 1;
+            // SAFETY: the value is never moved, `current` only grows within its capacity and then is moved to `full`,
+            // which moves the `Vec` but not its buffer. the buffer lives as long as `self`, and `index` is handed out once.
+            unsafe { &mut *chunks.current.as_mut_ptr().add(index) }
+        }
+
+        /// Allocates the values next to each other.
+        #[allow(clippy::mut_from_ref)]
+        pub fn alloc_iter(&self, values: impl IntoIterator<Item = T>) -> &mut [T] {
+            // collected first, the iterator could allocate in this arena too.
+            let mut values: Vec<T> = values.into_iter().collect();
+            let mut chunks = self.chunks.borrow_mut();
+            if chunks.current.capacity() - chunks.current.len() < values.len() {
+                chunks.grow(values.len());
+            }
+            let start = chunks.current.len();
+            chunks.current.append(&mut values);
+            let len = chunks.current.len() - start;
+            // SAFETY: as in `alloc`, the range is within the initialized length and not handed out before.
+            unsafe { std::slice::from_raw_parts_mut(chunks.current.as_mut_ptr().add(start), len) }
+        }
+
+        pub fn len(&self) -> usize {
+            let chunks = self.chunks.borrow();
+            chunks.current.len() + chunks.full.iter().map(Vec::len).sum::<usize>()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn stats(&self) -> Stats {
+            let chunks = self.chunks.borrow();
+            Stats {
+                allocated: self.len(),
+                chunks: chunks.full.len() + 1,
+                capacity: chunks.current.capacity() + chunks.full.iter().map(Vec::capacity).sum::<usize>(),
+            }
+        }
+
+        /// The values, in the order they were allocated.
+        pub fn into_vec(self) -> Vec<T> {
+            let Chunks { current, full } = self.chunks.into_inner();
+            full.into_iter().flatten().chain(current).collect()
+        }
+    }
+
+    impl<T> fmt::Debug for Arena<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Arena").field("stats", &self.stats()).finish()
+        }
+    }
+
+    /// A bump allocator for values of any `Copy` type, which never need dropping.
+    ///
+    /// ```
+    /// use awesome::arena::Bump;
+    ///
+    /// let bump = Bump::new();
+    /// let n = bump.alloc(7u64);
+    /// let name = bump.alloc_str("ferris");
+    /// let pair = bump.alloc((1u8, 'x'));
+    /// *n += 1;
+    /// name.make_ascii_uppercase();
+    ///
+    /// assert_eq!((*n, &*name, *pair), (8, "FERRIS", (1, 'x')));
+    /// assert_eq!(bump.stats().chunks, 1);
+    /// ```
+    pub struct Bump {
+        chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+        /// The free part of the newest chunk.
+        next: Cell<*mut u8>,
+        end: Cell<*mut u8>,
+        allocated: Cell<usize>,
+    }
+
+    impl Default for Bump {
+        fn default() -> Self {
+            Bump::new()
+        }
+    }
+
+    impl Bump {
+        const CHUNK: usize = 1024;
+
+        pub fn new() -> Self {
+            Bump {
+                chunks: RefCell::new(Vec::new()),
+                next: Cell::new(ptr::null_mut()),
+                end: Cell::new(ptr::null_mut()),
+                allocated: Cell::new(0),
+            }
+        }
+
+        #[allow(clippy::mut_from_ref)]
+        pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+            let slot = self.alloc_layout(Layout::new::<T>()).cast::<T>();
+            // SAFETY: `slot` is aligned, has room for a `T` and is never handed out again.
+            unsafe {
+                slot.as_ptr().write(value);
+                &mut *slot.as_ptr()
+            }
+        }
+
+        #[allow(clippy::mut_from_ref)]
+        pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+            let layout = Layout::array::<T>(values.len()).expect("the slice is too large");
+            let slots = self.alloc_layout(layout).cast::<T>();
+            // SAFETY: as in `alloc`, with room for `values.len()` values which can't overlap `values`.
+            unsafe {
+                ptr::copy_nonoverlapping(values.as_ptr(), slots.as_ptr(), values.len());
+                std::slice::from_raw_parts_mut(slots.as_ptr(), values.len())
+            }
+        }
+
+        #[allow(clippy::mut_from_ref)]
+        pub fn alloc_str(&self, s: &str) -> &mut str {
+            let bytes = self.alloc_slice_copy(s.as_bytes());
+            // SAFETY: the bytes were copied from a `str`.
+            unsafe { std::str::from_utf8_unchecked_mut(bytes) }
+        }
+
+        pub fn stats(&self) -> Stats {
+            let chunks = self.chunks.borrow();
+            Stats {
+                allocated: self.allocated.get(),
+                chunks: chunks.len(),
+                capacity: chunks.iter().map(|(_, layout)| layout.size()).sum(),
+            }
+        }
+
+        fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+            if layout.size() == 0 {
+                // any aligned pointer is fine for nothing, `align` is a power of two so it's never null.
+                return NonNull::new(ptr::without_provenance_mut(layout.align())).expect("alignments are not zero");
+            }
+            let (next, end) = (self.next.get(), self.end.get());
+            let padding = next.align_offset(layout.align());
+            if !next.is_null() && padding.checked_add(layout.size()).is_some_and(|needed| needed <= end as usize - next as usize) {
+                // SAFETY: `padding + size` bytes from `next` are still in the chunk.
+                let start = unsafe { next.add(padding) };
+                self.next.set(unsafe { start.add(layout.size()) });
+                self.allocated.set(self.allocated.get() + padding + layout.size());
+                // SAFETY: a pointer into a chunk isn't null.
+                return unsafe { NonNull::new_unchecked(start) };
+            }
+            self.new_chunk(layout);
+            self.alloc_layout(layout)
+        }
+
+        /// Starts a chunk big enough for `layout`, the rest of the current one is left unused.
+        fn new_chunk(&self, layout: Layout) {
+            let mut chunks = self.chunks.borrow_mut();
+            let last = chunks.last().map_or(Self::CHUNK / 2, |(_, layout)| layout.size());
+            let size = (last * 2).max(layout.size()).next_power_of_two();
+            let chunk_layout = Layout::from_size_align(size, layout.align().max(mem::align_of::<usize>())).expect("the chunk is too large");
+            // SAFETY: the size is not zero.
+            let chunk = NonNull::new(unsafe { alloc::alloc(chunk_layout) }).unwrap_or_else(|| alloc::handle_alloc_error(chunk_layout));
+            chunks.push((chunk, chunk_layout));
+            self.next.set(chunk.as_ptr());
+            // SAFETY: one past the end of the chunk.
+            self.end.set(unsafe { chunk.as_ptr().add(size) });
+        }
+    }
+
+    impl Drop for Bump {
+        fn drop(&mut self) {
+            for (chunk, layout) in self.chunks.get_mut().drain(..) {
+                // SAFETY: allocated in `new_chunk` with this layout, and the values in it are `Copy`, so they need no drop.
+                unsafe { alloc::dealloc(chunk.as_ptr(), layout) }
+            }
+        }
+    }
+
+    impl fmt::Debug for Bump {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Bump").field("stats", &self.stats()).finish()
+        }
+    }
+}
+
+/// # Dependency Injection
+///
+/// A container of services keyed by their `TypeId`, each registered with a factory and a lifetime:
+/// one instance for the container (singleton), one per scope, or a new one every time (transient).
+/// scopes are child containers which see the registrations of their parents and can override them.
+/// See [here](https://en.wikipedia.org/wiki/Dependency_injection) for more details.
+pub mod di {
+    use std::any::{type_name, Any, TypeId};
+    use std::cell::{OnceCell, RefCell};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ResolveError {
+        /// Nothing registered the type, in this container or its parents.
+        Missing(&'static str),
+        /// Resolving the first type needs itself, through the others.
+        Cycle(Vec<&'static str>),
+    }
+
+    impl fmt::Display for ResolveError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ResolveError::Missing(name) => write!(f, "{name} is not registered"),
+                ResolveError::Cycle(names) => write!(f, "dependency cycle: {}", names.join(" -> ")),
+            }
+        }
+    }
+
+    impl std::error::Error for ResolveError {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Lifetime {
+        /// Made once, by the container it is registered in.
+        Singleton,
+        /// Made once per scope, by each scope that resolves it.
+        Scoped,
+        /// Made for every `resolve`.
+        Transient,
+    }
+
+    type Factory = Box<dyn Fn(&Container<'_>) -> Result<Rc<dyn Any>, ResolveError>>;
+
+    struct Registration {
+        name: &'static str,
+        lifetime: Lifetime,
+        factory: Factory,
+        singleton: OnceCell<Rc<dyn Any>>,
+    }
+
+    /// The services, see the module docs.
+    ///
+    /// ```
+    /// use awesome::di::{Container, Lifetime};
+    /// use std::rc::Rc;
+    ///
+    /// struct Config {
+    ///     url: String,
+    /// }
+    ///
+    /// struct Database {
+    ///     config: Rc<Config>,
+    /// }
+    ///
+    /// let mut container = Container::new();
+    /// container.register_instance(Config { url: "postgres://localhost".to_string() });
+    /// container.register(Lifetime::Singleton, |c| Ok(Database { config: c.resolve()? }));
+    ///
+    /// let db = container.resolve::<Database>().unwrap();
+    /// assert_eq!(db.config.url, "postgres://localhost");
+    /// assert!(Rc::ptr_eq(&db, &container.resolve().unwrap()));
+    /// ```
+    #[derive(Default)]
+    pub struct Container<'p> {
+        registrations: HashMap<TypeId, Registration>,
+        parent: Option<&'p Container<'p>>,
+        scoped: RefCell<HashMap<TypeId, Rc<dyn Any>>>,
+        /// The types being made right now, to report cycles instead of overflowing the stack.
+        resolving: RefCell<Vec<(TypeId, &'static str)>>,
+    }
+
+    impl Container<'static> {
+        pub fn new() -> Self {
+            Container::default()
+        }
+    }
+
+    impl<'p> Container<'p> {
+        /// A child container, its registrations come first and its scoped services are its own.
+        pub fn scope(&self) -> Container<'_> {
+            Container {
+                registrations: HashMap::new(),
+                parent: Some(self),
+                scoped: RefCell::default(),
+                resolving: RefCell::default(),
+            }
+        }
+
+        pub fn register<T: 'static>(&mut self, lifetime: Lifetime, factory: impl Fn(&Container<'_>) -> Result<T, ResolveError> + 'static) -> &mut Self {
+            self.insert::<T>(lifetime, Box::new(move |container| Ok(Rc::new(factory(container)?))));
+            self
+        }
+
+        /// A singleton that already exists.
+        pub fn register_instance<T: 'static>(&mut self, value: T) -> &mut Self {
+            let instance: Rc<dyn Any> = Rc::new(value);
+            self.insert::<T>(Lifetime::Singleton, Box::new(move |_| Ok(instance.clone())));
+            self
+        }
+
+        /// `T` made by its [`FromContainer`] impl.
+        pub fn register_type<T: FromContainer + 'static>(&mut self, lifetime: Lifetime) -> &mut Self {
+            self.register(lifetime, T::from_container)
+        }
+
+        pub fn is_registered<T: 'static>(&self) -> bool {
+            self.find(TypeId::of::<T>()).is_some()
+        }
+
+        pub fn resolve<T: 'static>(&self) -> Result<Rc<T>, ResolveError> {
+            let instance = self.resolve_any(TypeId::of::<T>(), type_name::<T>())?;
+            Ok(instance.downcast::<T>().expect("registrations are keyed by their type"))
+        }
+
+        fn insert<T: 'static>(&mut self, lifetime: Lifetime, factory: Factory) {
+            let registration = Registration {
+                name: type_name::<T>(),
+                lifetime,
+                factory,
+                singleton: OnceCell::new(),
+            };
+            self.registrations.insert(TypeId::of::<T>(), registration);
+        }
+
+        /// The registration of `id` with the container it belongs to.
+        fn find(&self, id: TypeId) -> Option<(&Container<'p>, &Registration)> {
+            match self.registrations.get(&id) {
+                Some(registration) => Some((self, registration)),
+                None => self.parent.and_then(|parent| parent.find(id)),
+            }
+        }
+
+        fn resolve_any(&self, id: TypeId, name: &'static str) -> Result<Rc<dyn Any>, ResolveError> {
+            let (owner, registration) = self.find(id).ok_or(ResolveError::Missing(name))?;
+            match registration.lifetime {
+                Lifetime::Singleton => match registration.singleton.get() {
+                    Some(instance) => Ok(instance.clone()),
+                    // made by the container it is registered in, so it can't depend on anything from a scope.
+                    None => {
+                        let instance = owner.make(id, registration)?;
+                        Ok(registration.singleton.get_or_init(|| instance).clone())
+                    }
+                },
+                Lifetime::Scoped => {
+                    if let Some(instance) = self.scoped.borrow().get(&id) {
+                        return Ok(instance.clone());
+                    }
+                    let instance = self.make(id, registration)?;
+                    Ok(self.scoped.borrow_mut().entry(id).or_insert(instance).clone())
+                }
+                Lifetime::Transient => self.make(id, registration),
+            }
+        }
+
+        fn make(&self, id: TypeId, registration: &Registration) -> Result<Rc<dyn Any>, ResolveError> {
+            let mut resolving = self.resolving.borrow_mut();
+            if let Some(start) = resolving.iter().position(|&(other, _)| other == id) {
+                let mut cycle: Vec<_> = resolving[start..].iter().map(|&(_, name)| name).collect();
+                cycle.push(registration.name);
+                return Err(ResolveError::Cycle(cycle));
+            }
+            resolving.push((id, registration.name));
+            drop(resolving);
+            let instance = (registration.factory)(self);
+            self.resolving.borrow_mut().pop();
+            instance
+        }
+    }
+
+    impl fmt::Debug for Container<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut names: Vec<_> = self.registrations.values().map(|registration| (registration.name, registration.lifetime)).collect();
+            names.sort_by_key(|&(name, _)| name);
+            f.debug_struct("Container")
+                .field("registrations", &names)
+                .field("parent", &self.parent)
+                .finish_non_exhaustive()
+        }
+    }
+
+    /// Constructor injection, usually implemented with `injectable!`.
+    pub trait FromContainer: Sized {
+        fn from_container(container: &Container<'_>) -> Result<Self, ResolveError>;
+    }
+
+    /// A field that `injectable!` can fill in.
+    pub trait Inject: Sized {
+        fn inject(container: &Container<'_>) -> Result<Self, ResolveError>;
+    }
+
+    impl<T: 'static> Inject for Rc<T> {
+        fn inject(container: &Container<'_>) -> Result<Self, ResolveError> {
+            container.resolve()
+        }
+    }
+
+    /// `None` if `T` isn't registered, the other errors still fail.
+    impl<T: 'static> Inject for Option<Rc<T>> {
+        fn inject(container: &Container<'_>) -> Result<Self, ResolveError> {
+            match container.resolve() {
+                Ok(value) => Ok(Some(value)),
+                Err(ResolveError::Missing(_)) => Ok(None),
+                Err(error) => Err(error),
+            }
+        }
+    }
+
+    /// Declares a struct and implements [`FromContainer`] for it, resolving each field with [`Inject`].
+    ///
+    /// ```
+    /// use awesome::di::{Container, FromContainer, Lifetime};
+    /// use awesome::injectable;
+    /// use std::rc::Rc;
+    ///
+    /// struct Clock;
+    /// struct Logger;
+    ///
+    /// injectable! {
+    ///     pub struct Service {
+    ///         clock: Rc<Clock>,
+    ///         logger: Option<Rc<Logger>>,
+    ///     }
+    /// }
+    ///
+    /// let mut container = Container::new();
+    /// container.register_instance(Clock).register_type::<Service>(Lifetime::Transient);
+    ///
+    /// let service = container.resolve::<Service>().unwrap();
+    /// assert!(service.logger.is_none());
+    /// ```
+    #[macro_export]
+    macro_rules! injectable {
+        ($(#[$meta:meta])* $vis:vis struct $name:ident { $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)? }) => {
+            $(#[$meta])*
+            $vis struct $name {
+                $($(#[$field_meta])* $field_vis $field: $ty,)*
+            }
+
+            impl $crate::di::FromContainer for $name {
+                fn from_container(container: &$crate::di::Container<'_>) -> ::std::result::Result<Self, $crate::di::ResolveError> {
+                    ::std::result::Result::Ok($name {
+                        $($field: <$ty as $crate::di::Inject>::inject(container)?,)*
+                    })
+                }
+            }
+        };
+    }
+}
+
+/// # Plugin Registry
+///
+/// Plugins are registered by name without `linkme` or life-before-main: `register_plugin!` attaches a `Factory` constant
+/// to each plugin type, and `plugins!` aggregates them into a `Registry` explicitly, usually inside a `LazyLock` static.
+/// See [here](https://en.wikipedia.org/wiki/Plug-in_(computing)) for more details.
+pub mod plugin {
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    pub trait Plugin {
+        fn execute(&self, input: &str) -> String;
+    }
+
+    /// Makes a plugin, with what the registry lists about it.
+    #[derive(Clone, Copy)]
+    pub struct Factory {
+        pub name: &'static str,
+        pub description: &'static str,
+        create: fn() -> Box<dyn Plugin>,
+    }
+
+    impl Factory {
+        pub const fn new(name: &'static str, description: &'static str, create: fn() -> Box<dyn Plugin>) -> Self {
+            Factory { name, description, create }
+        }
+
+        pub fn create(&self) -> Box<dyn Plugin> {
+            (self.create)()
+        }
+    }
+
+    impl fmt::Debug for Factory {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Factory")
+                .field("name", &self.name)
+                .field("description", &self.description)
+                .finish_non_exhaustive()
+        }
+    }
+
+    /// Implemented by `register_plugin!`.
+    pub trait Registered: Plugin {
+        const FACTORY: Factory;
+    }
+
+    /// Two plugins have the same name.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DuplicatePlugin(pub &'static str);
+
+    impl fmt::Display for DuplicatePlugin {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "the plugin {:?} is registered twice", self.0)
+        }
+    }
+
+    impl std::error::Error for DuplicatePlugin {}
+
+    /// The factories by name.
+    #[derive(Debug, Default, Clone)]
+    pub struct Registry {
+        factories: BTreeMap<&'static str, Factory>,
+    }
+
+    impl Registry {
+        pub fn new() -> Self {
+            Registry::default()
+        }
+
+        pub fn register<P: Registered>(&mut self) -> Result<&mut Self, DuplicatePlugin> {
+            self.register_factory(P::FACTORY)
+        }
+
+        pub fn register_factory(&mut self, factory: Factory) -> Result<&mut Self, DuplicatePlugin> {
+            if self.factories.contains_key(factory.name) {
+                return Err(DuplicatePlugin(factory.name));
+            }
+            self.factories.insert(factory.name, factory);
+            Ok(self)
+        }
+
+        pub fn get(&self, name: &str) -> Option<&Factory> {
+            self.factories.get(name)
+        }
+
+        /// A new instance of the plugin `name`.
+        pub fn create(&self, name: &str) -> Option<Box<dyn Plugin>> {
+            self.get(name).map(Factory::create)
+        }
+
+        /// The factories, ordered by name.
+        pub fn iter(&self) -> impl Iterator<Item = &Factory> {
+            self.factories.values()
+        }
+
+        pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+            self.factories.keys().copied()
+        }
+
+        pub fn len(&self) -> usize {
+            self.factories.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.factories.is_empty()
+        }
+    }
+
+    impl<'a> IntoIterator for &'a Registry {
+        type Item = &'a Factory;
+        type IntoIter = std::collections::btree_map::Values<'a, &'static str, Factory>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.factories.values()
+        }
+    }
+
+    /// Registers a `Default + Plugin` type under a name: `register_plugin!(Type, "name", "description")`.
+    ///
+    /// ```
+    /// use awesome::plugin::{Plugin, Registered};
+    /// use awesome::register_plugin;
+    ///
+    /// #[derive(Default)]
+    /// struct Shout;
+    ///
+    /// impl Plugin for Shout {
+    ///     fn execute(&self, input: &str) -> String {
+    ///         format!("{input}!")
+    ///     }
+    /// }
+    ///
+    /// register_plugin!(Shout, "shout", "adds an exclamation mark");
+    ///
+    /// assert_eq!(Shout::FACTORY.name, "shout");
+    /// assert_eq!(Shout::FACTORY.create().execute("hi"), "hi!");
+    /// ```
+    #[macro_export]
+    macro_rules! register_plugin {
+        ($plugin:ty, $name:expr, $description:expr $(,)?) => {
+            impl $crate::plugin::Registered for $plugin {
+                const FACTORY: $crate::plugin::Factory =
+                    $crate::plugin::Factory::new($name, $description, || ::std::boxed::Box::new(<$plugin as ::std::default::Default>::default()));
+            }
+        };
+    }
+
+    /// A [`Registry`] of the registered plugin types, panics if two have the same name.
+    ///
+    /// ```
+    /// use awesome::plugin::samples::{Uppercase, WordCount};
+    /// use awesome::plugin::Registry;
+    /// use awesome::plugins;
+    /// use std::sync::LazyLock;
+    ///
+    /// // made on first use, no code runs before `main`.
+    /// static PLUGINS: LazyLock<Registry> = LazyLock::new(|| plugins![Uppercase, WordCount]);
+    ///
+    /// assert_eq!(PLUGINS.names().collect::<Vec<_>>(), ["uppercase", "word-count"]);
+    /// assert_eq!(PLUGINS.create("uppercase").unwrap().execute("abc"), "ABC");
+    /// ```
+    #[macro_export]
+    macro_rules! plugins {
+        ($($plugin:ty),* $(,)?) => {{
+            let mut registry = $crate::plugin::Registry::new();
+            $(
+                if let ::std::result::Result::Err(error) = registry.register::<$plugin>() {
+                    ::std::panic!("{}", error);
+                }
+            )*
+            registry
+        }};
+    }
+
+    /// Two plugins to try the registry with.
+    pub mod samples {
+        use super::{Plugin, Registry};
+        use std::sync::LazyLock;
+
+        #[derive(Debug, Default)]
+        pub struct Uppercase;
+
+        impl Plugin for Uppercase {
+            fn execute(&self, input: &str) -> String {
+                input.to_uppercase()
+            }
+        }
+
+        crate::register_plugin!(Uppercase, "uppercase", "converts the input to upper case");
+
+        #[derive(Debug, Default)]
+        pub struct WordCount;
+
+        impl Plugin for WordCount {
+            fn execute(&self, input: &str) -> String {
+                input.split_whitespace().count().to_string()
+            }
+        }
+
+        crate::register_plugin!(WordCount, "word-count", "counts the words of the input");
+
+        /// Both samples.
+        pub static BUILTIN: LazyLock<Registry> = LazyLock::new(|| crate::plugins![Uppercase, WordCount]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overloading_test() {
+        assert_eq!(overloading::foo(10), 100);
+        assert_eq!(overloading::foo("hello".to_string()), "hello!");
+    }
+
+    #[test]
+    fn overloading_ref_test() {
+        use crate::overloading::*;
+        use std::borrow::Cow;
+
+        let owned = "hello".to_string();
+
+        assert_eq!(foo("hello"), "hello!");
+        assert_eq!(foo(&owned), "hello!");
+        assert_eq!(foo(&10usize), 100);
+        assert_eq!(owned, "hello");
+
+        let mut buffer = "hi".to_string();
+        foo(&mut buffer).push('?');
+
+        assert_eq!(buffer, "hi!?");
+
+        let words = vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!(foo(words.as_slice()), vec!["a!", "b!"]);
+        assert_eq!(foo(&["c", "d"][..]), vec!["c!", "d!"]);
+        assert_eq!(words.len(), 2);
+
+        assert_eq!(foo_as_ref(&owned), "hello!");
+        assert_eq!(foo_as_ref("str"), "str!");
+        assert_eq!(foo_as_ref(&Box::<str>::from("box")), "box!");
+        assert_eq!(foo_as_ref(&Cow::Borrowed("cow")), "cow!");
+    }
+
+    #[test]
+    fn foo_or_default_test() {
+        // specific overloads
+        assert_eq!(foo_or_default!(10usize), 100);
+        assert_eq!(foo_or_default!("hi".to_string()), "hi!");
+        assert_eq!(foo_or_default!((1usize, 2usize)), (10, 20));
+
+        assert_eq!(foo_or_default!(-3i32), -30);
+
+        // `Display` fallback
+        assert_eq!(foo_or_default!('c'), "<c>");
+        assert_eq!(foo_or_default!(true), "<true>");
+    }
+
+    #[test]
+    fn overload_macro_test() {
+        overload! {
+            fn describe(x: i32) -> String {
+                format!("int {x}")
+            }
+            fn describe(s: &str) -> String {
+                format!("str {s}")
+            }
+            fn describe((a, b): (i32, i32)) -> i32 {
+                a + b
+            }
+        }
+
+        assert_eq!(describe(1), "int 1");
+        assert_eq!(describe("a"), "str a");
+        assert_eq!(describe((1, 2)), 3);
+    }
+
+    #[test]
+    fn overload_attribute_test() {
+        use crate::overloading::overload;
+
+        overload_set!(render);
+
+        #[overload(name = "render")]
+        fn render_number(x: i32) -> String {
+            format!("#{x}")
+        }
+
+        #[overload(name = "render")]
+        fn render_str(s: &str) -> String {
+            format!("'{s}'")
+        }
+
+        #[overload(name = "render")]
+        fn render_pair(a: i32, b: bool) -> (String, bool) {
+            (render_number(a), !b)
+        }
+
+        #[overload(name = "render")]
+        fn render_list<T: ToString>(items: Vec<T>) -> String {
+            items.iter().map(T::to_string).collect::<Vec<_>>().join(", ")
+        }
+
+        assert_eq!(render(7), "#7");
+        assert_eq!(render("x"), "'x'");
+        assert_eq!(render((1, true)), ("#1".to_string(), false));
+        assert_eq!(render(vec![1, 2, 3]), "1, 2, 3");
+        assert_eq!(render_str("y"), "'y'");
+    }
+
+    #[test]
+    fn overloading_new_test() {
+        use crate::overloading::{MyPoint, New};
+
+        assert_eq!(MyPoint::new(()), MyPoint { x: 0, y: 0 });
+        assert_eq!(MyPoint::new(3), MyPoint { x: 3, y: 3 });
+        assert_eq!(MyPoint::new((3, 4)), MyPoint { x: 3, y: 4 });
+        assert_eq!(MyPoint::new([5, 6]), MyPoint { x: 5, y: 6 });
+
+        // the trait can be used generically
+        fn make<T>(arg: T) -> MyPoint
+        where
+            MyPoint: New<T>,
+        {
+            <MyPoint as New<T>>::new(arg)
+        }
+        assert_eq!(make((1, 2)), MyPoint::new((1, 2)));
+    }
+
+    #[test]
+    fn overloading_args_test() {
+        use crate::overloading::*;
+
+        assert_eq!(foo(FooArgs::default()), "1x1");
+        assert_eq!(foo(FooArgs::default().width(3)), "3x1");
+        assert_eq!(foo(args!(width = 3, height = 5, label = "rect")), "rect: 3x5");
+        assert_eq!(args!(label = String::from("a"),), FooArgs::default().label("a"));
+
+        // positional overloads still work next to it
+        assert_eq!(foo(3usize), 30);
+        assert_eq!(foo((args!(width = 2), "b")), ("2x1".to_string(), "b!".to_string()));
+    }
+
+    #[test]
+    fn overloading_number_test() {
+        use crate::overloading::*;
+
+        assert_eq!(foo(3u8), 30u8);
+        assert_eq!(foo(-4i64), -40i64);
+        assert_eq!(foo(1.5f64), 15.0);
+        assert_eq!(foo(2.5f32), 25.0f32);
+
+        // the blanket overload and the concrete one live together
+        assert_eq!(foo("a".to_string()), "a!");
+        assert_eq!(foo((1u32, "b".to_string(), 0.5f64)), (10u32, "b!".to_string(), 5.0));
+
+        fn generic<T: Number>(x: T) -> T {
+            foo(x)
+        }
+
+        assert_eq!(generic(7u16), 70);
+    }
+
+    #[test]
+    fn from_foo_test() {
+        use crate::overloading::*;
+
+        let x: Option<usize> = from_foo("42");
+        let y: Result<f64, _> = from_foo("42");
+        let z: String = from_foo("42");
+
+        assert_eq!((x, y, z), (Some(42), Ok(42.0), "42".to_string()));
+        assert_eq!(from_foo::<Result<i64, _>>(" -7 "), Ok(-7));
+        assert_eq!(from_foo::<Vec<char>>("ab"), vec!['a', 'b']);
+        assert_eq!(from_foo::<Option<usize>>("12"), Some(12));
+        assert_eq!(from_foo::<Option<usize>>("twelve"), None);
+        assert_eq!(from_foo::<Option<bool>>("true"), Some(true));
+        // an invalid number is an error, not a panic.
+        let error: Result<usize, _> = from_foo("twelve");
+        assert_eq!(error.unwrap_err().to_string(), "invalid digit found in string");
+
+        // the overload is chosen by the parameter type of `sum`
+        fn sum(a: Option<f64>, b: Result<f64, std::num::ParseFloatError>) -> Option<f64> {
+            Some(a? + b.ok()?)
+        }
+
+        assert_eq!(sum(from_foo("1.5"), from_foo("2")), Some(3.5));
+        assert_eq!(sum(from_foo("1.5"), from_foo("two")), None);
+    }
+
+    #[test]
+    fn overloading_tuple_test() {
+        assert_eq!(overloading::foo((1usize, 2usize)), (10, 20));
+        assert_eq!(overloading::foo((1usize, "a".to_string())), (10, "a!".to_string()));
+        assert_eq!(overloading::foo(("a".to_string(), 2usize, "c".to_string())), ("a!".to_string(), 20, "c!".to_string()));
+        assert_eq!(overloading::foo((1usize, (2usize, 3usize))), (10, (20, 30)));
+        assert_eq!(
+            overloading::foo((1usize, 2usize, 3usize, 4usize, 5usize, 6usize, 7usize, 8usize)),
+            (10, 20, 30, 40, 50, 60, 70, 80)
+        );
+    }
+
+    #[test]
+    fn monoid_test() {
+        use crate::monoid::*;
+
+        assert_eq!(
+            "a".to_string().combine("b".to_string()).combine("c".to_string()),
+            "a".to_string().combine("b".to_string().combine("c".to_string()))
+        );
+        assert_eq!(String::mempty().combine("a".to_string()), "a");
+        assert_eq!(vec![1].combine(Vec::mempty()), vec![1]);
+        assert_eq!(vec![1, 2].combine(vec![3]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn semigroup_test() {
+        use crate::monoid::laws::*;
+        use crate::monoid::*;
+
+        let triples = |xs: &[i32]| -> Vec<(i32, i32, i32)> { xs.iter().flat_map(|&a| xs.iter().flat_map(move |&b| xs.iter().map(move |&c| (a, b, c)))).collect() };
+        let numbers = [-7, -1, 0, 1, 2, 5, 100];
+
+        assert!(check_associativity(triples(&numbers).into_iter().map(|(a, b, c)| (Sum(a), Sum(b), Sum(c)))));
+        assert!(check_associativity(triples(&numbers).into_iter().map(|(a, b, c)| (
+            Product(a),
+            Product(b),
+            Product(c)
+        ))));
+        assert!(check_associativity(triples(&numbers).into_iter().map(|(a, b, c)| (Min(a), Min(b), Min(c)))));
+        assert!(check_associativity(triples(&numbers).into_iter().map(|(a, _, c)| (Some(Max(a)), None, Some(Max(c))))));
+        assert!(check_associativity([("a".to_string(), "b".to_string(), "c".to_string())]));
+
+        assert!(check_identity(numbers.map(Sum)));
+        assert!(check_identity(numbers.map(Product)));
+        assert!(check_identity(numbers.map(Min)));
+        assert!(check_identity(numbers.map(Max)));
+        assert!(check_identity([Min(1.5), Min(f64::MAX)]));
+        assert!(check_identity([None, Some(vec![1]), Some(vec![])]));
+        assert!(check_identity(["", "ab"].map(String::from)));
+
+        assert_eq!(mconcat(["a", "b", "c"].map(String::from)), "abc");
+        assert_eq!(mconcat(numbers.map(Min)), Min(-7));
+        assert_eq!(mconcat(Vec::<Max<u8>>::new()), Max(0));
+        assert_eq!(mconcat([Some(Sum(2)), None, Some(Sum(3))]), Some(Sum(5)));
+        assert_eq!(sconcat(nonempty![Max((1, 'a')), Max((1, 'b')), Max((0, 'c'))]), Max((1, 'b')));
+        assert_eq!(sconcat(nonempty![vec![1], vec![2, 3]]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn functor_test() {
+        use crate::functor::*;
+
+        let (f, g) = (|x: i32| x + 1, |x: i32| x * 2);
+
+        // identity
+        assert_eq!(Some(1).fmap(|x| x), Some(1));
+        assert_eq!(Ok::<i32, String>(1).fmap(|x| x), Ok(1));
+        assert_eq!(vec![1, 2, 3].fmap(|x| x), vec![1, 2, 3]);
+        assert_eq!(Box::new(1).fmap(|x| x), Box::new(1));
+
+        // composition
+        assert_eq!(Some(1).fmap(|x| g(f(x))), Some(1).fmap(f).fmap(g));
+        assert_eq!(None::<i32>.fmap(|x| g(f(x))), None::<i32>.fmap(f).fmap(g));
+        assert_eq!(Ok::<i32, String>(1).fmap(|x| g(f(x))), Ok::<i32, String>(1).fmap(f).fmap(g));
+        assert_eq!(
+            Err::<i32, String>("e".to_string()).fmap(|x| g(f(x))),
+            Err::<i32, String>("e".to_string()).fmap(f).fmap(g)
+        );
+        assert_eq!(vec![1, 2, 3].fmap(|x| g(f(x))), vec![1, 2, 3].fmap(f).fmap(g));
+        assert_eq!(Box::new(1).fmap(|x| g(f(x))), Box::new(1).fmap(f).fmap(g));
+
+        assert_eq!(vec![1, 2, 3].fmap(|x| x.to_string()), vec!["1", "2", "3"]);
+
+        // `fmap` agrees with `bind` + `pure`
+        use crate::{applicative::Applicative, monad::Monad};
+
+        assert_eq!(Some(1).fmap(f), Some(1).bind(|x| Option::pure(f(x))));
+        assert_eq!(Ok::<i32, String>(1).fmap(f), Ok::<i32, String>(1).bind(|x| Result::pure(f(x))));
+    }
+
+    #[test]
+    fn applicative_test() {
+        use crate::applicative::*;
+
+        assert_eq!(Some(2).ap(Some(|x| x * 10)), Some(20));
+        assert_eq!(None::<i32>.ap(Some(|x| x * 10)), None);
+        assert_eq!(Ok::<i32, String>(2).ap(Ok(|x| x * 10)), Ok(20));
+        assert_eq!(Err::<i32, &str>("value").ap(Err::<fn(i32) -> i32, _>("function")), Err("function"));
+        assert_eq!(vec![1, 2].ap(vec![|x| x + 10, |x| x * 10]), vec![11, 12, 10, 20]);
+
+        // identity
+        assert_eq!(Some(1).ap(Option::pure(|x| x)), Some(1));
+        assert_eq!(vec![1, 2, 3].ap(Vec::pure(|x| x)), vec![1, 2, 3]);
+
+        let parse = |s: &str| s.parse::<i32>().map_err(|e| e.to_string());
+
+        assert_eq!(lift2(Some(1), Some(2), |a, b| a + b), Some(3));
+        assert_eq!(lift2(Some(1), None::<i32>, |a, b| a + b), None);
+        assert_eq!(lift2(parse("1"), parse("2"), |a, b| a * b), Ok(2));
+        assert!(lift2(parse("1"), parse("two"), |a, b| a * b).is_err());
+        assert_eq!(lift2(vec![1, 2], vec![10, 20], |a, b| a + b), vec![11, 21, 12, 22]);
+
+        assert_eq!(lift3(Some(1), Some(2), Some(3), |a, b, c| a + b + c), Some(6));
+        assert_eq!(lift3(Some(1), None::<i32>, Some(3), |a, b, c| a + b + c), None);
+        assert_eq!(lift3(parse("1"), parse("2"), parse("3"), |a, b, c| a * b * c), Ok(6));
+        assert_eq!(lift3(vec![1, 2], vec![10, 20], vec![100], |a, b, c| a + b + c), vec![111, 121, 112, 122]);
+    }
+
+    #[test]
+    fn alternative_test() {
+        use crate::{applicative::*, monad::Monad};
+
+        assert_eq!(Some(1).mplus(Some(2)), Some(1));
+        assert_eq!(None.mplus(Some(2)), Some(2));
+        assert_eq!(Option::<i32>::mzero().mplus(None), None);
+        assert_eq!(vec![1, 2].mplus(vec![3]), vec![1, 2, 3]);
+        assert_eq!(Vec::mzero().mplus(vec![1]), vec![1]);
+
+        let even = |x: i32| guard::<Option<()>>(x % 2 == 0).bind(move |_| Some(x));
+
+        assert_eq!(even(4), Some(4));
+        assert_eq!(even(3), None);
+
+        let triples = do_monad! {
+            a <- (1..=20).collect::<Vec<u32>>();
+            b <- (a..=20).collect::<Vec<u32>>();
+            c <- (b..=20).collect::<Vec<u32>>();
+            guard a * a + b * b == c * c;
+            return (a, b, c)
+        };
+
+        assert_eq!(triples, vec![(3, 4, 5), (5, 12, 13), (6, 8, 10), (8, 15, 17), (9, 12, 15), (12, 16, 20)]);
+
+        let positive = |x: i32| {
+            do_monad! {
+                guard x > 0;
+                _ <- Some("checked");
+                return x
+            }
+        };
+
+        assert_eq!(positive(1), Some(1));
+        assert_eq!(positive(-1), None);
+    }
+
+    #[test]
+    fn monad_test() {
+        use crate::monad::*;
+
+        assert_eq!(Some(10).bind(|x| Some(x * 10)), Some(100));
+        assert_eq!(None::<usize>.bind(|x| Some(x * 10)), None);
+
+        let (mul_5, div_10) = (|x: usize| Some(x * 5), |x: usize| Some(x / 10));
+
+        assert_eq!(Some(10).bind(mul_5).bind(div_10), Some(5));
+    }
+
+    #[test]
+    fn monad_laws_test() {
+        use crate::monad::laws::*;
+
+        let half = |x: i32| if x % 2 == 0 { Some(x / 2) } else { None };
+        let options = || (-50..50).map(|x| if x % 7 == 0 { None } else { Some(x) });
+
+        assert!(check_left_identity::<Option<i32>, _>(-50..50, half));
+        assert!(check_right_identity(options()));
+        assert!(check_associativity(options(), half, |x| Some(x.to_string())));
+
+        let parse = |x: i32| if x >= 0 { Ok(x as u32) } else { Err(format!("negative: {x}")) };
+        let results = || (-50..50).map(|x| if x % 5 == 0 { Err(format!("sample: {x}")) } else { Ok(x) });
+
+        assert!(check_left_identity::<Result<i32, String>, _>(-50..50, parse));
+        assert!(check_right_identity(results()));
+        assert!(check_associativity(results(), parse, |x| x.checked_mul(1_000_000).ok_or("overflow".to_string())));
+
+        let vecs = || (0..10).map(|n| (0..n).collect::<Vec<i32>>());
+
+        assert!(check_left_identity::<Vec<i32>, _>(0..10, |x| vec![x; x as usize]));
+        assert!(check_right_identity(vecs()));
+        assert!(check_associativity(vecs(), |x| vec![x, -x], |x| vec![x.to_string(); 2]));
+    }
+
+    #[test]
+    fn monad_result_test() {
+        use crate::monad::*;
+
+        let parse = |s: &str| s.parse::<usize>().map_err(|e| e.to_string());
+        let checked_div = |x: usize| 100usize.checked_div(x).ok_or("division by zero".to_string());
+
+        assert_eq!(parse("4").bind(checked_div), Ok(25));
+        assert_eq!(parse("0").bind(checked_div), Err("division by zero".to_string()));
+        assert!(parse("x").bind(checked_div).is_err());
+
+        let mut called = false;
+        let result = Err::<usize, String>("first".to_string()).bind(|x| {
+            called = true;
+            Ok(x + 1)
+        });
+
+        assert_eq!(result, Err("first".to_string()));
+        assert!(!called);
+    }
+
+    #[test]
+    fn monad_change_type_test() {
+        use crate::monad::*;
+
+        assert_eq!(Some(10).bind(|x| Some(x.to_string())), Some("10".to_string()));
+        assert_eq!(None::<usize>.bind(|x| Some(x.to_string())), None);
+
+        let len = |s: String| Ok::<usize, String>(s.len());
+
+        assert_eq!(Ok::<usize, String>(12345).bind(|x| Ok(x.to_string())).bind(len), Ok(5));
+    }
+
+    #[test]
+    fn monad_vec_test() {
+        use crate::monad::*;
+
+        assert_eq!(vec![1, 2, 3].bind(|x| vec![x; x]), vec![1, 2, 2, 3, 3, 3]);
+        assert_eq!(Vec::<i32>::new().bind(|x| vec![x]), Vec::<i32>::new());
+
+        let pairs = vec![1, 2].bind(|x| vec!['a', 'b'].bind(move |c| vec![(x, c)]));
+
+        assert_eq!(pairs, vec![(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]);
+
+        let triples = do_monad! {
+            a <- (1..=20).collect::<Vec<u32>>();
+            b <- (a..=20).collect::<Vec<u32>>();
+            c <- (b..=20).collect::<Vec<u32>>();
+            if a * a + b * b == c * c { vec![(a, b, c)] } else { vec![] }
+        };
+
+        assert_eq!(triples, vec![(3, 4, 5), (5, 12, 13), (6, 8, 10), (8, 15, 17), (9, 12, 15), (12, 16, 20)]);
+    }
+
+    #[test]
+    fn monad_combinators_test() {
+        use crate::monad::*;
+
+        assert_eq!(join(Some(Some(3))), Some(3));
+        assert_eq!(join(Some(None::<i32>)), None);
+        assert_eq!(join(None::<Option<i32>>), None);
+        assert_eq!(join(Ok::<Result<i32, &str>, &str>(Ok(3))), Ok(3));
+        assert_eq!(join(Ok::<Result<i32, &str>, &str>(Err("inner"))), Err("inner"));
+        assert_eq!(join(vec![vec![1, 2], vec![3]]), vec![1, 2, 3]);
+
+        assert_eq!(Some(1).then(Some("b")), Some("b"));
+        assert_eq!(Some(1).then(None::<&str>), None);
+        assert_eq!(Err::<i32, &str>("first").then(Ok::<&str, &str>("b")), Err("first"));
+        assert_eq!(Ok::<i32, &str>(1).then(Ok::<&str, &str>("b")), Ok("b"));
+        assert_eq!(vec![1, 2].then(vec!['a', 'b']), vec!['a', 'b', 'a', 'b']);
+
+        let parse = |s: &str| s.parse::<i32>().map_err(|e| e.to_string());
+        let reciprocal = |x: i32| if x == 0 { Err("zero".to_string()) } else { Ok(1.0 / x as f64) };
+        let mut parse_reciprocal = kleisli(parse, reciprocal);
+
+        assert_eq!(parse_reciprocal("4"), Ok(0.25));
+        assert_eq!(parse_reciprocal("0"), Err("zero".to_string()));
+        assert!(parse_reciprocal("x").is_err());
+
+        let mut inc_then_half = kleisli(|x: i32| Some(x + 1), |x: i32| if x % 2 == 0 { Some(x / 2) } else { None });
+
+        assert_eq!(inc_then_half(3), Some(2));
+        assert_eq!(inc_then_half(2), None);
+    }
+
+    #[test]
+    fn do_monad_test() {
+        let sum = do_monad! {
+            x <- Some(1);
+            y <- Some(2);
+            return x + y
+        };
+
+        assert_eq!(sum, Some(3));
+
+        let none: Option<i32> = do_monad! {
+            x <- Some(1);
+            y <- None::<i32>;
+            return x + y
+        };
+
+        assert_eq!(none, None);
+
+        let parse = |s: &str| s.parse::<i32>().map_err(|e| e.to_string());
+
+        let result = do_monad! {
+            x <- parse("10");
+            let doubled = x * 2;
+            y <- parse("5");
+            return (doubled + y).to_string()
+        };
+
+        assert_eq!(result, Ok("25".to_string()));
+
+        let error = do_monad! {
+            x <- parse("10");
+            y <- parse("five");
+            Ok(x + y)
+        };
+
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn state_test() {
+        use crate::monad::State;
+
+        // returns the current counter and increments it
+        let tick = || State::get().bind(|n: usize| State::put(n + 1).bind(move |_| State::pure(n)));
+
+        let program = tick().bind(move |a| tick().bind(move |b| State::modify(|n| n * 10).bind(move |_| tick().bind(move |c| State::pure(vec![a, b, c])))));
+
+        assert_eq!(program.run_state(0), (vec![0, 1, 20], 21));
+        assert_eq!(State::<usize, &str>::pure("value").run_state(7), ("value", 7));
+    }
+
+    #[test]
+    fn writer_test() {
+        use crate::monad::*;
+
+        let half = |x: i32| Writer::new(x / 2, format!("half of {x}; "));
+        let add = |y: i32| move |x: i32| Writer::new(x + y, format!("{x} plus {y}; "));
+
+        assert_eq!(half(10).bind(add(3)).bind(half).run_writer(), (4, "half of 10; 5 plus 3; half of 8; ".to_string()));
+
+        let logged = do_monad! {
+            _x <- Writer::tell(vec!["begin"]);
+            x <- Writer::new(1, vec!["one"]);
+            y <- Writer::new(2, vec!["two"]);
+            return x + y
+        };
+
+        assert_eq!(logged.run_writer(), (3, vec!["begin", "one", "two"]));
+    }
+
+    #[test]
+    fn reader_test() {
+        use crate::monad::Reader;
+
+        #[derive(Clone)]
+        struct Config {
+            name: String,
+            verbose: bool,
+        }
+
+        // none of these functions takes the config as a parameter
+        fn greeting() -> Reader<Config, String> {
+            Reader::asks(|c: &Config| format!("hello, {}", c.name))
+        }
+
+        fn suffix() -> Reader<Config, &'static str> {
+            Reader::asks(|c: &Config| if c.verbose { "!!!" } else { "." })
+        }
+
+        fn message() -> Reader<Config, String> {
+            greeting().bind(|g| suffix().bind(move |s| Reader::pure(g + s)))
+        }
+
+        let config = Config {
+            name: "rust".to_string(),
+            verbose: false,
+        };
+
+        assert_eq!(message().run_reader(&config), "hello, rust.");
+        assert_eq!(message().local(|c| Config { verbose: true, ..c.clone() }).run_reader(&config), "hello, rust!!!");
+        assert_eq!(Reader::ask().bind(|c: Config| Reader::pure(c.name.len())).run_reader(&config), 4);
+    }
+
+    #[test]
+    fn cont_test() {
+        use crate::monad::Cont;
+        use std::{cell::Cell, rc::Rc};
+
+        let square = |x: i32| Cont::pure(x * x);
+
+        assert_eq!(Cont::pure(3).bind(square).bind(move |x| Cont::pure(x + 1)).run_cont(|x| x * 2), 20);
+
+        // early exit: the rest of the computation inside `call_cc` is never run
+        let reached = Rc::new(Cell::new(false));
+        let flag = reached.clone();
+
+        let program = Cont::call_cc(move |escape| {
+            escape.exit(10).bind(move |()| {
+                flag.set(true);
+                Cont::pure(20)
+            })
+        })
+        .bind(|x| Cont::pure(x + 1));
+
+        assert_eq!(program.eval_cont(), 11);
+        assert!(!reached.get());
+
+        // exit only when the divisor is zero
+        let safe_div = |x: i32, y: i32| {
+            Cont::call_cc(move |escape| {
+                if y == 0 {
+                    escape.exit(Err("division by zero".to_string()))
+                } else {
+                    Cont::pure(Ok(x / y))
+                }
+            })
+        };
+
+        assert_eq!(safe_div(10, 2).eval_cont(), Ok(5));
+        assert_eq!(safe_div(10, 0).eval_cont(), Err("division by zero".to_string()));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn box_future_test() {
+        use crate::monad::BoxFuture;
+
+        let fetch = |id: u32| BoxFuture::new(async move { format!("user-{id}") });
+        let length = |name: String| BoxFuture::new(async move { name.len() });
+
+        assert_eq!(BoxFuture::pure(7).bind(fetch).await, "user-7");
+        assert_eq!(BoxFuture::pure(42).bind(fetch).bind(length).fmap(|x| x * 10).await, 70);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let received = BoxFuture::new(async move { rx.await.unwrap() }).bind(|x: i32| BoxFuture::pure(x + 1));
+
+        tokio::spawn(async move { tx.send(1).unwrap() });
+
+        assert_eq!(received.await, 2);
+    }
+
+    #[test]
+    fn option_t_test() {
+        use crate::monad::*;
+
+        assert_eq!(Identity(1).bind(|x| Identity(x + 1)), Identity(2));
+        assert_eq!(do_monad! { x <- Identity(1); y <- Identity(2); return x + y }, Identity(3));
+
+        let checked_sub = |x: u32| move |y: u32| OptionT(Identity(y.checked_sub(x)));
+
+        assert_eq!(
+            OptionT::lift(Identity(10)).bind(checked_sub(3)).bind(checked_sub(2)).run_option_t(),
+            Identity(Some(5))
+        );
+        assert_eq!(OptionT::lift(Identity(1)).bind(checked_sub(3)).bind(checked_sub(2)).run_option_t(), Identity(None));
+
+        // the log of `Writer` is kept until the computation fails
+        let step = |x: u32, log: &'static str| OptionT(Writer::new(x.checked_sub(1), vec![log]));
+
+        let (value, log) = OptionT::<Writer<Vec<&str>, _>>::pure(2)
+            .bind(move |x| step(x, "first"))
+            .bind(move |x| step(x, "second"))
+            .bind(move |x| step(x, "third"))
+            .bind(move |x| step(x, "skipped"))
+            .run_option_t()
+            .run_writer();
+
+        assert_eq!(value, None);
+        assert_eq!(log, vec!["first", "second", "third"]);
+
+        let (value, log) = OptionT::lift(Writer::new(1, vec!["lift"]))
+            .bind(move |x| step(x, "first"))
+            .bind(move |x| step(x, "second"))
+            .run_option_t()
+            .run_writer();
+
+        assert_eq!(value, None);
+        assert_eq!(log, vec!["lift", "first", "second"]);
+    }
+
+    #[test]
+    fn comonad_test() {
+        use crate::comonad::*;
+
+        let nonempty = NonEmptyVec::new(1, vec![2, 3, 4]);
+
+        assert_eq!(nonempty.extract(), &1);
+        assert_eq!(nonempty.clone().extend(|w| *w.extract()), nonempty);
+        assert_eq!(nonempty.clone().extend(|w| w.len()), NonEmptyVec::new(4, vec![3, 2, 1]));
+        assert_eq!(nonempty.extend(|w| w.iter().sum::<i32>()), NonEmptyVec::new(10, vec![9, 7, 4]));
+        assert_eq!(NonEmptyVec::<i32>::from_vec(vec![]), None);
+
+        let zipper = Zipper::new(vec![1.0, 2.0, 3.0, 4.0, 5.0], 2).unwrap();
+
+        assert_eq!(zipper.extract(), &3.0);
+        assert_eq!(zipper.left(), &[1.0, 2.0]);
+        assert_eq!(zipper.right(), &[4.0, 5.0]);
+        assert_eq!(zipper.clone().extend(|z| *z.extract()), zipper);
+
+        // trailing moving average of up to three elements
+        let average = |z: &Zipper<f64>| {
+            let start = z.left().len().saturating_sub(2);
+            let window = [&z.left()[start..], std::slice::from_ref(z.extract())].concat();
+            window.iter().sum::<f64>() / window.len() as f64
+        };
+
+        let averages = zipper.extend(average);
+
+        assert_eq!(averages.position(), 2);
+        assert_eq!(averages.into_vec(), vec![1.0, 1.5, 2.0, 3.0, 4.0]);
+
+        let moved = Zipper::new(vec!['a', 'b'], 0).unwrap().move_right().unwrap();
+
+        assert_eq!(moved.extract(), &'b');
+        assert_eq!(moved.clone().move_right(), None);
+        assert_eq!(moved.move_left().unwrap().move_left(), None);
+        assert_eq!(Zipper::<i32>::new(vec![], 0), None);
+    }
+
+    #[test]
+    fn free_test() {
+        use crate::free::kv::*;
+        use std::collections::HashMap;
+
+        // rename a key, the program is plain data until it is interpreted
+        let rename = |from: &'static str, to: &'static str| {
+            get(from).bind(move |value| match value {
+                Some(value) => put(to, &value).bind(move |()| delete(from)).map(|()| true),
+                None => Program::pure(false),
+            })
+        };
+
+        let mut store = HashMap::from([("old".to_string(), "value".to_string())]);
+
+        assert!(run_in_memory(rename("old", "new"), &mut store));
+        assert_eq!(store, HashMap::from([("new".to_string(), "value".to_string())]));
+        assert!(!run_in_memory(rename("old", "new"), &mut store));
+
+        let mut store = HashMap::new();
+        let program = put("a", "1").bind(move |()| rename("a", "b")).bind(|renamed| get("b").map(move |b| (renamed, b)));
+        let (result, log) = run_logging(program, &mut store);
+
+        assert_eq!(result, (true, Some("1".to_string())));
+        assert_eq!(log, vec!["put a = 1", "get a -> Some(\"1\")", "put b = 1", "delete a", "get b -> Some(\"1\")"]);
+
+        let counter = (0..1_000).fold(Program::pure(()), |program, i| program.bind(move |()| put("counter", &i.to_string())));
+
+        assert_eq!(run_in_memory(counter.bind(|()| get("counter")), &mut HashMap::new()), Some("999".to_string()));
+    }
+
+    #[test]
+    fn traverse_test() {
+        use crate::traverse::*;
+
+        assert_eq!(vec![Some(1), Some(2)].sequence(), Some(vec![1, 2]));
+        assert_eq!(vec![Some(1), None].sequence(), None);
+        assert_eq!([Ok::<i32, &str>(1), Ok(2), Ok(3)].sequence(), Ok(vec![1, 2, 3]));
+        assert_eq!([Ok(1), Err("second"), Err("third")].sequence(), Err("second"));
+        assert_eq!(Vec::<Option<i32>>::new().sequence(), Some(vec![]));
+        assert_eq!(vec![vec![1, 2], vec![3, 4]].sequence(), vec![vec![1, 3], vec![1, 4], vec![2, 3], vec![2, 4]]);
+
+        let parse = |s: &str| s.parse::<i32>().map_err(|_| format!("invalid: {s}"));
+
+        assert_eq!(["1", "2", "3"].traverse(parse), Ok(vec![1, 2, 3]));
+        assert_eq!(["1", "b", "c"].traverse(parse), Err("invalid: b".to_string()));
+        assert_eq!((1..=3).traverse(|x| if x > 0 { Some(x * 2) } else { None }), Some(vec![2, 4, 6]));
+        assert_eq!("12a".chars().traverse(|c| c.to_digit(10)), None);
+
+        // short-circuits at the first failure
+        let mut calls = 0;
+        let result = (1..=10).traverse(|x| {
+            calls += 1;
+            if x < 3 {
+                Some(x)
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(result, None);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn validated_test() {
+        use crate::{applicative::*, traverse::Traverse, validated::*};
+
+        #[derive(Debug, PartialEq)]
+        struct User {
+            name: String,
+            age: u32,
+        }
+
+        let name = |s: &str| {
+            if s.is_empty() {
+                Validated::fail("name is empty".to_string())
+            } else {
+                Validated::Valid(s.to_string())
+            }
+        };
+        let age = |s: &str| Validated::from(s.parse::<u32>().map_err(|_| vec![format!("invalid age: {s}")]));
+        let user = |n: &str, a: &str| name(n).map2(age(a), |name, age| User { name, age });
+
+        assert_eq!(
+            user("rust", "9"),
+            Validated::Valid(User {
+                name: "rust".to_string(),
+                age: 9
+            })
+        );
+        assert_eq!(user("", "9"), Validated::Invalid(vec!["name is empty".to_string()]));
+        assert_eq!(
+            user("", "nine"),
+            Validated::Invalid(vec!["name is empty".to_string(), "invalid age: nine".to_string()])
+        );
+
+        // unlike `Result`, every error is collected
+        assert_eq!(
+            lift3(age("x"), age("1"), age("y"), |a, b, c| a + b + c),
+            Validated::Invalid(vec!["invalid age: x".to_string(), "invalid age: y".to_string()])
+        );
+        assert_eq!(
+            ["1", "a", "2", "b"].traverse(age),
+            Validated::Invalid(vec!["invalid age: a".to_string(), "invalid age: b".to_string()])
+        );
+        assert_eq!(["1", "2"].traverse(age), Validated::Valid(vec![1, 2]));
+
+        assert_eq!(Validated::<Vec<String>, _>::Valid(1).ap(Validated::Valid(|x| x + 1)), Validated::Valid(2));
+        assert_eq!(user("", "x").into_result().unwrap_err().len(), 2);
+        assert_eq!(Result::from(Validated::<Vec<&str>, _>::Valid(1)), Ok(1));
+        assert!(!Validated::<Vec<&str>, i32>::from(Err(vec!["e"])).is_valid());
+    }
+
+    #[test]
+    fn either_test() {
+        use crate::{either::*, functor::Functor, monad::Monad};
+
+        let (left, right) = (Either::<&str, i32>::Left("left"), Either::<&str, i32>::Right(10));
+
+        assert_eq!(left.map_left(str::len), Either::Left(4));
+        assert_eq!(right.map_left(str::len), Either::Right(10));
+        assert_eq!(right.map_right(|x| x * 2), Either::Right(20));
+        assert_eq!(left.bimap(str::len, |x| x * 2), Either::Left(4));
+        assert_eq!(right.bimap(str::len, |x| x * 2), Either::Right(20));
+        assert_eq!(left.swap(), Either::Right("left"));
+        assert_eq!(right.either(str::len, |x| x as usize), 10);
+        assert_eq!((left.left(), right.right()), (Some("left"), Some(10)));
+
+        // right-biased
+        assert_eq!(right.fmap(|x| x + 1), Either::Right(11));
+        assert_eq!(left.fmap(|x| x + 1), Either::Left("left"));
+        assert_eq!(
+            right.bind(|x| if x > 5 { Either::Left("too large") } else { Either::Right(x) }),
+            Either::Left("too large")
+        );
+        assert_eq!(left.bind(|x| Either::<&str, i32>::Right(x + 1)), Either::Left("left"));
+
+        // `swap` makes the left side the biased one
+        assert_eq!(left.swap().fmap(str::len).swap(), Either::Left(4));
+
+        assert_eq!(Either::from("42".parse::<i32>()), Either::Right(42));
+        assert!(Either::from("x".parse::<i32>()).is_left());
+        assert_eq!(Result::from(right), Ok(10));
+        assert_eq!(Result::from(left), Err("left"));
+    }
+
+    #[test]
+    fn impl_macro_test() {
+        use crate::impl_macro::*;
+
+        assert_eq!(impl_macro::x().foo(8), 50);
+    }
+
+    #[test]
+    fn impl_macro_ops_test() {
+        use crate::impl_macro::*;
+
+        // +
+        assert_eq!(MyStruct(3u8).foo(4), 7);
+        assert_eq!(MyStruct(-3i64).foo(4), 1);
+        // *
+        assert_eq!(MyStruct(1.5f32).foo(2.0), 3.0);
+        assert_eq!(MyStruct(0.5f64).foo(0.5), 0.25);
+        // closures
+        assert!(MyStruct(true).foo(false));
+        assert!(!MyStruct(true).foo(true));
+        assert_eq!(MyStruct('a').foo('z'), 'z');
+    }
+
+    #[test]
+    fn newtype_test() {
+        newtype!(#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)] Meters(f64): Add, Sub, Mul<f64>, Div<f64>, Neg);
+        newtype!(#[derive(Debug, PartialEq)] Count(u32): Add, Rem, Mul<u32>,);
+        newtype!(Plain(String));
+
+        assert_eq!(Meters(1.0) + Meters(2.0), Meters(3.0));
+        assert_eq!(Meters(1.0) - Meters(2.0), -Meters(1.0));
+        assert_eq!(Meters(1.5) * 2.0 / 3.0, Meters(1.0));
+        assert!(Meters(1.0) < Meters::from(2.0));
+        let raw: f64 = Meters(4.0).into();
+        assert_eq!(raw, 4.0);
+
+        assert_eq!(Count(7) % Count(4) + Count(1), Count(4));
+        assert_eq!(Count(2) * 3, Count(6));
+        assert_eq!(String::from(Plain::from("a".to_string())), "a");
+    }
+
+    #[test]
+    fn delegate_test() {
+        use std::collections::HashSet;
+
+        struct Name {
+            inner: String,
+        }
+
+        struct Wrapper<T>(T);
+
+        struct Labeled<T, L> {
+            value: T,
+            _label: L,
+        }
+
+        delegate! {
+            impl Display, Debug, Clone, Default, PartialEq, Eq, Hash, AsRef, Deref for Name => inner: String;
+            impl<T> Display, LowerHex, Clone, PartialEq, PartialOrd, Ord, Eq for Wrapper<T> => 0: T;
+            impl<T: Copy, L> Display for Labeled<T, L> => value: T where L: Default;
+        }
+
+        let name = Name { inner: "kim".to_string() };
+        assert_eq!(name.to_string(), "kim");
+        assert_eq!(format!("{name:?}"), "\"kim\"");
+        assert_eq!(name.clone(), name);
+        assert_eq!(Name::default().inner, "");
+        assert_eq!(name.len(), 3);
+        assert_eq!(AsRef::<String>::as_ref(&name), "kim");
+        assert_eq!([name.clone(), name.clone()].into_iter().collect::<HashSet<_>>().len(), 1);
+
+        assert_eq!(format!("{:x}", Wrapper(255)), "ff");
+        assert!(Wrapper(1) < Wrapper(2));
+        assert_eq!(Wrapper(3).clone().max(Wrapper(2)).0, 3);
+        assert_eq!(Labeled { value: 1.5, _label: () }.to_string(), "1.5");
+    }
+
+    #[test]
+    fn impl_macro_traits_test() {
+        use crate::impl_macro::*;
+
+        assert_eq!(MyStruct(1i32).foo(2), 3);
+        assert!(MyStruct(1i32).bar(1));
+        assert!(!MyStruct(1u128).bar(2));
+        assert!(MyStruct(2.5f64).bar(2.5));
+        assert!(MyStruct('a').bar('a'));
+
+        fn both<T: Copy>(s: &MyStruct<T>, x: T) -> (T, bool)
+        where
+            MyStruct<T>: Foo<T, Output = T> + Bar<T>,
+        {
+            (s.foo(x), s.bar(x))
+        }
+        assert_eq!(both(&MyStruct(5u16), 5), (10, true));
+    }
+
+    #[test]
+    fn derive_foo_all_test() {
+        use crate::impl_macro::{Foo, FooAll};
+
+        #[derive(FooAll)]
+        #[foo(types(usize, i8, f32))]
+        struct Generic<T>(T);
+
+        #[derive(FooAll)]
+        #[foo(types(u32))]
+        struct Named {
+            total: u32,
+        }
+
+        assert_eq!(Generic(40usize).foo(2), 42);
+        assert_eq!(Generic(-1i8).foo(1), 0);
+        assert_eq!(Generic(1.5f32).foo(1.5), 3.0);
+        assert_eq!(Named { total: 7 }.foo(3), 10);
+    }
+
+    #[test]
+    fn impl_ops_test() {
+        use crate::impl_macro::{impl_ops, Op};
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Poly(Vec<i32>);
+
+        // `&self` and `&Self`, no clone is needed.
+        #[impl_ops]
+        impl Poly {
+            fn combine(&self, rhs: &Self, op: Op) -> Self {
+                let len = self.0.len().max(rhs.0.len());
+                let at = |v: &Vec<i32>, i: usize| v.get(i).copied().unwrap_or(0);
+
+                Poly(
+                    (0..len)
+                        .map(|i| match op {
+                            Op::Add => at(&self.0, i) + at(&rhs.0, i),
+                            Op::Sub => at(&self.0, i) - at(&rhs.0, i),
+                            Op::Mul => at(&self.0, i) * at(&rhs.0, i),
+                            Op::Div => at(&self.0, i).checked_div(at(&rhs.0, i)).unwrap_or(0),
+                        })
+                        .collect(),
+                )
+            }
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct Ratio<T>(T);
+
+        #[impl_ops]
+        impl<T: std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<Output = T> + std::ops::Div<Output = T> + Clone> Ratio<T> {
+            fn combine(self, rhs: Self, op: Op) -> Self {
+                Ratio(match op {
+                    Op::Add => self.0 + rhs.0,
+                    Op::Sub => self.0 - rhs.0,
+                    Op::Mul => self.0 * rhs.0,
+                    Op::Div => self.0 / rhs.0,
+                })
+            }
+        }
+
+        assert_eq!(Poly(vec![1, 2]) + Poly(vec![1, 1, 1]), Poly(vec![2, 3, 1]));
+        assert_eq!(Poly(vec![4, 4]) / Poly(vec![2]), Poly(vec![2, 0]));
+        let mut p = Poly(vec![3]);
+        p -= Poly(vec![1]);
+        p *= Poly(vec![5]);
+        assert_eq!(p, Poly(vec![10]));
+
+        assert_eq!(Ratio(1.5) * Ratio(2.0) - Ratio(1.0), Ratio(2.0));
+        let mut r = Ratio(9);
+        r += Ratio(3);
+        r /= Ratio(4);
+        assert_eq!(r, Ratio(3));
+    }
+
+    #[test]
+    fn impl_macro_ctors_test() {
+        use crate::impl_macro::*;
+
+        assert_eq!(MyStruct::<u8>::zero().0, 0);
+        assert_eq!(MyStruct::<i128>::from_inner(-1).foo(1), 0);
+        assert_eq!(MyStruct::<f64>::zero().foo(3.0), 0.0);
+        // the type must be given, every `MyStruct<$t>` has its own `from_inner`.
+        assert_eq!(MyStruct::<f32>::from_inner(2.0).foo(3.0), 6.0);
+        assert!(!MyStruct::<bool>::zero().0);
+
+        const ONE: MyStruct<usize> = MyStruct::<usize>::from_inner(1);
+        assert_eq!(ONE.foo(1), 2);
+    }
+
+    #[test]
+    fn impl_macro_wrapped_test() {
+        use crate::impl_macro::*;
+
+        let n = 5usize;
+        assert_eq!(MyStruct(1usize).foo(&n), 6);
+        assert_eq!(MyStruct(2.0f64).foo(&1.5), 3.0);
+        assert_eq!(MyStruct(1u8).foo(Some(2u8)), Some(3));
+        assert_eq!(MyStruct(1u8).foo(None::<u8>), None);
+        assert_eq!(MyStruct('b').foo(Some('a')), Some('b'));
+    }
+
+    #[test]
+    fn linq_test() {
+        let result = linq!(
+            from i in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10].iter();
+            where i % 2 == 0;
+            select i + 10;
+        );
+
+        assert_eq!(result.collect::<Vec<i32>>(), vec![12, 14, 16, 18, 20]);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item {
+        name: String,
+        category: &'static str,
+        price: f64,
+        qty: u32,
+    }
+
+    fn items() -> Vec<Item> {
+        let item = |name: &str, category, price, qty| Item {
+            name: name.to_string(),
+            category,
+            price,
+            qty,
+        };
+
+        vec![
+            item("apple", "fruit", 1.5, 10),
+            item("carrot", "vegetable", 0.5, 4),
+            item("banana", "fruit", 0.25, 12),
+            item("leek", "vegetable", 2.0, 1),
+            item("cherry", "fruit", 4.0, 3),
+        ]
+    }
+
+    #[test]
+    fn linq_group_by_test() {
+        let items = items();
+
+        let groups = linq!(
+            from x in items.iter();
+            group x by x.category;
+        );
+        let groups: Vec<(&str, Vec<&Item>)> = groups.collect();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, "fruit");
+        assert_eq!(groups[0].1, vec![&items[0], &items[2], &items[4]]);
+        assert_eq!(groups[1].0, "vegetable");
+        assert_eq!(groups[1].1, vec![&items[1], &items[3]]);
+
+        let names = linq!(
+            from x in items.iter();
+            where x.price < 3.0;
+            orderby x.name;
+            group (x.name.as_str()) by x.qty > 3;
+        );
+        assert_eq!(names.collect::<Vec<_>>(), vec![(true, vec!["apple", "banana", "carrot"]), (false, vec!["leek"])]);
+
+        let numbers = [1, 2, 3, 4, 5, 6, 7];
+        let by_rem = linq!(from n in numbers.iter(); group (n * 10) by n % 3;);
+        assert_eq!(by_rem.collect::<Vec<_>>(), vec![(1, vec![10, 40, 70]), (2, vec![20, 50]), (0, vec![30, 60])]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Owner {
+        id: u32,
+        name: &'static str,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Pet {
+        owner_id: u32,
+        name: &'static str,
+    }
+
+    fn owners() -> Vec<Owner> {
+        vec![Owner { id: 1, name: "kim" }, Owner { id: 2, name: "lee" }, Owner { id: 3, name: "park" }]
+    }
+
+    fn pets() -> Vec<Pet> {
+        vec![
+            Pet { owner_id: 2, name: "nabi" },
+            Pet { owner_id: 1, name: "bori" },
+            Pet { owner_id: 2, name: "coco" },
+            Pet { owner_id: 9, name: "stray" },
+        ]
+    }
+
+    #[test]
+    fn linq_join_test() {
+        let (owners, pets) = (owners(), pets());
+
+        let pairs = linq!(
+            from a in owners.iter();
+            join b in pets.iter() on a.id equals b.owner_id;
+            select (a.name, b.name);
+        );
+        // the owners without pets and the pets without owners are left out, the outer order is kept.
+        assert_eq!(pairs.collect::<Vec<_>>(), vec![("kim", "bori"), ("lee", "nabi"), ("lee", "coco")]);
+
+        let result = linq!(
+            from a in owners.iter();
+            join b in pets.iter().filter(|p| p.name.len() == 4) on a.id.max(1) equals b.owner_id;
+            where b.name != "nabi";
+            orderby descending b.name;
+            select format!("{}/{}", a.name, b.name);
+        );
+        assert_eq!(result.collect::<Vec<_>>(), vec!["lee/coco", "kim/bori"]);
+
+        // owned items that are not `Copy` on both sides, each match gets its own clones.
+        let same_category = linq!(
+            from x in items().into_iter();
+            join y in items().into_iter() on x.category equals y.category;
+            where x.name < y.name;
+            select format!("{}-{}", x.name, y.name);
+        );
+        assert_eq!(
+            same_category.collect::<Vec<_>>(),
+            vec!["apple-banana", "apple-cherry", "carrot-leek", "banana-cherry"]
+        );
+    }
+
+    #[test]
+    fn linq_from_test() {
+        let (xs, ys) = ([1, 2], ['a', 'b']);
+
+        let pairs = linq!(
+            from x in xs.iter();
+            from y in ys.iter();
+            select (*x, *y);
+        );
+        assert_eq!(pairs.collect::<Vec<_>>(), vec![(1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]);
+
+        struct Node {
+            name: &'static str,
+            children: Vec<u32>,
+        }
+
+        let nodes = [
+            Node { name: "a", children: vec![1, 2] },
+            Node { name: "b", children: vec![] },
+            Node { name: "c", children: vec![3] },
+        ];
+
+        let flattened = linq!(
+            from n in nodes.iter();
+            from c in n.children.iter();
+            where c % 2 == 1;
+            from i in 0..*c;
+            select format!("{}{}{}", n.name, c, i);
+        );
+        assert_eq!(flattened.collect::<Vec<_>>(), vec!["a10", "c30", "c31", "c32"]);
+
+        // owned items that are not `Copy` are cloned for each inner item.
+        let owned = linq!(
+            from x in items().into_iter().take(2);
+            from n in 0..2u32;
+            select format!("{}{n}", x.name);
+        );
+        assert_eq!(owned.collect::<Vec<_>>(), vec!["apple0", "apple1", "carrot0", "carrot1"]);
+    }
+
+    #[test]
+    fn linq_let_test() {
+        let items = items();
+
+        let totals = linq!(
+            from x in items.iter();
+            let total = x.price * x.qty as f64;
+            where total >= 3.0;
+            orderby descending total;
+            let label = format!("{}={}", x.name, total);
+            select (label, x.qty);
+        );
+        assert_eq!(
+            totals.collect::<Vec<_>>(),
+            vec![("apple=15".to_string(), 10), ("cherry=12".to_string(), 3), ("banana=3".to_string(), 12)]
+        );
+    }
+
+    #[test]
+    fn linq_paging_test() {
+        let numbers: Vec<i32> = (1..=20).collect();
+
+        let page = linq!(
+            from n in numbers.iter();
+            where n % 2 == 0;
+            skip 2;
+            take 3;
+            select *n;
+        );
+        assert_eq!(page.collect::<Vec<_>>(), vec![6, 8, 10]);
+
+        let run = linq!(
+            from n in numbers.iter();
+            skip_while *n < 5;
+            take_while n * n < 100;
+            where n % 3 != 0;
+            select *n;
+        );
+        assert_eq!(run.collect::<Vec<_>>(), vec![5, 7, 8]);
+
+        // lazy: `take` stops pulling from an endless source.
+        let mut pulled = 0;
+        let first = linq!(
+            from n in (0..).inspect(|_| pulled += 1);
+            skip 1;
+            take 2;
+            select n;
+        );
+        assert_eq!(first.collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(pulled, 3);
+    }
+
+    #[test]
+    fn linq_distinct_test() {
+        let numbers = [3, 1, 3, 2, 1, 4];
+        let unique = linq!(from n in numbers.iter(); distinct; select *n;);
+        assert_eq!(unique.collect::<Vec<_>>(), vec![3, 1, 2, 4]);
+
+        let items = items();
+        let categories = linq!(
+            from x in items.iter();
+            distinct by x.category;
+            select x.name.as_str();
+        );
+        assert_eq!(categories.collect::<Vec<_>>(), vec!["apple", "carrot"]);
+
+        let parity = linq!(
+            from n in numbers.iter();
+            where *n > 1;
+            distinct by *n % 2;
+            select *n;
+        );
+        assert_eq!(parity.collect::<Vec<_>>(), vec![3, 2]);
+
+        // owned items that are not `Copy`, the key is read through a reference.
+        let first_of_each = linq!(
+            from x in items.into_iter();
+            distinct by x.category;
+            select x.name;
+        );
+        assert_eq!(first_of_each.collect::<Vec<_>>(), vec!["apple", "carrot"]);
+    }
+
+    #[test]
+    fn linq_aggregate_test() {
+        let v = [1, 2, 3, 4, 5];
+
+        assert_eq!(linq!(from x in v.iter(); where *x > 2; count;), 3);
+        assert_eq!(linq!(from x in v.iter(); where *x > 2; sum x * 2;), 24);
+        assert_eq!(linq!(from x in v.iter(); min x % 3;), Some(0));
+        assert_eq!(linq!(from x in v.iter(); max 10 - x;), Some(9));
+        assert_eq!(linq!(from x in v.iter(); average *x;), Some(3.0));
+        assert_eq!(linq!(from x in v.iter(); where *x > 9; average *x;), None);
+        assert_eq!(linq!(from x in v.iter(); fold String::new(), acc => acc + &x.to_string();), "12345");
+
+        let items = items();
+        assert_eq!(linq!(from x in items.iter(); sum x.price * x.qty as f64;), 34.0);
+        assert_eq!(linq!(from x in items.iter(); where x.category == "fruit"; max x.price;), Some(4.0));
+        assert_eq!(linq!(from x in items.iter(); min x.name.as_str();), Some("apple"));
+    }
+
+    #[test]
+    fn linq_into_test() {
+        use std::collections::{BTreeSet, HashMap, HashSet};
+
+        let items = items();
+
+        let names = linq!(from x in items.iter(); where x.qty > 3; select x.name.as_str(); into Vec<_>;);
+        assert_eq!(names, vec!["apple", "carrot", "banana"]);
+
+        let categories = linq!(from x in items.iter(); select x.category; into HashSet<_>);
+        assert_eq!(categories, HashSet::from(["fruit", "vegetable"]));
+
+        let prices = linq!(from x in items.iter(); select (x.name.clone(), x.price); into HashMap<String, f64>;);
+        assert_eq!(prices["leek"], 2.0);
+
+        let groups = linq!(from x in items.iter(); group (x.qty) by x.category; into HashMap<_, _>;);
+        assert_eq!(groups["vegetable"], vec![4, 1]);
+
+        let sorted = linq!(from x in [3, 1, 2].iter(); select *x; into BTreeSet<i32>;);
+        assert_eq!(sorted.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn linq_where_test() {
+        use std::cell::RefCell;
+
+        let log = RefCell::new(Vec::new());
+        let check = |name: &'static str, n: i32, ok: bool| {
+            log.borrow_mut().push(format!("{name}{n}"));
+            ok
+        };
+
+        let result = linq!(
+            from n in [1, 2, 3, 4, 5, 6].iter();
+            where check("a", *n, n % 2 == 0);
+            where check("b", *n, *n > 2);
+            where check("c", *n, *n != 4);
+            select *n;
+        );
+        assert_eq!(result.collect::<Vec<_>>(), vec![6]);
+        // `b` only sees the even numbers, `c` only the ones `b` kept, item by item (lazy).
+        assert_eq!(log.borrow().join(" "), "a1 a2 b2 a3 a4 b4 c4 a5 a6 b6 c6");
+
+        // a later predicate can rely on the earlier ones, it would panic on 0 otherwise.
+        let ratios = linq!(
+            from n in [0, 2, 0, 5].iter();
+            where *n != 0;
+            where 10 / n > 2;
+            select 10 / n;
+        );
+        assert_eq!(ratios.collect::<Vec<_>>(), vec![5]);
+
+        // the items are moved through the filters, they don't have to be `Copy`.
+        let words = vec!["a".to_string(), "bb".to_string(), "ccc".to_string()];
+        let long = linq!(from w in words.into_iter(); where w.len() > 1; take_while w.len() < 3; select w + "!";);
+        assert_eq!(long.collect::<Vec<_>>(), vec!["bb!"]);
+    }
+
+    #[test]
+    fn try_linq_test() {
+        use std::num::ParseIntError;
+
+        let parse = |input: Vec<&str>| -> Result<Vec<i32>, ParseIntError> {
+            try_linq!(
+                from n in input.into_iter().map(str::parse::<i32>);
+                let square = n * n;
+                where square > 1;
+                select square + n;
+            )
+        };
+        assert_eq!(parse(vec!["1", "2", "3"]), Ok(vec![6, 12]));
+        assert_eq!(parse(vec![]), Ok(vec![]));
+        assert!(parse(vec!["1", "two", "3"]).is_err());
+
+        // short-circuit: nothing after the first `Err` is pulled.
+        let mut pulled = 0;
+        let source = [Ok(1), Err("bad"), Ok(3), Err("worse")].into_iter().inspect(|_| pulled += 1);
+        let result = try_linq!(from n in source; select n;);
+        assert_eq!(result, Err("bad"));
+        assert_eq!(pulled, 2);
+
+        // `?` inside a clause, the error is converted into the source's error type.
+        #[derive(Debug, PartialEq)]
+        struct Error(String);
+
+        impl From<ParseIntError> for Error {
+            fn from(e: ParseIntError) -> Self {
+                Error(e.to_string())
+            }
+        }
+
+        let rows: Vec<Result<&str, Error>> = vec![Ok("1,2"), Ok("3,x")];
+        let sums = try_linq!(
+            from row in rows;
+            let (a, b) = row.split_once(',').ok_or(Error("no comma".to_string()))?;
+            select a.parse::<i32>()? + b.parse::<i32>()?;
+        );
+        assert_eq!(sums, Err(Error("invalid digit found in string".to_string())));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn alinq_test() {
+        use futures::{stream, StreamExt};
+        use tokio::sync::mpsc;
+
+        let query = alinq!(
+            from n in stream::iter(vec![5, 1, 4, 2, 3]);
+            let doubled = n * 2;
+            where doubled > 2;
+            skip 1;
+            take 2;
+            select doubled + n;
+        );
+        assert_eq!(query.collect::<Vec<_>>().await, vec![12, 6]);
+
+        // items arriving over time, the query yields as they come.
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for i in 0..6 {
+                tx.send(i).unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+        let source = stream::poll_fn(move |cx| rx.poll_recv(cx));
+        let evens = alinq!(from n in source; where n % 2 == 0; select n * 10;);
+        assert_eq!(evens.collect::<Vec<_>>().await, vec![0, 20, 40]);
+    }
+
+    #[test]
+    fn linq_element_test() {
+        use crate::linq::SingleError;
+
+        let items = items();
+
+        assert_eq!(linq!(from x in items.iter(); where x.price > 1.0; first;), Some(&items[0]));
+        assert_eq!(linq!(from x in items.iter(); where x.price > 9.0; first x.name.as_str();), None);
+        assert_eq!(linq!(from x in items.iter(); where x.qty == 1; single x.name.as_str();), Ok("leek"));
+        assert_eq!(linq!(from x in items.iter(); where x.qty > 3; single;), Err(SingleError::MoreThanOne));
+        assert_eq!(linq!(from x in items.iter(); where x.qty > 99; single;), Err(SingleError::Empty));
+        assert!(linq!(from x in items.iter(); any x.category == "vegetable";));
+        assert!(!linq!(from x in items.iter(); all x.price < 3.0;));
+        assert!(linq!(from x in items.iter(); where x.category == "fruit"; all x.qty >= 3;));
+
+        // they stop early.
+        let mut pulled = 0;
+        assert!(linq!(from n in (1..).inspect(|_| pulled += 1); any n > 2;));
+        assert_eq!(pulled, 3);
+        assert_eq!(linq!(from n in 1..; where n % 7 == 0; first n * 2;), Some(14));
+        assert_eq!(linq!(from n in 1..; single;), Err(SingleError::MoreThanOne));
+    }
+
+    #[test]
+    fn linq_group_join_test() {
+        let (owners, pets) = (owners(), pets());
+
+        let result = linq!(
+            from a in owners.iter();
+            group join b in pets.iter() on a.id equals b.owner_id into g;
+            select (a.name, g.iter().map(|p| p.name).collect::<Vec<_>>());
+        );
+        assert_eq!(
+            result.collect::<Vec<_>>(),
+            vec![("kim", vec!["bori"]), ("lee", vec!["nabi", "coco"]), ("park", vec![])]
+        );
+
+        let counts = linq!(
+            from a in owners.iter();
+            join b in pets.iter() on a.id equals b.owner_id into g;
+            where !g.is_empty();
+            orderby descending g.len();
+            select format!("{}:{}", a.name, g.len());
+            into Vec<_>;
+        );
+        assert_eq!(counts, vec!["lee:2", "kim:1"]);
+    }
+
+    #[test]
+    fn linq_pattern_test() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let stock: HashMap<&str, u32> = HashMap::from([("apple", 3), ("banana", 0), ("cherry", 7)]);
+        let available = linq!(
+            from (name, count) in stock.iter();
+            where *count > 0;
+            orderby descending count;
+            select format!("{name}={count}");
+            into Vec<_>;
+        );
+        assert_eq!(available, vec!["cherry=7", "apple=3"]);
+
+        let ages = BTreeMap::from([(30, "kim"), (25, "lee")]);
+        let names = linq!(
+            from (age, name) in ages.into_iter();
+            let (decade, rest) = (age / 10, age % 10);
+            where rest != 0;
+            select (name, decade);
+        );
+        assert_eq!(names.collect::<Vec<_>>(), vec![("lee", 2)]);
+
+        let points = [(1, (2, 3)), (4, (5, 6))];
+        let flat = linq!(
+            from (x, (y, z)) in points.iter().copied();
+            from (a, b) in [(x, y), (y, z)];
+            select a * b;
+        );
+        assert_eq!(flat.collect::<Vec<_>>(), vec![2, 6, 20, 30]);
+        assert_eq!(try_linq!(from (a, b) in [Ok::<_, ()>((1, 2)), Ok((3, 4))]; select a + b;), Ok(vec![3, 7]));
+    }
+
+    #[test]
+    fn linq_zip_test() {
+        let names = ["kim", "lee", "park"];
+        let scores = [90, 75, 82, 60];
+
+        // `scores` is longer, its last item has no pair.
+        let report = linq!(
+            from n in names.iter();
+            zip s in scores.iter();
+            where *s > 80;
+            select format!("{n}:{s}");
+        );
+        assert_eq!(report.collect::<Vec<_>>(), vec!["kim:90", "park:82"]);
+
+        // the shorter source decides the length either way, `1..` is endless.
+        let numbered = linq!(from s in scores.iter(); zip i in 1..; zip n in names; select (i, n, *s););
+        assert_eq!(numbered.collect::<Vec<_>>(), vec![(1, "kim", 90), (2, "lee", 75), (3, "park", 82)]);
+    }
+
+    #[test]
+    fn linq_reverse_chunk_test() {
+        let numbers: Vec<i32> = (1..=7).collect();
+
+        let reversed = linq!(from n in numbers.iter(); where n % 2 == 1; reverse; select n * 10;);
+        assert_eq!(reversed.collect::<Vec<_>>(), vec![70, 50, 30, 10]);
+
+        let chunks = linq!(from n in numbers.iter(); chunk 3;);
+        assert_eq!(chunks.collect::<Vec<_>>(), vec![vec![&1, &2, &3], vec![&4, &5, &6], vec![&7]]);
+
+        let sums = linq!(
+            from n in numbers.iter();
+            reverse;
+            chunk 2 into pair;
+            where pair.len() == 2;
+            select pair.into_iter().sum::<i32>();
+        );
+        assert_eq!(sums.collect::<Vec<_>>(), vec![13, 9, 5]);
+
+        // the items are moved into the chunks, they don't need `Clone`.
+        struct Token(u8);
+        let tokens = linq!(from t in (0..5).map(Token); chunk 1 + 1;).map(|c| c.iter().map(|t| t.0).collect::<Vec<_>>());
+        assert_eq!(tokens.collect::<Vec<_>>(), vec![vec![0, 1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn linq_explain_test() {
+        let (items, owners, pets) = (items(), owners(), pets());
+
+        let (query, plan) = linq_explain!(from x in items.iter(); where x.price < 3.0; orderby descending x.qty; take 2; select x.name.as_str(););
+        assert_eq!(plan, "filter -> sort_by -> take(2) -> map");
+        assert_eq!(query.collect::<Vec<_>>(), vec!["banana", "apple"]);
+
+        let (count, plan) = linq_explain!(
+            from o in owners.iter();
+            join p in pets.iter() on o.id equals p.owner_id;
+            let len = p.name.len();
+            skip_while o.id < 2;
+            where len == 4;
+            count;
+        );
+        assert_eq!(plan, "join -> map -> skip_while -> filter -> count");
+        assert_eq!(count, 2);
+
+        let (sizes, plan) = linq_explain!(
+            from o in owners.iter();
+            group join p in pets.iter() on o.id equals p.owner_id into g;
+            reverse;
+            chunk 2 into c;
+            select c.len();
+            into Vec<_>
+        );
+        assert_eq!(plan, "group_join -> rev -> chunks(2) -> map -> collect");
+        assert_eq!(sizes, vec![2, 1]);
+
+        let (_, plan) = linq_explain!(from o in owners.iter(); join p in pets.iter() on o.id equals p.owner_id into g; group (o.name) by g.len(););
+        assert_eq!(plan, "group_join -> group_by");
+    }
+
+    #[test]
+    fn linq_select_record_test() {
+        #[derive(Debug, PartialEq)]
+        struct Stock {
+            name: String,
+            value: f64,
+        }
+
+        let items = items();
+
+        let stocks = linq!(
+            from x in items.iter();
+            where x.category == "vegetable";
+            select Stock { name: x.name.clone(), value: x.price * x.qty as f64 };
+            into Vec<_>;
+        );
+        assert_eq!(
+            stocks,
+            vec![
+                Stock {
+                    name: "carrot".to_string(),
+                    value: 2.0
+                },
+                Stock {
+                    name: "leek".to_string(),
+                    value: 2.0
+                }
+            ]
+        );
+
+        let records = linq!(
+            from x in items.iter();
+            where x.qty > 3;
+            select { name = x.name.as_str(), qty = x.qty, };
+            into Vec<_>;
+        );
+        assert_eq!(records.iter().map(|r| r.qty).sum::<u32>(), 26);
+        assert_eq!(format!("{:?}", records[0]), r#"Record { name: "apple", qty: 10 }"#);
+        // the fields are compared in order, `name` first.
+        assert_eq!(records.iter().max().map(|r| r.name), Some("carrot"));
+
+        let first = linq!(from x in items.iter(); orderby x.price; select { name = x.name.as_str() }; into Vec<_>);
+        assert_eq!(first[0].name, "banana");
+    }
+
+    #[test]
+    fn linq_query_iterator_ext_test() {
+        use crate::linq::{QueryIteratorExt, SingleError};
+
+        let items = items();
+
+        let names: Vec<_> = items
+            .iter()
+            .where_(|x| x.qty > 3)
+            .order_by_descending(|x| x.price)
+            .select(|x| x.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            linq!(from x in items.iter(); where x.qty > 3; orderby descending x.price; select x.name.as_str(); into Vec<_>)
+        );
+
+        let groups: Vec<_> = items.iter().group_by(|x| x.category).map(|(category, xs)| (category, xs.len())).collect();
+        assert_eq!(groups, vec![("fruit", 3), ("vegetable", 2)]);
+
+        let categories: Vec<_> = items.iter().distinct_by(|x| x.category).select(|x| x.name.as_str()).collect();
+        assert_eq!(categories, vec!["apple", "carrot"]);
+
+        assert_eq!(items.iter().where_(|x| x.price > 3.0).select(|x| x.qty).single(), Ok(3));
+        assert_eq!(items.iter().where_(|x| x.price > 9.0).single().map(|x| x.qty), Err(SingleError::Empty));
+
+        // `NaN` keys sort last, and first when descending.
+        let floats = (0..200u32).map(|i| if i % 3 == 0 { f64::NAN } else { f64::from(i * 37 % 200) });
+        let sorted: Vec<f64> = floats.clone().order_by(|x| *x).collect();
+        assert!(sorted[..133].windows(2).all(|w| w[0] <= w[1]) && sorted[133..].iter().all(|x| x.is_nan()));
+        let sorted: Vec<f64> = floats.order_by_descending(|x| *x).collect();
+        assert!(sorted[..67].iter().all(|x| x.is_nan()) && sorted[67..].windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn linq_orderby_test() {
+        let items = items();
+
+        let by_price = linq!(
+            from x in items.iter();
+            orderby x.price;
+            select x.name.as_str();
+        );
+        assert_eq!(by_price.collect::<Vec<_>>(), vec!["banana", "carrot", "apple", "leek", "cherry"]);
+
+        let by_name = linq!(
+            from x in items.iter();
+            where x.category == "fruit";
+            orderby descending x.name;
+            select x.name.clone();
+        );
+        assert_eq!(by_name.collect::<Vec<_>>(), vec!["cherry", "banana", "apple"]);
+
+        // stable: equal keys keep the source order, and the pipeline continues after `orderby`.
+        let by_category = linq!(
+            from x in items.iter();
+            orderby ascending x.category;
+            where x.qty > 1;
+            select x.name.as_str();
+        );
+        assert_eq!(by_category.collect::<Vec<_>>(), vec!["apple", "banana", "cherry", "carrot"]);
+
+        let numbers = [3, 1, 2];
+        let result = linq!(from n in numbers.iter(); orderby descending n; select n * 10;);
+        assert_eq!(result.collect::<Vec<_>>(), vec![30, 20, 10]);
+
+        // owned items that are not `Copy`, the key borrows them.
+        let by_qty = linq!(from x in items.clone().into_iter(); orderby x.qty; select x.name;);
+        assert_eq!(by_qty.collect::<Vec<_>>(), vec!["leek", "cherry", "carrot", "apple", "banana"]);
+
+        // `NaN` keys sort last instead of breaking the order.
+        let floats = (0..200u32).map(|i| if i % 3 == 0 { f64::NAN } else { f64::from(i * 37 % 200) });
+        let sorted: Vec<f64> = linq!(from x in floats; orderby x; select x;).collect();
+        assert!(sorted[..133].windows(2).all(|w| w[0] <= w[1]));
+        assert!(sorted[133..].iter().all(|x| x.is_nan()));
+    }
+
+    #[test]
+    fn compose_test() {
+        use crate::compose::{compose, pipe, try_compose};
+
+        let inc_then_double = compose(|x: i32| x + 1, |x| x * 2);
+        let double_then_inc = compose(|x: i32| x * 2, |x| x + 1);
+        assert_eq!((inc_then_double(5), double_then_inc(5)), (12, 11));
+
+        let steps: [&dyn Fn(String) -> String; 2] = [&|s| s.trim().to_string(), &|s| format!("<{s}>")];
+        assert_eq!(pipe(" hi ".to_string(), steps), "<hi>");
+        assert_eq!(pipe(7, Vec::<fn(i32) -> i32>::new()), 7);
+
+        let words = pipe!("b a c" => str::split_whitespace => |ws: std::str::SplitWhitespace<'static>| {
+            let mut ws: Vec<_> = ws.collect();
+            ws.sort();
+            ws
+        } => |ws: Vec<&str>| ws.join(","));
+        assert_eq!(words, "a,b,c");
+        assert_eq!(pipe!(1), 1);
+
+        let half = |n: i32| if n % 2 == 0 { Ok(n / 2) } else { Err(format!("{n} is odd")) };
+        assert_eq!(try_pipe!(12 => half => half), Ok(3));
+        assert_eq!(try_pipe!(12 => half => half => half), Err("3 is odd".to_string()));
+
+        let calls = std::cell::Cell::new(0);
+        let counted = |n: i32| {
+            calls.set(calls.get() + 1);
+            half(n)
+        };
+        // the steps after the first `Err` do not run.
+        assert!(try_pipe!(5 => counted => counted => counted).is_err());
+        assert_eq!(calls.get(), 1);
+
+        let quarter = try_compose(half, half);
+        assert_eq!((quarter(8), quarter(6)), (Ok(2), Err("3 is odd".to_string())));
+    }
+
+    #[test]
+    fn curry_test() {
+        use crate::curry::Curry;
+
+        fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+
+        let add5 = curry!(add)(5);
+        assert_eq!((add5(3), add5(-5)), (8, 0));
+
+        let pointer: fn(i32, i32) -> i32 = add;
+        assert_eq!(pointer.curry()(1)(2), 3);
+
+        let join = |a: String, b: &'static str, c: char, d: u8, e: bool| format!("{a}{b}{c}{d}{e}");
+        let step = join.curry()("a".to_string())("b")('c');
+        // every step can be called again, the arguments so far are cloned.
+        assert_eq!((step(1)(true), step(2)(false)), ("abc1true".to_string(), "abc2false".to_string()));
+
+        // the curried functions have a type, they can be stored.
+        type Op = Box<dyn Fn(i32) -> Box<dyn Fn(i32) -> i32>>;
+        let ops: Vec<Op> = vec![curry!(|a, b| a - b), curry!(|a: i32, b: i32| a * b)];
+        assert_eq!(ops.iter().map(|op| op(6)(3)).collect::<Vec<_>>(), vec![3, 18]);
+
+        let prefix = String::from("> ");
+        let line = curry3!(|p: &str, n: usize, s: &str| format!("{p}{}", s.repeat(n)));
+        let quoted = line(&prefix)(2);
+        assert_eq!((quoted("ab"), quoted("-")), ("> abab".to_string(), "> --".to_string()));
+        assert_eq!(curry4!(|a: u8, b: u8, c: u8, d: u8| [a, b, c, d])(1)(2)(3)(4), [1, 2, 3, 4]);
+        assert_eq!(curry5!(|a: i32, b, c, d, e| a + b + c + d + e)(1)(2)(3)(4)(5), 15);
+    }
+
+    #[test]
+    fn partial_test() {
+        fn between(low: i32, x: i32, high: i32) -> bool {
+            (low..=high).contains(&x)
+        }
+
+        let digit = partial!(between, 0, _, 9);
+        assert_eq!((digit(5), digit(10)), (true, false));
+
+        let outside = partial!(between, _, 50, _);
+        assert!(outside(0, 100) && !outside(60, 100));
+
+        let names = vec!["kim", "lee"];
+        let evaluated = std::cell::Cell::new(0);
+        let labelled = partial!(
+            |label: String, i: usize, names: &Vec<&str>| format!("{label}{}", names[i]),
+            {
+                evaluated.set(evaluated.get() + 1);
+                String::from("name: ")
+            },
+            _,
+            &names
+        );
+        assert_eq!((labelled(0), labelled(1)), ("name: kim".to_string(), "name: lee".to_string()));
+        // the fixed arguments are evaluated once and cloned for each call.
+        assert_eq!(evaluated.get(), 1);
+
+        assert_eq!(partial!(|| 42)(), 42);
+    }
+
+    #[test]
+    fn lazy_test() {
+        use crate::lazy::Lazy;
+        use std::cell::Cell;
+
+        let calls = Cell::new(0);
+        let expensive = || {
+            calls.set(calls.get() + 1);
+            (1..=10).sum::<i32>()
+        };
+
+        // call-by-name, the closure runs for each use.
+        assert_eq!(expensive() + expensive(), 110);
+        assert_eq!(calls.get(), 2);
+
+        // call-by-need, it runs on the first force only.
+        calls.set(0);
+        let value = Lazy::new(expensive);
+        assert_eq!(calls.get(), 0);
+        assert_eq!(format!("{value:?}"), "Lazy(<unevaluated>)");
+        assert_eq!(value.force() + value.force() + value.force(), 165);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(format!("{value:?}"), "Lazy(55)");
+        assert_eq!((value.into_forced(), Lazy::new(|| 1).into_forced()), (Some(55), None));
+
+        let steps = Cell::new(Vec::new());
+        let log = |step: &'static str| {
+            let mut v = steps.take();
+            v.push(step);
+            steps.set(v);
+        };
+        let chained = Lazy::new(|| {
+            log("source");
+            2
+        })
+        .map(|n| {
+            log("map");
+            n * 10
+        })
+        .and_then(|n| {
+            log("and_then");
+            Lazy::new(move || n + 1)
+        });
+        assert!(steps.take().is_empty());
+        assert_eq!(chained.into_inner(), 21);
+        assert_eq!(steps.take(), vec!["source", "map", "and_then"]);
+
+        assert_eq!(Lazy::ready("done").into_inner(), "done");
+    }
+
+    #[test]
+    fn stream_test() {
+        use crate::stream::{fibs, Stream};
+        use std::{cell::Cell, rc::Rc};
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let naturals = Stream::iterate(0u32, move |n| {
+            counter.set(counter.get() + 1);
+            n + 1
+        });
+
+        let evens = naturals.filter(|n| n % 2 == 0).map(|n| n * 10);
+        assert_eq!(evens.take(3).iter().collect::<Vec<_>>(), vec![0, 20, 40]);
+        let forced = calls.get();
+        // the cells are shared, walking the stream again computes nothing new.
+        assert_eq!(naturals.take(5).into_iter().sum::<u32>(), 10);
+        assert_eq!(calls.get(), forced);
+
+        assert_eq!(naturals.head(), Some(&0));
+        assert_eq!(naturals.tail().and_then(|t| t.head().copied()), Some(1));
+        assert!(Stream::<u8>::empty().tail().is_none());
+
+        let built = Stream::cons(1, || Stream::cons(2, Stream::empty));
+        assert_eq!(built.iter().chain(built.take(1)).collect::<Vec<_>>(), vec![1, 2, 1]);
+
+        assert_eq!(fibs().iter().nth(50), Some(12_586_269_025));
+        assert_eq!(fibs().iter().count(), 94);
+        assert_eq!(fibs().iter().last(), Some(12_200_160_415_121_876_738));
+
+        // a long forced stream is dropped without a recursion per cell.
+        let long = Stream::iterate(0u64, |n| n + 1);
+        assert_eq!(long.iter().nth(1_000_000), Some(1_000_000));
+        drop(long);
+    }
+
+    #[test]
+    fn memo_test() {
+        use crate::memo::{memoize, memoize_rec, memoize_rec_sync, memoize_sync};
+        use std::cell::Cell;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Cell::new(0);
+        let shout = memoize(|s: String| {
+            calls.set(calls.get() + 1);
+            s.to_uppercase()
+        });
+        assert_eq!([shout("a".into()), shout("b".into()), shout("a".into())], ["A", "B", "A"]);
+        assert_eq!(calls.get(), 2);
+
+        // without the cache this would take 2^n calls.
+        let calls = Cell::new(0);
+        let fib = memoize_rec(|fib, n: u32| {
+            calls.set(calls.get() + 1);
+            if n < 2 {
+                n as u128
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        });
+        assert_eq!(fib(150), 9_969_216_677_189_303_386_214_405_760_200);
+        assert_eq!(calls.get(), 151);
+
+        static SYNC_CALLS: AtomicUsize = AtomicUsize::new(0);
+        let collatz = memoize_rec_sync(|steps, n: u64| {
+            SYNC_CALLS.fetch_add(1, Ordering::Relaxed);
+            match n {
+                1 => 0,
+                n if n % 2 == 0 => 1 + steps(n / 2),
+                n => 1 + steps(3 * n + 1),
+            }
+        });
+        let cube = memoize_sync(|n: i64| n * n * n);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| (collatz(27), cube(-3)))).collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), (111, -27));
+            }
+        });
+        let after_threads = SYNC_CALLS.load(Ordering::Relaxed);
+        assert_eq!(collatz(27), 111);
+        assert_eq!(SYNC_CALLS.load(Ordering::Relaxed), after_threads);
+
+        thread_local!(static BINOMIAL_CALLS: Cell<usize> = const { Cell::new(0) });
+        memoize! {
+            fn binomial(n: u64, k: u64) -> u64 {
+                BINOMIAL_CALLS.with(|c| c.set(c.get() + 1));
+                if k == 0 || k == n { 1 } else { binomial(n - 1, k - 1) + binomial(n - 1, k) }
+            }
+        }
+        assert_eq!(binomial(60, 30), 118_264_581_564_861_424);
+        let calls = BINOMIAL_CALLS.with(Cell::get);
+        assert!(calls < 1000);
+        assert_eq!(binomial(60, 30), 118_264_581_564_861_424);
+        assert_eq!(BINOMIAL_CALLS.with(Cell::get), calls);
+    }
+
+    #[test]
+    fn pattern_test() {
+        use crate::pattern::{DivisibleBy, Pattern, Prefix, Split};
+
+        #[derive(Debug, PartialEq)]
+        enum Setting<'a> {
+            Flag(&'a str),
+            Pair(&'a str, &'a str),
+            Number(i64),
+            Other,
+        }
+
+        fn parse(line: &str) -> Setting<'_> {
+            // F#: `let (|Int|_|) (s: string) = ...`
+            let int = |s: &str| s.trim().parse::<i64>().ok();
+            match_with!(*line;
+                // the bindings can be refuted too, then the next pattern is tried.
+                (key, "") in Split('=') => Setting::Flag(key),
+                (key, value) in Split('=') => Setting::Pair(key, value),
+                name in Prefix("no-") => Setting::Flag(name),
+                n in int => Setting::Number(n),
+                _ => Setting::Other,
+            )
+        }
+        let lines: Vec<String> = ["color=", "width=80", "no-wrap", " 12 ", "?"].map(String::from).into();
+        assert_eq!(
+            lines.iter().map(|line| parse(line)).collect::<Vec<_>>(),
+            vec![
+                Setting::Flag("color"),
+                Setting::Pair("width", "80"),
+                Setting::Flag("wrap"),
+                Setting::Number(12),
+                Setting::Other
+            ]
+        );
+
+        let fizzbuzz = |n: u32| {
+            match_with!(n;
+                _ in DivisibleBy(15) => "FizzBuzz".to_string(),
+                _ in DivisibleBy(3) => "Fizz".to_string(),
+                _ in DivisibleBy(5) => "Buzz".to_string(),
+                _ => n.to_string(),
+            )
+        };
+        assert_eq!((9..=15).map(fizzbuzz).collect::<Vec<_>>(), ["Fizz", "Buzz", "11", "Fizz", "13", "14", "FizzBuzz"]);
+
+        assert_eq!(DivisibleBy(0).extract(&10), None);
+        assert_eq!(Prefix("ab").extract("abc"), Some("c"));
+    }
+
+    #[test]
+    fn optics_test() {
+        use crate::optics::{Lens, Prism};
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Address {
+            city: String,
+            zip: u32,
+        }
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Person {
+            name: String,
+            address: Address,
+        }
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Company {
+            ceo: Person,
+            size: usize,
+        }
+
+        let company = Company {
+            ceo: Person {
+                name: "kim".to_string(),
+                address: Address {
+                    city: "seoul".to_string(),
+                    zip: 4524,
+                },
+            },
+            size: 10,
+        };
+
+        let ceo_city = lens!(Company, ceo).compose(lens!(Person, address)).compose(lens!(Address, city));
+        assert_eq!(ceo_city.view(&company), "seoul");
+
+        let moved = ceo_city.set(company.clone(), "busan".to_string());
+        assert_eq!(moved.ceo.address.city, "busan");
+        // the other parts are kept, and the original is untouched.
+        assert_eq!((moved.ceo.address.zip, moved.size, company.ceo.address.city.as_str()), (4524, 10, "seoul"));
+
+        let zip = lens!(Company, ceo.address.zip);
+        assert_eq!(zip.over(moved, |z| z + 1).ceo.address.zip, 4525);
+
+        // the lens laws.
+        let name = lens!(Company, ceo.name);
+        assert_eq!(name.view(&name.set(company.clone(), "lee".to_string())), "lee");
+        assert_eq!(name.set(company.clone(), name.view(&company)), company);
+
+        // a lens does not have to be a field.
+        let upper: Lens<String, String> = Lens::new(|s: &String| s.to_uppercase(), |_, a: String| a.to_lowercase());
+        assert_eq!(name.clone().compose(upper).over(company, |n| n + "!").ceo.name, "kim!");
+
+        #[derive(Debug, Clone, PartialEq)]
+        enum Json {
+            Number(f64),
+            Text(String),
+            Array(Vec<Json>),
+        }
+
+        let number = prism!(Json::Number);
+        assert_eq!(number.preview(&Json::Number(1.0)), Some(1.0));
+        assert_eq!(number.preview(&Json::Text("1".to_string())), None);
+        assert_eq!(number.review(2.0), Json::Number(2.0));
+
+        let first: Prism<Vec<Json>, Json> = Prism::new(|v: &Vec<Json>| v.first().cloned(), |j| vec![j]);
+        let first_number = prism!(Json::Array).compose(first).compose(number);
+        let doubled = first_number.over(Json::Array(vec![Json::Number(21.0)]), |n| n * 2.0);
+        assert_eq!(doubled, Json::Array(vec![Json::Number(42.0)]));
+        assert_eq!(first_number.over(Json::Array(vec![]), |n| n * 2.0), Json::Array(vec![]));
+    }
+
+    #[test]
+    fn derive_lenses_test() {
+        use crate::optics::Lenses;
+
+        #[derive(Debug, Clone, PartialEq, Lenses)]
+        struct Config {
+            name: String,
+            window: Window<u32>,
+            r#type: u8,
+        }
+
+        #[derive(Debug, Clone, PartialEq, Lenses)]
+        struct Window<T>
+        where
+            T: Copy,
+        {
+            width: T,
+            height: T,
+        }
+
+        let config = Config {
+            name: "main".to_string(),
+            window: Window { width: 800, height: 600 },
+            r#type: 1,
+        };
+
+        let width = Config::window_lens().compose(Window::width_lens());
+        let wide = width.over(config.clone(), |w| w * 2);
+        assert_eq!(wide.window, Window { width: 1600, height: 600 });
+        assert_eq!(Config::name_lens().view(&wide), "main");
+        assert_eq!(Config::type_lens().set(wide, 7).r#type, 7);
+
+        // the constructor is the same as `lens!`.
+        assert_eq!(
+            Config::window_lens().compose(Window::height_lens()).view(&config),
+            lens!(Config, window.height).view(&config)
+        );
+    }
+
+    #[test]
+    fn zipper_test() {
+        use crate::zipper::{Tree, Zipper};
+
+        //       4
+        //     2   6
+        //    1 3 5
+        let tree = Tree::node(Tree::node(Tree::single(1), 2, Tree::single(3)), 4, Tree::node(Tree::single(5), 6, Tree::Leaf));
+
+        // moving around without editing gives the same tree back.
+        let zipper = Zipper::new(tree.clone()).down_left().and_then(Zipper::down_right).unwrap();
+        assert_eq!((zipper.value(), zipper.depth()), (Some(&3), 2));
+        assert_eq!(zipper.clone().to_tree(), tree);
+        assert_eq!(zipper.up().and_then(Zipper::up).map(Zipper::to_tree), Some(tree.clone()));
+
+        let edited = Zipper::new(tree.clone())
+            .down_left()
+            .and_then(Zipper::down_right)
+            .map(|z| z.modify(|n| n * 100))
+            .and_then(Zipper::up)
+            .and_then(Zipper::up)
+            .and_then(Zipper::down_right)
+            .and_then(Zipper::down_right)
+            .map(|z| z.replace(Tree::single(7)))
+            .unwrap()
+            .to_tree();
+        assert_eq!(edited.in_order(), vec![&1, &2, &300, &4, &5, &6, &7]);
+        // the original tree is untouched.
+        assert_eq!(tree.in_order(), vec![&1, &2, &3, &4, &5, &6]);
+
+        let leaf = Zipper::new(tree.clone()).down_right().and_then(Zipper::down_right).unwrap();
+        assert_eq!(leaf.focus(), &Tree::Leaf);
+        assert_eq!(leaf.clone().modify(|n| n + 1).to_tree(), tree);
+        assert!(leaf.down_left().is_none());
+        assert!(Zipper::new(tree).up().is_none());
+    }
+
+    #[test]
+    fn list_test() {
+        use crate::list::List;
+        use crate::{applicative::Applicative, functor::Functor, monad::Monad};
+
+        let base: List<i32> = (1..=3).collect();
+        assert_eq!(base.strong_count(), 1);
+
+        // both versions share `base`, nothing is copied.
+        let a = base.push_front(0);
+        let b = base.push_front(10);
+        assert_eq!(base.strong_count(), 3);
+        assert_eq!(format!("{a:?} {b:?} {base:?}"), "[0, 1, 2, 3] [10, 1, 2, 3] [1, 2, 3]");
+        assert_eq!(
+            (a.len(), a.head(), a.tail().as_ref(), base.tail().map(|t| t.len())),
+            (4, Some(&0), Some(&base), Some(2))
+        );
+
+        drop(a);
+        drop(b);
+        assert_eq!(base.strong_count(), 1);
+
+        assert_eq!(base.fold(0, |acc, x| acc * 10 + x), 123);
+        assert_eq!(base.map(|x| x * x), List::from_iter([1, 4, 9]));
+        assert_eq!(base.reverse(), List::from_iter([3, 2, 1]));
+        assert_eq!((&base).into_iter().sum::<i32>(), 6);
+        assert!(List::<u8>::new().tail().is_none() && List::<u8>::default().is_empty());
+
+        assert_eq!(base.clone().fmap(|x| x.to_string()), List::from_iter(["1", "2", "3"].map(String::from)));
+        assert_eq!(List::pure(5), List::new().push_front(5));
+        let fs: List<fn(i32) -> i32> = List::from_iter([|x| x + 1, |x| x * 10] as [fn(i32) -> i32; 2]);
+        assert_eq!(base.clone().ap(fs.clone()), List::from_iter([2, 3, 4, 10, 20, 30]));
+        // the function list is still shared with `fs`.
+        assert_eq!(base.clone().ap(fs.tail().unwrap()), List::from_iter([10, 20, 30]));
+        assert_eq!(fs.len(), 2);
+        assert_eq!(base.clone().bind(|x| (0..x).collect()), List::from_iter([0, 0, 1, 0, 1, 2]));
+
+        // `f` can return a list that is still shared, its values are shared with the result.
+        let ys: List<String> = List::from_iter(["a", "b"].map(String::from));
+        let pairs = base.clone().bind(|_| ys.clone());
+        assert_eq!(ys.strong_count(), 1);
+        assert_eq!(pairs, List::from_iter(["a", "b", "a", "b", "a", "b"].map(String::from)));
+        assert_eq!(pairs.len(), 6);
+
+        // a long list is dropped without a recursion per node.
+        let long: List<u32> = (0..200_000).collect();
+        assert_eq!(long.len(), 200_000);
+        drop(long);
+    }
+
+    #[test]
+    fn persistent_map_test() {
+        use crate::persistent_map::PersistentMap;
+        use std::collections::BTreeMap;
+
+        // a small xorshift, so the operations are random but the same on every run.
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        let mut versions = vec![(PersistentMap::new(), BTreeMap::new())];
+        for step in 0..3000 {
+            let (map, model) = versions.last().unwrap().clone();
+            let key = random(200);
+            let (map, model) = if random(3) == 0 {
+                let mut model = model;
+                model.remove(&key);
+                (map.remove(&key), model)
+            } else {
+                let mut model = model;
+                model.insert(key, step);
+                (map.insert(key, step), model)
+            };
+            assert_eq!(map.len(), model.len());
+            assert_eq!(map.get(&key), model.get(&key));
+            versions.push((map, model));
+        }
+
+        // every version is still intact after all the later changes.
+        for (map, model) in &versions {
+            assert!(map.entries().eq(model.iter()));
+        }
+
+        // sorted inserts stay balanced, a plain BST would recurse 100_000 levels deep here.
+        let sorted: PersistentMap<u32, ()> = (0..100_000).map(|k| (k, ())).collect();
+        let removed = (0..50_000).fold(sorted.clone(), |map, k| map.remove(&(k * 2)));
+        assert_eq!(
+            (sorted.len(), removed.len(), removed.contains_key(&3), removed.contains_key(&4)),
+            (100_000, 50_000, true, false)
+        );
+        assert_eq!(removed.remove(&4).len(), 50_000);
+
+        let small = PersistentMap::new().insert(2, "b").insert(1, "a");
+        assert_eq!(format!("{small:?}"), r#"{1: "a", 2: "b"}"#);
+        assert_eq!(small.insert(1, "z").len(), 2);
+        assert_eq!(small, PersistentMap::from_iter([(1, "a"), (2, "b")]));
+    }
+
+    #[test]
+    fn nonempty_test() {
+        use crate::nonempty::NonEmpty;
+
+        let words = nonempty!["pear", "fig", "banana", "kiwi"];
+        assert_eq!(
+            (words.len(), words.tail(), words.min(), words.max()),
+            (4, &["fig", "banana", "kiwi"][..], &"banana", &"pear")
+        );
+        assert_eq!(words.clone().map(str::len).fold1(usize::max), 6);
+        assert_eq!(words.iter().filter(|w| w.len() == 4).count(), 2);
+
+        let single = NonEmpty::singleton(7);
+        assert_eq!((single.head(), single.last(), single.max(), single.clone().fold1(|a, b| a - b)), (&7, &7, &7, 7));
+
+        let mut pair = NonEmpty::from((1, vec![2]));
+        pair.push(3);
+        pair.extend([4, 5]);
+        assert_eq!(Vec::from(pair.clone()), vec![1, 2, 3, 4, 5]);
+        assert_eq!(pair.into_iter().rev().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1]);
+
+        assert_eq!(NonEmpty::from_vec(vec!['a', 'b']), Some(nonempty!['a', 'b']));
+        assert_eq!(NonEmpty::<char>::from_vec(vec![]), None);
+        // the comonad module uses the same type.
+        assert_eq!(crate::comonad::NonEmptyVec::new(1, vec![]), nonempty![1]);
+    }
+
+    #[test]
+    fn foldable_test() {
+        use crate::foldable::Foldable;
+        use crate::list::List;
+        use crate::monoid::{Max, Min, Product, Sum};
+        use crate::zipper::Tree;
+
+        let show = |x: &i32, acc: String| format!("({x} {acc})");
+
+        assert_eq!(Some(3).fold_map(|&x| Sum(x)), Sum(3));
+        assert_eq!(None::<i32>.fold_map(|&x| Sum(x)), Sum(0));
+        assert_eq!(None::<i32>.length(), 0);
+        assert_eq!(Some(1).to_vec(), vec![1]);
+
+        let xs = vec![1, 2, 3, 4];
+        assert_eq!(xs.fold_map(|&x| Product(x)), Product(24));
+        assert_eq!(xs.fold_right(String::from("."), show), "(1 (2 (3 (4 .))))");
+        assert_eq!(xs.fold_left(0, |acc, x| acc * 10 + x), 1234);
+        assert_eq!(xs.length(), 4);
+
+        let list: List<i32> = xs.iter().copied().collect();
+        assert_eq!(list.fold_map(|&x| Some(Min(x))), Some(Min(1)));
+        assert_eq!(list.fold_right(String::from("."), show), "(1 (2 (3 (4 .))))");
+        assert_eq!(list.to_vec(), xs);
+        assert_eq!(list.length(), 4);
+        assert_eq!(List::<i32>::new().fold_map(|&x| Some(Max(x))), None);
+
+        //     4
+        //    / \
+        //   2   5
+        //  / \
+        // 1   3
+        let tree = Tree::node(Tree::node(Tree::single(1), 2, Tree::single(3)), 4, Tree::single(5));
+        assert_eq!(tree.to_vec(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(tree.fold_map(|x| vec![x * 10]), vec![10, 20, 30, 40, 50]);
+        assert_eq!(tree.fold_right(String::from("."), show), "(1 (2 (3 (4 (5 .)))))");
+        assert_eq!(tree.fold_left(String::from("."), |acc, x| format!("({acc} {x})")), "(((((. 1) 2) 3) 4) 5)");
+        assert_eq!(tree.length(), 5);
+        assert_eq!(Tree::<i32>::Leaf.to_vec(), Vec::<i32>::new());
+
+        assert_eq!(nonempty![1, 2, 3].fold_map(|x| x.to_string()), "123");
+        assert_eq!(nonempty![1, 2, 3].fold_right(0, |x, acc| x - acc), 2);
+    }
+
+    #[test]
+    fn bifunctor_test() {
+        use crate::bifunctor::Bifunctor;
+        use crate::either::Either;
+        use crate::profunctor::{Function, Profunctor};
+
+        assert_eq!((1, 'a').bimap(|x| x * 10, |c| c.to_ascii_uppercase()), (10, 'A'));
+        assert_eq!((1, 'a').first(|x| x + 1), (2, 'a'));
+        assert_eq!((1, 'a').second(u32::from), (1, 97));
+
+        let ok: Result<i32, String> = Ok(2);
+        let err: Result<i32, String> = Err("bad".to_string());
+        assert_eq!(ok.clone().bimap(|x| x * 2, |e| e.len()), Ok(4));
+        assert_eq!(err.clone().bimap(|x| x * 2, |e| e.len()), Err(3));
+        assert_eq!(err.second(|e| format!("error: {e}")), Err("error: bad".to_string()));
+        // identity law
+        assert_eq!(ok.clone().bimap(|x| x, |e| e), ok);
+
+        let left: Either<i32, &str> = Either::Left(1);
+        assert_eq!(Bifunctor::first(left, |x| x - 1), Either::Left(0));
+        assert_eq!(Bifunctor::second(Either::<i32, &str>::Right("abc"), str::len), Either::Right(3));
+        // composition law
+        let (f1, f2) = (|x: i32| x + 1, |x: i32| x * 3);
+        assert_eq!(Bifunctor::bimap((4, 5), |a| f2(f1(a)), |b| f2(f1(b))), (4, 5).bimap(f1, f1).bimap(f2, f2));
+
+        let double = Function::new(|x: i32| x * 2);
+        assert_eq!(double.call(21), 42);
+
+        // parse the input first, show the output after.
+        let pipeline = double.dimap(|s: &str| s.parse::<i32>().unwrap_or(0), |x| format!("<{x}>"));
+        assert_eq!(pipeline.call("5"), "<10>");
+        assert_eq!(pipeline.call("nope"), "<0>");
+
+        let length = Function::new(|s: String| s.len()).lmap(|n: u32| "x".repeat(n as usize));
+        assert_eq!(length.call(3), 3);
+
+        let is_even = Function::new(|x: i32| x % 2 == 0).rmap(|b| if b { "even" } else { "odd" });
+        assert_eq!((is_even.call(1), is_even.call(2)), ("odd", "even"));
+    }
+
+    #[test]
+    fn arrow_test() {
+        use crate::arrow::{kleisli, Arrow};
+
+        let inc = |x: i32| x + 1;
+        let double = |x: i32| x * 2;
+
+        assert_eq!(inc.then(double)(3), 8);
+        assert_eq!(double.then(inc)(3), 7);
+        assert_eq!(inc.first()((1, "a")), (2, "a"));
+        assert_eq!(inc.second()(("a", 1)), ("a", 2));
+        assert_eq!(inc.split(|s: &str| s.len())((1, "abc")), (2, 3));
+        assert_eq!(inc.fanout(double)(5), (6, 10));
+        // `split` is `first` then `second`.
+        assert_eq!(inc.split(double)((1, 1)), inc.first().then(double.second())((1, 1)));
+
+        // an order pipeline: (price, quantity) -> total with tax, and whether it ships for free.
+        let subtotal = |(price, quantity): (u32, u32)| price * quantity;
+        let with_tax = |cents: u32| cents + cents / 10;
+        let free_shipping = |cents: u32| cents >= 5000;
+        let order = subtotal.then(with_tax).fanout(subtotal.then(free_shipping));
+        assert_eq!(order((1200, 2)), (2640, false));
+        assert_eq!(order((1250, 4)), (5500, true));
+
+        let orders = [(100, 1), (2500, 2)].map(order);
+        assert_eq!(orders, [(110, false), (5500, true)]);
+
+        let parse = |s: &str| s.parse::<u32>().map_err(|e| e.to_string());
+        let checked = |x: u32| 100u32.checked_div(x).ok_or("zero".to_string());
+        let mut divide = kleisli(parse, checked);
+        assert_eq!(divide("4"), Ok(25));
+        assert_eq!(divide("0"), Err("zero".to_string()));
+        assert!(divide("-").is_err());
+
+        let mut dupes = kleisli(|n: usize| vec![n; n], |n: usize| vec![n, n * 10]);
+        assert_eq!(dupes(2), vec![2, 20, 2, 20]);
+        let mut lookup = kleisli(|x: i32| Some(x), |x: i32| Some(inc(x)));
+        assert_eq!([1, 2].map(|x| lookup(x).map(double)), [Some(4), Some(6)]);
+    }
+
+    #[test]
+    fn typelevel_test() {
+        use crate::typelevel::*;
+
+        // these are checked when compiling, a wrong one is a type error instead of a failing test.
+        const _: () = assert_same::<Add<S2, S3>, S5>();
+        const _: () = assert_same::<Add<S0, S4>, S4>();
+        const _: () = assert_same::<Add<S4, S0>, S4>();
+        const _: () = assert_same::<Mul<S2, S3>, S6>();
+        const _: () = assert_same::<Mul<S3, S3>, S9>();
+        const _: () = assert_same::<Mul<S0, S7>, S0>();
+        const _: () = assert_same::<Mul<S7, S1>, S7>();
+        const _: () = assert_same::<Add<Mul<S2, S4>, S2>, S10>();
+        const _: () = assert!(<Mul<Add<S2, S3>, Mul<S2, S10>> as Nat>::VALUE == 100);
+
+        assert_eq!(S0::VALUE, 0);
+        assert_eq!(S10::VALUE, 10);
+        assert_eq!(<Add<S7, S8>>::VALUE, 15);
+        assert_eq!(<Mul<S8, S9>>::VALUE, 72);
+
+        fn value<N: Nat>(_: N) -> usize {
+            N::VALUE
+        }
+        assert_eq!(value(<Add<S1, S1>>::default()), 2);
+    }
+
+    #[test]
+    fn hlist_test() {
+        use crate::hlist::*;
+
+        let list: HList![i32, &str, char] = hlist![1, "two", '3'];
+        assert_eq!(list.len(), 3);
+        assert_eq!(<HList![i32, &str, char]>::LEN, 3);
+        assert!(HNil.is_empty());
+        assert_eq!(list.head, 1);
+        assert_eq!(list.tail.head, "two");
+
+        let mut list = list.prepend(4.5);
+        assert_eq!(list.len(), 4);
+        assert_eq!(*list.get::<f64, _>(), 4.5);
+        assert_eq!(*list.get::<char, _>(), '3');
+        *list.get_mut::<i32, _>() += 10;
+        assert_eq!(list, hlist![4.5, 11, "two", '3']);
+
+        let hlist_pat![head, number, _, c] = list;
+        assert_eq!((head, number, c), (4.5, 11, '3'));
+
+        // a poly function that doubles the numbers and leaves the other types alone.
+        struct Double;
+
+        impl Poly<i32> for Double {
+            type Output = i32;
+
+            fn call(&mut self, value: i32) -> i32 {
+                value * 2
+            }
+        }
+
+        impl Poly<f64> for Double {
+            type Output = f64;
+
+            fn call(&mut self, value: f64) -> f64 {
+                value * 2.0
+            }
+        }
+
+        impl Poly<&'static str> for Double {
+            type Output = String;
+
+            fn call(&mut self, value: &'static str) -> String {
+                value.repeat(2)
+            }
+        }
+
+        assert_eq!(hlist![1, 2.5, "ab"].map(Double), hlist![2, 5.0, "abab".to_string()]);
+        assert_eq!(HNil.map(Double), HNil);
+
+        // a stateful poly function.
+        struct Count(usize);
+
+        impl<T> Poly<T> for &mut Count {
+            type Output = T;
+
+            fn call(&mut self, value: T) -> T {
+                self.0 += 1;
+                value
+            }
+        }
+
+        let mut count = Count(0);
+        let same = hlist![(), 'a', [1, 2]].map(&mut count);
+        assert_eq!((same, count.0), (hlist![(), 'a', [1, 2]], 3));
+
+        let list: HList![u8, bool] = (1, true).into();
+        assert_eq!(list, hlist![1u8, true]);
+        assert_eq!(list.into_tuple(), (1, true));
+        let tuple: (u8, bool) = list.into();
+        assert_eq!(tuple, (1, true));
+
+        let eight = hlist![1, 2u8, 3u16, 4u32, 5u64, 6i8, '7', "8"];
+        assert_eq!(eight.len(), 8);
+        assert_eq!(eight.into_tuple(), (1, 2, 3, 4, 5, 6, '7', "8"));
+        assert_eq!(HNil::from(()).into_tuple(), ());
+    }
+
+    #[test]
+    fn typestate_test() {
+        use crate::typestate::*;
+
+        let request = Request::builder().url("https://example.com/items").send();
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.url, "https://example.com/items");
+        assert!(request.headers.is_empty());
+        assert_eq!(request.body, None);
+
+        // the other methods work before and after the url, in any order.
+        let request = Request::builder()
+            .header("Accept", "application/json")
+            .method(Method::Put)
+            .url("https://example.com/items/1")
+            .header("Content-Type", "text/plain")
+            .body("new name")
+            .send();
+        assert_eq!(request.method, Method::Put);
+        assert_eq!(
+            request.headers,
+            vec![
+                ("Accept".to_string(), "application/json".to_string()),
+                ("Content-Type".to_string(), "text/plain".to_string())
+            ]
+        );
+        assert_eq!(request.body.as_deref(), Some("new name"));
+
+        // a builder without the url can be kept and finished later.
+        let template = Request::builder().method(Method::Delete).header("Authorization", "token");
+        let first = template.clone().url("https://example.com/a").send();
+        let second = template.url("https://example.com/b").send();
+        assert_eq!((first.method, second.method), (Method::Delete, Method::Delete));
+        assert_eq!(first.headers, second.headers);
+        assert_ne!(first.url, second.url);
+    }
+
+    #[test]
+    fn phantom_test() {
+        use crate::phantom::*;
+
+        assert_eq!(std::mem::size_of::<Quantity<f64, Meters>>(), std::mem::size_of::<f64>());
+
+        let distance = meters(30.0) + meters(12.0) - meters(2.0);
+        assert_eq!(distance, meters(40.0));
+        assert_eq!(distance * 2.0, meters(80.0));
+        assert_eq!(distance / 4.0, meters(10.0));
+        assert!(meters(1.0) < meters(2.0));
+
+        let speed: Quantity<f64, MetersPerSecond> = distance / seconds(5.0);
+        assert_eq!(speed.value(), 8.0);
+        let acceleration: Quantity<f64, MetersPerSecondSquared> = speed / seconds(2.0);
+        assert_eq!(acceleration.value(), 4.0);
+
+        // and back again.
+        let speed_after: Quantity<f64, MetersPerSecond> = acceleration * seconds(3.0);
+        let travelled: Quantity<f64, Meters> = speed_after * seconds(10.0);
+        assert_eq!(travelled, meters(120.0));
+        assert_eq!(seconds(10.0) * speed_after, travelled);
+
+        let area: Quantity<i32, SquareMeters> = meters(3) * meters(4);
+        assert_eq!(area.value(), 12);
+        assert_eq!(area / meters(4), meters(3));
+
+        assert_eq!(format!("{area}"), "12 m²");
+        assert_eq!(format!("{acceleration:?}"), "Quantity(4.0 m/s²)");
+        assert_eq!(Quantity::<u8, Seconds>::new(7).to_string(), "7 s");
+    }
+
+    #[test]
+    fn branded_test() {
+        use crate::branded::scope;
+
+        let sorted = scope(vec![5, 3, 8, 1], |mut v| {
+            assert_eq!(v.len(), 4);
+            assert!(v.index(4).is_none());
+
+            // a selection sort with branded indices only, none of the accesses is bounds checked.
+            let indices: Vec<_> = v.indices().collect();
+            for (n, &i) in indices.iter().enumerate() {
+                let smallest = indices[n..].iter().copied().min_by_key(|&j| v[j]).unwrap();
+                v.swap(i, smallest);
+            }
+            v.into_inner()
+        });
+        assert_eq!(sorted, vec![1, 3, 5, 8]);
+
+        let (words, pushed) = scope(vec!["a".to_string()], |mut v| {
+            let first = v.index(0).unwrap();
+            let second = v.push("b".to_string());
+            // the old index is still valid after a push.
+            v[first].push('!');
+            v.get_mut(second).push('?');
+            (v.into_inner(), second.value())
+        });
+        assert_eq!(words, vec!["a!", "b?"]);
+        assert_eq!(pushed, 1);
+
+        // nested scopes get different brands, each index is used with its own vector.
+        let sum = scope(vec![1, 2], |outer| {
+            scope(vec![10, 20, 30], |inner| {
+                let i = outer.index(1).unwrap();
+                let j = inner.index(2).unwrap();
+                outer[i] + inner[j]
+            })
+        });
+        assert_eq!(sum, 32);
+
+        assert!(scope(Vec::<()>::new(), |v| v.is_empty() && v.indices().next().is_none()));
+        assert_eq!(scope(vec![0], |v| format!("{:?}", v.index(0).unwrap())), "Index(0)");
+    }
+
+    #[test]
+    fn gadt_test() {
+        use crate::gadt::*;
+
+        assert_eq!(int(7).eval(), 7);
+        assert!(boolean(true).eval());
+        assert_eq!(mul(add(int(1), int(2)), int(4)).eval(), 12);
+        assert!(less(int(1), int(2)).eval());
+        assert!(not(equal(boolean(true), boolean(false))).eval());
+
+        // the branches can have any type, as long as it is the same on both sides.
+        let max = |a: i64, b: i64| if_(less(int(a), int(b)), int(b), int(a));
+        assert_eq!(max(3, 9).eval(), 9);
+        assert_eq!(max(9, 3).eval(), 9);
+        let flag = if_(boolean(false), boolean(false), not(boolean(false)));
+        assert!(flag.eval());
+
+        let p = pair(add(int(20), int(22)), less(int(5), int(1)));
+        assert_eq!(p.eval(), (42, false));
+        assert_eq!(fst(p).eval(), 42);
+        assert_eq!(fst(p).show(), "fst ((20 + 22), (5 < 1))");
+
+        // boxed expressions of different shapes, all with the type `i64`.
+        let exprs: Vec<Boxed<i64>> = vec![boxed(int(1)), boxed(add(int(1), int(1))), boxed(if_(boolean(true), int(3), int(0)))];
+        assert_eq!(exprs.iter().map(Expr::eval).collect::<Vec<_>>(), vec![1, 2, 3]);
+        let sum = exprs.into_iter().fold(boxed(int(0)), |acc, e| boxed(add(acc, e)));
+        assert_eq!(sum.eval(), 6);
+        assert_eq!(sum.show(), "(((0 + 1) + (1 + 1)) + if true then 3 else 0)");
+    }
+
+    #[test]
+    fn visitor_test() {
+        use crate::visitor::*;
+        use std::f64::consts::PI;
+
+        let circle = Circle { radius: 1.0 };
+        let square = Rect { width: 2.0, height: 2.0 };
+        let triangle = Triangle { a: 3.0, b: 4.0, c: 5.0 };
+
+        assert_eq!(circle.accept(&mut Area), PI);
+        assert_eq!(square.accept(&mut Perimeter), 8.0);
+        assert_eq!(triangle.accept(&mut Area), 6.0);
+        assert_eq!(triangle.accept(&mut Perimeter), 12.0);
+
+        let shapes: Vec<AnyShape> = vec![circle.into(), square.into(), triangle.into(), square.into()];
+        let mut count = Count::default();
+        shapes.iter().for_each(|shape| shape.accept(&mut count));
+        assert_eq!(
+            count,
+            Count {
+                circles: 1,
+                rects: 2,
+                triangles: 1
+            }
+        );
+
+        let doubled: Vec<AnyShape> = shapes.iter().map(|shape| shape.accept(&mut Scale(2.0))).collect();
+        assert_eq!(doubled[1], AnyShape::Rect(Rect { width: 4.0, height: 4.0 }));
+        // the area grows by the square of the factor.
+        for (before, after) in shapes.iter().zip(&doubled) {
+            assert!((after.accept(&mut Area) - 4.0 * before.accept(&mut Area)).abs() < 1e-9);
+            assert!((after.accept(&mut Perimeter) - 2.0 * before.accept(&mut Perimeter)).abs() < 1e-9);
+        }
+
+        // a visitor defined outside of the module, the shapes don't change.
+        struct Name;
+
+        impl Visitor for Name {
+            type Output = &'static str;
+
+            fn visit_circle(&mut self, _: &Circle) -> Self::Output {
+                "circle"
+            }
+
+            fn visit_rect(&mut self, rect: &Rect) -> Self::Output {
+                if rect.width == rect.height {
+                    "square"
+                } else {
+                    "rect"
+                }
+            }
+
+            fn visit_triangle(&mut self, _: &Triangle) -> Self::Output {
+                "triangle"
+            }
+        }
+
+        let names: Vec<_> = doubled.iter().map(|shape| shape.accept(&mut Name)).collect();
+        assert_eq!(names, ["circle", "square", "triangle", "square"]);
+    }
+
+    #[test]
+    fn parser_test() {
+        use crate::parser::arithmetic::{self, Expr};
+        use crate::parser::*;
+
+        assert_eq!(char_('a').parse("abc"), Ok(('a', "bc")));
+        assert_eq!(char_('a').parse("xbc").unwrap_err().to_string(), "expected 'a', found 'x'");
+        assert_eq!(string("let").parse("let x"), Ok(("let", " x")));
+        assert_eq!(digit().parse("").unwrap_err().to_string(), "expected a digit, found the end of the input");
+        assert_eq!(satisfy("a vowel", |c| "aeiou".contains(c)).parse("é").unwrap_err().found, Some('é'));
+
+        let number = digit().many1().map(|ds| ds.into_iter().collect::<String>().parse::<u32>().unwrap());
+        assert_eq!(number.parse("123abc"), Ok((123, "abc")));
+        assert_eq!(digit().many().parse("abc"), Ok((vec![], "abc")));
+        assert!(digit().many1().parse("abc").is_err());
+
+        let list = number.clone().token().sep_by(char_(',').token()).between(char_('[').token(), char_(']'));
+        assert_eq!(list.parse_all("[1, 22 ,333]"), Ok(vec![1, 22, 333]));
+        assert_eq!(list.parse_all("[]"), Ok(vec![]));
+        let error = list.parse_all("[1, 2").unwrap_err();
+        assert_eq!(error.to_string(), "expected ']', found the end of the input");
+        assert_eq!(error.offset("[1, 2"), 5);
+        // a separator without an item after it is reported where the item is missing.
+        let error = digit().sep_by(char_(',')).parse_all("1,").unwrap_err();
+        assert_eq!(
+            (error.to_string(), error.offset("1,")),
+            ("expected a digit, found the end of the input".to_string(), 2)
+        );
+        assert_eq!(digit().sep_by(char_(',')).parse_all(""), Ok(vec![]));
+
+        let sign = char_('-')
+            .optional()
+            .zip(number.clone())
+            .map(|(sign, n)| if sign.is_some() { -(n as i64) } else { n as i64 });
+        assert_eq!(sign.parse_all("-42"), Ok(-42));
+        assert_eq!(sign.parse_all("42"), Ok(42));
+
+        // the value of a parser picks the next one, a length prefixed string.
+        let prefixed = number
+            .clone()
+            .skip(char_(':'))
+            .and_then(|n| satisfy("a letter", char::is_alphabetic).many().map(move |cs| cs.len() == n as usize));
+        assert_eq!(prefixed.parse_all("3:abc"), Ok(true));
+        assert_eq!(string("a").then(eof()).parse("a"), Ok(((), "")));
+        assert_eq!(pure(1).parse("x"), Ok((1, "x")));
+        assert_eq!(fail::<()>("nothing").parse("x").unwrap_err().expected, "nothing");
+
+        let num = |n| Box::new(Expr::Num(n));
+        assert_eq!(arithmetic::parse("42"), Ok(Expr::Num(42)));
+        // left associative, `-` binds tighter than `*`.
+        assert_eq!(arithmetic::parse("1 - 2 - 3"), Ok(Expr::Sub(Box::new(Expr::Sub(num(1), num(2))), num(3))));
+        assert_eq!(arithmetic::parse("-2 * 3"), Ok(Expr::Mul(Box::new(Expr::Neg(num(2))), num(3))));
+
+        let cases = [("1 + 2 * 3", 7), ("(1 + 2) * 3", 9), (" 10 / 3 - -1 ", 4), ("2 * (3 + (4 - 1)) / 3", 4), ("--5", 5)];
+        for (input, value) in cases {
+            assert_eq!(arithmetic::parse(input).unwrap().eval(), Some(value), "{input}");
+        }
+        assert_eq!(arithmetic::parse("1 / 0").unwrap().eval(), None);
+        assert_eq!(arithmetic::parse("99999999999999999999").unwrap_err().expected, "a smaller number");
+        assert_eq!(arithmetic::parse("1 + x").unwrap_err().to_string(), "expected '-' or a number or '(', found 'x'");
+
+        let error = arithmetic::parse("1 + * 2").unwrap_err();
+        assert_eq!(error.to_string(), "expected '-' or a number or '(', found '*'");
+        assert_eq!(error.offset("1 + * 2"), 4);
+        assert_eq!(arithmetic::parse("(1 + 2").unwrap_err().to_string(), "expected ')', found the end of the input");
+    }
+
+    #[test]
+    fn json_test() {
+        use crate::json::{parse_json, Json};
+        use std::collections::BTreeMap;
+
+        assert_eq!(parse_json("null"), Ok(Json::Null));
+        assert_eq!(parse_json(" true "), Ok(Json::Bool(true)));
+        assert_eq!(parse_json("\n\tfalse\r\n"), Ok(Json::Bool(false)));
+
+        let numbers = [
+            ("0", 0.0),
+            ("-0", -0.0),
+            ("42", 42.0),
+            ("-7", -7.0),
+            ("3.25", 3.25),
+            ("1e3", 1000.0),
+            ("2.5E-2", 0.025),
+            ("-1.5e+2", -150.0),
+        ];
+        for (input, number) in numbers {
+            assert_eq!(parse_json(input), Ok(Json::Number(number)), "{input}");
+        }
+
+        let strings = [
+            (r#""""#, ""),
+            (r#""hello world""#, "hello world"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#""a\\b\/c""#, "a\\b/c"),
+            (r#""\b\f\n\r\t""#, "\u{8}\u{c}\n\r\t"),
+            (r#""café""#, "café"),
+            (r#""😀""#, "😀"),
+            ("\"日本語\"", "日本語"),
+        ];
+        for (input, string) in strings {
+            assert_eq!(parse_json(input), Ok(Json::String(string.to_string())), "{input}");
+        }
+
+        assert_eq!(parse_json("[]"), Ok(Json::Array(vec![])));
+        assert_eq!(parse_json("[ ]"), Ok(Json::Array(vec![])));
+        assert_eq!(parse_json("{ }"), Ok(Json::Object(BTreeMap::new())));
+        assert_eq!(
+            parse_json("[1, [2, [3]], []]"),
+            Ok(Json::Array(vec![
+                Json::Number(1.0),
+                Json::Array(vec![Json::Number(2.0), Json::Array(vec![Json::Number(3.0)])]),
+                Json::Array(vec![])
+            ]))
+        );
+
+        let document = r#"
+            {
+                "id": 7,
+                "name": "widget",
+                "tags": ["a", "b"],
+                "dimensions": { "width": 2.5, "height": null },
+                "active": true,
+                "name": "duplicate, the last one wins"
+            }
+        "#;
+        let json = parse_json(document).unwrap();
+        assert_eq!(json.get("id"), Some(&Json::Number(7.0)));
+        assert_eq!(json.get("name"), Some(&Json::String("duplicate, the last one wins".to_string())));
+        assert_eq!(json.get("dimensions").and_then(|d| d.get("height")), Some(&Json::Null));
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(Json::Null.get("id"), None);
+
+        // printing and parsing again gives the same value.
+        let printed = json.to_string();
+        assert_eq!(
+            printed,
+            r#"{"active":true,"dimensions":{"height":null,"width":2.5},"id":7,"name":"duplicate, the last one wins","tags":["a","b"]}"#
+        );
+        assert_eq!(parse_json(&printed), Ok(json));
+        let tricky = Json::String("quote \" backslash \\ newline \n bell \u{7}".to_string());
+        assert_eq!(tricky.to_string(), r#""quote \" backslash \\ newline \n bell \u0007""#);
+        assert_eq!(parse_json(&tricky.to_string()), Ok(tricky));
+        assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+
+        // (input, offset of the error, message)
+        let errors = [
+            ("", 0, "expected a JSON value, found the end of the input"),
+            ("nul", 0, "expected a JSON value, found 'n'"),
+            ("[1, 2", 5, "expected ']', found the end of the input"),
+            ("[1,]", 3, "expected a JSON value, found ']'"),
+            ("[tru]", 1, "expected ']' or a JSON value, found 't'"),
+            ("{\"a\" 1}", 5, "expected ':', found '1'"),
+            ("{\"a\": 1,}", 8, "expected '\"', found '}'"),
+            ("{1: 2}", 1, "expected '}' or '\"', found '1'"),
+            ("\"unterminated", 13, "expected '\"', found the end of the input"),
+            ("\"line\nbreak\"", 5, "expected '\"', found '\\n'"),
+            (r#""\x""#, 2, "expected an escape char or 'u', found 'x'"),
+            (r#""\u12""#, 5, "expected a hex digit, found '\"'"),
+            (r#""\ud83d""#, 7, "expected a low surrogate, found '\"'"),
+            (r#""\ude00""#, 7, "expected a high surrogate before the low one, found '\"'"),
+            ("01", 1, "expected the end of the input, found '1'"),
+            ("1.", 2, "expected a digit, found the end of the input"),
+            ("1e+", 3, "expected a digit, found the end of the input"),
+            ("-", 1, "expected '0' or a digit, found the end of the input"),
+            ("[1] [2]", 4, "expected the end of the input, found '['"),
+        ];
+        for (input, offset, message) in errors {
+            let error = parse_json(input).unwrap_err();
+            assert_eq!((error.offset(input), error.to_string().as_str()), (offset, message), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn pratt_test() {
+        use crate::parser::arithmetic;
+        use crate::pratt::*;
+
+        let table = OperatorTable::arithmetic();
+        let show = |input: &str| table.parse(input).map(|e| e.to_string());
+
+        assert_eq!(show("42"), Ok("42".to_string()));
+        assert_eq!(show("1 - 2 - 3"), Ok("(- (- 1 2) 3)".to_string()));
+        assert_eq!(show("2 ^ 3 ^ 4"), Ok("(^ 2 (^ 3 4))".to_string()));
+        assert_eq!(show("a * (b + c) % d"), Ok("(% (* a (+ b c)) d)".to_string()));
+        assert_eq!(show("--x"), Ok("(- (- x))".to_string()));
+        assert_eq!(show("1 - -2"), Ok("(- 1 (- 2))".to_string()));
+        assert_eq!(show("n! ^ 2"), Ok("(^ (! n) 2)".to_string()));
+        assert_eq!(show("-n!"), Ok("(- (! n))".to_string()));
+        assert_eq!(show("((x))"), Ok("x".to_string()));
+        assert_eq!(show("f() + g(1, h(x) * 2)"), Ok("(+ (f) (g 1 (* (h x) 2)))".to_string()));
+        assert_eq!(show("let x = 1 + 2 in x * x"), Ok("(let x (+ 1 2) (* x x))".to_string()));
+        assert_eq!(
+            show("let sq(n) = n * n in let y = 3 in sq(y)"),
+            Ok("(let (sq n) (* n n) (let y 3 (sq y)))".to_string())
+        );
+        assert_eq!(show("let answer = 42"), Ok("(let answer 42)".to_string()));
+        assert_eq!(table.parse("f(1,)"), Err(PrattError::UnexpectedToken(")".to_string(), 4)));
+        assert_eq!(table.parse("let 1 = 2"), Err(PrattError::UnexpectedToken("1".to_string(), 4)));
+        assert_eq!(table.parse("let x 2"), Err(PrattError::UnexpectedToken("2".to_string(), 6)));
+
+        assert_eq!(table.parse("1 +"), Err(PrattError::UnexpectedEnd));
+        assert_eq!(table.parse("1 2"), Err(PrattError::UnexpectedToken("2".to_string(), 2)));
+        assert_eq!(table.parse("(1 + 2"), Err(PrattError::UnexpectedEnd));
+        assert_eq!(table.parse("1 + 2)"), Err(PrattError::UnexpectedToken(")".to_string(), 5)));
+        assert_eq!(table.parse("* 1"), Err(PrattError::UnexpectedToken("*".to_string(), 0)));
+        assert_eq!(table.parse("1 $ 2"), Err(PrattError::UnexpectedChar('$', 2)));
+        assert_eq!(table.parse("99999999999999999999").unwrap_err().to_string(), "number too large at 0");
+
+        // still left associative at the highest precedence.
+        let top = OperatorTable::new().binary("-", u8::MAX, Assoc::Left).binary("+", u8::MAX - 1, Assoc::Left);
+        assert_eq!(top.parse("1 - 2 - 3 + 4").map(|e| e.to_string()), Ok("(+ (- (- 1 2) 3) 4)".to_string()));
+
+        // operators added at runtime, multi-char symbols and words are fine.
+        let logic = OperatorTable::new()
+            .binary("||", 1, Assoc::Left)
+            .binary("&&", 2, Assoc::Left)
+            .binary("==", 3, Assoc::Left)
+            .binary("<=", 3, Assoc::Left)
+            .binary("<", 3, Assoc::Left)
+            .binary("+", 4, Assoc::Left)
+            .binary("**", 6, Assoc::Right)
+            .binary("*", 5, Assoc::Left)
+            .prefix("!", 7);
+        assert_eq!(logic.parse("a <= b && !c || d == 1").unwrap().to_string(), "(|| (&& (<= a b) (! c)) (== d 1))");
+        assert_eq!(logic.parse("2 * 3 ** 2 ** 2 < x").unwrap().to_string(), "(< (* 2 (** 3 (** 2 2))) x)");
+        // `-` is not an operator of `logic`.
+        assert_eq!(logic.parse("-a"), Err(PrattError::UnexpectedChar('-', 0)));
+        // changing the table changes the parse.
+        assert_eq!(
+            OperatorTable::arithmetic().binary("-", 10, Assoc::Right).parse("1 - 2 - 3").unwrap().to_string(),
+            "(- 1 (- 2 3))"
+        );
+
+        // the same trees as the combinator parser, for the operators they both have.
+        fn convert(expr: Expr) -> arithmetic::Expr {
+            let pair = |a: Box<Expr>, b: Box<Expr>| (Box::new(convert(*a)), Box::new(convert(*b)));
+            match expr {
+                Expr::Num(n) => arithmetic::Expr::Num(n),
+                Expr::Prefix(op, e) if op == "-" => arithmetic::Expr::Neg(Box::new(convert(*e))),
+                Expr::Binary(op, a, b) => {
+                    let (a, b) = pair(a, b);
+                    match op.as_str() {
+                        "+" => arithmetic::Expr::Add(a, b),
+                        "-" => arithmetic::Expr::Sub(a, b),
+                        "*" => arithmetic::Expr::Mul(a, b),
+                        "/" => arithmetic::Expr::Div(a, b),
+                        op => panic!("no {op} in the combinator parser"),
+                    }
+                }
+                e => panic!("no {e} in the combinator parser"),
+            }
+        }
+
+        let table = OperatorTable::new()
+            .binary("+", 1, Assoc::Left)
+            .binary("-", 1, Assoc::Left)
+            .binary("*", 2, Assoc::Left)
+            .binary("/", 2, Assoc::Left)
+            .prefix("-", 3);
+        for input in [
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "10 / 3 - -1",
+            "2 * (3 + (4 - 1)) / 3",
+            "--5",
+            "1 - 2 - 3 * 4 / 5",
+            "-2 * 3",
+        ] {
+            assert_eq!(convert(table.parse(input).unwrap()), arithmetic::parse(input).unwrap(), "{input}");
+        }
+    }
+
+    #[test]
+    fn interpreter_test() {
+        use crate::interpreter::*;
+        use crate::pratt::PrattError;
+
+        let run = |line: &str| eval(&table().parse(line)?, &Env::standard());
+
+        assert_eq!(run("1 + 2 * 3"), Ok(Value::Int(7)));
+        assert_eq!(run("2 ^ 10 - 5!"), Ok(Value::Int(904)));
+        assert_eq!(run("7 / 2 + 7 % 2"), Ok(Value::Int(4)));
+        assert_eq!(run("1 < 2 && !(3 == 4) || false"), Ok(Value::Bool(true)));
+        assert_eq!(run("let x = 5 in let y = x * 2 in y - x"), Ok(Value::Int(5)));
+        assert_eq!(run("max(abs(-7), min(3, 9))"), Ok(Value::Int(7)));
+
+        // the inner binding shadows the outer one, only inside its body.
+        assert_eq!(run("let x = 1 in (let x = 10 in x) + x"), Ok(Value::Int(11)));
+        // closures capture the environment where they are defined, not where they are called.
+        assert_eq!(run("let n = 100 in let add_n(x) = x + n in let n = 1 in add_n(1)"), Ok(Value::Int(101)));
+        assert_eq!(run("let fib(n) = if(n < 2, n, fib(n - 1) + fib(n - 2)) in fib(15)"), Ok(Value::Int(610)));
+        assert_eq!(run("let gcd(a, b) = if(b == 0, a, gcd(b, a % b)) in gcd(84, 36)"), Ok(Value::Int(12)));
+        // `&&` and `if` don't evaluate what they don't need, `1 / 0` would fail.
+        assert_eq!(run("false && 1 / 0 == 1"), Ok(Value::Bool(false)));
+        assert_eq!(run("if(true, 1, 1 / 0)"), Ok(Value::Int(1)));
+
+        assert_eq!(run("1 / 0"), Err(EvalError::DivisionByZero));
+        assert_eq!(run("2 ^ 64"), Err(EvalError::Overflow));
+        assert_eq!(run("25!"), Err(EvalError::Overflow));
+        assert_eq!(run("y + 1"), Err(EvalError::UnboundVariable("y".to_string())));
+        assert_eq!(run("1 + true"), Err(EvalError::TypeMismatch("int", "bool")));
+        assert_eq!(run("if(1, 2, 3)").unwrap_err().to_string(), "expected bool, found int");
+        assert_eq!(run("let f(a, b) = a in f(1)").unwrap_err().to_string(), "`f` takes 2 arguments, 1 given");
+        assert_eq!(run("abs(1, 2)"), Err(EvalError::Arity("abs".to_string(), 1, 2)));
+        assert_eq!(run("let x = 1 in x(2)"), Err(EvalError::NotAFunction("x".to_string())));
+        assert_eq!(run("1 +"), Err(EvalError::Parse(PrattError::UnexpectedEnd)));
+
+        // a session, the definitions stay for the next lines.
+        let mut repl = Repl::new();
+        let session = [
+            ("let rate = 3", "3"),
+            ("let cost(items) = items * rate + 1", "<fn cost(items)>"),
+            ("cost(4)", "13"),
+            ("let rate = 10", "10"),
+            // `cost` still sees the old `rate`.
+            ("cost(4)", "13"),
+            ("let square(x) = x * x", "<fn square(x)>"),
+            ("let sum_squares(n) = if(n == 0, 0, square(n) + sum_squares(n - 1))", "<fn sum_squares(n)>"),
+            ("sum_squares(10)", "385"),
+            ("square == square", "true"),
+            ("abs", "<builtin abs>"),
+            ("let ok = sum_squares(3) > rate in !ok", "false"),
+        ];
+        for (line, output) in session {
+            assert_eq!(repl.eval(line).map(|v| v.to_string()), Ok(output.to_string()), "{line}");
+        }
+        assert_eq!(repl.eval("ok"), Err(EvalError::UnboundVariable("ok".to_string())));
+        // a failed line changes nothing.
+        assert!(repl.eval("let broken = 1 / 0").is_err());
+        assert!(repl.eval("broken").is_err());
+        assert_eq!(repl.eval("rate"), Ok(Value::Int(10)));
+    }
+
+    #[test]
+    fn regexlite_test() {
+        use crate::regexlite::{Regex, RegexError};
+
+        // (pattern, text, is_match, is_full_match)
+        let cases = [
+            ("abc", "abc", true, true),
+            ("abc", "xabcx", true, false),
+            ("abc", "ab", false, false),
+            ("a.c", "abc", true, true),
+            ("a.c", "a\nc", true, true),
+            ("a.c", "ac", false, false),
+            ("ab*c", "ac", true, true),
+            ("ab*c", "abbbbc", true, true),
+            ("ab+c", "ac", false, false),
+            ("ab+c", "abbc", true, true),
+            ("colou?r", "color", true, true),
+            ("colou?r", "colour", true, true),
+            ("colou?r", "colouur", false, false),
+            ("cat|dog", "hotdog", true, false),
+            ("cat|dog", "dog", true, true),
+            ("cat|dog|bird", "bird", true, true),
+            ("(ab)+", "ababab", true, true),
+            ("(ab)+", "aba", true, false),
+            ("a(b|c)*d", "abcbcd", true, true),
+            ("a(b|c)*d", "abxd", false, false),
+            ("(a|b)*abb", "babaabb", true, true),
+            ("", "", true, true),
+            ("", "anything", true, false),
+            ("a|", "", true, true),
+            ("(a*)*", "aaa", true, true),
+            ("(a?)+b", "b", true, true),
+            (r"1\+1", "1+1", true, true),
+            (r"\(\.\)", "(.)", true, true),
+            (r"\(\.\)", "(x)", false, false),
+            ("é+", "ééé", true, true),
+            (".*", "", true, true),
+        ];
+        for (pattern, text, is_match, is_full_match) in cases {
+            let re = Regex::new(pattern).unwrap();
+            assert_eq!((re.is_match(text), re.is_full_match(text)), (is_match, is_full_match), "{pattern:?} on {text:?}");
+        }
+
+        assert_eq!(Regex::new("(ab").unwrap_err(), RegexError::UnbalancedParen(0));
+        assert_eq!(Regex::new("ab)").unwrap_err(), RegexError::UnbalancedParen(2));
+        assert_eq!(Regex::new("a(b(c)").unwrap_err(), RegexError::UnbalancedParen(1));
+        assert_eq!(Regex::new("*a").unwrap_err(), RegexError::NothingToRepeat(0));
+        assert_eq!(Regex::new("a|+").unwrap_err(), RegexError::NothingToRepeat(2));
+        assert_eq!(Regex::new("ab\\").unwrap_err().to_string(), "trailing backslash");
+
+        // the pathological case of backtracking engines, `a?^n a^n` on `a^n`, is linear here.
+        let n = 30;
+        let re = Regex::new(&format!("{}{}", "a?".repeat(n), "a".repeat(n))).unwrap();
+        assert!(re.is_full_match(&"a".repeat(n)));
+        assert!(!re.is_full_match(&"a".repeat(n - 1)));
+        assert!(re.state_count() <= 3 * n + 1);
+    }
+
+    #[test]
+    fn fsm_test() {
+        use crate::fsm::{state_machine, Hooks, InvalidTransition, Machine, StateMachine};
+
+        state_machine! {
+            Turnstile {
+                Locked --insert_coin--> Unlocked,
+                Unlocked --push--> Locked,
+                Unlocked --insert_coin--> Unlocked,
+            }
+        }
+
+        assert_eq!(TurnstileState::default(), TurnstileState::Locked);
+        assert_eq!(
+            Turnstile::transition(TurnstileState::Locked, TurnstileEvent::InsertCoin),
+            Ok(TurnstileState::Unlocked)
+        );
+        assert_eq!(
+            Turnstile::transition(TurnstileState::Unlocked, TurnstileEvent::InsertCoin),
+            Ok(TurnstileState::Unlocked)
+        );
+        let err = Turnstile::transition(TurnstileState::Locked, TurnstileEvent::Push).unwrap_err();
+        assert_eq!(
+            err,
+            InvalidTransition {
+                state: TurnstileState::Locked,
+                event: TurnstileEvent::Push
+            }
+        );
+        assert_eq!(err.to_string(), "no transition from Locked on Push");
+        assert_eq!(Turnstile::TRANSITIONS[1], (TurnstileState::Unlocked, TurnstileEvent::Push, TurnstileState::Locked));
+
+        #[derive(Default)]
+        struct Trace(Vec<String>);
+
+        impl Hooks<TurnstileState, TurnstileEvent> for Trace {
+            fn on_exit(&mut self, state: TurnstileState, event: TurnstileEvent) {
+                self.0.push(format!("exit {state:?} on {event:?}"));
+            }
+
+            fn on_enter(&mut self, state: TurnstileState, event: TurnstileEvent) {
+                self.0.push(format!("enter {state:?} on {event:?}"));
+            }
+        }
+
+        let mut turnstile = Machine::<Turnstile, _>::with_hooks(TurnstileState::default(), Trace::default());
+        assert!(turnstile.fire(TurnstileEvent::Push).is_err());
+        assert_eq!(turnstile.state(), TurnstileState::Locked);
+        assert!(turnstile.hooks().0.is_empty());
+
+        assert_eq!(turnstile.fire(TurnstileEvent::InsertCoin), Ok(TurnstileState::Unlocked));
+        assert_eq!(turnstile.fire(TurnstileEvent::Push), Ok(TurnstileState::Locked));
+        assert_eq!(
+            turnstile.hooks().0,
+            [
+                "exit Locked on InsertCoin",
+                "enter Unlocked on InsertCoin",
+                "exit Unlocked on Push",
+                "enter Locked on Push"
+            ]
+        );
+
+        let mut plain = Machine::<Turnstile>::new(TurnstileState::Unlocked);
+        assert_eq!(plain.fire(TurnstileEvent::Push), Ok(TurnstileState::Locked));
+    }
+
+    #[test]
+    fn event_bus_test() {
+        use crate::event_bus::{Bus, Handler};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        #[derive(Debug, PartialEq)]
+        struct Deposit(u32);
+        struct Withdraw(u32);
+
+        struct Ledger(Rc<RefCell<Vec<String>>>);
+
+        impl Handler<Withdraw> for Ledger {
+            fn handle(&mut self, Withdraw(amount): &Withdraw) {
+                self.0.borrow_mut().push(format!("ledger -{amount}"));
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = Bus::new();
+        let first = bus.subscribe({
+            let log = log.clone();
+            move |Deposit(amount): &Deposit| log.borrow_mut().push(format!("first +{amount}"))
+        });
+        bus.subscribe({
+            let log = log.clone();
+            move |Deposit(amount): &Deposit| log.borrow_mut().push(format!("second +{amount}"))
+        });
+        bus.subscribe(Ledger(log.clone()));
+
+        assert_eq!(bus.subscribers::<Deposit>(), 2);
+        assert_eq!(bus.publish(Deposit(10)), 2);
+        assert_eq!(bus.publish(Withdraw(3)), 1);
+        assert_eq!(bus.publish("nobody listens"), 0);
+        assert_eq!(*log.borrow(), ["first +10", "second +10", "ledger -3"]);
+
+        assert!(bus.unsubscribe(first));
+        assert!(!bus.unsubscribe(first));
+        log.borrow_mut().clear();
+        assert_eq!(bus.publish(Deposit(5)), 1);
+        assert_eq!(*log.borrow(), ["second +5"]);
+
+        let third = bus.subscribe({
+            let log = log.clone();
+            move |Deposit(amount): &Deposit| log.borrow_mut().push(format!("third +{amount}"))
+        });
+        log.borrow_mut().clear();
+        bus.publish(Deposit(1));
+        assert_eq!(*log.borrow(), ["second +1", "third +1"]);
+        assert!(bus.unsubscribe(third));
+        assert_eq!(bus.subscribers::<Withdraw>(), 1);
+    }
+
+    #[test]
+    fn actor_test() {
+        use crate::actor::{spawn_actor, Actor, Stopped};
+        use std::sync::mpsc;
+        use std::thread;
+
+        #[derive(Default)]
+        struct Bank {
+            balance: u64,
+            events: Vec<&'static str>,
+        }
+
+        enum Msg {
+            Deposit(u64),
+            Withdraw(u64),
+            Balance,
+        }
+
+        impl Actor for Bank {
+            type Msg = Msg;
+            type Reply = Result<u64, &'static str>;
+
+            fn handle(&mut self, msg: Msg) -> Self::Reply {
+                match msg {
+                    Msg::Deposit(amount) => self.balance += amount,
+                    Msg::Withdraw(amount) if amount > self.balance => return Err("insufficient funds"),
+                    Msg::Withdraw(amount) => self.balance -= amount,
+                    Msg::Balance => {}
+                }
+                Ok(self.balance)
+            }
+
+            fn started(&mut self) {
+                self.events.push("started");
+            }
+
+            fn stopped(&mut self) {
+                self.events.push("stopped");
+            }
+        }
+
+        let bank = spawn_actor(Bank::default());
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let bank = bank.clone();
+                thread::spawn(move || (0..25).for_each(|_| bank.send(Msg::Deposit(1)).unwrap()))
+            })
+            .collect();
+        workers.into_iter().for_each(|worker| worker.join().unwrap());
+        assert_eq!(bank.ask(Msg::Balance), Ok(Ok(100)));
+        assert_eq!(bank.ask(Msg::Withdraw(150)), Ok(Err("insufficient funds")));
+        assert_eq!(bank.ask(Msg::Withdraw(40)), Ok(Ok(60)));
+
+        // queued messages are handled before the shutdown.
+        bank.send(Msg::Deposit(5)).unwrap();
+        let other = bank.clone();
+        let bank = bank.shutdown().unwrap();
+        assert_eq!(bank.balance, 65);
+        assert_eq!(bank.events, ["started", "stopped"]);
+        assert!(other.shutdown().is_none());
+        assert_eq!(other.send(Msg::Deposit(1)), Err(Stopped));
+        assert_eq!(other.ask(Msg::Balance), Err(Stopped));
+
+        // dropping every address stops the actor too.
+        struct Echo(mpsc::Sender<&'static str>);
+
+        impl Actor for Echo {
+            type Msg = &'static str;
+            type Reply = ();
+
+            fn handle(&mut self, msg: &'static str) {
+                self.0.send(msg).unwrap();
+            }
+
+            fn stopped(&mut self) {
+                self.0.send("bye").unwrap();
+            }
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let echo = spawn_actor(Echo(tx));
+        echo.send("hi").unwrap();
+        drop(echo);
+        assert_eq!(rx.iter().collect::<Vec<_>>(), ["hi", "bye"]);
+    }
+
+    #[test]
+    fn channel_ext_test() {
+        use crate::channel_ext::{fan_out, filter_channel, map_channel, merge};
+        use std::sync::mpsc;
+        use std::thread;
+
+        let (a, ra) = mpsc::channel();
+        let (b, rb) = mpsc::channel();
+        let producers = [
+            thread::spawn(move || (0..100).for_each(|i| a.send(i).unwrap())),
+            thread::spawn(move || (100..200).for_each(|i| b.send(i).unwrap())),
+        ];
+        // ends once both senders are dropped.
+        let merged: Vec<i32> = merge(ra, rb).iter().collect();
+        producers.into_iter().for_each(|producer| producer.join().unwrap());
+        assert_eq!(merged.len(), 200);
+        let (low, high): (Vec<i32>, Vec<i32>) = merged.into_iter().partition(|&i| i < 100);
+        assert_eq!(low, (0..100).collect::<Vec<_>>());
+        assert_eq!(high, (100..200).collect::<Vec<_>>());
+
+        let (tx, rx) = mpsc::channel();
+        let evens = map_channel(filter_channel(rx, |i: &u32| i.is_multiple_of(2)), |i| format!("#{i}"));
+        (0..7).for_each(|i| tx.send(i).unwrap());
+        drop(tx);
+        assert_eq!(evens.iter().collect::<Vec<_>>(), ["#0", "#2", "#4", "#6"]);
+
+        // the dropped receiver is skipped and the others keep getting values in turn.
+        let (tx, rx) = mpsc::channel();
+        let mut outputs = fan_out(rx, 3);
+        drop(outputs.remove(1));
+        (0..6).for_each(|i| tx.send(i).unwrap());
+        drop(tx);
+        let first: Vec<_> = outputs[0].iter().collect();
+        let last: Vec<_> = outputs[1].iter().collect();
+        assert_eq!(first.len() + last.len(), 6);
+        assert!(first.windows(2).all(|w| w[0] < w[1]) && last.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(first[0], 0);
+
+        // dropping the output stops the forwarding thread, so the input's sender sees a disconnect.
+        let (tx, rx) = mpsc::channel();
+        let mapped = map_channel(rx, |i: i32| i + 1);
+        tx.send(1).unwrap();
+        assert_eq!(mapped.recv(), Ok(2));
+        drop(mapped);
+        tx.send(2).unwrap();
+        let mut disconnected = false;
+        for _ in 0..1000 {
+            if tx.send(3).is_err() {
+                disconnected = true;
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(disconnected);
+
+        // rejected values aren't sent, so the filter only notices the dropped receiver at the next matching value.
+        let (tx, rx) = mpsc::channel();
+        drop(filter_channel(rx, |i: &u32| i.is_multiple_of(2)));
+        for _ in 0..50 {
+            tx.send(1).unwrap();
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        tx.send(2).unwrap();
+        assert!((0..1000).any(|_| {
+            thread::sleep(std::time::Duration::from_millis(1));
+            tx.send(1).is_err()
+        }));
+    }
+
+    #[test]
+    fn pool_test() {
+        use crate::pool::Pool;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        fn fib(pool: &Pool, n: u64) -> u64 {
+            if n < 12 {
+                return (0..n).fold((0, 1), |(a, b), _| (b, a + b)).0;
+            }
+            let (a, b) = pool.join(|| fib(pool, n - 1), || fib(pool, n - 2));
+            a + b
+        }
+
+        fn is_prime(n: u64) -> bool {
+            n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+        }
+
+        let pool = Pool::new(4);
+        assert_eq!(pool.threads(), 4);
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        (0..100).for_each(|_| {
+            let counter = counter.clone();
+            pool.execute(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+        });
+        pool.execute(|| panic!("a failing job doesn't take its worker down"));
+        let task = pool.spawn(|| "done");
+        assert_eq!(task.join(), "done");
+
+        // nested joins inside the workers, which only finish because waiting threads steal work.
+        assert_eq!(fib(&Pool::new(2), 25), 75025);
+
+        let mut data: Vec<u64> = (0..1000).collect();
+        pool.scope(|s| {
+            for chunk in data.chunks_mut(100) {
+                s.spawn(move || chunk.iter_mut().for_each(|x| *x *= 2));
+            }
+        });
+        assert_eq!(data.iter().sum::<u64>(), 999_000);
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.scope(|s| {
+                s.spawn(|| panic!("boom"));
+                s.spawn(|| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            })
+        }));
+        assert!(panicked.is_err());
+        assert!(std::panic::catch_unwind(|| Pool::new(1).spawn(|| -> u8 { panic!("boom") }).join()).is_err());
+
+        // the same counts as a sequential run.
+        let ranges: Vec<_> = (0..16u64).map(|i| i * 20_000..(i + 1) * 20_000).collect();
+        let sequential: usize = ranges.iter().map(|range| range.clone().filter(|&n| is_prime(n)).count()).sum();
+        let mut counts = vec![0; ranges.len()];
+        pool.scope(|s| {
+            for (range, count) in ranges.iter().zip(counts.iter_mut()) {
+                s.spawn(move || *count = range.clone().filter(|&n| is_prime(n)).count());
+            }
+        });
+        assert_eq!(counts.iter().sum::<usize>(), sequential);
+
+        // dropping the pool finishes what was queued.
+        drop(pool);
+        assert_eq!(counter.load(Ordering::SeqCst), 101);
+    }
+
+    #[test]
+    fn stm_test() {
+        use crate::stm::{atomically, TVar};
+        use std::thread;
+
+        let counter = TVar::new(0u64);
+        let attempts = TVar::new(0u64);
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..500 {
+                        atomically(|tx| {
+                            let v = tx.read(&counter)?;
+                            tx.write(&counter, v + 1)
+                        });
+                    }
+                });
+            }
+        });
+        assert_eq!(counter.get(), 4000);
+
+        // transfers keep the total, and no attempt ever sees it broken.
+        let accounts: Vec<TVar<i64>> = (0..4).map(|_| TVar::new(1000)).collect();
+        thread::scope(|s| {
+            for worker in 0..4usize {
+                let accounts = &accounts;
+                let attempts = &attempts;
+                s.spawn(move || {
+                    for i in 0..300usize {
+                        let (from, to) = (&accounts[(worker + i) % 4], &accounts[(worker + i + 1) % 4]);
+                        atomically(|tx| {
+                            let a = tx.read(from)?;
+                            let b = tx.read(to)?;
+                            tx.write(from, a - 7)?;
+                            tx.write(to, b + 7)
+                        });
+                        atomically(|tx| {
+                            let mut total = 0;
+                            for account in accounts {
+                                total += tx.read(account)?;
+                            }
+                            assert_eq!(total, 4000);
+                            let n = tx.read(attempts)?;
+                            tx.write(attempts, n + 1)
+                        });
+                    }
+                });
+            }
+        });
+        assert_eq!(accounts.iter().map(TVar::get).sum::<i64>(), 4000);
+        assert_eq!(attempts.get(), 1200);
+
+        // a write is visible to later reads in the same transaction, and an explicit retry runs it again.
+        let flag = TVar::new(false);
+        let mut runs = 0;
+        let seen = atomically(|tx| {
+            runs += 1;
+            if runs < 3 {
+                return tx.retry();
+            }
+            tx.write(&flag, true)?;
+            tx.read(&flag)
+        });
+        assert!(seen && flag.get());
+        assert_eq!(runs, 3);
+        assert_eq!(format!("{flag:?}"), "TVar(true)");
+    }
+
+    #[test]
+    fn rate_limiter_test() {
+        use crate::rate_limiter::{throttled, Clock, Limiter, ManualClock, SlidingWindow, TokenBucket};
+        use std::time::Duration;
+
+        let clock = ManualClock::new();
+        let bucket = TokenBucket::with_clock(4.0, 2, clock.clone());
+        assert!(bucket.try_acquire() && bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+        assert_eq!(bucket.available_in(), Duration::from_millis(250));
+        clock.advance(Duration::from_millis(100));
+        assert!(!bucket.try_acquire());
+        clock.advance(Duration::from_millis(150));
+        assert!(bucket.try_acquire());
+
+        // idle time refills only up to the burst.
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(bucket.tokens(), 2.0);
+
+        // blocking sleeps on the clock, so the calls come out spaced by the rate.
+        let start = clock.now();
+        bucket.acquire_blocking();
+        bucket.acquire_blocking();
+        bucket.acquire_blocking();
+        bucket.acquire_blocking();
+        assert_eq!(clock.now() - start, Duration::from_millis(500));
+
+        let window = SlidingWindow::with_clock(3, Duration::from_secs(1), clock.clone());
+        let mut log = Vec::new();
+        for _ in 0..10 {
+            log.push(window.try_acquire());
+            clock.advance(Duration::from_millis(200));
+        }
+        assert_eq!(log, [true, true, true, false, false, true, true, true, false, false]);
+
+        // a burst of 5, but never more than 2 a second.
+        let clock = ManualClock::new();
+        let both = TokenBucket::with_clock(10.0, 5, clock.clone()).and(SlidingWindow::with_clock(2, Duration::from_secs(1), clock.clone()));
+        assert_eq!((0..5).filter(|_| both.try_acquire()).count(), 2);
+        assert_eq!(both.available_in(), Duration::from_secs(1));
+
+        let mut times = Vec::new();
+        let mut ping = throttled(both, |i: u32| {
+            times.push((i, clock.now().as_millis()));
+        });
+        (0..4).for_each(&mut ping);
+        drop(ping);
+        assert_eq!(times, [(0, 1000), (1, 1000), (2, 2000), (3, 2000)]);
+    }
+
+    #[test]
+    fn retry_test() {
+        use crate::rate_limiter::{Clock, ManualClock};
+        use crate::retry::{Exponential, Fixed, Policy, Retry};
+        use std::time::Duration;
+
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            Timeout,
+            NotFound,
+        }
+
+        let clock = ManualClock::new();
+        let mut attempts = Vec::new();
+        let result: Result<(), Error> = Retry::new(Exponential::new(Duration::from_millis(100)).max_attempts(4)).clock(clock.clone()).run(|| {
+            attempts.push(clock.now().as_millis());
+            Err(Error::Timeout)
+        });
+        assert_eq!(result, Err(Error::Timeout));
+        // 4 attempts, waiting 100, 200 and 400ms between them.
+        assert_eq!(attempts, [0, 100, 300, 700]);
+
+        // a final error isn't retried.
+        let clock = ManualClock::new();
+        let mut calls = 0;
+        let result: Result<(), Error> = Retry::new(Fixed(Duration::from_secs(1)))
+            .retry_if(|e| *e == Error::Timeout)
+            .clock(clock.clone())
+            .run(|| {
+                calls += 1;
+                Err(if calls < 3 { Error::Timeout } else { Error::NotFound })
+            });
+        assert_eq!((result, calls, clock.now()), (Err(Error::NotFound), 3, Duration::from_secs(2)));
+
+        // jitter stays under the delay it randomizes, and is the same for the same seed.
+        let mut policy = Exponential::new(Duration::from_secs(1)).jitter(42);
+        let delays: Vec<_> = (1..=8).map(|failures| policy.delay(failures).unwrap()).collect();
+        assert!(delays.iter().zip(0..).all(|(delay, i)| *delay <= Duration::from_secs(1 << i)));
+        assert!(delays.windows(2).any(|w| w[0] != w[1]));
+        let mut again = Exponential::new(Duration::from_secs(1)).jitter(42);
+        assert_eq!((1..=8).map(|failures| again.delay(failures).unwrap()).collect::<Vec<_>>(), delays);
+        assert_eq!(Fixed(Duration::from_secs(1)).jitter(7).max_attempts(2).delay(2), None);
+
+        // huge exponents saturate at the maximum instead of overflowing.
+        let mut capped = Exponential::new(Duration::from_secs(1)).factor(10.0).max_delay(Duration::from_secs(60));
+        assert_eq!(capped.delay(1000), Some(Duration::from_secs(60)));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn retry_async_test() {
+        use crate::retry::{Fixed, Policy, Retry};
+        use std::cell::RefCell;
+        use std::time::Duration;
+
+        let slept = RefCell::new(Vec::new());
+        let calls = RefCell::new(0);
+        let result = Retry::new(Fixed(Duration::from_millis(50)).max_attempts(5))
+            .run_async(
+                |delay| {
+                    slept.borrow_mut().push(delay);
+                    std::future::ready(())
+                },
+                || async {
+                    *calls.borrow_mut() += 1;
+                    let calls = *calls.borrow();
+                    if calls < 3 {
+                        Err("unavailable")
+                    } else {
+                        Ok(calls * 10)
+                    }
+                },
+            )
+            .await;
+        assert_eq!(result, Ok(30));
+        assert_eq!(*slept.borrow(), [Duration::from_millis(50); 2]);
+    }
+
+    #[test]
+    fn circuit_breaker_test() {
+        use crate::circuit_breaker::{BreakerError, CircuitBreaker, CircuitState};
+        use crate::rate_limiter::ManualClock;
+        use std::time::Duration;
+
+        let clock = ManualClock::new();
+        let breaker = CircuitBreaker::with_clock(3, Duration::from_secs(10), clock.clone()).success_threshold(2);
+        let ok = || Ok::<_, String>("ok");
+        let fail = || Err::<&str, _>("down".to_string());
+
+        // closed: a success resets the failures in a row.
+        assert!(breaker.call(fail).is_err() && breaker.call(fail).is_err());
+        assert_eq!(breaker.call(ok), Ok("ok"));
+        assert!(breaker.call(fail).is_err() && breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // closed -> open on the third failure in a row, and open calls aren't made.
+        assert_eq!(breaker.call(fail), Err(BreakerError::Failed("down".to_string())));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        clock.advance(Duration::from_secs(4));
+        let mut called = false;
+        assert_eq!(
+            breaker.call(|| {
+                called = true;
+                ok()
+            }),
+            Err(BreakerError::Open {
+                retry_in: Duration::from_secs(6)
+            })
+        );
+        assert!(!called);
+
+        // open -> half-open after the cooldown, half-open -> open on any failure.
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.call(ok), Ok("ok"));
+        assert!(breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(
+            breaker.call(ok),
+            Err(BreakerError::Open {
+                retry_in: Duration::from_secs(10)
+            })
+        );
+
+        // half-open -> closed after enough successes in a row.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(breaker.call(ok), Ok("ok"));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.call(ok), Ok("ok"));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.call(fail).is_err() && breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let error: BreakerError<std::fmt::Error> = BreakerError::Open {
+            retry_in: Duration::from_millis(1500),
+        };
+        assert_eq!(error.to_string(), "the circuit is open, retry in 1.5s");
+        assert_eq!(
+            BreakerError::Failed(std::fmt::Error).to_string(),
+            "the call failed: an error occurred when formatting an argument"
+        );
+    }
+
+    #[test]
+    fn cache_test() {
+        use crate::cache::{Cache, Lru, Policy};
+        use crate::rate_limiter::ManualClock;
+        use std::time::Duration;
+
+        let mut lru = Cache::lru(3);
+        for (key, value) in [(1, "one"), (2, "two"), (3, "three")] {
+            lru.insert(key, value);
+        }
+        assert_eq!(lru.get(&1), Some(&"one"));
+        assert_eq!(lru.peek(&2), Some(&"two"));
+        assert_eq!(lru.insert(4, "four"), None);
+        // peeking doesn't count, so 2 was the least recently used.
+        assert!(!lru.contains_key(&2));
+        assert_eq!(lru.policy().keys().copied().collect::<Vec<_>>(), [4, 1, 3]);
+        assert_eq!(lru.insert(3, "THREE"), Some("three"));
+        assert_eq!(lru.remove(&1), Some("one"));
+        lru.insert(5, "five");
+        lru.insert(6, "six");
+        assert_eq!(lru.policy().keys().copied().collect::<Vec<_>>(), [6, 5, 3]);
+        let mut entries: Vec<_> = lru.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort();
+        assert_eq!(entries, [(3, "THREE"), (5, "five"), (6, "six")]);
+
+        // the nodes of removed keys are reused.
+        let mut policy = Lru::new();
+        (0..100).for_each(|i| {
+            policy.on_insert(&i);
+            policy.on_remove(&i);
+        });
+        assert_eq!(policy.victim(), None);
+        policy.on_insert(&7);
+        assert_eq!(policy.victim(), Some(7));
+
+        let mut lfu = Cache::lfu(2);
+        lfu.insert("a", 1);
+        lfu.insert("b", 2);
+        lfu.get(&"a");
+        lfu.insert("c", 3);
+        assert!(lfu.contains_key(&"a") && !lfu.contains_key(&"b"));
+        assert_eq!(lfu.policy().uses(&"a"), 2);
+        // "c" has fewer uses than "a".
+        lfu.insert("d", 4);
+        assert!(lfu.contains_key(&"a") && !lfu.contains_key(&"c"));
+
+        let mut computed = 0;
+        let mut square = |cache: &mut Cache<u64, u64, _>, n: u64| {
+            *cache.get_or_insert_with(n, || {
+                computed += 1;
+                n * n
+            })
+        };
+        let mut squares = Cache::lfu(10);
+        assert_eq!([3, 4, 3, 3].map(|n| square(&mut squares, n)), [9, 16, 9, 9]);
+        assert_eq!(computed, 2);
+
+        let clock = ManualClock::new();
+        let mut ttl = Cache::ttl(2, Duration::from_secs(60), clock.clone());
+        ttl.insert("session", 1);
+        clock.advance(Duration::from_secs(30));
+        ttl.insert("token", 2);
+        assert_eq!(ttl.get(&"session"), Some(&1));
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(ttl.get(&"session"), None);
+        assert_eq!(ttl.len(), 1);
+        assert_eq!(ttl.iter().count(), 1);
+        // re-inserting starts over.
+        ttl.insert("token", 3);
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(ttl.peek(&"token"), Some(&3));
+        ttl.insert("a", 4);
+        ttl.insert("b", 5);
+        assert!(!ttl.contains_key(&"token"));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(ttl.purge(), 2);
+        assert!(ttl.is_empty());
+    }
+
+    #[test]
+    fn interner_test() {
+        use crate::interner::{Interner, Prehashed, Symbol};
+        use std::collections::HashSet;
+
+        crate::static_symbols! {
+            mod well_known {
+                TRUE = "true",
+                FALSE = "false",
+                SELF_ = "self",
+            }
+        }
+
+        let mut interner = well_known::interner();
+        assert_eq!(well_known::STRINGS.len(), 3);
+        assert_eq!(interner.len(), 3);
+        assert_eq!(interner.get("false"), Some(well_known::FALSE));
+        assert_eq!(well_known::TRUE.index(), 0);
+        assert_eq!(interner.resolve(well_known::SELF_), "self");
+
+        let words = "the quick brown fox jumps over the lazy dog the end".split(' ');
+        let symbols: Vec<Symbol> = words.clone().map(|word| interner.intern(word)).collect();
+        assert_eq!(symbols[0], symbols[6]);
+        assert_eq!(symbols[0], symbols[9]);
+        assert_eq!(symbols.iter().collect::<HashSet<_>>().len(), 9);
+        assert_eq!(interner.len(), 12);
+        assert_eq!(symbols.iter().map(|&symbol| interner.resolve(symbol)).collect::<Vec<_>>(), words.collect::<Vec<_>>());
+        assert_eq!(interner.iter().nth(3), Some((symbols[0], "the")));
+
+        // the hash is computed once, and looks the string up in any interner.
+        let fox = Prehashed::new("fox");
+        assert_eq!(fox.hash(), Prehashed::new("fox").hash());
+        assert_eq!(interner.get_prehashed(fox), Some(symbols[3]));
+        let mut other = Interner::new();
+        assert_eq!(other.get_prehashed(fox), None);
+        assert_eq!(other.intern_prehashed(fox).index(), 0);
+        assert_eq!(other.intern(""), other.intern(""));
+        assert_eq!(format!("{other:?}"), r#"["fox", ""]"#);
+
+        assert!(std::panic::catch_unwind(|| Interner::with_symbols(&["a", "b", "a"])).is_err());
+    }
+
+    #[test]
+    fn arena_test() {
+        use crate::arena::{Arena, Bump, Stats};
+        use crate::parser::arithmetic::{parse_in, Node};
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        // kept small, so the unsafe parts stay quick to check under miri.
+        struct Tracked(Rc<Cell<usize>>, u32);
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(0));
+        let arena = Arena::with_capacity(2);
+        let first = arena.alloc(Tracked(dropped.clone(), 1));
+        let more = arena.alloc_iter((2..=4).map(|i| Tracked(dropped.clone(), i)));
+        let last = arena.alloc(Tracked(dropped.clone(), 5));
+        // earlier references stay valid while the arena grows.
+        first.1 *= 10;
+        assert_eq!((first.1, more.iter().map(|t| t.1).sum::<u32>(), last.1), (10, 9, 5));
+        assert_eq!(
+            arena.stats(),
+            Stats {
+                allocated: 5,
+                chunks: 2,
+                capacity: 6
+            }
+        );
+        drop(arena);
+        assert_eq!(dropped.get(), 5);
+
+        let arena = Arena::new();
+        arena.alloc_iter(Vec::<u8>::new());
+        arena.alloc(1u8);
+        assert_eq!(arena.into_vec(), [1]);
+        let units = Arena::new();
+        (0..3).for_each(|_| *units.alloc(()) = ());
+        assert_eq!(units.len(), 3);
+
+        // ASTs from the parser, with the nodes pointing into the same arena.
+        let nodes = Arena::new();
+        let sum = parse_in(&nodes, "1 + 2 * (3 - 4)").unwrap();
+        let other = parse_in(&nodes, "-5").unwrap();
+        assert_eq!((sum.eval(), other.eval()), (Some(-1), Some(-5)));
+        assert_eq!(*other, Node::Neg(&Node::Num(5)));
+        assert_eq!(nodes.len(), 9);
+        assert!(parse_in(&nodes, "1 +").is_err());
+
+        let bump = Bump::new();
+        let byte = bump.alloc(1u8);
+        let wide = bump.alloc(2u128);
+        let words = bump.alloc_slice_copy(&[3u32, 4, 5]);
+        let empty: &mut [u64] = bump.alloc_slice_copy(&[]);
+        assert_eq!(wide as *mut u128 as usize % std::mem::align_of::<u128>(), 0);
+        assert_eq!(words.as_ptr() as usize % std::mem::align_of::<u32>(), 0);
+        assert_eq!((*byte, *wide, &*words, empty.len()), (1, 2, &[3, 4, 5][..], 0));
+        assert_eq!(bump.alloc(()), &());
+
+        // bigger than a chunk, and then more chunks as they fill up.
+        let big = bump.alloc_slice_copy(&[7u8; 3000]);
+        assert!(big.iter().all(|&b| b == 7));
+        (0..64).for_each(|i| assert_eq!(*bump.alloc([i; 16]), [i; 16]));
+        let stats = bump.stats();
+        assert!(stats.chunks >= 3 && stats.allocated <= stats.capacity);
+        assert_eq!(*byte, 1);
+    }
+
+    #[test]
+    fn di_test() {
+        use crate::di::{Container, Lifetime, ResolveError};
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct Counter(Cell<u32>);
+
+        impl Counter {
+            fn next(&self) -> u32 {
+                self.0.set(self.0.get() + 1);
+                self.0.get()
+            }
+        }
+
+        struct Connection(u32);
+        struct Request(u32);
+
+        crate::injectable! {
+            struct Handler {
+                connection: Rc<Connection>,
+                request: Rc<Request>,
+            }
+        }
+
+        let mut root = Container::new();
+        root.register_instance(Counter(Cell::new(0)))
+            .register(Lifetime::Singleton, |c| Ok(Connection(c.resolve::<Counter>()?.next())))
+            .register(Lifetime::Scoped, |c| Ok(Request(c.resolve::<Counter>()?.next() * 100)))
+            .register_type::<Handler>(Lifetime::Transient);
+
+        // a singleton is made once, a transient every time.
+        let a = root.resolve::<Handler>().unwrap();
+        let b = root.resolve::<Handler>().unwrap();
+        assert!(!Rc::ptr_eq(&a, &b));
+        assert!(Rc::ptr_eq(&a.connection, &b.connection));
+        assert_eq!(a.connection.0, 1);
+
+        // scoped services are shared within a scope, and made again in another one.
+        assert!(Rc::ptr_eq(&a.request, &b.request));
+        let first = root.scope();
+        let second = root.scope();
+        let (x, y) = (first.resolve::<Handler>().unwrap(), first.resolve::<Handler>().unwrap());
+        let z = second.resolve::<Handler>().unwrap();
+        assert!(Rc::ptr_eq(&x.request, &y.request) && !Rc::ptr_eq(&x.request, &z.request));
+        assert!(Rc::ptr_eq(&x.connection, &a.connection) && Rc::ptr_eq(&z.connection, &a.connection));
+        assert_eq!((a.request.0, x.request.0, z.request.0), (200, 300, 400));
+
+        // a scope can override its parents.
+        let mut nested = first.scope();
+        nested.register_instance(Request(7));
+        assert_eq!(nested.resolve::<Handler>().unwrap().request.0, 7);
+        assert_eq!(first.resolve::<Request>().unwrap().0, 300);
+
+        assert_eq!(root.resolve::<String>().unwrap_err(), ResolveError::Missing("alloc::string::String"));
+        assert!(!root.is_registered::<String>() && nested.is_registered::<Counter>());
+
+        #[derive(Debug)]
+        struct Chicken;
+        struct Egg;
+
+        let mut broken = Container::new();
+        broken.register(Lifetime::Transient, |c| c.resolve::<Egg>().map(|_| Chicken));
+        broken.register(Lifetime::Transient, |c| c.resolve::<Chicken>().map(|_| Egg));
+        let error = broken.resolve::<Chicken>().unwrap_err();
+        assert!(matches!(&error, ResolveError::Cycle(names) if names.len() == 3));
+        assert!(error.to_string().starts_with("dependency cycle: "));
+        // the failed attempt leaves nothing behind.
+        broken.register_instance(Egg);
+        assert!(broken.resolve::<Chicken>().is_ok());
+    }
+
+    #[test]
+    fn plugin_test() {
+        use crate::plugin::samples::{Uppercase, WordCount, BUILTIN};
+        use crate::plugin::{DuplicatePlugin, Factory, Plugin, Registered, Registry};
+
+        let listing: Vec<_> = BUILTIN.iter().map(|factory| (factory.name, factory.description)).collect();
+        assert_eq!(
+            listing,
+            [("uppercase", "converts the input to upper case"), ("word-count", "counts the words of the input")]
+        );
+        assert_eq!(BUILTIN.create("word-count").unwrap().execute("the quick brown fox"), "4");
+        assert!(BUILTIN.create("missing").is_none());
+
+        // a plugin with state, registered by hand next to the samples.
+        struct Prefix(String);
+
+        impl Plugin for Prefix {
+            fn execute(&self, input: &str) -> String {
+                format!("{}{input}", self.0)
+            }
+        }
+
+        let mut registry = BUILTIN.clone();
+        registry
+            .register_factory(Factory::new("prefix", "adds a prefix", || Box::new(Prefix("> ".to_string()))))
+            .unwrap();
+        assert_eq!(registry.len(), 3);
+        // the plugins run in name order: prefix, then uppercase, then word-count.
+        let run = |factories: &mut dyn Iterator<Item = &Factory>| factories.fold("hello world".to_string(), |text, factory| factory.create().execute(&text));
+        assert_eq!(run(&mut registry.iter().take(2)), "> HELLO WORLD");
+        assert_eq!(run(&mut registry.iter()), "3");
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["prefix", "uppercase", "word-count"]);
+
+        assert_eq!(registry.register::<Uppercase>().unwrap_err(), DuplicatePlugin("uppercase"));
+        assert_eq!(DuplicatePlugin("uppercase").to_string(), "the plugin \"uppercase\" is registered twice");
+        assert!(std::panic::catch_unwind(|| crate::plugins![WordCount, WordCount]).is_err());
+
+        let mut empty = Registry::new();
+        assert!(empty.is_empty());
+        empty.register::<WordCount>().unwrap().register::<Uppercase>().unwrap();
+        assert_eq!((&empty).into_iter().count(), 2);
+        assert_eq!(WordCount::FACTORY.name, "word-count");
     }
 }
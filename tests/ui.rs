@@ -0,0 +1,9 @@
+//! Compile-fail tests, the `.stderr` files are the expected diagnostics.
+//!
+//! run `TRYBUILD=overwrite cargo test --test ui` to update them after a change of the messages.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
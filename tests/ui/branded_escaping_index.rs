@@ -0,0 +1,7 @@
+use awesome::branded::scope;
+
+fn main() {
+    // an index can not leave its scope, it would be usable with the next vector.
+    let index = scope(vec![1, 2, 3], |v| v.index(2).unwrap());
+    scope(vec![4], |v| v[index]);
+}
@@ -0,0 +1,11 @@
+use awesome::branded::scope;
+
+fn main() {
+    // an index of one vector can not be used with another one.
+    scope(vec![1, 2, 3], |a| {
+        scope(vec![4], |b| {
+            let i = a.index(2).unwrap();
+            let _ = b[i];
+        })
+    });
+}
@@ -0,0 +1,15 @@
+use awesome::impl_macro::FooAll;
+
+#[derive(FooAll)]
+#[foo(typs(u8))]
+struct Typo<T>(T);
+
+#[derive(FooAll)]
+#[foo(types = "u8")]
+struct NotAList<T>(T);
+
+#[derive(FooAll)]
+#[foo(types(u8 u16))]
+struct MissingComma<T>(T);
+
+fn main() {}
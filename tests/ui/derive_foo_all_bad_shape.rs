@@ -0,0 +1,17 @@
+use awesome::impl_macro::FooAll;
+
+#[derive(FooAll)]
+#[foo(types(u8))]
+struct TwoFields(u8, u8);
+
+#[derive(FooAll)]
+#[foo(types(u8))]
+enum NotAStruct {
+    A(u8),
+}
+
+#[derive(FooAll)]
+#[foo(types(u8))]
+struct Borrowed<'a, T>(&'a T);
+
+fn main() {}
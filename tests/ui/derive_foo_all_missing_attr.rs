@@ -0,0 +1,6 @@
+use awesome::impl_macro::FooAll;
+
+#[derive(FooAll)]
+struct Adder<T>(T);
+
+fn main() {}
@@ -0,0 +1,14 @@
+use awesome::optics::Lenses;
+
+#[derive(Lenses)]
+struct Tuple(u8, u8);
+
+#[derive(Lenses)]
+enum NotAStruct {
+    A(u8),
+}
+
+#[derive(Lenses)]
+struct Unit;
+
+fn main() {}
@@ -0,0 +1,5 @@
+use awesome::overloading::foo;
+
+fn main() {
+    foo(true);
+}
@@ -0,0 +1,5 @@
+use awesome::overloading::from_foo;
+
+fn main() {
+    let _: bool = from_foo("true");
+}
@@ -0,0 +1,9 @@
+use awesome::gadt::*;
+
+fn main() {
+    // the condition is not a `bool`.
+    let _ = if_(int(1), int(10), int(20));
+
+    // the branches have different types.
+    let _ = if_(boolean(true), int(10), boolean(false));
+}
@@ -0,0 +1,5 @@
+use awesome::overloading::MyPoint;
+
+fn main() {
+    MyPoint::new("3, 4");
+}
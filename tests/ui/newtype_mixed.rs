@@ -0,0 +1,13 @@
+use awesome::newtype;
+
+newtype!(Meters(f64): Add, Mul<f64>);
+newtype!(Seconds(f64): Add);
+
+fn main() {
+    // there's no `Add<f64>`, only `Mul<f64>`.
+    let _ = Meters(1.0) + 1.0;
+    // different newtypes of the same inner type don't mix.
+    let _ = Meters(1.0) + Seconds(1.0);
+    // and a raw value is not a newtype.
+    let _: Meters = 1.0;
+}
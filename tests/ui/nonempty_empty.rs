@@ -0,0 +1,5 @@
+use awesome::nonempty;
+
+fn main() {
+    let _: awesome::nonempty::NonEmpty<i32> = nonempty![];
+}
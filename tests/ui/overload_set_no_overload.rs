@@ -0,0 +1,14 @@
+use awesome::overload;
+
+overload! {
+    fn area(r: f64) -> f64 {
+        r * r
+    }
+    fn area((w, h): (f64, f64)) -> f64 {
+        w * h
+    }
+}
+
+fn main() {
+    area("1.0");
+}
@@ -0,0 +1,9 @@
+use awesome::phantom::{meters, seconds, Meters, Quantity};
+
+fn main() {
+    // meters and seconds can not be added.
+    let _ = meters(1.0) + seconds(2.0);
+
+    // a speed is not a distance.
+    let _: Quantity<f64, Meters> = meters(10.0) / seconds(2.0);
+}
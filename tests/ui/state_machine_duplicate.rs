@@ -0,0 +1,10 @@
+use awesome::fsm::state_machine;
+
+state_machine! {
+    Door {
+        Closed --open--> Open,
+        Closed --open--> Closed,
+    }
+}
+
+fn main() {}
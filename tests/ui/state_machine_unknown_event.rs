@@ -0,0 +1,9 @@
+use awesome::fsm::state_machine;
+
+state_machine! {
+    Door { Closed --open--> Open, Open --close--> Closed }
+}
+
+fn main() {
+    let _ = Door::transition(DoorState::Open, DoorEvent::Lock);
+}
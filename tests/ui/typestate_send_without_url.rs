@@ -0,0 +1,5 @@
+use awesome::typestate::{Method, Request};
+
+fn main() {
+    let _ = Request::builder().method(Method::Post).body("hello").send();
+}
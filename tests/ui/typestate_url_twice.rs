@@ -0,0 +1,5 @@
+use awesome::typestate::Request;
+
+fn main() {
+    let _ = Request::builder().url("https://example.com").url("https://example.org").send();
+}